@@ -2,15 +2,17 @@ import { Admin } from "@/components/admin";
 import simpleRestProvider from 'ra-data-simple-rest';
 
 import { Dashboard } from "./components/dashboard/Dashboard";
+import { authProvider, httpClient } from "./lib/authProvider";
 
 
 
-const dataProvider = simpleRestProvider('/api');
+const dataProvider = simpleRestProvider('/api', httpClient);
 
 function App() {
     return (
         <Admin
             dataProvider={dataProvider}
+            authProvider={authProvider}
             dashboard={Dashboard}
         >
             
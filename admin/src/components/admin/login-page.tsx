@@ -76,14 +76,13 @@ export const LoginPage = (props: { redirectTo?: string }) => {
             <div className="flex flex-col space-y-2 text-center">
               <h1 className="text-2xl font-semibold tracking-tight">Sign in</h1>
               <p className="text-sm leading-none text-muted-foreground">
-                Try janedoe@acme.com / password
+                Sign in with an admin or moderator account
               </p>
             </div>
             <Form className="space-y-8" onSubmit={handleSubmit}>
               <TextInput
-                label="Email"
-                source="email"
-                type="email"
+                label="Username or email"
+                source="login"
                 validate={required()}
               />
               <TextInput
@@ -92,6 +91,12 @@ export const LoginPage = (props: { redirectTo?: string }) => {
                 type="password"
                 validate={required()}
               />
+              <TextInput
+                label="Two-factor code"
+                source="totp_code"
+                autoComplete="one-time-code"
+                helperText="Only if two-factor authentication is enabled"
+              />
               <Button
                 type="submit"
                 className="cursor-pointer"
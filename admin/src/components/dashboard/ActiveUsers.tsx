@@ -0,0 +1,17 @@
+import { Activity, CalendarDays, CalendarRange } from "lucide-react";
+
+import CardWithIcon from "./CardWithIcon";
+import { useStats, type ActiveUsers as ActiveUsersStats } from "./useStats";
+
+export const ActiveUsers = () => {
+  const { data } = useStats<ActiveUsersStats>("active-users");
+  return (
+    <>
+      <CardWithIcon to="/" icon={Activity} title="Daily active users" subtitle={data?.daily} />
+      <CardWithIcon to="/" icon={CalendarDays} title="Weekly active users" subtitle={data?.weekly} />
+      <CardWithIcon to="/" icon={CalendarRange} title="Monthly active users" subtitle={data?.monthly} />
+    </>
+  );
+};
+
+export default ActiveUsers;
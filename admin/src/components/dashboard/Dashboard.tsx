@@ -1,6 +1,9 @@
 import { Translate } from "ra-core";
 import { Breadcrumb, BreadcrumbPage } from "@/components/admin";
 
+import ActiveUsers from "./ActiveUsers";
+import Registrations from "./Registrations";
+import Retention from "./Retention";
 import Welcome from "./Welcome";
 
 export const Dashboard = () => {
@@ -17,14 +20,17 @@ export const Dashboard = () => {
         <div className="flex flex-col md:flex-row gap-4 mb-4">
           <div className="flex flex-col gap-4 md:basis-1/2">
             <div className="flex flex-col md:flex-row gap-4">
+              <ActiveUsers />
             </div>
             <div>
+              <Registrations />
             </div>
             <div>
             </div>
           </div>
           <div className="md:basis-1/2">
             <div className="flex flex-col md:flex-row gap-4">
+              <Retention />
             </div>
           </div>
         </div>
@@ -0,0 +1,40 @@
+import { useEffect, useRef } from "react";
+import * as echarts from "echarts";
+import { Card, CardContent, CardHeader, CardTitle } from "@/components/ui/card";
+
+import { useStats, type DailyCount } from "./useStats";
+
+export const Registrations = () => {
+  const { data } = useStats<DailyCount[]>("registrations?days=30");
+  const chartRef = useRef<HTMLDivElement>(null);
+
+  useEffect(() => {
+    if (!chartRef.current || !data) return;
+    const chart = echarts.init(chartRef.current);
+    chart.setOption({
+      tooltip: { trigger: "axis" },
+      xAxis: { type: "category", data: data.map((d) => d.day) },
+      yAxis: { type: "value", minInterval: 1 },
+      series: [{ type: "bar", data: data.map((d) => d.count) }],
+    });
+    const resize = () => chart.resize();
+    window.addEventListener("resize", resize);
+    return () => {
+      window.removeEventListener("resize", resize);
+      chart.dispose();
+    };
+  }, [data]);
+
+  return (
+    <Card>
+      <CardHeader>
+        <CardTitle>New registrations (30 days)</CardTitle>
+      </CardHeader>
+      <CardContent>
+        <div ref={chartRef} className="h-64 w-full" />
+      </CardContent>
+    </Card>
+  );
+};
+
+export default Registrations;
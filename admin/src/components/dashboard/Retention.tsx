@@ -0,0 +1,52 @@
+import { Card, CardContent, CardHeader, CardTitle } from "@/components/ui/card";
+import {
+  Table,
+  TableBody,
+  TableCell,
+  TableHead,
+  TableHeader,
+  TableRow,
+} from "@/components/ui/table";
+
+import { useStats, type Cohort } from "./useStats";
+
+const percent = (value: number, size: number) =>
+  size === 0 ? "-" : `${Math.round((value / size) * 100)}%`;
+
+export const Retention = () => {
+  const { data = [] } = useStats<Cohort[]>("retention?weeks=8");
+  const columns = Math.max(0, ...data.map((cohort) => cohort.retained.length));
+  return (
+    <Card>
+      <CardHeader>
+        <CardTitle>Weekly retention</CardTitle>
+      </CardHeader>
+      <CardContent>
+        <Table>
+          <TableHeader>
+            <TableRow>
+              <TableHead>Cohort</TableHead>
+              <TableHead>Users</TableHead>
+              {Array.from({ length: columns }, (_, week) => (
+                <TableHead key={week}>W{week}</TableHead>
+              ))}
+            </TableRow>
+          </TableHeader>
+          <TableBody>
+            {data.map((cohort) => (
+              <TableRow key={cohort.week}>
+                <TableCell>{cohort.week}</TableCell>
+                <TableCell>{cohort.size}</TableCell>
+                {cohort.retained.map((value, week) => (
+                  <TableCell key={week}>{percent(value, cohort.size)}</TableCell>
+                ))}
+              </TableRow>
+            ))}
+          </TableBody>
+        </Table>
+      </CardContent>
+    </Card>
+  );
+};
+
+export default Retention;
@@ -0,0 +1,33 @@
+import { useQuery } from "@tanstack/react-query";
+import { httpClient } from "@/lib/authProvider";
+
+/**
+ * Fetches one of the admin statistics endpoints under /api/admin/stats.
+ * Goes through the authProvider's httpClient, as the stats require an admin session.
+ */
+export const useStats = <T,>(path: string) =>
+  useQuery<T>({
+    queryKey: ["admin-stats", path],
+    queryFn: async () => {
+      const { json } = await httpClient(`/api/admin/stats/${path}`);
+      return json as T;
+    },
+  });
+
+export interface ActiveUsers {
+  as_of: string;
+  daily: number;
+  weekly: number;
+  monthly: number;
+}
+
+export interface DailyCount {
+  day: string;
+  count: number;
+}
+
+export interface Cohort {
+  week: string;
+  size: number;
+  retained: number[];
+}
@@ -0,0 +1,69 @@
+import { fetchUtils, type AuthProvider, type HttpError } from "ra-core";
+
+const TOKEN_KEY = "token";
+
+export const getToken = () => localStorage.getItem(TOKEN_KEY);
+
+/**
+ * fetchJson with the session token from `/api/auth/login` as a bearer token.
+ * Shared by the dataProvider and the dashboard statistics.
+ */
+export const httpClient = (url: string, options: fetchUtils.Options = {}) => {
+  const headers = new Headers(options.headers);
+  const token = getToken();
+  if (token) {
+    headers.set("Authorization", `Bearer ${token}`);
+  }
+  return fetchUtils.fetchJson(url, { ...options, headers });
+};
+
+interface Session {
+  token: string;
+  user_id: number;
+  two_factor_enrollment_required: boolean;
+}
+
+export const authProvider: AuthProvider = {
+  login: async ({ login, password, totp_code }) => {
+    const response = await fetch("/api/auth/login", {
+      method: "POST",
+      headers: new Headers({ "Content-Type": "application/json", Accept: "application/json" }),
+      body: JSON.stringify({ login, password, totp_code: totp_code || null }),
+    });
+    const body = await response.json().catch(() => ({}));
+    if (!response.ok) {
+      throw new Error(body.error ?? response.statusText);
+    }
+    const session = body as Session;
+    if (session.two_factor_enrollment_required) {
+      // Such a session only works for /auth/2fa, so the admin can't use it
+      throw new Error("Set up two-factor authentication before signing in to the admin");
+    }
+    localStorage.setItem(TOKEN_KEY, session.token);
+  },
+  logout: async () => {
+    if (getToken()) {
+      await httpClient("/api/auth/logout", { method: "POST" }).catch(() => undefined);
+      localStorage.removeItem(TOKEN_KEY);
+    }
+  },
+  checkAuth: async () => {
+    if (!getToken()) {
+      throw new Error("Not signed in");
+    }
+  },
+  checkError: async (error: HttpError) => {
+    if (error.status === 401) {
+      localStorage.removeItem(TOKEN_KEY);
+      throw error;
+    }
+  },
+  getIdentity: async () => {
+    const { json } = await httpClient("/api/me");
+    return {
+      id: json.id,
+      fullName: json.display_name ?? json.username,
+      avatar: json.avatar_url ?? undefined,
+    };
+  },
+};
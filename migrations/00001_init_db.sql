@@ -77,7 +77,7 @@ CREATE TABLE IF NOT EXISTS public.chapter (
     ,
     release_date VARCHAR
     ,
-    source VARCHAR
+    source_id INTEGER NOT NULL
     ,
     title VARCHAR
     ,
@@ -132,8 +132,8 @@ CREATE TRIGGER set_last_updated
 
 DO $$
 BEGIN
-    IF NOT EXISTS (SELECT 1 FROM pg_type WHERE typname = 'status-origin') THEN
-        CREATE TYPE status-origin AS ENUM ('ongoing', 'completed', 'hiatus', 'cancelled');
+    IF NOT EXISTS (SELECT 1 FROM pg_type WHERE typname = 'status_origin') THEN
+        CREATE TYPE status_origin AS ENUM ('ongoing', 'completed', 'hiatus', 'cancelled');
     END IF;
 END $$;
 CREATE TABLE IF NOT EXISTS public.novel (
@@ -189,7 +189,7 @@ CREATE TABLE IF NOT EXISTS public.novel (
     ,
     total_chapters INTEGER
     ,
-    type VARCHAR
+    type_id INTEGER NOT NULL
     ,
     views INTEGER
     ,
@@ -249,7 +249,7 @@ CREATE TABLE IF NOT EXISTS public.reading_list (
     ,
     status VARCHAR
     ,
-    user VARCHAR
+    user_id INTEGER NOT NULL
     );
 
 DROP TRIGGER IF EXISTS set_last_updated ON public.reading_list;
@@ -266,7 +266,7 @@ CREATE TABLE IF NOT EXISTS public.review (
     ,
     helpful_count INTEGER
     ,
-    novel VARCHAR
+    novel_id INTEGER NOT NULL
     ,
     rating VARCHAR
     ,
@@ -274,7 +274,7 @@ CREATE TABLE IF NOT EXISTS public.review (
     ,
     title VARCHAR
     ,
-    user VARCHAR
+    user_id INTEGER NOT NULL
     );
 
 DROP TRIGGER IF EXISTS set_last_updated ON public.review;
@@ -299,7 +299,7 @@ CREATE TABLE IF NOT EXISTS public.source (
     ,
     completely_translated BOOLEAN
     ,
-    group VARCHAR
+    group_id INTEGER NOT NULL
     ,
     is_official BOOLEAN
     ,
@@ -307,7 +307,7 @@ CREATE TABLE IF NOT EXISTS public.source (
     ,
     name VARCHAR
     ,
-    novel VARCHAR
+    novel_id INTEGER NOT NULL
     ,
     status VARCHAR
     ,
@@ -338,7 +338,7 @@ CREATE TABLE IF NOT EXISTS public.tag (
     ,
     novels VARCHAR
     ,
-    slug VARCHAR
+    slug VARCHAR UNIQUE
     ,
     usage_count INTEGER
     );
@@ -376,7 +376,7 @@ CREATE TABLE IF NOT EXISTS public.user (
     ,
     display_name VARCHAR
     ,
-    email VARCHAR
+    email VARCHAR UNIQUE
     ,
     joined_date VARCHAR
     ,
@@ -388,7 +388,7 @@ CREATE TABLE IF NOT EXISTS public.user (
     ,
     reviews VARCHAR
     ,
-    username VARCHAR
+    username VARCHAR UNIQUE
     );
 
 DROP TRIGGER IF EXISTS set_last_updated ON public.user;
@@ -421,15 +421,31 @@ CREATE TABLE IF NOT EXISTS Novel_Publisher (
     PRIMARY KEY (novel_id, publisher_id)
 );
 CREATE INDEX IF NOT EXISTS idx_Novel_Publisher_publisher_id_novel_id ON Novel_Publisher(publisher_id,novel_id);
-CREATE TABLE IF NOT EXISTS Novel_ReadingList (
+CREATE TABLE IF NOT EXISTS Novel_Reading_List (
     novel_id INTEGER NOT NULL REFERENCES public.novel(id) ON DELETE CASCADE,
     reading_list_id INTEGER NOT NULL REFERENCES public.reading_list(id) ON DELETE CASCADE,
     PRIMARY KEY (novel_id, reading_list_id)
 );
-CREATE INDEX IF NOT EXISTS idx_Novel_ReadingList_reading_list_id_novel_id ON Novel_ReadingList(reading_list_id,novel_id);
+CREATE INDEX IF NOT EXISTS idx_Novel_Reading_List_reading_list_id_novel_id ON Novel_Reading_List(reading_list_id,novel_id);
 CREATE TABLE IF NOT EXISTS Novel_Tag (
     novel_id INTEGER NOT NULL REFERENCES public.novel(id) ON DELETE CASCADE,
     tag_id INTEGER NOT NULL REFERENCES public.tag(id) ON DELETE CASCADE,
     PRIMARY KEY (novel_id, tag_id)
 );
 CREATE INDEX IF NOT EXISTS idx_Novel_Tag_tag_id_novel_id ON Novel_Tag(tag_id,novel_id);
+
+-- belongs_to foreign keys, added once every table exists
+ALTER TABLE public.chapter DROP CONSTRAINT IF EXISTS chapter_source_id_fkey, ADD CONSTRAINT chapter_source_id_fkey FOREIGN KEY (source_id) REFERENCES public.source(id) ON DELETE CASCADE;
+ALTER TABLE public.novel DROP CONSTRAINT IF EXISTS novel_type_id_fkey, ADD CONSTRAINT novel_type_id_fkey FOREIGN KEY (type_id) REFERENCES public.type(id);
+ALTER TABLE public.reading_list DROP CONSTRAINT IF EXISTS reading_list_user_id_fkey, ADD CONSTRAINT reading_list_user_id_fkey FOREIGN KEY (user_id) REFERENCES public.user(id) ON DELETE CASCADE;
+ALTER TABLE public.review DROP CONSTRAINT IF EXISTS review_novel_id_fkey, ADD CONSTRAINT review_novel_id_fkey FOREIGN KEY (novel_id) REFERENCES public.novel(id) ON DELETE CASCADE;
+ALTER TABLE public.review DROP CONSTRAINT IF EXISTS review_user_id_fkey, ADD CONSTRAINT review_user_id_fkey FOREIGN KEY (user_id) REFERENCES public.user(id) ON DELETE CASCADE;
+ALTER TABLE public.source DROP CONSTRAINT IF EXISTS source_group_id_fkey, ADD CONSTRAINT source_group_id_fkey FOREIGN KEY (group_id) REFERENCES public.group(id) ON DELETE CASCADE;
+ALTER TABLE public.source DROP CONSTRAINT IF EXISTS source_novel_id_fkey, ADD CONSTRAINT source_novel_id_fkey FOREIGN KEY (novel_id) REFERENCES public.novel(id) ON DELETE CASCADE;
+CREATE INDEX IF NOT EXISTS idx_chapter_source_id ON public.chapter(source_id);
+CREATE INDEX IF NOT EXISTS idx_novel_type_id ON public.novel(type_id);
+CREATE INDEX IF NOT EXISTS idx_reading_list_user_id ON public.reading_list(user_id);
+CREATE INDEX IF NOT EXISTS idx_review_novel_id ON public.review(novel_id);
+CREATE INDEX IF NOT EXISTS idx_review_user_id ON public.review(user_id);
+CREATE INDEX IF NOT EXISTS idx_source_group_id ON public.source(group_id);
+CREATE INDEX IF NOT EXISTS idx_source_novel_id ON public.source(novel_id);
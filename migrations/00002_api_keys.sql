@@ -0,0 +1,33 @@
+CREATE TABLE IF NOT EXISTS public.api_key (
+    id SERIAL PRIMARY KEY,
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    last_updated TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+    ,
+    name VARCHAR NOT NULL
+    ,
+    key_prefix VARCHAR NOT NULL
+    ,
+    key_hash VARCHAR NOT NULL UNIQUE
+    ,
+    scopes VARCHAR NOT NULL
+    ,
+    user_id INTEGER REFERENCES public.user(id) ON DELETE CASCADE
+    ,
+    group_id INTEGER REFERENCES public.group(id) ON DELETE CASCADE
+    ,
+    expires_at TIMESTAMP
+    ,
+    last_used_at TIMESTAMP
+    ,
+    revoked_at TIMESTAMP
+    ,
+    CONSTRAINT api_key_owner CHECK (user_id IS NOT NULL OR group_id IS NOT NULL)
+    );
+
+DROP TRIGGER IF EXISTS set_last_updated ON public.api_key;
+CREATE TRIGGER set_last_updated
+    BEFORE UPDATE ON public.api_key
+    FOR EACH ROW EXECUTE FUNCTION update_last_updated_column();
+
+CREATE INDEX IF NOT EXISTS idx_api_key_user_id ON public.api_key(user_id);
+CREATE INDEX IF NOT EXISTS idx_api_key_group_id ON public.api_key(group_id);
@@ -0,0 +1,24 @@
+ALTER TABLE public.user ADD COLUMN IF NOT EXISTS email_verified_at TIMESTAMP;
+
+CREATE TABLE IF NOT EXISTS public.user_token (
+    id SERIAL PRIMARY KEY,
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    last_updated TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+    ,
+    user_id INTEGER NOT NULL REFERENCES public.user(id) ON DELETE CASCADE
+    ,
+    purpose VARCHAR NOT NULL
+    ,
+    token_hash VARCHAR NOT NULL UNIQUE
+    ,
+    expires_at TIMESTAMP NOT NULL
+    ,
+    used_at TIMESTAMP
+    );
+
+DROP TRIGGER IF EXISTS set_last_updated ON public.user_token;
+CREATE TRIGGER set_last_updated
+    BEFORE UPDATE ON public.user_token
+    FOR EACH ROW EXECUTE FUNCTION update_last_updated_column();
+
+CREATE INDEX IF NOT EXISTS idx_user_token_user_id_purpose ON public.user_token(user_id, purpose);
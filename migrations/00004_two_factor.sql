@@ -0,0 +1,25 @@
+ALTER TABLE public.user ADD COLUMN IF NOT EXISTS role VARCHAR NOT NULL DEFAULT 'user';
+ALTER TABLE public.user ADD COLUMN IF NOT EXISTS totp_secret VARCHAR;
+ALTER TABLE public.user ADD COLUMN IF NOT EXISTS totp_enabled_at TIMESTAMP;
+ALTER TABLE public.user ADD COLUMN IF NOT EXISTS totp_last_step BIGINT;
+
+ALTER TABLE public.api_key ADD COLUMN IF NOT EXISTS session BOOLEAN NOT NULL DEFAULT FALSE;
+
+CREATE TABLE IF NOT EXISTS public.user_recovery_code (
+    id SERIAL PRIMARY KEY,
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    last_updated TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+    ,
+    user_id INTEGER NOT NULL REFERENCES public.user(id) ON DELETE CASCADE
+    ,
+    code_hash VARCHAR NOT NULL
+    ,
+    used_at TIMESTAMP
+    );
+
+DROP TRIGGER IF EXISTS set_last_updated ON public.user_recovery_code;
+CREATE TRIGGER set_last_updated
+    BEFORE UPDATE ON public.user_recovery_code
+    FOR EACH ROW EXECUTE FUNCTION update_last_updated_column();
+
+CREATE INDEX IF NOT EXISTS idx_user_recovery_code_user_id ON public.user_recovery_code(user_id);
@@ -0,0 +1,2 @@
+ALTER TABLE public.user ADD COLUMN IF NOT EXISTS show_reading_lists BOOLEAN NOT NULL DEFAULT TRUE;
+ALTER TABLE public.user ADD COLUMN IF NOT EXISTS show_reviews BOOLEAN NOT NULL DEFAULT TRUE;
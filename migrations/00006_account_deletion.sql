@@ -0,0 +1,7 @@
+ALTER TABLE public.user ADD COLUMN IF NOT EXISTS deletion_scheduled_for TIMESTAMP;
+
+-- Reviews outlive their author; a NULL user_id marks an anonymized review.
+ALTER TABLE public.review ALTER COLUMN user_id DROP NOT NULL;
+
+CREATE INDEX IF NOT EXISTS idx_user_deletion_scheduled_for ON public.user(deletion_scheduled_for)
+    WHERE deletion_scheduled_for IS NOT NULL;
@@ -0,0 +1,15 @@
+-- last_active is maintained by the server from now on; keep client-set values that parse.
+ALTER TABLE public.user ALTER COLUMN last_active TYPE TIMESTAMP
+    USING CASE WHEN last_active ~ '^\d{4}-\d{2}-\d{2}([ T]\d{2}:\d{2}(:\d{2}(\.\d+)?)?)?$' THEN last_active::timestamp END;
+
+-- One row per user per day with authenticated activity; the source for active-user and retention stats.
+CREATE TABLE IF NOT EXISTS public.user_activity (
+    user_id INTEGER NOT NULL REFERENCES public.user(id) ON DELETE CASCADE
+    ,
+    day DATE NOT NULL
+    ,
+    PRIMARY KEY (user_id, day)
+    );
+
+CREATE INDEX IF NOT EXISTS idx_user_activity_day ON public.user_activity(day);
+CREATE INDEX IF NOT EXISTS idx_user_created_at ON public.user(created_at);
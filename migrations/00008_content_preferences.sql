@@ -0,0 +1,20 @@
+CREATE TABLE IF NOT EXISTS public.user_preference (
+    id SERIAL PRIMARY KEY,
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    last_updated TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+    ,
+    user_id INTEGER NOT NULL UNIQUE REFERENCES public.user(id) ON DELETE CASCADE
+    ,
+    excluded_tag_ids VARCHAR NOT NULL DEFAULT ''
+    ,
+    original_languages VARCHAR NOT NULL DEFAULT ''
+    ,
+    chapter_languages VARCHAR NOT NULL DEFAULT ''
+    ,
+    hide_licensed BOOLEAN NOT NULL DEFAULT FALSE
+    );
+
+DROP TRIGGER IF EXISTS set_last_updated ON public.user_preference;
+CREATE TRIGGER set_last_updated
+    BEFORE UPDATE ON public.user_preference
+    FOR EACH ROW EXECUTE FUNCTION update_last_updated_column();
@@ -0,0 +1,65 @@
+CREATE TABLE IF NOT EXISTS public.library_entry (
+    id SERIAL PRIMARY KEY,
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    last_updated TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+    ,
+    user_id INTEGER NOT NULL REFERENCES public.user(id) ON DELETE CASCADE
+    ,
+    novel_id INTEGER NOT NULL REFERENCES public.novel(id) ON DELETE CASCADE
+    ,
+    status VARCHAR NOT NULL DEFAULT 'plan_to_read'
+    ,
+    last_read_chapter_id INTEGER REFERENCES public.chapter(id) ON DELETE SET NULL
+    ,
+    last_read_at TIMESTAMP
+    ,
+    personal_rating VARCHAR
+    ,
+    notes VARCHAR
+    ,
+    started_date VARCHAR
+    ,
+    completed_date VARCHAR
+    ,
+    UNIQUE (user_id, novel_id)
+    );
+
+DROP TRIGGER IF EXISTS set_last_updated ON public.library_entry;
+CREATE TRIGGER set_last_updated
+    BEFORE UPDATE ON public.library_entry
+    FOR EACH ROW EXECUTE FUNCTION update_last_updated_column();
+
+CREATE INDEX IF NOT EXISTS idx_library_entry_novel_id ON public.library_entry(novel_id);
+
+CREATE TABLE IF NOT EXISTS public.library_chapter_read (
+    library_entry_id INTEGER NOT NULL REFERENCES public.library_entry(id) ON DELETE CASCADE,
+    chapter_id INTEGER NOT NULL REFERENCES public.chapter(id) ON DELETE CASCADE,
+    read_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (library_entry_id, chapter_id)
+);
+CREATE INDEX IF NOT EXISTS idx_library_chapter_read_chapter_id ON public.library_chapter_read(chapter_id);
+
+-- Copy every (reading list, novel) pair into a per-novel entry. reading_list.current_chapter
+-- is a chapter number, so it is resolved to that novel's chapter with the same number.
+-- When a novel sits in several lists of the same user, the most recently updated list wins.
+INSERT INTO public.library_entry (user_id, novel_id, status, last_read_chapter_id, personal_rating, notes, started_date, completed_date, created_at)
+SELECT DISTINCT ON (rl.user_id, nrl.novel_id)
+    rl.user_id,
+    nrl.novel_id,
+    COALESCE(rl.status, 'plan_to_read'),
+    (SELECT c.id
+        FROM public.chapter c
+        JOIN public.chapter_novel cn ON cn.chapter_id = c.id
+        WHERE cn.novel_id = nrl.novel_id AND c.number = rl.current_chapter::varchar
+        ORDER BY c.id
+        LIMIT 1),
+    rl.personal_rating,
+    rl.notes,
+    rl.started_date,
+    rl.completed_date,
+    rl.created_at
+FROM public.reading_list rl
+JOIN public.novel_reading_list nrl ON nrl.reading_list_id = rl.id
+WHERE rl.user_id IS NOT NULL
+ORDER BY rl.user_id, nrl.novel_id, rl.last_updated DESC
+ON CONFLICT (user_id, novel_id) DO NOTHING;
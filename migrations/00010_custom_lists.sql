@@ -0,0 +1,18 @@
+ALTER TABLE public.reading_list ADD COLUMN IF NOT EXISTS name VARCHAR;
+ALTER TABLE public.reading_list ADD COLUMN IF NOT EXISTS description VARCHAR;
+ALTER TABLE public.reading_list ADD COLUMN IF NOT EXISTS visibility VARCHAR NOT NULL DEFAULT 'private';
+ALTER TABLE public.reading_list ADD COLUMN IF NOT EXISTS share_token VARCHAR UNIQUE;
+
+CREATE INDEX IF NOT EXISTS idx_reading_list_public ON public.reading_list(last_updated)
+    WHERE visibility = 'public';
+
+ALTER TABLE public.novel_reading_list ADD COLUMN IF NOT EXISTS position INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE public.novel_reading_list ADD COLUMN IF NOT EXISTS blurb VARCHAR;
+
+CREATE TABLE IF NOT EXISTS public.reading_list_follow (
+    user_id INTEGER NOT NULL REFERENCES public.user(id) ON DELETE CASCADE,
+    reading_list_id INTEGER NOT NULL REFERENCES public.reading_list(id) ON DELETE CASCADE,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (user_id, reading_list_id)
+);
+CREATE INDEX IF NOT EXISTS idx_reading_list_follow_reading_list_id ON public.reading_list_follow(reading_list_id);
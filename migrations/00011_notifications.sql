@@ -0,0 +1,56 @@
+CREATE TABLE IF NOT EXISTS public.notification (
+    id SERIAL PRIMARY KEY,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+    ,
+    user_id INTEGER NOT NULL REFERENCES public.user(id) ON DELETE CASCADE
+    ,
+    kind VARCHAR NOT NULL
+    ,
+    title VARCHAR NOT NULL
+    ,
+    novel_id INTEGER REFERENCES public.novel(id) ON DELETE CASCADE
+    ,
+    chapter_id INTEGER REFERENCES public.chapter(id) ON DELETE CASCADE
+    ,
+    source_id INTEGER REFERENCES public.source(id) ON DELETE CASCADE
+    ,
+    group_id INTEGER REFERENCES public.group(id) ON DELETE CASCADE
+    ,
+    actor_id INTEGER REFERENCES public.user(id) ON DELETE SET NULL
+    ,
+    read_at TIMESTAMP
+    );
+CREATE INDEX IF NOT EXISTS idx_notification_user_id_created_at ON public.notification(user_id, created_at DESC);
+CREATE INDEX IF NOT EXISTS idx_notification_unread ON public.notification(user_id) WHERE read_at IS NULL;
+
+CREATE TABLE IF NOT EXISTS public.notification_preference (
+    id SERIAL PRIMARY KEY,
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    last_updated TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+    ,
+    user_id INTEGER NOT NULL UNIQUE REFERENCES public.user(id) ON DELETE CASCADE
+    ,
+    new_chapter BOOLEAN NOT NULL DEFAULT TRUE
+    ,
+    new_source BOOLEAN NOT NULL DEFAULT TRUE
+    );
+
+DROP TRIGGER IF EXISTS set_last_updated ON public.notification_preference;
+CREATE TRIGGER set_last_updated
+    BEFORE UPDATE ON public.notification_preference
+    FOR EACH ROW EXECUTE FUNCTION update_last_updated_column();
+
+CREATE TABLE IF NOT EXISTS public.notification_mute (
+    user_id INTEGER NOT NULL REFERENCES public.user(id) ON DELETE CASCADE,
+    novel_id INTEGER NOT NULL REFERENCES public.novel(id) ON DELETE CASCADE,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (user_id, novel_id)
+);
+
+CREATE TABLE IF NOT EXISTS public.group_follow (
+    user_id INTEGER NOT NULL REFERENCES public.user(id) ON DELETE CASCADE,
+    group_id INTEGER NOT NULL REFERENCES public.group(id) ON DELETE CASCADE,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (user_id, group_id)
+);
+CREATE INDEX IF NOT EXISTS idx_group_follow_group_id ON public.group_follow(group_id);
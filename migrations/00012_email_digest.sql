@@ -0,0 +1,6 @@
+ALTER TABLE public.notification_preference ADD COLUMN IF NOT EXISTS digest_frequency VARCHAR NOT NULL DEFAULT 'never';
+ALTER TABLE public.notification_preference ADD COLUMN IF NOT EXISTS digest_sent_at TIMESTAMP;
+ALTER TABLE public.notification_preference ADD COLUMN IF NOT EXISTS unsubscribe_token VARCHAR UNIQUE;
+
+CREATE INDEX IF NOT EXISTS idx_notification_preference_digest ON public.notification_preference(digest_sent_at)
+    WHERE digest_frequency <> 'never';
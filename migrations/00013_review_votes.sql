@@ -0,0 +1,38 @@
+CREATE TABLE IF NOT EXISTS public.review_vote (
+    user_id INTEGER NOT NULL REFERENCES public.user(id) ON DELETE CASCADE,
+    review_id INTEGER NOT NULL REFERENCES public.review(id) ON DELETE CASCADE,
+    helpful BOOLEAN NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (user_id, review_id)
+);
+CREATE INDEX IF NOT EXISTS idx_review_vote_review_id ON public.review_vote(review_id);
+
+-- The counts are derived from review_vote from now on; earlier hand-entered values cannot be
+-- attributed to anyone and are dropped.
+UPDATE public.review SET helpful_count = 0;
+ALTER TABLE public.review ALTER COLUMN helpful_count SET DEFAULT 0;
+ALTER TABLE public.review ALTER COLUMN helpful_count SET NOT NULL;
+ALTER TABLE public.review ADD COLUMN IF NOT EXISTS unhelpful_count INTEGER NOT NULL DEFAULT 0;
+
+CREATE OR REPLACE FUNCTION update_review_vote_counts()
+RETURNS TRIGGER AS $$
+DECLARE
+    target INTEGER;
+BEGIN
+    IF TG_OP = 'DELETE' THEN
+        target := OLD.review_id;
+    ELSE
+        target := NEW.review_id;
+    END IF;
+    UPDATE public.review SET
+        helpful_count = (SELECT COUNT(*) FROM public.review_vote v WHERE v.review_id = target AND v.helpful),
+        unhelpful_count = (SELECT COUNT(*) FROM public.review_vote v WHERE v.review_id = target AND NOT v.helpful)
+    WHERE id = target;
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS update_review_vote_counts ON public.review_vote;
+CREATE TRIGGER update_review_vote_counts
+    AFTER INSERT OR UPDATE OR DELETE ON public.review_vote
+    FOR EACH ROW EXECUTE FUNCTION update_review_vote_counts();
@@ -0,0 +1,47 @@
+CREATE TABLE IF NOT EXISTS public.comment (
+    id SERIAL PRIMARY KEY,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    last_updated TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+    ,
+    user_id INTEGER REFERENCES public.user(id) ON DELETE SET NULL
+    ,
+    review_id INTEGER REFERENCES public.review(id) ON DELETE CASCADE
+    ,
+    chapter_id INTEGER REFERENCES public.chapter(id) ON DELETE CASCADE
+    ,
+    parent_id INTEGER REFERENCES public.comment(id) ON DELETE CASCADE
+    ,
+    root_id INTEGER REFERENCES public.comment(id) ON DELETE CASCADE
+    ,
+    content VARCHAR NOT NULL
+    ,
+    spoiler BOOLEAN NOT NULL DEFAULT FALSE
+    ,
+    edited_at TIMESTAMP
+    ,
+    deleted_at TIMESTAMP
+    ,
+    CHECK ((review_id IS NULL) <> (chapter_id IS NULL))
+    );
+
+DROP TRIGGER IF EXISTS set_last_updated ON public.comment;
+CREATE TRIGGER set_last_updated
+    BEFORE UPDATE ON public.comment
+    FOR EACH ROW EXECUTE FUNCTION update_last_updated_column();
+
+CREATE INDEX IF NOT EXISTS idx_comment_review_roots ON public.comment(review_id, created_at) WHERE parent_id IS NULL;
+CREATE INDEX IF NOT EXISTS idx_comment_chapter_roots ON public.comment(chapter_id, created_at) WHERE parent_id IS NULL;
+CREATE INDEX IF NOT EXISTS idx_comment_root_id ON public.comment(root_id);
+
+CREATE TABLE IF NOT EXISTS public.comment_revision (
+    id SERIAL PRIMARY KEY,
+    comment_id INTEGER NOT NULL REFERENCES public.comment(id) ON DELETE CASCADE,
+    content VARCHAR NOT NULL,
+    spoiler BOOLEAN NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+CREATE INDEX IF NOT EXISTS idx_comment_revision_comment_id ON public.comment_revision(comment_id);
+
+-- Replies to a review notify its author.
+ALTER TABLE public.notification ADD COLUMN IF NOT EXISTS review_id INTEGER REFERENCES public.review(id) ON DELETE CASCADE;
+ALTER TABLE public.notification_preference ADD COLUMN IF NOT EXISTS review_reply BOOLEAN NOT NULL DEFAULT TRUE;
@@ -0,0 +1,54 @@
+ALTER TABLE public.review ADD COLUMN IF NOT EXISTS hidden_at TIMESTAMP;
+ALTER TABLE public.user ADD COLUMN IF NOT EXISTS banned_at TIMESTAMP;
+
+CREATE TABLE IF NOT EXISTS public.report (
+    id SERIAL PRIMARY KEY,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+    ,
+    last_updated TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+    ,
+    reporter_id INTEGER REFERENCES public.user(id) ON DELETE SET NULL
+    ,
+    target_type VARCHAR NOT NULL
+    ,
+    target_id INTEGER NOT NULL
+    ,
+    reason VARCHAR NOT NULL
+    ,
+    details TEXT
+    ,
+    status VARCHAR NOT NULL DEFAULT 'open'
+    ,
+    resolved_by INTEGER REFERENCES public.user(id) ON DELETE SET NULL
+    ,
+    resolved_at TIMESTAMP
+    );
+CREATE INDEX IF NOT EXISTS idx_report_status_created_at ON public.report(status, created_at);
+CREATE INDEX IF NOT EXISTS idx_report_target ON public.report(target_type, target_id);
+-- A user can only have one open report per target.
+CREATE UNIQUE INDEX IF NOT EXISTS idx_report_open_per_reporter ON public.report(reporter_id, target_type, target_id) WHERE status = 'open';
+
+DROP TRIGGER IF EXISTS set_last_updated ON public.report;
+CREATE TRIGGER set_last_updated
+    BEFORE UPDATE ON public.report
+    FOR EACH ROW EXECUTE FUNCTION update_last_updated_column();
+
+-- Kept after the targets and reports are gone, so nothing here cascades.
+CREATE TABLE IF NOT EXISTS public.moderation_log (
+    id SERIAL PRIMARY KEY,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+    ,
+    moderator_id INTEGER REFERENCES public.user(id) ON DELETE SET NULL
+    ,
+    action VARCHAR NOT NULL
+    ,
+    target_type VARCHAR NOT NULL
+    ,
+    target_id INTEGER NOT NULL
+    ,
+    report_id INTEGER
+    ,
+    note TEXT
+    );
+CREATE INDEX IF NOT EXISTS idx_moderation_log_created_at ON public.moderation_log(created_at DESC);
+CREATE INDEX IF NOT EXISTS idx_moderation_log_target ON public.moderation_log(target_type, target_id);
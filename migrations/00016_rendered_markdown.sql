@@ -0,0 +1,7 @@
+-- Sanitized HTML rendered from the Markdown source next to it. Existing rows are rendered by the
+-- server at startup.
+ALTER TABLE public.author ADD COLUMN IF NOT EXISTS bio_html TEXT;
+ALTER TABLE public.group ADD COLUMN IF NOT EXISTS description_html TEXT;
+ALTER TABLE public.novel ADD COLUMN IF NOT EXISTS description_html TEXT;
+ALTER TABLE public.review ADD COLUMN IF NOT EXISTS content_html TEXT;
+ALTER TABLE public.user ADD COLUMN IF NOT EXISTS bio_html TEXT;
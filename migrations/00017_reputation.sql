@@ -0,0 +1,10 @@
+-- The user whose content or account a moderation action was taken against, kept even after
+-- the content itself is deleted. Dismissals have none.
+ALTER TABLE public.moderation_log ADD COLUMN IF NOT EXISTS offender_id INTEGER REFERENCES public.user(id) ON DELETE SET NULL;
+UPDATE public.moderation_log l SET offender_id = CASE l.target_type
+        WHEN 'user' THEN l.target_id
+        WHEN 'review' THEN (SELECT r.user_id FROM public.review r WHERE r.id = l.target_id)
+        WHEN 'comment' THEN (SELECT c.user_id FROM public.comment c WHERE c.id = l.target_id)
+    END
+    WHERE l.action <> 'dismiss' AND l.offender_id IS NULL;
+CREATE INDEX IF NOT EXISTS idx_moderation_log_offender_id ON public.moderation_log(offender_id) WHERE offender_id IS NOT NULL;
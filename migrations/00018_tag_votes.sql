@@ -0,0 +1,41 @@
+ALTER TABLE public.novel_tag ADD COLUMN IF NOT EXISTS created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP;
+ALTER TABLE public.novel_tag ADD COLUMN IF NOT EXISTS proposed_by INTEGER REFERENCES public.user(id) ON DELETE SET NULL;
+ALTER TABLE public.novel_tag ADD COLUMN IF NOT EXISTS upvotes INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE public.novel_tag ADD COLUMN IF NOT EXISTS downvotes INTEGER NOT NULL DEFAULT 0;
+
+CREATE TABLE IF NOT EXISTS public.novel_tag_vote (
+    user_id INTEGER NOT NULL REFERENCES public.user(id) ON DELETE CASCADE,
+    novel_id INTEGER NOT NULL,
+    tag_id INTEGER NOT NULL,
+    up BOOLEAN NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (user_id, novel_id, tag_id),
+    FOREIGN KEY (novel_id, tag_id) REFERENCES public.novel_tag(novel_id, tag_id) ON DELETE CASCADE
+);
+CREATE INDEX IF NOT EXISTS idx_novel_tag_vote_novel_id_tag_id ON public.novel_tag_vote(novel_id, tag_id);
+
+CREATE OR REPLACE FUNCTION update_novel_tag_vote_counts()
+RETURNS TRIGGER AS $$
+DECLARE
+    target_novel INTEGER;
+    target_tag INTEGER;
+BEGIN
+    IF TG_OP = 'DELETE' THEN
+        target_novel := OLD.novel_id;
+        target_tag := OLD.tag_id;
+    ELSE
+        target_novel := NEW.novel_id;
+        target_tag := NEW.tag_id;
+    END IF;
+    UPDATE public.novel_tag SET
+        upvotes = (SELECT COUNT(*) FROM public.novel_tag_vote v WHERE v.novel_id = target_novel AND v.tag_id = target_tag AND v.up),
+        downvotes = (SELECT COUNT(*) FROM public.novel_tag_vote v WHERE v.novel_id = target_novel AND v.tag_id = target_tag AND NOT v.up)
+    WHERE novel_id = target_novel AND tag_id = target_tag;
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS update_novel_tag_vote_counts ON public.novel_tag_vote;
+CREATE TRIGGER update_novel_tag_vote_counts
+    AFTER INSERT OR UPDATE OR DELETE ON public.novel_tag_vote
+    FOR EACH ROW EXECUTE FUNCTION update_novel_tag_vote_counts();
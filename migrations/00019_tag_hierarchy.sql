@@ -0,0 +1,13 @@
+ALTER TABLE public.tag ADD COLUMN IF NOT EXISTS parent_id INTEGER REFERENCES public.tag(id) ON DELETE SET NULL;
+CREATE INDEX IF NOT EXISTS idx_tag_parent_id ON public.tag(parent_id) WHERE parent_id IS NOT NULL;
+
+CREATE TABLE IF NOT EXISTS public.tag_alias (
+    id SERIAL PRIMARY KEY,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+    ,
+    tag_id INTEGER NOT NULL REFERENCES public.tag(id) ON DELETE CASCADE
+    ,
+    name VARCHAR NOT NULL
+    );
+CREATE INDEX IF NOT EXISTS idx_tag_alias_tag_id ON public.tag_alias(tag_id);
+CREATE UNIQUE INDEX IF NOT EXISTS idx_tag_alias_name ON public.tag_alias(lower(name));
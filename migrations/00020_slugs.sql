@@ -0,0 +1,26 @@
+-- URL slugs generated from names. Existing rows get theirs from the server at startup, which
+-- transliterates Unicode names; Postgres has no portable way to do that.
+ALTER TABLE public.artist ADD COLUMN IF NOT EXISTS slug VARCHAR;
+ALTER TABLE public.author ADD COLUMN IF NOT EXISTS slug VARCHAR;
+ALTER TABLE public.group ADD COLUMN IF NOT EXISTS slug VARCHAR;
+ALTER TABLE public.novel ADD COLUMN IF NOT EXISTS slug VARCHAR;
+ALTER TABLE public.publisher ADD COLUMN IF NOT EXISTS slug VARCHAR;
+CREATE UNIQUE INDEX IF NOT EXISTS idx_artist_slug ON public.artist(slug);
+CREATE UNIQUE INDEX IF NOT EXISTS idx_author_slug ON public.author(slug);
+CREATE UNIQUE INDEX IF NOT EXISTS idx_group_slug ON public.group(slug);
+CREATE UNIQUE INDEX IF NOT EXISTS idx_novel_slug ON public.novel(slug);
+CREATE UNIQUE INDEX IF NOT EXISTS idx_publisher_slug ON public.publisher(slug);
+
+-- Slugs a row used to have, so old links keep resolving after a rename.
+CREATE TABLE IF NOT EXISTS public.slug_history (
+    id SERIAL PRIMARY KEY,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+    ,
+    resource VARCHAR NOT NULL
+    ,
+    slug VARCHAR NOT NULL
+    ,
+    target_id INTEGER NOT NULL
+    );
+CREATE UNIQUE INDEX IF NOT EXISTS idx_slug_history_resource_slug ON public.slug_history(resource, slug);
+CREATE INDEX IF NOT EXISTS idx_slug_history_target ON public.slug_history(resource, target_id);
@@ -0,0 +1,91 @@
+-- Genres move from the comma-separated novel.genres column to tags of the genre category, linked
+-- through novel_tag. The column stays as a read-only list derived from those tags. Tags created
+-- here have no slug yet; the server generates them at startup.
+WITH names AS (
+    SELECT DISTINCT ON (lower(trim(g.name))) trim(g.name) AS name
+    FROM public.novel n
+    CROSS JOIN LATERAL regexp_split_to_table(n.genres, ',') AS g(name)
+    WHERE trim(g.name) <> ''
+    ORDER BY lower(trim(g.name)), trim(g.name)
+)
+INSERT INTO public.tag (category, name)
+SELECT 'genre', names.name FROM names
+WHERE NOT EXISTS (
+    SELECT 1 FROM public.tag t
+    WHERE t.category = 'genre' AND lower(t.name) = lower(names.name)
+)
+AND NOT EXISTS (
+    SELECT 1 FROM public.tag_alias a JOIN public.tag t ON t.id = a.tag_id
+    WHERE t.category = 'genre' AND lower(a.name) = lower(names.name)
+);
+
+INSERT INTO public.novel_tag (novel_id, tag_id)
+SELECT DISTINCT n.id, t.id
+FROM public.novel n
+CROSS JOIN LATERAL regexp_split_to_table(n.genres, ',') AS g(name)
+JOIN public.tag t ON t.category = 'genre' AND (
+    lower(t.name) = lower(trim(g.name))
+    OR EXISTS (SELECT 1 FROM public.tag_alias a WHERE a.tag_id = t.id AND lower(a.name) = lower(trim(g.name)))
+)
+ON CONFLICT (novel_id, tag_id) DO NOTHING;
+
+CREATE OR REPLACE FUNCTION novel_genres(target_novel INTEGER)
+RETURNS VARCHAR AS $$
+    SELECT string_agg(t.name, ', ' ORDER BY t.name)
+    FROM public.novel_tag nt
+    JOIN public.tag t ON t.id = nt.tag_id
+    WHERE nt.novel_id = target_novel AND t.category = 'genre'
+$$ LANGUAGE sql STABLE;
+
+-- Whatever a client writes to novel.genres is replaced by the derived value.
+CREATE OR REPLACE FUNCTION derive_novel_genres()
+RETURNS TRIGGER AS $$
+BEGIN
+    NEW.genres := novel_genres(NEW.id);
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS derive_genres ON public.novel;
+CREATE TRIGGER derive_genres
+    BEFORE INSERT OR UPDATE OF genres ON public.novel
+    FOR EACH ROW EXECUTE FUNCTION derive_novel_genres();
+
+CREATE OR REPLACE FUNCTION refresh_novel_genres()
+RETURNS TRIGGER AS $$
+DECLARE
+    target_novel INTEGER;
+BEGIN
+    IF TG_OP = 'DELETE' THEN
+        target_novel := OLD.novel_id;
+    ELSE
+        target_novel := NEW.novel_id;
+    END IF;
+    UPDATE public.novel SET genres = NULL
+    WHERE id = target_novel AND genres IS DISTINCT FROM novel_genres(target_novel);
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS refresh_genres ON public.novel_tag;
+CREATE TRIGGER refresh_genres
+    AFTER INSERT OR DELETE ON public.novel_tag
+    FOR EACH ROW EXECUTE FUNCTION refresh_novel_genres();
+
+-- Renaming a tag or moving it in or out of the genre category changes every novel it is on.
+CREATE OR REPLACE FUNCTION refresh_tag_novel_genres()
+RETURNS TRIGGER AS $$
+BEGIN
+    UPDATE public.novel SET genres = NULL
+    WHERE id IN (SELECT novel_id FROM public.novel_tag WHERE tag_id = NEW.id)
+    AND genres IS DISTINCT FROM novel_genres(id);
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS refresh_novel_genres ON public.tag;
+CREATE TRIGGER refresh_novel_genres
+    AFTER UPDATE OF name, category ON public.tag
+    FOR EACH ROW EXECUTE FUNCTION refresh_tag_novel_genres();
+
+UPDATE public.novel SET genres = NULL WHERE genres IS DISTINCT FROM novel_genres(id);
@@ -0,0 +1,18 @@
+-- Which roles must enroll in two-factor authentication before they get a usable session.
+-- Admins change it through /api/admin/two-factor-policy; a missing role is not required.
+CREATE TABLE IF NOT EXISTS public.two_factor_policy (
+    role VARCHAR PRIMARY KEY,
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    last_updated TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+    ,
+    required BOOLEAN NOT NULL DEFAULT FALSE
+    );
+
+DROP TRIGGER IF EXISTS set_last_updated ON public.two_factor_policy;
+CREATE TRIGGER set_last_updated
+    BEFORE UPDATE ON public.two_factor_policy
+    FOR EACH ROW EXECUTE FUNCTION update_last_updated_column();
+
+INSERT INTO public.two_factor_policy (role, required)
+VALUES ('user', FALSE), ('moderator', TRUE), ('admin', TRUE)
+ON CONFLICT (role) DO NOTHING;
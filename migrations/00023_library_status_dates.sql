@@ -0,0 +1,30 @@
+-- When a library entry last became completed or dropped, so reading stats can date them. The
+-- columns keep their value when the status moves on, like a finished novel being re-read.
+ALTER TABLE public.library_entry ADD COLUMN IF NOT EXISTS completed_at TIMESTAMP;
+ALTER TABLE public.library_entry ADD COLUMN IF NOT EXISTS dropped_at TIMESTAMP;
+
+-- Entries that are completed or dropped already are dated by their last update, the best guess left.
+UPDATE public.library_entry SET completed_at = last_updated WHERE status = 'completed' AND completed_at IS NULL;
+UPDATE public.library_entry SET dropped_at = last_updated WHERE status = 'dropped' AND dropped_at IS NULL;
+
+CREATE OR REPLACE FUNCTION stamp_library_status()
+RETURNS TRIGGER AS $$
+BEGIN
+    IF TG_OP = 'INSERT' OR NEW.status IS DISTINCT FROM OLD.status THEN
+        IF NEW.status = 'completed' THEN
+            NEW.completed_at := CURRENT_TIMESTAMP;
+        ELSIF NEW.status = 'dropped' THEN
+            NEW.dropped_at := CURRENT_TIMESTAMP;
+        END IF;
+    END IF;
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS stamp_status ON public.library_entry;
+CREATE TRIGGER stamp_status
+    BEFORE INSERT OR UPDATE OF status ON public.library_entry
+    FOR EACH ROW EXECUTE FUNCTION stamp_library_status();
+
+CREATE INDEX IF NOT EXISTS idx_library_entry_user_completed_at ON public.library_entry(user_id, completed_at) WHERE completed_at IS NOT NULL;
+CREATE INDEX IF NOT EXISTS idx_library_entry_user_dropped_at ON public.library_entry(user_id, dropped_at) WHERE dropped_at IS NOT NULL;
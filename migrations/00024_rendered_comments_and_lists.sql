@@ -0,0 +1,5 @@
+-- Rendered HTML for comments and for reading list descriptions and blurbs, like 00016. Existing
+-- rows are rendered by the server at startup.
+ALTER TABLE public.comment ADD COLUMN IF NOT EXISTS content_html TEXT;
+ALTER TABLE public.reading_list ADD COLUMN IF NOT EXISTS description_html TEXT;
+ALTER TABLE public.novel_reading_list ADD COLUMN IF NOT EXISTS blurb_html TEXT;
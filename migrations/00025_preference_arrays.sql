@@ -0,0 +1,15 @@
+-- Content preference lists become arrays; as whitespace separated strings a language such as
+-- "Brazilian Portuguese" could not be stored.
+ALTER TABLE public.user_preference
+    ALTER COLUMN excluded_tag_ids DROP DEFAULT,
+    ALTER COLUMN excluded_tag_ids TYPE INTEGER[]
+        USING COALESCE(regexp_split_to_array(NULLIF(trim(excluded_tag_ids), ''), '\s+')::INTEGER[], '{}'),
+    ALTER COLUMN excluded_tag_ids SET DEFAULT '{}',
+    ALTER COLUMN original_languages DROP DEFAULT,
+    ALTER COLUMN original_languages TYPE TEXT[]
+        USING COALESCE(regexp_split_to_array(NULLIF(trim(original_languages), ''), '\s+'), '{}'),
+    ALTER COLUMN original_languages SET DEFAULT '{}',
+    ALTER COLUMN chapter_languages DROP DEFAULT,
+    ALTER COLUMN chapter_languages TYPE TEXT[]
+        USING COALESCE(regexp_split_to_array(NULLIF(trim(chapter_languages), ''), '\s+'), '{}'),
+    ALTER COLUMN chapter_languages SET DEFAULT '{}';
@@ -1,6 +1,12 @@
 use sea_orm::DatabaseConnection;
+use std::sync::Arc;
+use crate::services::{mail::MailSender, rate_limit::RateLimiter};
 
 #[derive(Clone)]
 pub struct AppState {
     pub db: DatabaseConnection,
-}
\ No newline at end of file
+    pub rate_limiter: RateLimiter,
+    pub mailer: Arc<dyn MailSender>,
+    /// Public base URL used to build links in outgoing mail.
+    pub app_url: String,
+}
@@ -0,0 +1,154 @@
+use serde_json::json;
+use axum::{Router, extract::{Path, State}, http::StatusCode, routing::{delete, get, post}, response::IntoResponse, Json};
+use serde::{Deserialize, Serialize};
+use sea_orm::{ActiveModelTrait, EntityTrait, QueryFilter, QueryOrder, Set, IntoActiveModel, ConnectionTrait};
+use sea_orm::prelude::*;
+use crate::app_state::AppState;
+use crate::models::api_key::{ActiveModel, Column, Entity, Model};
+use crate::services::auth::{self, Principal};
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct ApiKey {
+    pub id: i32,
+    pub created_at: DateTime,
+    pub last_updated: DateTime,
+    pub name: String,
+    pub key_prefix: String,
+    pub scopes: Vec<String>,
+    pub user_id: Option<i32>,
+    pub group_id: Option<i32>,
+    pub expires_at: Option<DateTime>,
+    pub last_used_at: Option<DateTime>,
+    pub revoked_at: Option<DateTime>
+
+}
+
+impl From<Model> for ApiKey {
+    fn from(model: Model) -> Self {
+        Self {
+            id: model.id,
+            created_at: model.created_at,
+            last_updated: model.last_updated,
+            name: model.name.clone(),
+            key_prefix: model.key_prefix.clone(),
+            scopes: model.scope_list(),
+            user_id: model.user_id,
+            group_id: model.group_id,
+            expires_at: model.expires_at,
+            last_used_at: model.last_used_at,
+            revoked_at: model.revoked_at
+
+        }
+    }
+}
+
+/// Returned once on creation; the plaintext `key` cannot be retrieved again.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ApiKeyCreated {
+    #[serde(flatten)]
+    pub api_key: ApiKey,
+    pub key: String,
+}
+
+/// The new key belongs to the caller's user or group and can grant no more than the caller's own key.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ApiKeyCreate {
+    pub name: String,
+    pub scopes: Vec<String>,
+    pub expires_at: Option<DateTime>
+
+}
+
+async fn load_item<C>(
+    db: &C,
+    id: i32,
+) -> Result<Model, (StatusCode, Json<serde_json::Value>)>
+where
+    C: ConnectionTrait,
+{
+    Entity::find_by_id(id)
+        .one(db)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"error": e.to_string()}))))?
+        .ok_or_else(|| (StatusCode::NOT_FOUND, Json(json!({"error": "not found"}))))
+}
+
+/// A caller may only manage keys belonging to its own user or group.
+fn check_owner(principal: &Principal, user_id: Option<i32>, group_id: Option<i32>) -> Result<(), (StatusCode, Json<serde_json::Value>)> {
+    let owns_user = principal.user_id.is_some() && principal.user_id == user_id;
+    let owns_group = principal.group_id.is_some() && principal.group_id == group_id;
+    if owns_user || owns_group {
+        Ok(())
+    } else {
+        Err((StatusCode::FORBIDDEN, Json(json!({"error": "api key belongs to another owner"}))))
+    }
+}
+
+pub async fn list(state: State<AppState>, principal: Principal) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let mut query = Entity::find().filter(Column::Session.eq(false)).order_by_asc(Column::Id);
+    if let Some(user_id) = principal.user_id {
+        query = query.filter(Column::UserId.eq(user_id));
+    }
+    if let Some(group_id) = principal.group_id {
+        query = query.filter(Column::GroupId.eq(group_id));
+    }
+    let models = query
+        .all(&state.db)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"error": e.to_string()}))))?;
+    let responses: Vec<ApiKey> = models.into_iter().map(Into::into).collect();
+
+    Ok(Json(responses))
+}
+
+pub async fn create(state: State<AppState>, principal: Principal, Json(create): Json<ApiKeyCreate>) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    auth::validate_scopes(&create.scopes)?;
+    if let Some(scope) = create.scopes.iter().find(|scope| !principal.covers(scope)) {
+        return Err((StatusCode::FORBIDDEN, Json(json!({"error": format!("cannot grant scope {} beyond your own", scope)}))));
+    }
+    let key = auth::generate_key();
+    let active_model = ActiveModel {
+        name: Set(create.name),
+        key_prefix: Set(auth::display_prefix(&key)),
+        key_hash: Set(auth::hash_key(&key)),
+        scopes: Set(create.scopes.join(" ")),
+        user_id: Set(principal.user_id),
+        group_id: Set(principal.group_id),
+        expires_at: Set(create.expires_at),
+        ..Default::default()
+    };
+    let model = active_model.insert(&state.db)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"failed to insert item": e.to_string()}))))?;
+    let resp = ApiKeyCreated { api_key: model.into(), key };
+    Ok((StatusCode::CREATED, Json(resp)))
+}
+
+pub async fn read_one(state: State<AppState>, principal: Principal, Path(id): Path<i32>) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let model = load_item(&state.db, id).await?;
+    check_owner(&principal, model.user_id, model.group_id)?;
+    let resp: ApiKey = model.into();
+    Ok(Json(resp))
+}
+
+/// Revoking keeps the row so `last_used_at` stays available for auditing.
+pub async fn revoke(state: State<AppState>, principal: Principal, Path(id): Path<i32>) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let model = load_item(&state.db, id).await?;
+    check_owner(&principal, model.user_id, model.group_id)?;
+    if model.revoked_at.is_none() {
+        let mut active_model = model.into_active_model();
+        active_model.revoked_at = Set(Some(chrono::Utc::now().naive_utc()));
+        active_model.update(&state.db)
+            .await
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"failed to update item": e.to_string()}))))?;
+    }
+    Ok(StatusCode::NO_CONTENT)
+}
+
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/api-keys", get(list))
+        .route("/api-keys", post(create))
+        .route("/api-keys/{id}", get(read_one))
+        .route("/api-keys/{id}", delete(revoke))
+}
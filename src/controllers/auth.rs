@@ -0,0 +1,250 @@
+use serde_json::json;
+use axum::{Router, extract::State, http::StatusCode, routing::post, response::IntoResponse, Json};
+use serde::{Deserialize, Serialize};
+use sea_orm::{ActiveModelTrait, EntityTrait, QueryFilter, Set, IntoActiveModel, ConnectionTrait, ExprTrait, TransactionTrait};
+use sea_orm::prelude::*;
+use crate::app_state::AppState;
+use crate::models::user::{self, ActiveModel, Column, Entity};
+use crate::models::{api_key, user_token::Purpose};
+use crate::services::{account, account_data, auth::{self, Principal}, password, totp};
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Register {
+    pub username: String,
+    pub email: String,
+    pub password: String,
+    pub display_name: Option<String>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Registered {
+    pub id: i32,
+    pub username: String,
+    pub email: String,
+    pub email_verified: bool,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct TokenConfirm {
+    pub token: String,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct EmailRequest {
+    pub email: String,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PasswordResetConfirm {
+    pub token: String,
+    pub password: String,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Login {
+    /// Username or email.
+    pub login: String,
+    pub password: String,
+    pub totp_code: Option<String>,
+    pub recovery_code: Option<String>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Session {
+    pub token: String,
+    pub user_id: i32,
+    pub expires_at: Option<DateTime>,
+    pub scopes: Vec<String>,
+    /// Set when the user's role requires 2FA that is not set up yet; the session can then
+    /// only be used for the `/auth/2fa` endpoints.
+    pub two_factor_enrollment_required: bool,
+}
+
+fn db_error(e: DbErr) -> (StatusCode, Json<serde_json::Value>) {
+    (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"error": e.to_string()})))
+}
+
+fn check_password(password: &str) -> Result<(), (StatusCode, Json<serde_json::Value>)> {
+    if password.chars().count() < password::MIN_LENGTH {
+        return Err((StatusCode::UNPROCESSABLE_ENTITY, Json(json!({"error": format!("password must be at least {} characters", password::MIN_LENGTH)}))));
+    }
+    Ok(())
+}
+
+async fn find_by_email<C>(db: &C, email: &str) -> Result<Option<user::Model>, (StatusCode, Json<serde_json::Value>)>
+where
+    C: ConnectionTrait,
+{
+    Entity::find()
+        .filter(Column::Email.eq(email.trim()))
+        .one(db)
+        .await
+        .map_err(db_error)
+}
+
+pub async fn register(state: State<AppState>, Json(register): Json<Register>) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    check_password(&register.password)?;
+    let username = register.username.trim().to_string();
+    let email = register.email.trim().to_string();
+    if username.is_empty() || !email.contains('@') {
+        return Err((StatusCode::UNPROCESSABLE_ENTITY, Json(json!({"error": "a username and a valid email are required"}))));
+    }
+    let taken = Entity::find()
+        .filter(Column::Username.eq(username.as_str()).or(Column::Email.eq(email.as_str())))
+        .one(&state.db)
+        .await
+        .map_err(db_error)?;
+    if taken.is_some() {
+        return Err((StatusCode::CONFLICT, Json(json!({"error": "username or email already registered"}))));
+    }
+    let password_hash = password::hash(&register.password)
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"error": e.to_string()}))))?;
+    let active_model = ActiveModel {
+        username: Set(username),
+        email: Set(email),
+        display_name: Set(register.display_name),
+        password_hash: Set(password_hash),
+        ..Default::default()
+    };
+    let model = active_model.insert(&state.db)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"failed to insert item": e.to_string()}))))?;
+    account::send_verification(&state, &model).await.map_err(db_error)?;
+    let resp = Registered { id: model.id, username: model.username, email: model.email, email_verified: false };
+    Ok((StatusCode::CREATED, Json(resp)))
+}
+
+pub async fn verify_email(state: State<AppState>, Json(confirm): Json<TokenConfirm>) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let user_id = account::consume_token(&state.db, &confirm.token, Purpose::EmailVerification)
+        .await
+        .map_err(db_error)?
+        .ok_or_else(|| (StatusCode::BAD_REQUEST, Json(json!({"error": "invalid or expired token"}))))?;
+    let model = Entity::find_by_id(user_id)
+        .one(&state.db)
+        .await
+        .map_err(db_error)?
+        .ok_or_else(|| (StatusCode::NOT_FOUND, Json(json!({"error": "not found"}))))?;
+    let mut active_model = model.into_active_model();
+    active_model.email_verified_at = Set(Some(chrono::Utc::now().naive_utc()));
+    active_model.update(&state.db)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"failed to update item": e.to_string()}))))?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Always answers 202 so the endpoint cannot be used to probe which emails are registered.
+pub async fn resend_verification(state: State<AppState>, Json(request): Json<EmailRequest>) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    if let Some(model) = find_by_email(&state.db, &request.email).await?
+        && model.email_verified_at.is_none() {
+        account::send_verification(&state, &model).await.map_err(db_error)?;
+    }
+    Ok(StatusCode::ACCEPTED)
+}
+
+/// Always answers 202 so the endpoint cannot be used to probe which emails are registered.
+pub async fn request_password_reset(state: State<AppState>, Json(request): Json<EmailRequest>) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    if let Some(model) = find_by_email(&state.db, &request.email).await? {
+        account::send_password_reset(&state, &model).await.map_err(db_error)?;
+    }
+    Ok(StatusCode::ACCEPTED)
+}
+
+/// A new password revokes every session and API key, so whoever knew the old one is signed out.
+pub async fn confirm_password_reset(state: State<AppState>, Json(confirm): Json<PasswordResetConfirm>) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    check_password(&confirm.password)?;
+    let password_hash = password::hash(&confirm.password)
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"error": e.to_string()}))))?;
+    let txn = state.db.begin().await.map_err(db_error)?;
+    let user_id = account::consume_token(&txn, &confirm.token, Purpose::PasswordReset)
+        .await
+        .map_err(db_error)?
+        .ok_or_else(|| (StatusCode::BAD_REQUEST, Json(json!({"error": "invalid or expired token"}))))?;
+    let model = Entity::find_by_id(user_id)
+        .one(&txn)
+        .await
+        .map_err(db_error)?
+        .ok_or_else(|| (StatusCode::NOT_FOUND, Json(json!({"error": "not found"}))))?;
+    let verified = model.email_verified_at.is_some();
+    let mut active_model = model.into_active_model();
+    active_model.password_hash = Set(password_hash);
+    // Receiving the reset mail proves ownership of the address as well.
+    if !verified {
+        active_model.email_verified_at = Set(Some(chrono::Utc::now().naive_utc()));
+    }
+    active_model.update(&txn)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"failed to update item": e.to_string()}))))?;
+    account::revoke_tokens(&txn, user_id, Purpose::PasswordReset).await.map_err(db_error)?;
+    auth::revoke_user_keys(&txn, user_id).await.map_err(db_error)?;
+    txn.commit().await.map_err(db_error)?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+pub async fn login(state: State<AppState>, Json(login): Json<Login>) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let login_name = login.login.trim();
+    let model = Entity::find()
+        .filter(Column::Username.eq(login_name).or(Column::Email.eq(login_name)))
+        .one(&state.db)
+        .await
+        .map_err(db_error)?
+        .filter(|model| password::verify(&login.password, &model.password_hash))
+        .ok_or_else(|| (StatusCode::UNAUTHORIZED, Json(json!({"error": "invalid credentials"}))))?;
+    if model.banned_at.is_some() {
+        return Err((StatusCode::FORBIDDEN, Json(json!({"error": "account is banned"}))));
+    }
+    if model.totp_enabled_at.is_some() {
+        if login.totp_code.is_none() && login.recovery_code.is_none() {
+            return Err((StatusCode::UNAUTHORIZED, Json(json!({"error": "two-factor code required", "two_factor_required": true}))));
+        }
+        let passed = account::check_second_factor(&state.db, &model, login.totp_code.as_deref(), login.recovery_code.as_deref())
+            .await
+            .map_err(db_error)?;
+        if !passed {
+            return Err((StatusCode::UNAUTHORIZED, Json(json!({"error": "invalid two-factor code", "two_factor_required": true}))));
+        }
+    }
+    let model = if model.deletion_scheduled_for.is_some() {
+        account_data::cancel_deletion(&state.db, model).await.map_err(db_error)?
+    } else {
+        model
+    };
+    let enrollment_required = model.totp_enabled_at.is_none() && totp::required_for(&state.db, model.role).await.map_err(db_error)?;
+    let scopes = if enrollment_required { vec![] } else { vec!["*".to_string()] };
+    let (token, session) = auth::issue_session(&state.db, model.id, scopes.clone()).await.map_err(db_error)?;
+    let resp = Session {
+        token,
+        user_id: model.id,
+        expires_at: session.expires_at,
+        scopes,
+        two_factor_enrollment_required: enrollment_required,
+    };
+    Ok(Json(resp))
+}
+
+pub async fn logout(state: State<AppState>, principal: Principal) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let model = api_key::Entity::find_by_id(principal.key_id)
+        .one(&state.db)
+        .await
+        .map_err(db_error)?
+        .ok_or_else(|| (StatusCode::NOT_FOUND, Json(json!({"error": "not found"}))))?;
+    if !model.session {
+        return Err((StatusCode::BAD_REQUEST, Json(json!({"error": "not a session; revoke api keys through /api-keys"}))));
+    }
+    let mut active_model = model.into_active_model();
+    active_model.revoked_at = Set(Some(chrono::Utc::now().naive_utc()));
+    active_model.update(&state.db)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"failed to update item": e.to_string()}))))?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/auth/login", post(login))
+        .route("/auth/logout", post(logout))
+        .route("/auth/register", post(register))
+        .route("/auth/verify-email", post(verify_email))
+        .route("/auth/verify-email/resend", post(resend_verification))
+        .route("/auth/password-reset", post(request_password_reset))
+        .route("/auth/password-reset/confirm", post(confirm_password_reset))
+}
@@ -1,10 +1,11 @@
 use serde_json::json;
-use axum::{Router, extract::{Path, State}, http::StatusCode, routing::{delete, get, patch, post, put}, response::IntoResponse, Json};
+use axum::{Router, extract::{Path, State}, http::{StatusCode, Uri}, routing::{delete, get, patch, post, put}, response::{IntoResponse, Response}, Json};
 use serde::{Deserialize, Serialize};
 use sea_orm::{ActiveModelTrait, ModelTrait, EntityTrait, Set, IntoActiveModel, ConnectionTrait};
 use sea_orm::EntityLoaderTrait;
 use sea_orm::prelude::*;
 use crate::app_state::AppState;
+use crate::services::{markdown, slugs::{self, Resource}};
 use crate::models::author::{ActiveModel, Entity, Model, ModelEx, };
 use super::{novel::Novel as Novel, };
 #[derive(Clone, Debug, Default, Deserialize, Serialize)]
@@ -13,10 +14,12 @@ pub struct Author {
     pub created_at: DateTime,
     pub last_updated: DateTime,
     pub bio: Option<String>,
+    pub bio_html: Option<String>,
     pub country: Option<String>,
     pub name: String,
     pub native_name: Option<String>,
     pub novels: Option<Vec<Novel>>,
+    pub slug: Option<String>,
     pub website: Option<String>
     
 }
@@ -28,10 +31,12 @@ impl From<Model> for Author {
             created_at: model.created_at,
             last_updated: model.last_updated,
             bio: model.bio,
+            bio_html: model.bio_html,
             country: model.country,
             name: model.name,
             native_name: model.native_name,
             novels: None,
+            slug: model.slug,
             website: model.website
             
         }
@@ -45,10 +50,12 @@ impl From<ModelEx> for Author {
             created_at: model.created_at,
             last_updated: model.last_updated,
             bio: model.bio,
+            bio_html: model.bio_html,
             country: model.country,
             name: model.name,
             native_name: model.native_name,
             novels: Some(model.novels.into_iter().map(Novel::from).collect()),
+            slug: model.slug,
             website: model.website,
             
         }
@@ -70,6 +77,7 @@ impl From<AuthorCreate> for ActiveModel {
     fn from(source: AuthorCreate) -> Self {
         ActiveModel {
             bio: Set(source.bio.clone()),
+            bio_html: Set(markdown::render_opt(source.bio.as_deref())),
             country: Set(source.country.clone()),
             name: Set(source.name.clone()),
             native_name: Set(source.native_name.clone()),
@@ -95,6 +103,7 @@ impl AuthorUpdate {
         ActiveModel {
             id: Set(id),
             bio: Set(self.bio.clone()),
+            bio_html: Set(markdown::render_opt(self.bio.as_deref())),
             country: Set(self.country.clone()),
             name: Set(self.name.clone()),
             native_name: Set(self.native_name.clone()),
@@ -119,6 +128,7 @@ impl AuthorPatch {
     pub fn patch_active_model(&self, active_model: &mut ActiveModel) {
         if self.bio.is_some() {
             active_model.bio = Set(self.bio.clone());
+            active_model.bio_html = Set(markdown::render_opt(self.bio.as_deref()));
         }if self.country.is_some() {
             active_model.country = Set(self.country.clone());
         }if let Some(value) = &self.name {
@@ -157,8 +167,13 @@ pub async fn list(state: State<AppState>) -> Result<impl IntoResponse, (StatusCo
 }
 
 pub async fn create(state: State<AppState>, Json(create): Json<AuthorCreate>) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let source = create.name.clone();
     let active_model:ActiveModel = create.into();
-    let model = active_model.insert(&state.db)
+    let model = slugs::insert_with(&state.db, Resource::Author, &source, |slug| {
+        let mut active_model = active_model.clone();
+        active_model.slug = Set(Some(slug));
+        active_model.insert(&state.db)
+    })
         .await
         .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"failed to insert item": e.to_string()}))))?;
         let resp: Author = model.into();
@@ -173,7 +188,10 @@ pub async fn patch_one(state: State<AppState>, Path(id): Path<i32>, Json(patch):
     let model = active_model.update(&state.db)
         .await
         .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"failed to update item": e.to_string()}))))?;
-    let resp: Author = model.into();
+    let slug = slugs::assign(&state.db, Resource::Author, model.id, &model.name)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"error": e.to_string()}))))?;
+    let resp: Author = Model { slug: Some(slug), ..model }.into();
     Ok(Json(resp))
 }
 
@@ -183,7 +201,10 @@ pub async fn put_one(state: State<AppState>, Path(id): Path<i32>, Json(update):
     let model = active_model.update(&state.db)
         .await
         .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"failed to update item": e.to_string()}))))?;
-    let resp: Author = model.into();
+    let slug = slugs::assign(&state.db, Resource::Author, model.id, &model.name)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"error": e.to_string()}))))?;
+    let resp: Author = Model { slug: Some(slug), ..model }.into();
     Ok(Json(resp))
 }
 
@@ -207,10 +228,17 @@ pub async fn read_one(state: State<AppState>, Path(id): Path<i32>) -> Result<imp
     Ok(Json(resp))
 }
 
+/// Serves the row a slug names. Old slugs redirect to the current one.
+pub async fn read_by_slug(state: State<AppState>, uri: Uri, Path(slug): Path<String>) -> Result<Response, (StatusCode, Json<serde_json::Value>)> {
+    let db = state.db.clone();
+    slugs::serve(&db, Resource::Author, &slug, &uri, |id| read_one(state, Path(id))).await
+}
+
 pub fn routes() -> Router<AppState> {
     Router::new()
         .route("/authors", get(list))
         .route("/authors", post(create))
+        .route("/authors/by-slug/{slug}", get(read_by_slug))
         .route("/authors/{id}", get(read_one))
         .route("/authors/{id}", delete(remove))
         .route("/authors/{id}", patch(patch_one))
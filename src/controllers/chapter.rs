@@ -1,10 +1,11 @@
 use serde_json::json;
-use axum::{Router, extract::{Path, State}, http::StatusCode, routing::{delete, get, patch, post, put}, response::IntoResponse, Json};
+use axum::{Router, extract::{Path, Query, State}, http::StatusCode, routing::{delete, get, patch, post, put}, response::IntoResponse, Json};
 use serde::{Deserialize, Serialize};
-use sea_orm::{ActiveModelTrait, ModelTrait, EntityTrait, Set, IntoActiveModel, ConnectionTrait};
+use sea_orm::{ActiveModelTrait, ModelTrait, EntityTrait, QueryFilter, Set, IntoActiveModel, ConnectionTrait};
 use sea_orm::EntityLoaderTrait;
 use sea_orm::prelude::*;
 use crate::app_state::AppState;
+use crate::services::{auth::Principal, notifications, preferences::{self, ListOptions}};
 use crate::models::chapter::{ActiveModel, Entity, Model, ModelEx, };
 use super::{novel::Novel as Novel, source::Source as Source, };
 #[derive(Clone, Debug, Default, Deserialize, Serialize)]
@@ -17,12 +18,12 @@ pub struct Chapter {
     pub language: String,
     pub novels: Option<Vec<Novel>>,
     pub number: String,
-    pub part: Option<i8>,
+    pub part: Option<i32>,
     pub release_date: Option<String>,
     pub source: Source,
     pub title: String,
-    pub views: Option<i8>,
-    pub volume: Option<i8>
+    pub views: Option<i32>,
+    pub volume: Option<i32>
     
 }
 
@@ -77,12 +78,12 @@ pub struct ChapterCreate {
     pub language: String,
     pub novels: Option<Vec<Novel>>,
     pub number: String,
-    pub part: Option<i8>,
+    pub part: Option<i32>,
     pub release_date: Option<String>,
     pub source: Source,
     pub title: String,
-    pub views: Option<i8>,
-    pub volume: Option<i8>
+    pub views: Option<i32>,
+    pub volume: Option<i32>
     
 }
 
@@ -110,12 +111,12 @@ pub struct ChapterUpdate {
     pub language: String,
     pub novels: Option<Vec<Novel>>,
     pub number: String,
-    pub part: Option<i8>,
+    pub part: Option<i32>,
     pub release_date: Option<String>,
     pub source: Source,
     pub title: String,
-    pub views: Option<i8>,
-    pub volume: Option<i8>
+    pub views: Option<i32>,
+    pub volume: Option<i32>
     
 }
 
@@ -145,12 +146,12 @@ pub struct ChapterPatch {
     pub language: Option<String>,
     pub novels: Option<Vec<Novel>>,
     pub number: Option<String>,
-    pub part: Option<i8>,
+    pub part: Option<i32>,
     pub release_date: Option<String>,
     pub source: Option<Source>,
     pub title: Option<String>,
-    pub views: Option<i8>,
-    pub volume: Option<i8>
+    pub views: Option<i32>,
+    pub volume: Option<i32>
     
 }
 
@@ -194,8 +195,15 @@ where
         .ok_or_else(|| (StatusCode::NOT_FOUND, Json(json!({"error": "not found"}))))
 }
 
-pub async fn list(state: State<AppState>) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
-    let models = Entity::load()
+pub async fn list(state: State<AppState>, principal: Option<Principal>, Query(options): Query<ListOptions>) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let preference = preferences::for_request(&state.db, principal.as_ref(), &options)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"error": e.to_string()}))))?;
+    let mut loader = Entity::load();
+    if let Some(preference) = &preference {
+        loader = loader.filter(preferences::chapter_condition(preference));
+    }
+    let models = loader
         .with(crate::models::novel::Entity)
         .with(crate::models::source::Entity)
         .all(&state.db)
@@ -211,6 +219,7 @@ pub async fn create(state: State<AppState>, Json(create): Json<ChapterCreate>) -
     let model = active_model.insert(&state.db)
         .await
         .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"failed to insert item": e.to_string()}))))?;
+        notifications::log_failure(notifications::new_chapter(&state.db, &model).await, "new chapter");
         let resp: Chapter = model.into();
         Ok(Json(resp))
 
@@ -0,0 +1,352 @@
+use serde_json::json;
+use axum::{Router, extract::{Path, Query, State}, http::StatusCode, routing::{delete, get, patch, post}, response::IntoResponse, Json};
+use serde::{Deserialize, Serialize};
+use sea_orm::{ActiveModelTrait, ConnectionTrait, EntityTrait, IntoActiveModel, PaginatorTrait, QueryFilter, QueryOrder, Set, TransactionTrait};
+use sea_orm::prelude::*;
+use std::collections::HashMap;
+use crate::app_state::AppState;
+use crate::models::{chapter, comment_revision, review, user};
+use crate::models::comment::{ActiveModel, Column, Entity, Model};
+use crate::models::moderation_log::Action;
+use crate::models::report::TargetType;
+use crate::models::user::Role;
+use crate::services::{auth::{self, Principal}, markdown, moderation, notifications};
+
+const DEFAULT_PER_PAGE: u64 = 20;
+const MAX_PER_PAGE: u64 = 100;
+
+/// A comment with its replies. Deleted comments keep their place in the thread but lose their text.
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct Comment {
+    pub id: i32,
+    pub created_at: DateTime,
+    pub edited_at: Option<DateTime>,
+    pub user_id: Option<i32>,
+    pub username: Option<String>,
+    pub parent_id: Option<i32>,
+    pub content: Option<String>,
+    pub content_html: Option<String>,
+    pub spoiler: bool,
+    pub deleted: bool,
+    pub replies: Vec<Comment>,
+}
+
+impl Comment {
+    fn new(model: Model, username: Option<String>) -> Self {
+        let deleted = model.deleted_at.is_some();
+        Self {
+            id: model.id,
+            created_at: model.created_at,
+            edited_at: model.edited_at,
+            user_id: if deleted { None } else { model.user_id },
+            username: if deleted { None } else { username },
+            parent_id: model.parent_id,
+            content: if deleted { None } else { Some(model.content) },
+            content_html: if deleted { None } else { model.content_html },
+            spoiler: model.spoiler,
+            deleted,
+            replies: vec![],
+        }
+    }
+}
+
+/// One page of top-level comments, each with its whole thread.
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct CommentPage {
+    pub page: u64,
+    pub per_page: u64,
+    pub total: u64,
+    pub comments: Vec<Comment>,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct PageOptions {
+    pub page: Option<u64>,
+    pub per_page: Option<u64>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CommentCreate {
+    pub content: String,
+    #[serde(default)]
+    pub spoiler: bool,
+    /// The comment being replied to; a new thread when absent.
+    pub parent_id: Option<i32>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CommentPatch {
+    pub content: Option<String>,
+    pub spoiler: Option<bool>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct CommentRevision {
+    pub content: String,
+    pub spoiler: bool,
+    /// When this version was replaced.
+    pub replaced_at: DateTime,
+}
+
+/// What a comment hangs off.
+#[derive(Clone, Copy, Debug)]
+enum Target {
+    Review(i32),
+    Chapter(i32),
+}
+
+impl Target {
+    fn column(self) -> (Column, i32) {
+        match self {
+            Target::Review(id) => (Column::ReviewId, id),
+            Target::Chapter(id) => (Column::ChapterId, id),
+        }
+    }
+}
+
+fn db_error(e: DbErr) -> (StatusCode, Json<serde_json::Value>) {
+    (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"error": e.to_string()})))
+}
+
+async fn check_target<C: ConnectionTrait>(db: &C, target: Target) -> Result<(), (StatusCode, Json<serde_json::Value>)> {
+    let exists = match target {
+        Target::Review(id) => review::Entity::find_by_id(id)
+            .filter(review::Column::HiddenAt.is_null())
+            .one(db)
+            .await
+            .map_err(db_error)?
+            .is_some(),
+        Target::Chapter(id) => chapter::Entity::find_by_id(id).one(db).await.map_err(db_error)?.is_some(),
+    };
+    if !exists {
+        return Err((StatusCode::NOT_FOUND, Json(json!({"error": "not found"}))));
+    }
+    Ok(())
+}
+
+async fn load_item<C: ConnectionTrait>(db: &C, id: i32) -> Result<Model, (StatusCode, Json<serde_json::Value>)> {
+    Entity::find_by_id(id)
+        .one(db)
+        .await
+        .map_err(db_error)?
+        .ok_or_else(|| (StatusCode::NOT_FOUND, Json(json!({"error": "not found"}))))
+}
+
+async fn usernames<C: ConnectionTrait>(db: &C, models: &[Model]) -> Result<HashMap<i32, String>, DbErr> {
+    let ids: Vec<i32> = models.iter().filter_map(|model| model.user_id).collect();
+    if ids.is_empty() {
+        return Ok(HashMap::new());
+    }
+    Ok(user::Entity::find()
+        .filter(user::Column::Id.is_in(ids))
+        .all(db)
+        .await?
+        .into_iter()
+        .map(|user| (user.id, user.username))
+        .collect())
+}
+
+/// Nests replies under their parents; `replies` must be ordered oldest first.
+fn build_threads(roots: Vec<Model>, replies: Vec<Model>, names: &HashMap<i32, String>) -> Vec<Comment> {
+    let to_comment = |model: Model| {
+        let username = model.user_id.and_then(|id| names.get(&id).cloned());
+        Comment::new(model, username)
+    };
+    let mut children: HashMap<i32, Vec<Comment>> = HashMap::new();
+    for reply in replies {
+        if let Some(parent_id) = reply.parent_id {
+            children.entry(parent_id).or_default().push(to_comment(reply));
+        }
+    }
+    fn attach(comment: &mut Comment, children: &mut HashMap<i32, Vec<Comment>>) {
+        let mut replies = children.remove(&comment.id).unwrap_or_default();
+        for reply in &mut replies {
+            attach(reply, children);
+        }
+        comment.replies = replies;
+    }
+    roots.into_iter()
+        .map(|root| {
+            let mut comment = to_comment(root);
+            attach(&mut comment, &mut children);
+            comment
+        })
+        .collect()
+}
+
+async fn list_for(state: &AppState, target: Target, options: PageOptions) -> Result<CommentPage, (StatusCode, Json<serde_json::Value>)> {
+    check_target(&state.db, target).await?;
+    let (column, id) = target.column();
+    let per_page = options.per_page.unwrap_or(DEFAULT_PER_PAGE).clamp(1, MAX_PER_PAGE);
+    let page = options.page.unwrap_or(1).max(1);
+    let paginator = Entity::find()
+        .filter(column.eq(id))
+        .filter(Column::ParentId.is_null())
+        .order_by_asc(Column::CreatedAt)
+        .order_by_asc(Column::Id)
+        .paginate(&state.db, per_page);
+    let total = paginator.num_items().await.map_err(db_error)?;
+    let roots = paginator.fetch_page(page - 1).await.map_err(db_error)?;
+    let replies = if roots.is_empty() {
+        vec![]
+    } else {
+        Entity::find()
+            .filter(Column::RootId.is_in(roots.iter().map(|root| root.id)))
+            .order_by_asc(Column::CreatedAt)
+            .order_by_asc(Column::Id)
+            .all(&state.db)
+            .await
+            .map_err(db_error)?
+    };
+    let names = usernames(&state.db, &roots.iter().chain(&replies).cloned().collect::<Vec<_>>()).await.map_err(db_error)?;
+    Ok(CommentPage { page, per_page, total, comments: build_threads(roots, replies, &names) })
+}
+
+async fn create_for(state: &AppState, principal: &Principal, target: Target, create: CommentCreate) -> Result<Comment, (StatusCode, Json<serde_json::Value>)> {
+    let author = auth::current_user(&state.db, principal).await?;
+    if create.content.trim().is_empty() {
+        return Err((StatusCode::UNPROCESSABLE_ENTITY, Json(json!({"error": "comment must not be empty"}))));
+    }
+    check_target(&state.db, target).await?;
+    let (column, id) = target.column();
+    let mut active_model = ActiveModel {
+        user_id: Set(Some(author.id)),
+        content_html: Set(Some(markdown::render(&create.content))),
+        content: Set(create.content),
+        spoiler: Set(create.spoiler),
+        ..Default::default()
+    };
+    match target {
+        Target::Review(id) => active_model.review_id = Set(Some(id)),
+        Target::Chapter(id) => active_model.chapter_id = Set(Some(id)),
+    }
+    if let Some(parent_id) = create.parent_id {
+        let parent = Entity::find_by_id(parent_id)
+            .filter(column.eq(id))
+            .one(&state.db)
+            .await
+            .map_err(db_error)?
+            .ok_or_else(|| (StatusCode::NOT_FOUND, Json(json!({"error": "parent comment not found"}))))?;
+        if parent.deleted_at.is_some() {
+            return Err((StatusCode::UNPROCESSABLE_ENTITY, Json(json!({"error": "cannot reply to a deleted comment"}))));
+        }
+        active_model.parent_id = Set(Some(parent.id));
+        active_model.root_id = Set(Some(parent.root_id.unwrap_or(parent.id)));
+    }
+    let model = active_model.insert(&state.db)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"failed to insert item": e.to_string()}))))?;
+    notifications::log_failure(notifications::review_reply(&state.db, &model).await, "review reply");
+    Ok(Comment::new(model, Some(author.username)))
+}
+
+pub async fn list_for_review(state: State<AppState>, Path(id): Path<i32>, Query(options): Query<PageOptions>) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    Ok(Json(list_for(&state, Target::Review(id), options).await?))
+}
+
+pub async fn list_for_chapter(state: State<AppState>, Path(id): Path<i32>, Query(options): Query<PageOptions>) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    Ok(Json(list_for(&state, Target::Chapter(id), options).await?))
+}
+
+pub async fn create_for_review(state: State<AppState>, principal: Principal, Path(id): Path<i32>, Json(create): Json<CommentCreate>) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    Ok((StatusCode::CREATED, Json(create_for(&state, &principal, Target::Review(id), create).await?)))
+}
+
+pub async fn create_for_chapter(state: State<AppState>, principal: Principal, Path(id): Path<i32>, Json(create): Json<CommentCreate>) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    Ok((StatusCode::CREATED, Json(create_for(&state, &principal, Target::Chapter(id), create).await?)))
+}
+
+/// Edits a comment, keeping the previous version in its history. Only the author may edit.
+pub async fn patch_one(state: State<AppState>, principal: Principal, Path(id): Path<i32>, Json(patch): Json<CommentPatch>) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let author = auth::current_user(&state.db, &principal).await?;
+    let model = load_item(&state.db, id).await?;
+    if model.user_id != Some(author.id) {
+        return Err((StatusCode::FORBIDDEN, Json(json!({"error": "not your comment"}))));
+    }
+    if model.deleted_at.is_some() {
+        return Err((StatusCode::UNPROCESSABLE_ENTITY, Json(json!({"error": "comment was deleted"}))));
+    }
+    if patch.content.as_deref().is_some_and(|content| content.trim().is_empty()) {
+        return Err((StatusCode::UNPROCESSABLE_ENTITY, Json(json!({"error": "comment must not be empty"}))));
+    }
+    let now = chrono::Utc::now().naive_utc();
+    let txn = state.db.begin().await.map_err(db_error)?;
+    comment_revision::ActiveModel {
+        comment_id: Set(model.id),
+        content: Set(model.content.clone()),
+        spoiler: Set(model.spoiler),
+        created_at: Set(now),
+        ..Default::default()
+    }
+    .insert(&txn)
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"failed to insert item": e.to_string()}))))?;
+    let mut active_model = model.into_active_model();
+    if let Some(content) = patch.content {
+        active_model.content_html = Set(Some(markdown::render(&content)));
+        active_model.content = Set(content);
+    }
+    if let Some(spoiler) = patch.spoiler {
+        active_model.spoiler = Set(spoiler);
+    }
+    active_model.edited_at = Set(Some(now));
+    let model = active_model.update(&txn)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"failed to update item": e.to_string()}))))?;
+    txn.commit().await.map_err(db_error)?;
+    Ok(Json(Comment::new(model, Some(author.username))))
+}
+
+/// Soft-deletes a comment so its replies stay readable. Authors and moderators may delete;
+/// a moderator deleting someone else's comment leaves an audit entry.
+pub async fn remove(state: State<AppState>, principal: Principal, Path(id): Path<i32>) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let caller = auth::current_user(&state.db, &principal).await?;
+    let model = load_item(&state.db, id).await?;
+    let own = model.user_id == Some(caller.id);
+    if !own && !matches!(caller.role, Role::Moderator | Role::Admin) {
+        return Err((StatusCode::FORBIDDEN, Json(json!({"error": "not your comment"}))));
+    }
+    if model.deleted_at.is_none() {
+        let author_id = model.user_id;
+        let txn = state.db.begin().await.map_err(db_error)?;
+        let mut active_model = model.into_active_model();
+        active_model.deleted_at = Set(Some(chrono::Utc::now().naive_utc()));
+        active_model.update(&txn)
+            .await
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"failed to update item": e.to_string()}))))?;
+        if !own {
+            moderation::record(&txn, caller.id, Action::Delete, (TargetType::Comment, id), author_id, None, None).await.map_err(db_error)?;
+        }
+        txn.commit().await.map_err(db_error)?;
+    }
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Earlier versions of a comment, oldest first.
+pub async fn history(state: State<AppState>, Path(id): Path<i32>) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let model = load_item(&state.db, id).await?;
+    if model.deleted_at.is_some() {
+        return Ok(Json(Vec::<CommentRevision>::new()));
+    }
+    let revisions: Vec<CommentRevision> = comment_revision::Entity::find()
+        .filter(comment_revision::Column::CommentId.eq(id))
+        .order_by_asc(comment_revision::Column::Id)
+        .all(&state.db)
+        .await
+        .map_err(db_error)?
+        .into_iter()
+        .map(|revision| CommentRevision { content: revision.content, spoiler: revision.spoiler, replaced_at: revision.created_at })
+        .collect();
+    Ok(Json(revisions))
+}
+
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/reviews/{id}/comments", get(list_for_review))
+        .route("/reviews/{id}/comments", post(create_for_review))
+        .route("/chapters/{id}/comments", get(list_for_chapter))
+        .route("/chapters/{id}/comments", post(create_for_chapter))
+        .route("/comments/{id}", patch(patch_one))
+        .route("/comments/{id}", delete(remove))
+        .route("/comments/{id}/history", get(history))
+}
@@ -0,0 +1,188 @@
+use axum::{Router, body::{Body, Bytes}, extract::{Query, State}, http::{StatusCode, header}, routing::get, response::IntoResponse, Json};
+use futures_util::stream;
+use serde::{Deserialize, Serialize};
+use sea_orm::{DatabaseConnection, DbBackend, FromQueryResult, Statement};
+use sea_orm::prelude::*;
+use crate::app_state::AppState;
+use crate::models::library_entry::Status;
+use crate::services::auth::{self, Principal};
+
+/// Rows fetched per query while streaming, so memory stays flat for large libraries.
+const PAGE_SIZE: i64 = 500;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExportFormat {
+    Csv,
+    Json,
+    Mal,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ExportOptions {
+    pub format: ExportFormat,
+}
+
+/// One novel of the user's library.
+#[derive(Clone, Debug, Serialize, FromQueryResult)]
+pub struct ExportedEntry {
+    pub entry_id: i32,
+    pub novel_id: i32,
+    pub title: String,
+    pub status: Status,
+    /// Number of the last read chapter.
+    pub current_chapter: Option<String>,
+    pub last_read: Option<DateTime>,
+    pub personal_rating: Option<String>,
+    pub notes: Option<String>,
+    pub started_date: Option<String>,
+    pub completed_date: Option<String>,
+}
+
+async fn fetch_page(db: &DatabaseConnection, user_id: i32, after: i32) -> Result<Vec<ExportedEntry>, DbErr> {
+    ExportedEntry::find_by_statement(Statement::from_sql_and_values(DbBackend::Postgres, r#"
+        SELECT le.id AS entry_id, n.id AS novel_id, n.default_name AS title, le.status,
+            c.number AS current_chapter, le.last_read_at AS last_read, le.personal_rating, le.notes,
+            le.started_date, le.completed_date
+        FROM public.library_entry le
+        JOIN public.novel n ON n.id = le.novel_id
+        LEFT JOIN public.chapter c ON c.id = le.last_read_chapter_id
+        WHERE le.user_id = $1 AND le.id > $2
+        ORDER BY le.id
+        LIMIT $3"#, [user_id.into(), after.into(), PAGE_SIZE.into()]))
+        .all(db)
+        .await
+}
+
+/// Status names as NovelUpdates and MAL write them; `import::parse_status` reads them back.
+fn status_label(status: &Status) -> &'static str {
+    match status {
+        Status::Reading => "Reading",
+        Status::Completed => "Completed",
+        Status::PlanToRead => "Plan to Read",
+        Status::OnHold => "On-Hold",
+        Status::Dropped => "Dropped",
+    }
+}
+
+fn cdata(text: &str) -> String {
+    format!("<![CDATA[{}]]>", text.replace("]]>", "]]]]><![CDATA[>"))
+}
+
+fn header_chunk(format: ExportFormat) -> String {
+    match format {
+        ExportFormat::Csv => "Title,Status,Progress,Rating,Notes,Started,Completed,Last Read\n".to_string(),
+        ExportFormat::Json => "[".to_string(),
+        ExportFormat::Mal => "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<myanimelist>\n  <myinfo>\n    <user_export_type>2</user_export_type>\n  </myinfo>\n".to_string(),
+    }
+}
+
+fn footer_chunk(format: ExportFormat) -> String {
+    match format {
+        ExportFormat::Csv => String::new(),
+        ExportFormat::Json => "]\n".to_string(),
+        ExportFormat::Mal => "</myanimelist>\n".to_string(),
+    }
+}
+
+fn rows_chunk(format: ExportFormat, rows: &[ExportedEntry], first: bool) -> Result<String, DbErr> {
+    let mut out = String::new();
+    match format {
+        ExportFormat::Csv => {
+            let mut writer = csv::WriterBuilder::new().has_headers(false).from_writer(vec![]);
+            for row in rows {
+                let last_read = row.last_read.map(|last_read| last_read.format("%Y-%m-%d %H:%M:%S").to_string()).unwrap_or_default();
+                writer.write_record([
+                    row.title.as_str(),
+                    status_label(&row.status),
+                    row.current_chapter.as_deref().unwrap_or_default(),
+                    row.personal_rating.as_deref().unwrap_or_default(),
+                    row.notes.as_deref().unwrap_or_default(),
+                    row.started_date.as_deref().unwrap_or_default(),
+                    row.completed_date.as_deref().unwrap_or_default(),
+                    last_read.as_str(),
+                ]).map_err(|e| DbErr::Custom(e.to_string()))?;
+            }
+            let bytes = writer.into_inner().map_err(|e| DbErr::Custom(e.to_string()))?;
+            out.push_str(&String::from_utf8_lossy(&bytes));
+        }
+        ExportFormat::Json => {
+            for (index, row) in rows.iter().enumerate() {
+                if !(first && index == 0) {
+                    out.push(',');
+                }
+                out.push_str(&serde_json::to_string(row).map_err(|e| DbErr::Custom(e.to_string()))?);
+            }
+        }
+        ExportFormat::Mal => {
+            for row in rows {
+                out.push_str(&format!(
+                    "  <manga>\n    <manga_title>{}</manga_title>\n    <my_read_chapters>{}</my_read_chapters>\n    <my_status>{}</my_status>\n    <my_score>{}</my_score>\n    <my_comments>{}</my_comments>\n    <my_start_date>{}</my_start_date>\n    <my_finish_date>{}</my_finish_date>\n  </manga>\n",
+                    cdata(&row.title),
+                    row.current_chapter.as_deref().and_then(|chapter| chapter.parse::<f64>().ok()).map_or(0, |chapter| chapter.floor() as i64),
+                    status_label(&row.status),
+                    row.personal_rating.as_deref().and_then(|rating| rating.parse::<f64>().ok()).map_or(0, |rating| rating.round() as i64),
+                    cdata(row.notes.as_deref().unwrap_or_default()),
+                    row.started_date.as_deref().unwrap_or("0000-00-00"),
+                    row.completed_date.as_deref().unwrap_or("0000-00-00"),
+                ));
+            }
+        }
+    }
+    Ok(out)
+}
+
+enum Phase {
+    Header,
+    Rows { after: i32, first: bool },
+    Footer,
+    Done,
+}
+
+/// Streams every library entry of the signed-in user as CSV, JSON or MAL XML.
+pub async fn export(state: State<AppState>, principal: Principal, Query(options): Query<ExportOptions>) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let model = auth::current_user(&state.db, &principal).await?;
+    let format = options.format;
+    let (content_type, extension) = match format {
+        ExportFormat::Csv => ("text/csv; charset=utf-8", "csv"),
+        ExportFormat::Json => ("application/json", "json"),
+        ExportFormat::Mal => ("application/xml", "xml"),
+    };
+    let filename = format!("attachment; filename=\"novelupdates-{}-reading-lists.{}\"", model.username, extension);
+    let db = state.db.clone();
+    let user_id = model.id;
+    let chunks = stream::unfold(Phase::Header, move |phase| {
+        let db = db.clone();
+        async move {
+            match phase {
+                Phase::Header => Some((Ok(Bytes::from(header_chunk(format))), Phase::Rows { after: 0, first: true })),
+                Phase::Rows { after, first } => {
+                    let rows = match fetch_page(&db, user_id, after).await {
+                        Ok(rows) => rows,
+                        Err(e) => return Some((Err(e), Phase::Done)),
+                    };
+                    let Some(last) = rows.last() else {
+                        return Some((Ok(Bytes::from(footer_chunk(format))), Phase::Done));
+                    };
+                    let next = if rows.len() < PAGE_SIZE as usize {
+                        Phase::Footer
+                    } else {
+                        Phase::Rows { after: last.entry_id, first: false }
+                    };
+                    Some((rows_chunk(format, &rows, first).map(Bytes::from), next))
+                }
+                Phase::Footer => Some((Ok(Bytes::from(footer_chunk(format))), Phase::Done)),
+                Phase::Done => None,
+            }
+        }
+    });
+    Ok((
+        [(header::CONTENT_TYPE, content_type.to_string()), (header::CONTENT_DISPOSITION, filename)],
+        Body::from_stream(chunks),
+    ))
+}
+
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/me/reading-lists/export", get(export))
+}
@@ -1,10 +1,11 @@
 use serde_json::json;
-use axum::{Router, extract::{Path, State}, http::StatusCode, routing::{delete, get, patch, post, put}, response::IntoResponse, Json};
+use axum::{Router, extract::{Path, State}, http::{StatusCode, Uri}, routing::{delete, get, patch, post, put}, response::{IntoResponse, Response}, Json};
 use serde::{Deserialize, Serialize};
 use sea_orm::{ActiveModelTrait, ModelTrait, EntityTrait, Set, IntoActiveModel, ConnectionTrait};
 use sea_orm::EntityLoaderTrait;
 use sea_orm::prelude::*;
 use crate::app_state::AppState;
+use crate::services::{markdown, slugs::{self, Resource}};
 use crate::models::group::{ActiveModel, Entity, Model, ModelEx, Status};
 use super::{source::Source as Source, };
 #[derive(Clone, Debug, Default, Deserialize, Serialize)]
@@ -13,13 +14,15 @@ pub struct Group {
     pub created_at: DateTime,
     pub last_updated: DateTime,
     pub description: Option<String>,
+    pub description_html: Option<String>,
     pub discord_url: Option<String>,
     pub founded_date: Option<String>,
     pub language: Option<String>,
     pub logo_url: Option<String>,
-    pub member_count: Option<i8>,
+    pub member_count: Option<i32>,
     pub name: String,
     pub patreon_url: Option<String>,
+    pub slug: Option<String>,
     pub sources: Option<Vec<Source>>,
     pub status: Option<Status>,
     pub website: Option<String>
@@ -33,6 +36,7 @@ impl From<Model> for Group {
             created_at: model.created_at,
             last_updated: model.last_updated,
             description: model.description,
+            description_html: model.description_html,
             discord_url: model.discord_url,
             founded_date: model.founded_date,
             language: model.language,
@@ -40,6 +44,7 @@ impl From<Model> for Group {
             member_count: model.member_count,
             name: model.name,
             patreon_url: model.patreon_url,
+            slug: model.slug,
             sources: vec![].into(),
             status: model.status,
             website: model.website
@@ -55,6 +60,7 @@ impl From<ModelEx> for Group {
             created_at: model.created_at,
             last_updated: model.last_updated,
             description: model.description,
+            description_html: model.description_html,
             discord_url: model.discord_url,
             founded_date: model.founded_date,
             language: model.language,
@@ -62,6 +68,7 @@ impl From<ModelEx> for Group {
             member_count: model.member_count,
             name: model.name,
             patreon_url: model.patreon_url,
+            slug: model.slug,
             sources: Some(model.sources.into_iter().map(Source::from).collect()),
             status: model.status,
             website: model.website,
@@ -77,7 +84,7 @@ pub struct GroupCreate {
     pub founded_date: Option<String>,
     pub language: Option<String>,
     pub logo_url: Option<String>,
-    pub member_count: Option<i8>,
+    pub member_count: Option<i32>,
     pub name: String,
     pub patreon_url: Option<String>,
     pub sources: Option<Vec<Source>>,
@@ -90,6 +97,7 @@ impl From<GroupCreate> for ActiveModel {
     fn from(source: GroupCreate) -> Self {
         ActiveModel {
             description: Set(source.description.clone()),
+            description_html: Set(markdown::render_opt(source.description.as_deref())),
             discord_url: Set(source.discord_url.clone()),
             founded_date: Set(source.founded_date.clone()),
             language: Set(source.language.clone()),
@@ -111,7 +119,7 @@ pub struct GroupUpdate {
     pub founded_date: Option<String>,
     pub language: Option<String>,
     pub logo_url: Option<String>,
-    pub member_count: Option<i8>,
+    pub member_count: Option<i32>,
     pub name: String,
     pub patreon_url: Option<String>,
     pub sources: Option<Vec<Source>>,
@@ -125,6 +133,7 @@ impl GroupUpdate {
         ActiveModel {
             id: Set(id),
             description: Set(self.description.clone()),
+            description_html: Set(markdown::render_opt(self.description.as_deref())),
             discord_url: Set(self.discord_url.clone()),
             founded_date: Set(self.founded_date.clone()),
             language: Set(self.language.clone()),
@@ -146,7 +155,7 @@ pub struct GroupPatch {
     pub founded_date: Option<String>,
     pub language: Option<String>,
     pub logo_url: Option<String>,
-    pub member_count: Option<i8>,
+    pub member_count: Option<i32>,
     pub name: Option<String>,
     pub patreon_url: Option<String>,
     pub sources: Option<Vec<Source>>,
@@ -159,6 +168,7 @@ impl GroupPatch {
     pub fn patch_active_model(&self, active_model: &mut ActiveModel) {
         if self.description.is_some() {
             active_model.description = Set(self.description.clone());
+            active_model.description_html = Set(markdown::render_opt(self.description.as_deref()));
         }if self.discord_url.is_some() {
             active_model.discord_url = Set(self.discord_url.clone());
         }if self.founded_date.is_some() {
@@ -207,8 +217,13 @@ pub async fn list(state: State<AppState>) -> Result<impl IntoResponse, (StatusCo
 }
 
 pub async fn create(state: State<AppState>, Json(create): Json<GroupCreate>) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let source = create.name.clone();
     let active_model:ActiveModel = create.into();
-    let model = active_model.insert(&state.db)
+    let model = slugs::insert_with(&state.db, Resource::Group, &source, |slug| {
+        let mut active_model = active_model.clone();
+        active_model.slug = Set(Some(slug));
+        active_model.insert(&state.db)
+    })
         .await
         .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"failed to insert item": e.to_string()}))))?;
         let resp: Group = model.into();
@@ -223,7 +238,10 @@ pub async fn patch_one(state: State<AppState>, Path(id): Path<i32>, Json(patch):
     let model = active_model.update(&state.db)
         .await
         .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"failed to update item": e.to_string()}))))?;
-    let resp: Group = model.into();
+    let slug = slugs::assign(&state.db, Resource::Group, model.id, &model.name)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"error": e.to_string()}))))?;
+    let resp: Group = Model { slug: Some(slug), ..model }.into();
     Ok(Json(resp))
 }
 
@@ -233,7 +251,10 @@ pub async fn put_one(state: State<AppState>, Path(id): Path<i32>, Json(update):
     let model = active_model.update(&state.db)
         .await
         .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"failed to update item": e.to_string()}))))?;
-    let resp: Group = model.into();
+    let slug = slugs::assign(&state.db, Resource::Group, model.id, &model.name)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"error": e.to_string()}))))?;
+    let resp: Group = Model { slug: Some(slug), ..model }.into();
     Ok(Json(resp))
 }
 
@@ -257,10 +278,17 @@ pub async fn read_one(state: State<AppState>, Path(id): Path<i32>) -> Result<imp
     Ok(Json(resp))
 }
 
+/// Serves the row a slug names. Old slugs redirect to the current one.
+pub async fn read_by_slug(state: State<AppState>, uri: Uri, Path(slug): Path<String>) -> Result<Response, (StatusCode, Json<serde_json::Value>)> {
+    let db = state.db.clone();
+    slugs::serve(&db, Resource::Group, &slug, &uri, |id| read_one(state, Path(id))).await
+}
+
 pub fn routes() -> Router<AppState> {
     Router::new()
         .route("/groups", get(list))
         .route("/groups", post(create))
+        .route("/groups/by-slug/{slug}", get(read_by_slug))
         .route("/groups/{id}", get(read_one))
         .route("/groups/{id}", delete(remove))
         .route("/groups/{id}", patch(patch_one))
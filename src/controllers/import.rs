@@ -0,0 +1,160 @@
+use serde_json::json;
+use axum::{Router, extract::{Query, State}, http::StatusCode, routing::post, response::IntoResponse, Json};
+use serde::{Deserialize, Serialize};
+use sea_orm::{ActiveModelTrait, ConnectionTrait, EntityTrait, IntoActiveModel, QueryFilter, QuerySelect, Set, TransactionTrait};
+use sea_orm::prelude::*;
+use std::collections::HashSet;
+use crate::app_state::AppState;
+use crate::models::{chapter, chapter_novel, library_entry};
+use crate::models::library_entry::Status;
+use crate::services::auth::Principal;
+use crate::services::import::{self, Candidate, Format, ImportedEntry};
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ImportOptions {
+    pub format: Format,
+    /// Status for formats that do not carry one (a saved NovelUpdates list page).
+    #[serde(default)]
+    pub status: Option<Status>,
+    /// Match and report without writing anything.
+    #[serde(default)]
+    pub dry_run: bool,
+    /// Replace library entries that already exist instead of skipping them.
+    #[serde(default)]
+    pub overwrite: bool,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct MatchedEntry {
+    pub title: String,
+    pub novel_id: i32,
+    pub novel_name: String,
+    pub confidence: f64,
+    pub status: Status,
+    pub progress: Option<String>,
+    /// `None` when there is no progress or the chapter number is not known for the novel.
+    pub last_read_chapter_id: Option<i32>,
+}
+
+/// An entry that needs manual resolution, e.g. through `PUT /library/{novel_id}`.
+#[derive(Clone, Debug, Serialize)]
+pub struct UnmatchedEntry {
+    pub entry: ImportedEntry,
+    pub candidates: Vec<Candidate>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct ImportReport {
+    pub format: Format,
+    pub dry_run: bool,
+    pub total: usize,
+    pub imported: Vec<MatchedEntry>,
+    /// Matched, but already in the library and `overwrite` was not set.
+    pub skipped: Vec<MatchedEntry>,
+    pub unmatched: Vec<UnmatchedEntry>,
+}
+
+fn db_error(e: DbErr) -> (StatusCode, Json<serde_json::Value>) {
+    (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"error": e.to_string()})))
+}
+
+async fn find_chapter<C: ConnectionTrait>(db: &C, novel_id: i32, number: &str) -> Result<Option<i32>, DbErr> {
+    chapter_novel::Entity::find()
+        .inner_join(chapter::Entity)
+        .filter(chapter_novel::Column::NovelId.eq(novel_id))
+        .filter(chapter::Column::Number.eq(number))
+        .select_only()
+        .column(chapter::Column::Id)
+        .into_tuple()
+        .one(db)
+        .await
+}
+
+/// Imports an uploaded NovelUpdates CSV/HTML or MAL XML export into the library.
+/// The raw file is the request body; `?format=csv|html|mal` says how to read it. Titles are
+/// matched before the transaction opens, so it only covers the writes.
+pub async fn create(state: State<AppState>, principal: Principal, Query(options): Query<ImportOptions>, body: String) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let user_id = principal.user_id
+        .ok_or_else(|| (StatusCode::FORBIDDEN, Json(json!({"error": "api key is not tied to a user"}))))?;
+    let entries = import::parse(options.format, &body, options.status.clone().unwrap_or_default())
+        .map_err(|e| (StatusCode::BAD_REQUEST, Json(json!({"error": format!("could not read export: {e}")}))))?;
+    let titles: Vec<String> = entries.iter().map(|entry| entry.title.clone()).collect();
+    let exact = import::exact_matches(&state.db, &titles).await.map_err(db_error)?;
+    let existing: HashSet<i32> = library_entry::Entity::find()
+        .filter(library_entry::Column::UserId.eq(user_id))
+        .select_only()
+        .column(library_entry::Column::NovelId)
+        .into_tuple::<i32>()
+        .all(&state.db)
+        .await
+        .map_err(db_error)?
+        .into_iter()
+        .collect();
+    let mut report = ImportReport { format: options.format, dry_run: options.dry_run, total: entries.len(), imported: vec![], skipped: vec![], unmatched: vec![] };
+    let mut seen = HashSet::new();
+    let mut writes = vec![];
+    for entry in entries {
+        let candidates = match exact.get(&entry.title) {
+            Some(candidate) => vec![candidate.clone()],
+            None => {
+                let pool = import::candidate_pool(&state.db, &entry.title).await.map_err(db_error)?;
+                import::candidates(&pool, &entry.title)
+            }
+        };
+        let Some(best) = candidates.first().filter(|best| best.confidence >= import::MATCH_THRESHOLD).cloned() else {
+            report.unmatched.push(UnmatchedEntry { entry, candidates });
+            continue;
+        };
+        let last_read_chapter_id = match &entry.progress {
+            Some(number) => find_chapter(&state.db, best.novel_id, number).await.map_err(db_error)?,
+            None => None,
+        };
+        let matched = MatchedEntry {
+            title: entry.title.clone(),
+            novel_id: best.novel_id,
+            novel_name: best.default_name,
+            confidence: best.confidence,
+            status: entry.status.clone(),
+            progress: entry.progress.clone(),
+            last_read_chapter_id,
+        };
+        // Two rows of the same export resolving to one novel: the first one wins.
+        if !seen.insert(best.novel_id) || (existing.contains(&best.novel_id) && !options.overwrite) {
+            report.skipped.push(matched);
+            continue;
+        }
+        report.imported.push(matched);
+        writes.push((best.novel_id, last_read_chapter_id, entry));
+    }
+    if options.dry_run || writes.is_empty() {
+        return Ok(Json(report));
+    }
+    let txn = state.db.begin().await.map_err(db_error)?;
+    for (novel_id, last_read_chapter_id, entry) in writes {
+        let existing_entry = library_entry::Entity::find()
+            .filter(library_entry::Column::UserId.eq(user_id))
+            .filter(library_entry::Column::NovelId.eq(novel_id))
+            .one(&txn)
+            .await
+            .map_err(db_error)?;
+        let mut active_model = match &existing_entry {
+            Some(existing_entry) => existing_entry.clone().into_active_model(),
+            None => library_entry::ActiveModel { user_id: Set(user_id), novel_id: Set(novel_id), ..Default::default() },
+        };
+        active_model.status = Set(entry.status);
+        active_model.last_read_chapter_id = Set(last_read_chapter_id);
+        active_model.personal_rating = Set(entry.rating);
+        active_model.notes = Set(entry.notes);
+        active_model.started_date = Set(entry.started_date);
+        active_model.completed_date = Set(entry.completed_date);
+        if existing_entry.is_some() { active_model.update(&txn).await } else { active_model.insert(&txn).await }
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"failed to insert item": e.to_string()}))))?;
+    }
+    txn.commit().await.map_err(db_error)?;
+    Ok(Json(report))
+}
+
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/me/import", post(create))
+}
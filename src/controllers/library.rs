@@ -0,0 +1,275 @@
+use serde_json::json;
+use axum::{Router, extract::{Path, Query, State}, http::StatusCode, routing::{delete, get, post, put}, response::IntoResponse, Json};
+use serde::{Deserialize, Serialize};
+use sea_orm::{ActiveModelTrait, ConnectionTrait, EntityTrait, IntoActiveModel, ModelTrait, QueryFilter, QueryOrder, QuerySelect, Set, sea_query::{Expr, OnConflict}};
+use sea_orm::prelude::*;
+use std::collections::HashMap;
+use crate::app_state::AppState;
+use crate::models::{chapter_novel, library_chapter_read, novel};
+use crate::models::library_entry::{ActiveModel, Column, Entity, Model, Status};
+use crate::services::auth::Principal;
+use super::profile::ProfileNovel;
+
+/// One novel in the signed-in user's library, with its own status and progress.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct LibraryEntry {
+    pub id: i32,
+    pub created_at: DateTime,
+    pub last_updated: DateTime,
+    pub novel: ProfileNovel,
+    pub status: Status,
+    pub last_read_chapter_id: Option<i32>,
+    pub last_read_at: Option<DateTime>,
+    pub chapters_read: u64,
+    pub personal_rating: Option<String>,
+    pub notes: Option<String>,
+    pub started_date: Option<String>,
+    pub completed_date: Option<String>,
+    /// Only filled in when reading a single entry.
+    pub read_chapter_ids: Option<Vec<i32>>,
+}
+
+impl LibraryEntry {
+    fn new(model: Model, novel: ProfileNovel, chapters_read: u64) -> Self {
+        Self {
+            id: model.id,
+            created_at: model.created_at,
+            last_updated: model.last_updated,
+            novel,
+            status: model.status,
+            last_read_chapter_id: model.last_read_chapter_id,
+            last_read_at: model.last_read_at,
+            chapters_read,
+            personal_rating: model.personal_rating,
+            notes: model.notes,
+            started_date: model.started_date,
+            completed_date: model.completed_date,
+            read_chapter_ids: None,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct LibraryEntryUpdate {
+    pub status: Status,
+    pub last_read_chapter_id: Option<i32>,
+    pub personal_rating: Option<String>,
+    pub notes: Option<String>,
+    pub started_date: Option<String>,
+    pub completed_date: Option<String>,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct LibraryFilter {
+    pub status: Option<Status>,
+}
+
+fn db_error(e: DbErr) -> (StatusCode, Json<serde_json::Value>) {
+    (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"error": e.to_string()})))
+}
+
+fn require_user(principal: &Principal) -> Result<i32, (StatusCode, Json<serde_json::Value>)> {
+    principal.user_id.ok_or_else(|| (StatusCode::FORBIDDEN, Json(json!({"error": "api key is not tied to a user"}))))
+}
+
+async fn load_novel<C: ConnectionTrait>(db: &C, novel_id: i32) -> Result<novel::Model, (StatusCode, Json<serde_json::Value>)> {
+    novel::Entity::find_by_id(novel_id)
+        .one(db)
+        .await
+        .map_err(db_error)?
+        .ok_or_else(|| (StatusCode::NOT_FOUND, Json(json!({"error": "novel not found"}))))
+}
+
+async fn find_entry<C: ConnectionTrait>(db: &C, user_id: i32, novel_id: i32) -> Result<Option<Model>, (StatusCode, Json<serde_json::Value>)> {
+    Entity::find()
+        .filter(Column::UserId.eq(user_id))
+        .filter(Column::NovelId.eq(novel_id))
+        .one(db)
+        .await
+        .map_err(db_error)
+}
+
+async fn load_item<C: ConnectionTrait>(db: &C, user_id: i32, novel_id: i32) -> Result<Model, (StatusCode, Json<serde_json::Value>)> {
+    find_entry(db, user_id, novel_id)
+        .await?
+        .ok_or_else(|| (StatusCode::NOT_FOUND, Json(json!({"error": "not found"}))))
+}
+
+/// Checks that the chapter is one of the novel's chapters.
+async fn check_chapter<C: ConnectionTrait>(db: &C, novel_id: i32, chapter_id: i32) -> Result<(), (StatusCode, Json<serde_json::Value>)> {
+    chapter_novel::Entity::find_by_id((chapter_id, novel_id))
+        .one(db)
+        .await
+        .map_err(db_error)?
+        .map(|_| ())
+        .ok_or_else(|| (StatusCode::NOT_FOUND, Json(json!({"error": "chapter does not belong to this novel"}))))
+}
+
+async fn read_chapter_ids<C: ConnectionTrait>(db: &C, entry_id: i32) -> Result<Vec<i32>, DbErr> {
+    library_chapter_read::Entity::find()
+        .filter(library_chapter_read::Column::LibraryEntryId.eq(entry_id))
+        .order_by_asc(library_chapter_read::Column::ReadAt)
+        .select_only()
+        .column(library_chapter_read::Column::ChapterId)
+        .into_tuple()
+        .all(db)
+        .await
+}
+
+async fn entry_response<C: ConnectionTrait>(db: &C, model: Model, novel: novel::Model) -> Result<LibraryEntry, (StatusCode, Json<serde_json::Value>)> {
+    let ids = read_chapter_ids(db, model.id).await.map_err(db_error)?;
+    let mut resp = LibraryEntry::new(model, novel.into(), ids.len() as u64);
+    resp.read_chapter_ids = Some(ids);
+    Ok(resp)
+}
+
+pub async fn list(state: State<AppState>, principal: Principal, Query(filter): Query<LibraryFilter>) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let user_id = require_user(&principal)?;
+    let mut query = Entity::find().filter(Column::UserId.eq(user_id)).order_by_desc(Column::LastUpdated);
+    if let Some(status) = filter.status {
+        query = query.filter(Column::Status.eq(status));
+    }
+    let models = query
+        .find_also_related(novel::Entity)
+        .all(&state.db)
+        .await
+        .map_err(db_error)?;
+    let counts: HashMap<i32, i64> = library_chapter_read::Entity::find()
+        .select_only()
+        .column(library_chapter_read::Column::LibraryEntryId)
+        .column_as(library_chapter_read::Column::ChapterId.count(), "chapters_read")
+        .filter(library_chapter_read::Column::LibraryEntryId.is_in(models.iter().map(|(model, _)| model.id)))
+        .group_by(library_chapter_read::Column::LibraryEntryId)
+        .into_tuple::<(i32, i64)>()
+        .all(&state.db)
+        .await
+        .map_err(db_error)?
+        .into_iter()
+        .collect();
+    let responses: Vec<LibraryEntry> = models
+        .into_iter()
+        .map(|(model, novel)| {
+            let chapters_read = counts.get(&model.id).copied().unwrap_or(0) as u64;
+            LibraryEntry::new(model, novel.map(Into::into).unwrap_or_default(), chapters_read)
+        })
+        .collect();
+    Ok(Json(responses))
+}
+
+pub async fn read_one(state: State<AppState>, principal: Principal, Path(novel_id): Path<i32>) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let user_id = require_user(&principal)?;
+    let model = load_item(&state.db, user_id, novel_id).await?;
+    let novel = load_novel(&state.db, novel_id).await?;
+    Ok(Json(entry_response(&state.db, model, novel).await?))
+}
+
+/// Adds the novel to the library or replaces the entry's fields.
+pub async fn put_one(state: State<AppState>, principal: Principal, Path(novel_id): Path<i32>, Json(update): Json<LibraryEntryUpdate>) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let user_id = require_user(&principal)?;
+    let novel = load_novel(&state.db, novel_id).await?;
+    if let Some(chapter_id) = update.last_read_chapter_id {
+        check_chapter(&state.db, novel_id, chapter_id).await?;
+    }
+    let existing = find_entry(&state.db, user_id, novel_id).await?;
+    let is_new = existing.is_none();
+    let mut active_model = match existing {
+        Some(existing) => existing.into_active_model(),
+        None => ActiveModel { user_id: Set(user_id), novel_id: Set(novel_id), ..Default::default() },
+    };
+    active_model.status = Set(update.status);
+    active_model.last_read_chapter_id = Set(update.last_read_chapter_id);
+    active_model.personal_rating = Set(update.personal_rating);
+    active_model.notes = Set(update.notes);
+    active_model.started_date = Set(update.started_date);
+    active_model.completed_date = Set(update.completed_date);
+    let model = if is_new { active_model.insert(&state.db).await } else { active_model.update(&state.db).await }
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"failed to update item": e.to_string()}))))?;
+    let status = if is_new { StatusCode::CREATED } else { StatusCode::OK };
+    Ok((status, Json(entry_response(&state.db, model, novel).await?)))
+}
+
+pub async fn remove(state: State<AppState>, principal: Principal, Path(novel_id): Path<i32>) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let user_id = require_user(&principal)?;
+    let model = load_item(&state.db, user_id, novel_id).await?;
+    model.delete(&state.db)
+        .await
+        .map_err(db_error)?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Marks a chapter read, adding the novel to the library as `reading` if needed.
+pub async fn mark_read(state: State<AppState>, principal: Principal, Path((novel_id, chapter_id)): Path<(i32, i32)>) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let user_id = require_user(&principal)?;
+    let novel = load_novel(&state.db, novel_id).await?;
+    check_chapter(&state.db, novel_id, chapter_id).await?;
+    let now = chrono::Utc::now().naive_utc();
+    // One upsert, so two first reads of the same novel can't both try to create the entry
+    let entry = ActiveModel {
+        user_id: Set(user_id),
+        novel_id: Set(novel_id),
+        status: Set(Status::Reading),
+        last_read_chapter_id: Set(Some(chapter_id)),
+        last_read_at: Set(Some(now)),
+        ..Default::default()
+    };
+    let model = Entity::insert(entry)
+        .on_conflict(
+            OnConflict::columns([Column::UserId, Column::NovelId])
+                .update_columns([Column::LastReadChapterId, Column::LastReadAt])
+                .value(Column::Status, Expr::cust("CASE WHEN library_entry.status = 'plan_to_read' THEN 'reading' ELSE library_entry.status END"))
+                .to_owned(),
+        )
+        .exec_with_returning(&state.db)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"failed to update item": e.to_string()}))))?;
+    let read = library_chapter_read::ActiveModel {
+        library_entry_id: Set(model.id),
+        chapter_id: Set(chapter_id),
+        read_at: Set(now),
+    };
+    library_chapter_read::Entity::insert(read)
+        .on_conflict(OnConflict::columns([library_chapter_read::Column::LibraryEntryId, library_chapter_read::Column::ChapterId]).do_nothing().to_owned())
+        .do_nothing()
+        .exec(&state.db)
+        .await
+        .map_err(db_error)?;
+    Ok(Json(entry_response(&state.db, model, novel).await?))
+}
+
+/// Marks a chapter unread. If it was the last read chapter, the previous one read takes its place.
+pub async fn mark_unread(state: State<AppState>, principal: Principal, Path((novel_id, chapter_id)): Path<(i32, i32)>) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let user_id = require_user(&principal)?;
+    let novel = load_novel(&state.db, novel_id).await?;
+    let model = load_item(&state.db, user_id, novel_id).await?;
+    library_chapter_read::Entity::delete_by_id((model.id, chapter_id))
+        .exec(&state.db)
+        .await
+        .map_err(db_error)?;
+    let model = if model.last_read_chapter_id == Some(chapter_id) {
+        let previous = library_chapter_read::Entity::find()
+            .filter(library_chapter_read::Column::LibraryEntryId.eq(model.id))
+            .order_by_desc(library_chapter_read::Column::ReadAt)
+            .one(&state.db)
+            .await
+            .map_err(db_error)?;
+        let mut active_model = model.into_active_model();
+        active_model.last_read_chapter_id = Set(previous.as_ref().map(|read| read.chapter_id));
+        active_model.last_read_at = Set(previous.map(|read| read.read_at));
+        active_model.update(&state.db)
+            .await
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"failed to update item": e.to_string()}))))?
+    } else {
+        model
+    };
+    Ok(Json(entry_response(&state.db, model, novel).await?))
+}
+
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/library", get(list))
+        .route("/library/{novel_id}", get(read_one))
+        .route("/library/{novel_id}", put(put_one))
+        .route("/library/{novel_id}", delete(remove))
+        .route("/library/{novel_id}/chapters/{chapter_id}/read", post(mark_read))
+        .route("/library/{novel_id}/chapters/{chapter_id}/read", delete(mark_unread))
+}
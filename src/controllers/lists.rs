@@ -0,0 +1,341 @@
+use serde_json::json;
+use axum::{Router, extract::{Path, Query, State}, http::StatusCode, routing::{delete, get, post, put}, response::IntoResponse, Json};
+use serde::{Deserialize, Serialize};
+use sea_orm::{ActiveModelTrait, ConnectionTrait, DbBackend, EntityTrait, FromQueryResult, IntoActiveModel, PaginatorTrait, QueryFilter, QueryOrder, Set, Statement, TransactionTrait, sea_query::{Expr, OnConflict}};
+use sea_orm::prelude::*;
+use std::collections::HashSet;
+use crate::app_state::AppState;
+use crate::models::{novel, novel_reading_list, reading_list_follow, user, user_preference};
+use crate::models::reading_list::{Column, Entity, Model, Visibility};
+use crate::services::{auth::Principal, markdown, preferences::{self, ListOptions}};
+use super::profile::ProfileNovel;
+
+const DEFAULT_LIMIT: u64 = 20;
+const MAX_LIMIT: u64 = 100;
+
+/// Shared by every listing; callers append the WHERE and ORDER BY clauses.
+const SUMMARY_SELECT: &str = r#"
+    SELECT rl.id, rl.name, rl.description, rl.description_html, rl.last_updated, u.username AS owner,
+        (SELECT COUNT(*) FROM public.novel_reading_list nrl WHERE nrl.reading_list_id = rl.id) AS novel_count,
+        (SELECT COUNT(*) FROM public.reading_list_follow f WHERE f.reading_list_id = rl.id) AS follower_count
+    FROM public.reading_list rl
+    JOIN public.user u ON u.id = rl.user_id
+    WHERE u.deletion_scheduled_for IS NULL"#;
+
+/// A public list as shown when browsing.
+#[derive(Clone, Debug, Default, Serialize, FromQueryResult)]
+pub struct ListSummary {
+    pub id: i32,
+    pub name: Option<String>,
+    pub description: Option<String>,
+    pub description_html: Option<String>,
+    pub last_updated: DateTime,
+    pub owner: String,
+    pub novel_count: i64,
+    pub follower_count: i64,
+}
+
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct ListNovel {
+    pub position: i32,
+    pub blurb: Option<String>,
+    pub blurb_html: Option<String>,
+    pub novel: ProfileNovel,
+}
+
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct ListDetail {
+    pub id: i32,
+    pub created_at: DateTime,
+    pub last_updated: DateTime,
+    pub name: Option<String>,
+    pub description: Option<String>,
+    pub description_html: Option<String>,
+    pub visibility: Visibility,
+    pub owner: String,
+    pub follower_count: u64,
+    pub following: bool,
+    /// Only shown to the owner.
+    pub share_token: Option<String>,
+    pub novels: Vec<ListNovel>,
+}
+
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ListSort {
+    /// Most followed first.
+    #[default]
+    Popular,
+    /// Most recently updated first.
+    Recent,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct BrowseOptions {
+    #[serde(default)]
+    pub sort: ListSort,
+    pub limit: Option<u64>,
+    pub offset: Option<u64>,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct ShareOptions {
+    pub token: Option<String>,
+}
+
+/// One novel of a list, in the order given.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ListNovelUpdate {
+    pub novel_id: i32,
+    pub blurb: Option<String>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ShareToken {
+    pub share_token: String,
+}
+
+fn db_error(e: DbErr) -> (StatusCode, Json<serde_json::Value>) {
+    (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"error": e.to_string()})))
+}
+
+fn require_user(principal: &Principal) -> Result<i32, (StatusCode, Json<serde_json::Value>)> {
+    principal.user_id.ok_or_else(|| (StatusCode::FORBIDDEN, Json(json!({"error": "api key is not tied to a user"}))))
+}
+
+async fn load_item<C: ConnectionTrait>(db: &C, id: i32) -> Result<Model, (StatusCode, Json<serde_json::Value>)> {
+    Entity::find_by_id(id)
+        .one(db)
+        .await
+        .map_err(db_error)?
+        .ok_or_else(|| (StatusCode::NOT_FOUND, Json(json!({"error": "not found"}))))
+}
+
+async fn load_owned<C: ConnectionTrait>(db: &C, id: i32, user_id: i32) -> Result<Model, (StatusCode, Json<serde_json::Value>)> {
+    let model = load_item(db, id).await?;
+    if model.user_id != user_id {
+        return Err((StatusCode::FORBIDDEN, Json(json!({"error": "not your reading list"}))));
+    }
+    Ok(model)
+}
+
+/// Public lists are readable by anyone, unlisted ones with the share token, private ones by the owner only.
+fn can_read(model: &Model, user_id: Option<i32>, token: Option<&str>) -> bool {
+    match model.visibility {
+        Visibility::Public => true,
+        _ if user_id == Some(model.user_id) => true,
+        Visibility::Unlisted => token.is_some() && model.share_token.as_deref() == token,
+        Visibility::Private => false,
+    }
+}
+
+async fn summaries<C: ConnectionTrait>(db: &C, clause: &str, values: Vec<sea_orm::Value>) -> Result<Vec<ListSummary>, DbErr> {
+    ListSummary::find_by_statement(Statement::from_sql_and_values(DbBackend::Postgres, format!("{SUMMARY_SELECT} {clause}"), values))
+        .all(db)
+        .await
+}
+
+/// Novels the reader's content preferences hide are left out.
+async fn detail<C: ConnectionTrait>(db: &C, model: Model, user_id: Option<i32>, preference: Option<&user_preference::Model>) -> Result<ListDetail, DbErr> {
+    let owner = user::Entity::find_by_id(model.user_id)
+        .one(db)
+        .await?
+        .map(|owner| owner.username)
+        .unwrap_or_default();
+    let mut novels: Vec<ListNovel> = novel_reading_list::Entity::find()
+        .filter(novel_reading_list::Column::ReadingListId.eq(model.id))
+        .order_by_asc(novel_reading_list::Column::Position)
+        .order_by_asc(novel_reading_list::Column::NovelId)
+        .find_also_related(novel::Entity)
+        .all(db)
+        .await?
+        .into_iter()
+        .map(|(entry, novel)| ListNovel {
+            position: entry.position,
+            blurb: entry.blurb,
+            blurb_html: entry.blurb_html,
+            novel: novel.map(Into::into).unwrap_or_default(),
+        })
+        .collect();
+    if let Some(preference) = preference {
+        let allowed = preferences::allowed_novel_ids(db, preference, novels.iter().map(|entry| entry.novel.id).collect()).await?;
+        novels.retain(|entry| allowed.contains(&entry.novel.id));
+    }
+    let follower_count = reading_list_follow::Entity::find()
+        .filter(reading_list_follow::Column::ReadingListId.eq(model.id))
+        .count(db)
+        .await?;
+    let following = match user_id {
+        Some(user_id) => reading_list_follow::Entity::find_by_id((user_id, model.id)).one(db).await?.is_some(),
+        None => false,
+    };
+    let is_owner = user_id == Some(model.user_id);
+    Ok(ListDetail {
+        id: model.id,
+        created_at: model.created_at,
+        last_updated: model.last_updated,
+        name: model.name,
+        description: model.description,
+        description_html: model.description_html,
+        visibility: model.visibility,
+        owner,
+        follower_count,
+        following,
+        share_token: if is_owner { model.share_token } else { None },
+        novels,
+    })
+}
+
+/// Browses public lists.
+pub async fn list(state: State<AppState>, Query(options): Query<BrowseOptions>) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let order = match options.sort {
+        ListSort::Popular => "follower_count DESC, rl.last_updated DESC",
+        ListSort::Recent => "rl.last_updated DESC",
+    };
+    let limit = options.limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT);
+    let clause = format!("AND rl.visibility = 'public' ORDER BY {order}, rl.id LIMIT $1 OFFSET $2");
+    let responses = summaries(&state.db, &clause, vec![(limit as i64).into(), (options.offset.unwrap_or(0) as i64).into()])
+        .await
+        .map_err(db_error)?;
+    Ok(Json(responses))
+}
+
+/// Public lists the signed-in user follows.
+pub async fn followed(state: State<AppState>, principal: Principal) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let user_id = require_user(&principal)?;
+    let clause = "AND rl.visibility = 'public'
+        AND rl.id IN (SELECT f.reading_list_id FROM public.reading_list_follow f WHERE f.user_id = $1)
+        ORDER BY rl.last_updated DESC, rl.id";
+    let responses = summaries(&state.db, clause, vec![user_id.into()])
+        .await
+        .map_err(db_error)?;
+    Ok(Json(responses))
+}
+
+/// Reads a list; unlisted lists need `?token=`.
+pub async fn read_one(state: State<AppState>, principal: Option<Principal>, Path(id): Path<i32>, Query(options): Query<ShareOptions>, Query(list_options): Query<ListOptions>) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let user_id = principal.as_ref().and_then(|principal| principal.user_id);
+    let model = load_item(&state.db, id).await?;
+    if !can_read(&model, user_id, options.token.as_deref()) {
+        return Err((StatusCode::NOT_FOUND, Json(json!({"error": "not found"}))));
+    }
+    let preference = preferences::for_request(&state.db, principal.as_ref(), &list_options).await.map_err(db_error)?;
+    Ok(Json(detail(&state.db, model, user_id, preference.as_ref()).await.map_err(db_error)?))
+}
+
+/// Resolves a share link.
+pub async fn read_shared(state: State<AppState>, principal: Option<Principal>, Path(token): Path<String>, Query(options): Query<ListOptions>) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let user_id = principal.as_ref().and_then(|principal| principal.user_id);
+    let model = Entity::find()
+        .filter(Column::ShareToken.eq(token))
+        .filter(Column::Visibility.ne(Visibility::Private))
+        .one(&state.db)
+        .await
+        .map_err(db_error)?
+        .ok_or_else(|| (StatusCode::NOT_FOUND, Json(json!({"error": "not found"}))))?;
+    let preference = preferences::for_request(&state.db, principal.as_ref(), &options).await.map_err(db_error)?;
+    Ok(Json(detail(&state.db, model, user_id, preference.as_ref()).await.map_err(db_error)?))
+}
+
+/// Replaces the novels of a list; their order in the body is the order of the list.
+pub async fn put_novels(state: State<AppState>, principal: Principal, Path(id): Path<i32>, Json(novels): Json<Vec<ListNovelUpdate>>) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let user_id = require_user(&principal)?;
+    let model = load_owned(&state.db, id, user_id).await?;
+    let ids: HashSet<i32> = novels.iter().map(|entry| entry.novel_id).collect();
+    if ids.len() != novels.len() {
+        return Err((StatusCode::UNPROCESSABLE_ENTITY, Json(json!({"error": "a novel can only be listed once"}))));
+    }
+    let found = novel::Entity::find()
+        .filter(novel::Column::Id.is_in(ids.iter().copied()))
+        .count(&state.db)
+        .await
+        .map_err(db_error)?;
+    if found as usize != ids.len() {
+        return Err((StatusCode::NOT_FOUND, Json(json!({"error": "novel not found"}))));
+    }
+    let txn = state.db.begin().await.map_err(db_error)?;
+    novel_reading_list::Entity::delete_many()
+        .filter(novel_reading_list::Column::ReadingListId.eq(model.id))
+        .exec(&txn)
+        .await
+        .map_err(db_error)?;
+    if !novels.is_empty() {
+        let rows = novels.into_iter().enumerate().map(|(position, entry)| novel_reading_list::ActiveModel {
+            novel_id: Set(entry.novel_id),
+            reading_list_id: Set(model.id),
+            position: Set(position as i32),
+            blurb_html: Set(markdown::render_opt(entry.blurb.as_deref())),
+            blurb: Set(entry.blurb),
+        });
+        novel_reading_list::Entity::insert_many(rows)
+            .exec(&txn)
+            .await
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"failed to insert item": e.to_string()}))))?;
+    }
+    Entity::update_many()
+        .col_expr(Column::LastUpdated, Expr::current_timestamp())
+        .filter(Column::Id.eq(model.id))
+        .exec(&txn)
+        .await
+        .map_err(db_error)?;
+    txn.commit().await.map_err(db_error)?;
+    let model = load_item(&state.db, id).await?;
+    Ok(Json(detail(&state.db, model, Some(user_id), None).await.map_err(db_error)?))
+}
+
+/// Issues a new share token, invalidating links made with the previous one.
+pub async fn rotate_share_token(state: State<AppState>, principal: Principal, Path(id): Path<i32>) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let user_id = require_user(&principal)?;
+    let model = load_owned(&state.db, id, user_id).await?;
+    let share_token = hex::encode(rand::random::<[u8; 16]>());
+    let mut active_model = model.into_active_model();
+    active_model.share_token = Set(Some(share_token.clone()));
+    active_model.update(&state.db)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"failed to update item": e.to_string()}))))?;
+    Ok(Json(ShareToken { share_token }))
+}
+
+pub async fn follow(state: State<AppState>, principal: Principal, Path(id): Path<i32>) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let user_id = require_user(&principal)?;
+    let model = load_item(&state.db, id).await?;
+    if model.visibility != Visibility::Public {
+        return Err((StatusCode::NOT_FOUND, Json(json!({"error": "not found"}))));
+    }
+    if model.user_id == user_id {
+        return Err((StatusCode::UNPROCESSABLE_ENTITY, Json(json!({"error": "cannot follow your own reading list"}))));
+    }
+    let active_model = reading_list_follow::ActiveModel {
+        user_id: Set(user_id),
+        reading_list_id: Set(model.id),
+        created_at: Set(chrono::Utc::now().naive_utc()),
+    };
+    reading_list_follow::Entity::insert(active_model)
+        .on_conflict(OnConflict::columns([reading_list_follow::Column::UserId, reading_list_follow::Column::ReadingListId]).do_nothing().to_owned())
+        .do_nothing()
+        .exec(&state.db)
+        .await
+        .map_err(db_error)?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+pub async fn unfollow(state: State<AppState>, principal: Principal, Path(id): Path<i32>) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let user_id = require_user(&principal)?;
+    reading_list_follow::Entity::delete_by_id((user_id, id))
+        .exec(&state.db)
+        .await
+        .map_err(db_error)?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/lists", get(list))
+        .route("/lists/followed", get(followed))
+        .route("/lists/shared/{token}", get(read_shared))
+        .route("/lists/{id}", get(read_one))
+        .route("/lists/{id}/novels", put(put_novels))
+        .route("/lists/{id}/share-token", post(rotate_share_token))
+        .route("/lists/{id}/follow", post(follow))
+        .route("/lists/{id}/follow", delete(unfollow))
+}
@@ -0,0 +1,277 @@
+use serde_json::json;
+use axum::{Router, extract::{Query, State}, http::{StatusCode, header}, routing::{delete, get, patch, put}, response::IntoResponse, Json};
+use serde::{Deserialize, Serialize};
+use sea_orm::{ActiveModelTrait, EntityTrait, QueryFilter, Set, IntoActiveModel};
+use sea_orm::prelude::*;
+use crate::app_state::AppState;
+use crate::models::user::{ActiveModel, Column, Entity, Model, Role};
+use crate::models::{user_preference, user_token::Purpose};
+use crate::services::{account, account_data, auth::{self, Principal}, markdown, password, reading_stats::{self, Period}, reputation::{self, Reputation}};
+use super::profile::{self, ProfileStats};
+
+/// The signed-in user's own account, including fields never shown on the public profile.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct Account {
+    pub id: i32,
+    pub created_at: DateTime,
+    pub username: String,
+    pub email: String,
+    pub email_verified_at: Option<DateTime>,
+    pub display_name: Option<String>,
+    pub avatar_url: Option<String>,
+    pub bio: Option<String>,
+    pub bio_html: Option<String>,
+    pub last_active: Option<DateTime>,
+    pub role: Role,
+    pub two_factor_enabled: bool,
+    pub privacy: Privacy,
+    pub stats: ProfileStats,
+    pub reputation: Reputation,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct Privacy {
+    pub show_reading_lists: bool,
+    pub show_reviews: bool,
+}
+
+impl Account {
+    fn new(model: Model, stats: ProfileStats, reputation: Reputation) -> Self {
+        Self {
+            id: model.id,
+            created_at: model.created_at,
+            username: model.username,
+            email: model.email,
+            email_verified_at: model.email_verified_at,
+            display_name: model.display_name,
+            avatar_url: model.avatar_url,
+            bio: model.bio,
+            bio_html: model.bio_html,
+            last_active: model.last_active,
+            role: model.role,
+            two_factor_enabled: model.totp_enabled_at.is_some(),
+            privacy: Privacy { show_reading_lists: model.show_reading_lists, show_reviews: model.show_reviews },
+            stats,
+            reputation,
+        }
+    }
+}
+
+/// Profile fields a user may change themselves. Email, password and role have dedicated flows.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct AccountPatch {
+    pub display_name: Option<String>,
+    pub avatar_url: Option<String>,
+    pub bio: Option<String>,
+    pub show_reading_lists: Option<bool>,
+    pub show_reviews: Option<bool>,
+}
+
+impl AccountPatch {
+    pub fn patch_active_model(&self, active_model: &mut ActiveModel) {
+        if self.display_name.is_some() {
+            active_model.display_name = Set(self.display_name.clone());
+        }if self.avatar_url.is_some() {
+            active_model.avatar_url = Set(self.avatar_url.clone());
+        }if self.bio.is_some() {
+            active_model.bio = Set(self.bio.clone());
+            active_model.bio_html = Set(markdown::render_opt(self.bio.as_deref()));
+        }if let Some(value) = self.show_reading_lists {
+            active_model.show_reading_lists = Set(value);
+        }if let Some(value) = self.show_reviews {
+            active_model.show_reviews = Set(value);
+        }
+    }
+}
+
+/// Content filters applied to novel and chapter listings; pass `?unfiltered=true` to bypass them.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct Preferences {
+    #[serde(default)]
+    pub excluded_tag_ids: Vec<i32>,
+    #[serde(default)]
+    pub original_languages: Vec<String>,
+    #[serde(default)]
+    pub chapter_languages: Vec<String>,
+    #[serde(default)]
+    pub hide_licensed: bool,
+}
+
+impl From<user_preference::Model> for Preferences {
+    fn from(model: user_preference::Model) -> Self {
+        Self {
+            excluded_tag_ids: model.excluded_tag_ids,
+            original_languages: model.original_languages,
+            chapter_languages: model.chapter_languages,
+            hide_licensed: model.hide_licensed,
+        }
+    }
+}
+
+/// Trims each language and drops empty and repeated ones, keeping the order given.
+fn clean_languages(languages: Vec<String>) -> Vec<String> {
+    let mut cleaned: Vec<String> = Vec::with_capacity(languages.len());
+    for language in languages {
+        let language = language.trim();
+        if !language.is_empty() && !cleaned.iter().any(|seen| seen == language) {
+            cleaned.push(language.to_string());
+        }
+    }
+    cleaned
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct EmailChange {
+    pub email: String,
+    pub password: String,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct DeleteAccount {
+    pub password: String,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct DeletionScheduled {
+    pub deletion_scheduled_for: DateTime,
+}
+
+fn db_error(e: DbErr) -> (StatusCode, Json<serde_json::Value>) {
+    (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"error": e.to_string()})))
+}
+
+pub async fn read(state: State<AppState>, principal: Principal) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let model = auth::current_user(&state.db, &principal).await?;
+    let stats = profile::profile_stats(&state.db, model.id).await.map_err(db_error)?;
+    let reputation = reputation::for_user(&state.db, &model).await.map_err(db_error)?;
+    Ok(Json(Account::new(model, stats, reputation)))
+}
+
+pub async fn patch_one(state: State<AppState>, principal: Principal, Json(patch): Json<AccountPatch>) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let model = auth::current_user(&state.db, &principal).await?;
+    let mut active_model = model.into_active_model();
+    patch.patch_active_model(&mut active_model);
+    let model = active_model.update(&state.db)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"failed to update item": e.to_string()}))))?;
+    let stats = profile::profile_stats(&state.db, model.id).await.map_err(db_error)?;
+    let reputation = reputation::for_user(&state.db, &model).await.map_err(db_error)?;
+    Ok(Json(Account::new(model, stats, reputation)))
+}
+
+/// Moves the account to a new address, which has to be verified again. Verification links sent
+/// to the old address stop working.
+pub async fn put_email(state: State<AppState>, principal: Principal, Json(change): Json<EmailChange>) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let model = auth::current_user(&state.db, &principal).await?;
+    if !password::verify(&change.password, &model.password_hash) {
+        return Err((StatusCode::UNAUTHORIZED, Json(json!({"error": "invalid credentials"}))));
+    }
+    let email = change.email.trim().to_string();
+    if !email.contains('@') {
+        return Err((StatusCode::UNPROCESSABLE_ENTITY, Json(json!({"error": "a valid email is required"}))));
+    }
+    let model = if email == model.email {
+        model
+    } else {
+        let taken = Entity::find()
+            .filter(Column::Email.eq(email.as_str()))
+            .one(&state.db)
+            .await
+            .map_err(db_error)?;
+        if taken.is_some() {
+            return Err((StatusCode::CONFLICT, Json(json!({"error": "email already registered"}))));
+        }
+        let mut active_model = model.into_active_model();
+        active_model.email = Set(email);
+        active_model.email_verified_at = Set(None);
+        let model = active_model.update(&state.db)
+            .await
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"failed to update item": e.to_string()}))))?;
+        account::revoke_tokens(&state.db, model.id, Purpose::EmailVerification).await.map_err(db_error)?;
+        account::send_verification(&state, &model).await.map_err(db_error)?;
+        model
+    };
+    let stats = profile::profile_stats(&state.db, model.id).await.map_err(db_error)?;
+    let reputation = reputation::for_user(&state.db, &model).await.map_err(db_error)?;
+    Ok(Json(Account::new(model, stats, reputation)))
+}
+
+async fn find_preference(state: &State<AppState>, user_id: i32) -> Result<Option<user_preference::Model>, (StatusCode, Json<serde_json::Value>)> {
+    user_preference::Entity::find()
+        .filter(user_preference::Column::UserId.eq(user_id))
+        .one(&state.db)
+        .await
+        .map_err(db_error)
+}
+
+pub async fn read_preferences(state: State<AppState>, principal: Principal) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let model = auth::current_user(&state.db, &principal).await?;
+    let resp = find_preference(&state, model.id).await?.map(Preferences::from).unwrap_or_default();
+    Ok(Json(resp))
+}
+
+pub async fn put_preferences(state: State<AppState>, principal: Principal, Json(update): Json<Preferences>) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let model = auth::current_user(&state.db, &principal).await?;
+    let existing = find_preference(&state, model.id).await?;
+    let is_new = existing.is_none();
+    let mut active_model = match existing {
+        Some(existing) => existing.into_active_model(),
+        None => user_preference::ActiveModel { user_id: Set(model.id), ..Default::default() },
+    };
+    let mut excluded_tag_ids = update.excluded_tag_ids;
+    excluded_tag_ids.sort_unstable();
+    excluded_tag_ids.dedup();
+    active_model.excluded_tag_ids = Set(excluded_tag_ids);
+    active_model.original_languages = Set(clean_languages(update.original_languages));
+    active_model.chapter_languages = Set(clean_languages(update.chapter_languages));
+    active_model.hide_licensed = Set(update.hide_licensed);
+    let saved = if is_new { active_model.insert(&state.db).await } else { active_model.update(&state.db).await }
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"failed to update item": e.to_string()}))))?;
+    Ok(Json(Preferences::from(saved)))
+}
+
+pub async fn export(state: State<AppState>, principal: Principal) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let model = auth::current_user(&state.db, &principal).await?;
+    let filename = format!("attachment; filename=\"novelupdates-{}-export.json\"", model.username);
+    let resp = account_data::export(&state.db, model).await.map_err(db_error)?;
+    Ok(([(header::CONTENT_DISPOSITION, filename)], Json(resp)))
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct StatsOptions {
+    /// Limits the statistics to one calendar year; all time when absent.
+    pub year: Option<i32>,
+}
+
+/// Reading recap built from chapter reads and library entries.
+pub async fn stats(state: State<AppState>, principal: Principal, Query(options): Query<StatsOptions>) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let model = auth::current_user(&state.db, &principal).await?;
+    let period = match options.year {
+        Some(year) => Period::year(year).ok_or_else(|| (StatusCode::BAD_REQUEST, Json(json!({"error": "invalid year"}))))?,
+        None => Period::default(),
+    };
+    let resp = reading_stats::for_user(&state.db, model.id, period).await.map_err(db_error)?;
+    Ok(Json(resp))
+}
+
+/// Schedules the account for deletion after the grace period. Logging in again cancels it.
+pub async fn delete_one(state: State<AppState>, principal: Principal, Json(delete): Json<DeleteAccount>) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let model = auth::current_user(&state.db, &principal).await?;
+    if !password::verify(&delete.password, &model.password_hash) {
+        return Err((StatusCode::UNAUTHORIZED, Json(json!({"error": "invalid credentials"}))));
+    }
+    let deletion_scheduled_for = account_data::schedule_deletion(&state.db, model).await.map_err(db_error)?;
+    Ok((StatusCode::ACCEPTED, Json(DeletionScheduled { deletion_scheduled_for })))
+}
+
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/me", get(read))
+        .route("/me", patch(patch_one))
+        .route("/me", delete(delete_one))
+        .route("/me/email", put(put_email))
+        .route("/me/export", get(export))
+        .route("/me/preferences", get(read_preferences))
+        .route("/me/preferences", put(put_preferences))
+        .route("/me/stats", get(stats))
+}
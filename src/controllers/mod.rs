@@ -1,35 +1,67 @@
 
 
+pub mod api_key;
 pub mod artist;
+pub mod auth;
 pub mod author;
 pub mod chapter;
+pub mod comment;
+pub mod export;
 pub mod group;
+pub mod import;
+pub mod library;
+pub mod lists;
+pub mod me;
+pub mod notification;
 pub mod novel;
+pub mod novel_tag;
+pub mod profile;
 pub mod publisher;
 pub mod reading_list;
+pub mod report;
 pub mod review;
 pub mod source;
+pub mod stats;
 pub mod tag;
 pub mod r#type;
+pub mod two_factor;
+pub mod updates;
 pub mod user;
-use axum::Router;
+use axum::{Router, middleware};
 use crate::app_state::AppState;
 
 /// Returns a Router with all entity controllers merged.
 pub fn routes(prefix:&str, state:AppState) -> Router {
     let router = Router::new()
+        .merge(api_key::routes())
         .merge(artist::routes())
+        .merge(auth::routes())
         .merge(author::routes())
         .merge(chapter::routes())
+        .merge(comment::routes())
+        .merge(export::routes())
         .merge(group::routes())
+        .merge(import::routes())
+        .merge(library::routes())
+        .merge(lists::routes())
+        .merge(me::routes())
+        .merge(notification::routes())
         .merge(novel::routes())
+        .merge(novel_tag::routes())
+        .merge(profile::routes())
         .merge(publisher::routes())
         .merge(reading_list::routes())
+        .merge(report::routes())
         .merge(review::routes())
         .merge(source::routes())
+        .merge(stats::routes())
         .merge(tag::routes())
         .merge(r#type::routes())
+        .merge(two_factor::routes())
+        .merge(updates::routes())
         .merge(user::routes())
+        .layer(middleware::from_fn_with_state(state.clone(), crate::services::rate_limit::limit))
+        .layer(middleware::from_fn_with_state(state.clone(), crate::services::auth::authenticate))
         ;
     Router::new()
         .nest(prefix, router)
@@ -0,0 +1,325 @@
+use serde_json::json;
+use axum::{Router, extract::{Path, Query, State}, http::StatusCode, routing::{delete, get, post, put}, response::{Html, IntoResponse}, Json};
+use serde::{Deserialize, Serialize};
+use sea_orm::{ActiveModelTrait, ConnectionTrait, EntityTrait, IntoActiveModel, ModelTrait, PaginatorTrait, QueryFilter, QueryOrder, QuerySelect, Set, sea_query::{Expr, OnConflict}};
+use sea_orm::prelude::*;
+use crate::app_state::AppState;
+use crate::models::{group, group_follow, notification_mute, notification_preference, novel};
+use crate::models::notification_preference::DigestFrequency;
+use crate::models::notification::{Column, Entity, Kind, Model};
+use crate::services::{auth::Principal, digest};
+
+const DEFAULT_LIMIT: u64 = 50;
+const MAX_LIMIT: u64 = 200;
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct Notification {
+    pub id: i32,
+    pub created_at: DateTime,
+    pub kind: Kind,
+    pub title: String,
+    pub novel_id: Option<i32>,
+    pub chapter_id: Option<i32>,
+    pub source_id: Option<i32>,
+    pub group_id: Option<i32>,
+    pub review_id: Option<i32>,
+    pub actor_id: Option<i32>,
+    pub read_at: Option<DateTime>,
+}
+
+impl From<Model> for Notification {
+    fn from(model: Model) -> Self {
+        Self {
+            id: model.id,
+            created_at: model.created_at,
+            kind: model.kind,
+            title: model.title,
+            novel_id: model.novel_id,
+            chapter_id: model.chapter_id,
+            source_id: model.source_id,
+            group_id: model.group_id,
+            review_id: model.review_id,
+            actor_id: model.actor_id,
+            read_at: model.read_at,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct NotificationFilter {
+    #[serde(default)]
+    pub unread: bool,
+    pub limit: Option<u64>,
+    pub offset: Option<u64>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct UnreadCount {
+    pub unread: u64,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct MarkedRead {
+    pub notifications: u64,
+}
+
+/// Which kinds of notification are created for the user, and how often new chapters are mailed.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct NotificationPreferences {
+    pub new_chapter: bool,
+    pub new_source: bool,
+    pub review_reply: bool,
+    #[serde(default)]
+    pub digest: DigestFrequency,
+}
+
+impl Default for NotificationPreferences {
+    fn default() -> Self {
+        Self { new_chapter: true, new_source: true, review_reply: true, digest: DigestFrequency::Never }
+    }
+}
+
+impl From<notification_preference::Model> for NotificationPreferences {
+    fn from(model: notification_preference::Model) -> Self {
+        Self {
+            new_chapter: model.new_chapter,
+            new_source: model.new_source,
+            review_reply: model.review_reply,
+            digest: model.digest_frequency,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct UnsubscribeOptions {
+    pub token: String,
+}
+
+fn db_error(e: DbErr) -> (StatusCode, Json<serde_json::Value>) {
+    (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"error": e.to_string()})))
+}
+
+fn require_user(principal: &Principal) -> Result<i32, (StatusCode, Json<serde_json::Value>)> {
+    principal.user_id.ok_or_else(|| (StatusCode::FORBIDDEN, Json(json!({"error": "api key is not tied to a user"}))))
+}
+
+async fn load_item<C: ConnectionTrait>(db: &C, user_id: i32, id: i32) -> Result<Model, (StatusCode, Json<serde_json::Value>)> {
+    Entity::find_by_id(id)
+        .filter(Column::UserId.eq(user_id))
+        .one(db)
+        .await
+        .map_err(db_error)?
+        .ok_or_else(|| (StatusCode::NOT_FOUND, Json(json!({"error": "not found"}))))
+}
+
+pub async fn list(state: State<AppState>, principal: Principal, Query(filter): Query<NotificationFilter>) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let user_id = require_user(&principal)?;
+    let mut query = Entity::find()
+        .filter(Column::UserId.eq(user_id))
+        .order_by_desc(Column::CreatedAt)
+        .order_by_desc(Column::Id)
+        .limit(filter.limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT))
+        .offset(filter.offset.unwrap_or(0));
+    if filter.unread {
+        query = query.filter(Column::ReadAt.is_null());
+    }
+    let models = query.all(&state.db).await.map_err(db_error)?;
+    let responses: Vec<Notification> = models.into_iter().map(Into::into).collect();
+    Ok(Json(responses))
+}
+
+/// The unread badge.
+pub async fn unread_count(state: State<AppState>, principal: Principal) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let user_id = require_user(&principal)?;
+    let unread = Entity::find()
+        .filter(Column::UserId.eq(user_id))
+        .filter(Column::ReadAt.is_null())
+        .count(&state.db)
+        .await
+        .map_err(db_error)?;
+    Ok(Json(UnreadCount { unread }))
+}
+
+pub async fn mark_read(state: State<AppState>, principal: Principal, Path(id): Path<i32>) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let user_id = require_user(&principal)?;
+    let model = load_item(&state.db, user_id, id).await?;
+    if model.read_at.is_some() {
+        return Ok(Json(Notification::from(model)));
+    }
+    let mut active_model = model.into_active_model();
+    active_model.read_at = Set(Some(chrono::Utc::now().naive_utc()));
+    let model = active_model.update(&state.db)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"failed to update item": e.to_string()}))))?;
+    Ok(Json(Notification::from(model)))
+}
+
+pub async fn mark_all_read(state: State<AppState>, principal: Principal) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let user_id = require_user(&principal)?;
+    let result = Entity::update_many()
+        .col_expr(Column::ReadAt, Expr::value(chrono::Utc::now().naive_utc()))
+        .filter(Column::UserId.eq(user_id))
+        .filter(Column::ReadAt.is_null())
+        .exec(&state.db)
+        .await
+        .map_err(db_error)?;
+    Ok(Json(MarkedRead { notifications: result.rows_affected }))
+}
+
+pub async fn remove(state: State<AppState>, principal: Principal, Path(id): Path<i32>) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let user_id = require_user(&principal)?;
+    let model = load_item(&state.db, user_id, id).await?;
+    model.delete(&state.db)
+        .await
+        .map_err(db_error)?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn find_preference<C: ConnectionTrait>(db: &C, user_id: i32) -> Result<Option<notification_preference::Model>, (StatusCode, Json<serde_json::Value>)> {
+    notification_preference::Entity::find()
+        .filter(notification_preference::Column::UserId.eq(user_id))
+        .one(db)
+        .await
+        .map_err(db_error)
+}
+
+pub async fn read_preferences(state: State<AppState>, principal: Principal) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let user_id = require_user(&principal)?;
+    let resp = find_preference(&state.db, user_id).await?.map(NotificationPreferences::from).unwrap_or_default();
+    Ok(Json(resp))
+}
+
+pub async fn put_preferences(state: State<AppState>, principal: Principal, Json(update): Json<NotificationPreferences>) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let user_id = require_user(&principal)?;
+    let existing = find_preference(&state.db, user_id).await?;
+    let is_new = existing.is_none();
+    let mut active_model = match existing {
+        Some(existing) => existing.into_active_model(),
+        None => notification_preference::ActiveModel { user_id: Set(user_id), ..Default::default() },
+    };
+    active_model.new_chapter = Set(update.new_chapter);
+    active_model.new_source = Set(update.new_source);
+    active_model.review_reply = Set(update.review_reply);
+    active_model.digest_frequency = Set(update.digest);
+    let saved = if is_new { active_model.insert(&state.db).await } else { active_model.update(&state.db).await }
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"failed to update item": e.to_string()}))))?;
+    Ok(Json(NotificationPreferences::from(saved)))
+}
+
+/// Target of the link in digest mails; works without signing in. Only asks for confirmation, so
+/// link scanners that open every URL in a mail do not unsubscribe anyone.
+pub async fn confirm_unsubscribe(state: State<AppState>, Query(options): Query<UnsubscribeOptions>) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    if !digest::token_exists(&state.db, &options.token).await.map_err(db_error)? {
+        return Err((StatusCode::NOT_FOUND, Json(json!({"error": "unknown unsubscribe token"}))));
+    }
+    Ok(Html(format!(
+        "<!doctype html>\n<title>Unsubscribe</title>\n<p>Stop receiving NovelUpdates digest mails?</p>\n\
+         <form method=\"post\" action=\"{}/api/digest/unsubscribe?token={}\">\n\
+         <input type=\"hidden\" name=\"List-Unsubscribe\" value=\"One-Click\">\n\
+         <button type=\"submit\">Unsubscribe</button>\n</form>\n",
+        state.app_url, options.token,
+    )))
+}
+
+/// Turns the digest off. Both the confirmation form and RFC 8058 one-click unsubscribe from mail
+/// clients post here; the token in the query string is all that is read.
+pub async fn unsubscribe(state: State<AppState>, Query(options): Query<UnsubscribeOptions>) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    if !digest::unsubscribe(&state.db, &options.token).await.map_err(db_error)? {
+        return Err((StatusCode::NOT_FOUND, Json(json!({"error": "unknown unsubscribe token"}))));
+    }
+    Ok(Html("<!doctype html>\n<title>Unsubscribed</title>\n<p>You will no longer receive digest mails.</p>\n"))
+}
+
+/// Ids of the novels the user muted.
+pub async fn list_mutes(state: State<AppState>, principal: Principal) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let user_id = require_user(&principal)?;
+    let novel_ids: Vec<i32> = notification_mute::Entity::find()
+        .filter(notification_mute::Column::UserId.eq(user_id))
+        .order_by_asc(notification_mute::Column::NovelId)
+        .select_only()
+        .column(notification_mute::Column::NovelId)
+        .into_tuple()
+        .all(&state.db)
+        .await
+        .map_err(db_error)?;
+    Ok(Json(novel_ids))
+}
+
+pub async fn mute(state: State<AppState>, principal: Principal, Path(novel_id): Path<i32>) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let user_id = require_user(&principal)?;
+    novel::Entity::find_by_id(novel_id)
+        .one(&state.db)
+        .await
+        .map_err(db_error)?
+        .ok_or_else(|| (StatusCode::NOT_FOUND, Json(json!({"error": "novel not found"}))))?;
+    let active_model = notification_mute::ActiveModel {
+        user_id: Set(user_id),
+        novel_id: Set(novel_id),
+        created_at: Set(chrono::Utc::now().naive_utc()),
+    };
+    notification_mute::Entity::insert(active_model)
+        .on_conflict(OnConflict::columns([notification_mute::Column::UserId, notification_mute::Column::NovelId]).do_nothing().to_owned())
+        .do_nothing()
+        .exec(&state.db)
+        .await
+        .map_err(db_error)?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+pub async fn unmute(state: State<AppState>, principal: Principal, Path(novel_id): Path<i32>) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let user_id = require_user(&principal)?;
+    notification_mute::Entity::delete_by_id((user_id, novel_id))
+        .exec(&state.db)
+        .await
+        .map_err(db_error)?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Following a group notifies the user whenever it starts a new source.
+pub async fn follow_group(state: State<AppState>, principal: Principal, Path(group_id): Path<i32>) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let user_id = require_user(&principal)?;
+    group::Entity::find_by_id(group_id)
+        .one(&state.db)
+        .await
+        .map_err(db_error)?
+        .ok_or_else(|| (StatusCode::NOT_FOUND, Json(json!({"error": "not found"}))))?;
+    let active_model = group_follow::ActiveModel {
+        user_id: Set(user_id),
+        group_id: Set(group_id),
+        created_at: Set(chrono::Utc::now().naive_utc()),
+    };
+    group_follow::Entity::insert(active_model)
+        .on_conflict(OnConflict::columns([group_follow::Column::UserId, group_follow::Column::GroupId]).do_nothing().to_owned())
+        .do_nothing()
+        .exec(&state.db)
+        .await
+        .map_err(db_error)?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+pub async fn unfollow_group(state: State<AppState>, principal: Principal, Path(group_id): Path<i32>) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let user_id = require_user(&principal)?;
+    group_follow::Entity::delete_by_id((user_id, group_id))
+        .exec(&state.db)
+        .await
+        .map_err(db_error)?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/me/notifications", get(list))
+        .route("/me/notifications/unread-count", get(unread_count))
+        .route("/me/notifications/read", post(mark_all_read))
+        .route("/me/notifications/preferences", get(read_preferences))
+        .route("/me/notifications/preferences", put(put_preferences))
+        .route("/me/notifications/mutes", get(list_mutes))
+        .route("/me/notifications/mutes/{novel_id}", put(mute))
+        .route("/me/notifications/mutes/{novel_id}", delete(unmute))
+        .route("/me/notifications/{id}", delete(remove))
+        .route("/me/notifications/{id}/read", post(mark_read))
+        .route("/digest/unsubscribe", get(confirm_unsubscribe))
+        .route("/digest/unsubscribe", post(unsubscribe))
+        .route("/groups/{id}/follow", post(follow_group))
+        .route("/groups/{id}/follow", delete(unfollow_group))
+}
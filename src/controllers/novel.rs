@@ -1,11 +1,15 @@
 use serde_json::json;
-use axum::{Router, extract::{Path, State}, http::StatusCode, routing::{delete, get, patch, post, put}, response::IntoResponse, Json};
+use axum::{Router, extract::{Path, Query, State}, http::{StatusCode, Uri}, routing::{delete, get, patch, post, put}, response::{IntoResponse, Response}, Json};
 use serde::{Deserialize, Serialize};
-use sea_orm::{ActiveModelTrait, ModelTrait, EntityTrait, Set, IntoActiveModel, ConnectionTrait};
+use sea_orm::{ActiveModelTrait, ModelTrait, EntityTrait, QueryFilter, Set, IntoActiveModel, ConnectionTrait};
 use sea_orm::EntityLoaderTrait;
 use sea_orm::prelude::*;
 use crate::app_state::AppState;
+use crate::services::{auth::Principal, markdown, preferences::{self, ListOptions}, slugs::{self, Resource}, tags};
 use crate::models::novel::{ActiveModel, Entity, Model, ModelEx, StatusOrigin};
+use crate::models::tag::Category;
+use crate::models::reading_list::Visibility;
+use super::novel_tag;
 use super::{artist::Artist as Artist, author::Author as Author, chapter::Chapter as Chapter, publisher::Publisher as Publisher, reading_list::ReadingList as ReadingList, review::Review as Review, source::Source as Source, tag::Tag as Tag, r#type::Type as Type, };
 #[derive(Clone, Debug, Default, Deserialize, Serialize)]
 pub struct Novel {
@@ -22,23 +26,26 @@ pub struct Novel {
     pub cover_image_url: Option<String>,
     pub default_name: String,
     pub description: Option<String>,
+    pub description_html: Option<String>,
     pub english_publisher: Option<String>,
+    /// Derived from the novel's genre tags; read-only.
     pub genres: Option<String>,
     pub licensed: Option<bool>,
     pub native_name: Option<String>,
-    pub original_language: String,
+    pub original_language: Option<String>,
     pub publishers: Option<Vec<Publisher>>,
-    pub rating_count: Option<i8>,
+    pub rating_count: Option<i32>,
     pub reading_lists: Option<Vec<ReadingList>>,
     pub release_frequency: Option<String>,
     pub reviews: Option<Vec<Review>>,
+    pub slug: Option<String>,
     pub sources: Option<Vec<Source>>,
     pub status_origin: Option<StatusOrigin>,
     pub tags: Option<Vec<Tag>>,
-    pub total_chapters: Option<i8>,
+    pub total_chapters: Option<i32>,
     pub r#type: Type,
-    pub views: Option<i8>,
-    pub year: Option<i8>
+    pub views: Option<i32>,
+    pub year: Option<i32>
     
 }
 
@@ -58,6 +65,7 @@ impl From<Model> for Novel {
             cover_image_url: model.cover_image_url,
             default_name: model.default_name,
             description: model.description,
+            description_html: model.description_html,
             english_publisher: model.english_publisher,
             genres: model.genres,
             licensed: model.licensed,
@@ -68,6 +76,7 @@ impl From<Model> for Novel {
             reading_lists: None,
             release_frequency: model.release_frequency,
             reviews: vec![].into(),
+            slug: model.slug,
             sources: vec![].into(),
             status_origin: model.status_origin,
             tags: None,
@@ -96,6 +105,7 @@ impl From<ModelEx> for Novel {
             cover_image_url: model.cover_image_url,
             default_name: model.default_name,
             description: model.description,
+            description_html: model.description_html,
             english_publisher: model.english_publisher,
             genres: model.genres,
             licensed: model.licensed,
@@ -103,9 +113,10 @@ impl From<ModelEx> for Novel {
             original_language: model.original_language,
             publishers: Some(model.publishers.into_iter().map(Publisher::from).collect()),
             rating_count: model.rating_count,
-            reading_lists: Some(model.reading_lists.into_iter().map(ReadingList::from).collect()),
+            reading_lists: Some(model.reading_lists.into_iter().filter(|list| list.visibility == Visibility::Public).map(ReadingList::from).collect()),
             release_frequency: model.release_frequency,
-            reviews: Some(model.reviews.into_iter().map(Review::from).collect()),
+            reviews: Some(model.reviews.into_iter().filter(|review| review.hidden_at.is_none()).map(Review::from).collect()),
+            slug: model.slug,
             sources: Some(model.sources.into_iter().map(Source::from).collect()),
             status_origin: model.status_origin,
             tags: Some(model.tags.into_iter().map(Tag::from).collect()),
@@ -131,22 +142,21 @@ pub struct NovelCreate {
     pub default_name: String,
     pub description: Option<String>,
     pub english_publisher: Option<String>,
-    pub genres: Option<String>,
     pub licensed: Option<bool>,
     pub native_name: Option<String>,
     pub original_language: String,
     pub publishers: Option<Vec<Publisher>>,
-    pub rating_count: Option<i8>,
+    pub rating_count: Option<i32>,
     pub reading_lists: Option<Vec<ReadingList>>,
     pub release_frequency: Option<String>,
     pub reviews: Option<Vec<Review>>,
     pub sources: Option<Vec<Source>>,
     pub status_origin: Option<StatusOrigin>,
     pub tags: Option<Vec<Tag>>,
-    pub total_chapters: Option<i8>,
+    pub total_chapters: Option<i32>,
     pub r#type: Type,
-    pub views: Option<i8>,
-    pub year: Option<i8>
+    pub views: Option<i32>,
+    pub year: Option<i32>
     
 }
 
@@ -160,11 +170,11 @@ impl From<NovelCreate> for ActiveModel {
             cover_image_url: Set(source.cover_image_url.clone()),
             default_name: Set(source.default_name.clone()),
             description: Set(source.description.clone()),
+            description_html: Set(markdown::render_opt(source.description.as_deref())),
             english_publisher: Set(source.english_publisher.clone()),
-            genres: Set(source.genres.clone()),
             licensed: Set(source.licensed.clone()),
             native_name: Set(source.native_name.clone()),
-            original_language: Set(source.original_language.clone()),
+            original_language: Set(Some(source.original_language.clone())),
             rating_count: Set(source.rating_count.clone()),
             release_frequency: Set(source.release_frequency.clone()),
             status_origin: Set(source.status_origin.clone()),
@@ -189,22 +199,21 @@ pub struct NovelUpdate {
     pub default_name: String,
     pub description: Option<String>,
     pub english_publisher: Option<String>,
-    pub genres: Option<String>,
     pub licensed: Option<bool>,
     pub native_name: Option<String>,
     pub original_language: String,
     pub publishers: Option<Vec<Publisher>>,
-    pub rating_count: Option<i8>,
+    pub rating_count: Option<i32>,
     pub reading_lists: Option<Vec<ReadingList>>,
     pub release_frequency: Option<String>,
     pub reviews: Option<Vec<Review>>,
     pub sources: Option<Vec<Source>>,
     pub status_origin: Option<StatusOrigin>,
     pub tags: Option<Vec<Tag>>,
-    pub total_chapters: Option<i8>,
+    pub total_chapters: Option<i32>,
     pub r#type: Type,
-    pub views: Option<i8>,
-    pub year: Option<i8>
+    pub views: Option<i32>,
+    pub year: Option<i32>
     
 }
 
@@ -219,11 +228,11 @@ impl NovelUpdate {
             cover_image_url: Set(self.cover_image_url.clone()),
             default_name: Set(self.default_name.clone()),
             description: Set(self.description.clone()),
+            description_html: Set(markdown::render_opt(self.description.as_deref())),
             english_publisher: Set(self.english_publisher.clone()),
-            genres: Set(self.genres.clone()),
             licensed: Set(self.licensed.clone()),
             native_name: Set(self.native_name.clone()),
-            original_language: Set(self.original_language.clone()),
+            original_language: Set(Some(self.original_language.clone())),
             rating_count: Set(self.rating_count.clone()),
             release_frequency: Set(self.release_frequency.clone()),
             status_origin: Set(self.status_origin.clone()),
@@ -249,22 +258,21 @@ pub struct NovelPatch {
     pub default_name: Option<String>,
     pub description: Option<String>,
     pub english_publisher: Option<String>,
-    pub genres: Option<String>,
     pub licensed: Option<bool>,
     pub native_name: Option<String>,
     pub original_language: Option<String>,
     pub publishers: Option<Vec<Publisher>>,
-    pub rating_count: Option<i8>,
+    pub rating_count: Option<i32>,
     pub reading_lists: Option<Vec<ReadingList>>,
     pub release_frequency: Option<String>,
     pub reviews: Option<Vec<Review>>,
     pub sources: Option<Vec<Source>>,
     pub status_origin: Option<StatusOrigin>,
     pub tags: Option<Vec<Tag>>,
-    pub total_chapters: Option<i8>,
+    pub total_chapters: Option<i32>,
     pub r#type: Option<Type>,
-    pub views: Option<i8>,
-    pub year: Option<i8>
+    pub views: Option<i32>,
+    pub year: Option<i32>
     
 }
 
@@ -284,16 +292,15 @@ impl NovelPatch {
             active_model.default_name = Set(value.clone());
         }if self.description.is_some() {
             active_model.description = Set(self.description.clone());
+            active_model.description_html = Set(markdown::render_opt(self.description.as_deref()));
         }if self.english_publisher.is_some() {
             active_model.english_publisher = Set(self.english_publisher.clone());
-        }if self.genres.is_some() {
-            active_model.genres = Set(self.genres.clone());
         }if self.licensed.is_some() {
             active_model.licensed = Set(self.licensed.clone());
         }if self.native_name.is_some() {
             active_model.native_name = Set(self.native_name.clone());
         }if let Some(value) = &self.original_language {
-            active_model.original_language = Set(value.clone());
+            active_model.original_language = Set(Some(value.clone()));
         }if self.rating_count.is_some() {
             active_model.rating_count = Set(self.rating_count.clone());
         }if self.release_frequency.is_some() {
@@ -312,6 +319,42 @@ impl NovelPatch {
     }
 }
 
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct NovelFilter {
+    pub tag_id: Option<i32>,
+    /// A tag name, slug or alias.
+    pub tag: Option<String>,
+    /// Like `tag`, but only matches tags of the genre category.
+    pub genre: Option<String>,
+    /// Also match novels tagged with anything below the tag or genre.
+    #[serde(default)]
+    pub include_descendants: bool,
+}
+
+/// One set of tag ids per tag the filter names; a novel needs a tag from every set. An unknown
+/// tag, or a `genre` that is not a genre tag, matches nothing.
+async fn filter_tag_ids<C: ConnectionTrait>(db: &C, filter: &NovelFilter) -> Result<Vec<Vec<i32>>, DbErr> {
+    let mut roots = Vec::new();
+    match (filter.tag_id, &filter.tag) {
+        (Some(tag_id), _) => roots.push(Some(tag_id)),
+        (None, Some(name)) => roots.push(tags::resolve(db, name).await?.map(|tag| tag.id)),
+        (None, None) => {}
+    }
+    if let Some(genre) = &filter.genre {
+        let genre = tags::resolve(db, genre).await?.filter(|tag| tag.category == Some(Category::Genre));
+        roots.push(genre.map(|tag| tag.id));
+    }
+    let mut sets = Vec::new();
+    for root in roots {
+        sets.push(match root {
+            Some(tag_id) if filter.include_descendants => tags::with_descendants(db, tag_id).await?,
+            Some(tag_id) => vec![tag_id],
+            None => Vec::new(),
+        });
+    }
+    Ok(sets)
+}
+
 async fn load_item<C>(
     db: &C,
     id: i32,
@@ -326,8 +369,27 @@ where
         .ok_or_else(|| (StatusCode::NOT_FOUND, Json(json!({"error": "not found"}))))
 }
 
-pub async fn list(state: State<AppState>) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
-    let models = Entity::load()
+pub async fn list(state: State<AppState>, principal: Option<Principal>, Query(options): Query<ListOptions>, Query(filter): Query<NovelFilter>) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let preference = preferences::for_request(&state.db, principal.as_ref(), &options)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"error": e.to_string()}))))?;
+    let tag_sets = filter_tag_ids(&state.db, &filter)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"error": e.to_string()}))))?;
+    let mut loader = Entity::load();
+    if let Some(preference) = &preference {
+        loader = loader.filter(preferences::novel_condition(preference));
+    }
+    for tag_ids in tag_sets {
+        loader = loader.filter(crate::models::novel::Column::Id.in_subquery(
+            sea_orm::sea_query::Query::select()
+                .column(crate::models::novel_tag::Column::NovelId)
+                .from(crate::models::novel_tag::Entity)
+                .and_where(crate::models::novel_tag::Column::TagId.is_in(tag_ids))
+                .to_owned(),
+        ));
+    }
+    let models = loader
         .with(crate::models::artist::Entity)
         .with(crate::models::author::Entity)
         .with(crate::models::chapter::Entity)
@@ -340,14 +402,22 @@ pub async fn list(state: State<AppState>) -> Result<impl IntoResponse, (StatusCo
         .all(&state.db)
         .await
         .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"error": e.to_string()}))))?;
-    let responses: Vec<Novel> = models.into_iter().map(Into::into).collect();
+    let mut responses: Vec<Novel> = models.into_iter().map(Into::into).collect();
+    novel_tag::rank_tags(&state.db, &mut responses)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"error": e.to_string()}))))?;
 
     Ok(Json(responses))
 }
 
 pub async fn create(state: State<AppState>, Json(create): Json<NovelCreate>) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let source = create.default_name.clone();
     let active_model:ActiveModel = create.into();
-    let model = active_model.insert(&state.db)
+    let model = slugs::insert_with(&state.db, Resource::Novel, &source, |slug| {
+        let mut active_model = active_model.clone();
+        active_model.slug = Set(Some(slug));
+        active_model.insert(&state.db)
+    })
         .await
         .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"failed to insert item": e.to_string()}))))?;
         let resp: Novel = model.into();
@@ -362,7 +432,10 @@ pub async fn patch_one(state: State<AppState>, Path(id): Path<i32>, Json(patch):
     let model = active_model.update(&state.db)
         .await
         .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"failed to update item": e.to_string()}))))?;
-    let resp: Novel = model.into();
+    let slug = slugs::assign(&state.db, Resource::Novel, model.id, &model.default_name)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"error": e.to_string()}))))?;
+    let resp: Novel = Model { slug: Some(slug), ..model }.into();
     Ok(Json(resp))
 }
 
@@ -372,7 +445,10 @@ pub async fn put_one(state: State<AppState>, Path(id): Path<i32>, Json(update):
     let model = active_model.update(&state.db)
         .await
         .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"failed to update item": e.to_string()}))))?;
-    let resp: Novel = model.into();
+    let slug = slugs::assign(&state.db, Resource::Novel, model.id, &model.default_name)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"error": e.to_string()}))))?;
+    let resp: Novel = Model { slug: Some(slug), ..model }.into();
     Ok(Json(resp))
 }
 
@@ -384,9 +460,16 @@ pub async fn remove(state: State<AppState>, Path(id): Path<i32>) -> Result<impl
     Ok(StatusCode::NO_CONTENT)
 }
 
-pub async fn read_one(state: State<AppState>, Path(id): Path<i32>) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
-    let model = Entity::load()
-        .filter_by_id(id)
+/// A novel the signed-in user's content preferences hide is not found, unless `?unfiltered=true`.
+pub async fn read_one(state: State<AppState>, principal: Option<Principal>, Query(options): Query<ListOptions>, Path(id): Path<i32>) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let preference = preferences::for_request(&state.db, principal.as_ref(), &options)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"error": e.to_string()}))))?;
+    let mut loader = Entity::load().filter_by_id(id);
+    if let Some(preference) = &preference {
+        loader = loader.filter(preferences::novel_condition(preference));
+    }
+    let model = loader
         .with(crate::models::artist::Entity)
         .with(crate::models::author::Entity)
         .with(crate::models::chapter::Entity)
@@ -400,14 +483,24 @@ pub async fn read_one(state: State<AppState>, Path(id): Path<i32>) -> Result<imp
         .await
         .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"error": e.to_string()}))))?
         .ok_or_else(|| (StatusCode::NOT_FOUND, Json(json!({"error": "not found"}))))?;
-    let resp: Novel = model.into();
+    let mut resp: Novel = model.into();
+    novel_tag::rank_tags(&state.db, std::slice::from_mut(&mut resp))
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"error": e.to_string()}))))?;
     Ok(Json(resp))
 }
 
+/// Serves the row a slug names. Old slugs redirect to the current one.
+pub async fn read_by_slug(state: State<AppState>, principal: Option<Principal>, options: Query<ListOptions>, uri: Uri, Path(slug): Path<String>) -> Result<Response, (StatusCode, Json<serde_json::Value>)> {
+    let db = state.db.clone();
+    slugs::serve(&db, Resource::Novel, &slug, &uri, |id| read_one(state, principal, options, Path(id))).await
+}
+
 pub fn routes() -> Router<AppState> {
     Router::new()
         .route("/novels", get(list))
         .route("/novels", post(create))
+        .route("/novels/by-slug/{slug}", get(read_by_slug))
         .route("/novels/{id}", get(read_one))
         .route("/novels/{id}", delete(remove))
         .route("/novels/{id}", patch(patch_one))
@@ -0,0 +1,228 @@
+use serde_json::json;
+use axum::{Router, extract::{Path, State}, http::StatusCode, routing::{delete, get, post, put}, response::IntoResponse, Json};
+use serde::{Deserialize, Serialize};
+use sea_orm::{ActiveModelTrait, ConnectionTrait, EntityTrait, QueryFilter, Set, TransactionTrait, sea_query::OnConflict};
+use sea_orm::prelude::*;
+use std::collections::HashMap;
+use std::env;
+use crate::app_state::AppState;
+use crate::models::{novel, novel_tag_vote, tag};
+use crate::models::novel_tag::{ActiveModel, Column, Entity, Model};
+use crate::services::{auth::{self, Principal}, reputation::{self, Ability}};
+use super::{novel::Novel, tag::Tag};
+
+/// Tags whose relevance (upvotes minus downvotes) falls below `TAG_HIDE_THRESHOLD` are hidden.
+pub fn hide_threshold() -> i32 {
+    env::var("TAG_HIDE_THRESHOLD").ok().and_then(|v| v.parse().ok()).unwrap_or(-2)
+}
+
+fn relevance(model: &Model) -> i32 {
+    model.upvotes - model.downvotes
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct NovelTag {
+    pub tag: Tag,
+    pub upvotes: i32,
+    pub downvotes: i32,
+    pub relevance: i32,
+    pub hidden: bool,
+    pub proposed_by: Option<i32>,
+    /// The caller's vote: `true` up, `false` down, `None` when not voted or anonymous.
+    pub my_vote: Option<bool>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct TagProposal {
+    pub tag_id: i32,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct TagVote {
+    pub up: bool,
+}
+
+fn db_error(e: DbErr) -> (StatusCode, Json<serde_json::Value>) {
+    (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"error": e.to_string()})))
+}
+
+fn require_user(principal: &Principal) -> Result<i32, (StatusCode, Json<serde_json::Value>)> {
+    principal.user_id
+        .ok_or_else(|| (StatusCode::FORBIDDEN, Json(json!({"error": "api key is not tied to a user"}))))
+}
+
+/// Drops hidden tags from the novels and orders the rest by relevance, then name.
+pub async fn rank_tags<C: ConnectionTrait>(db: &C, novels: &mut [Novel]) -> Result<(), DbErr> {
+    let novel_ids: Vec<i32> = novels.iter().map(|novel| novel.id).collect();
+    if novel_ids.is_empty() {
+        return Ok(());
+    }
+    let scores: HashMap<(i32, i32), i32> = Entity::find()
+        .filter(Column::NovelId.is_in(novel_ids))
+        .all(db)
+        .await?
+        .iter()
+        .map(|model| ((model.novel_id, model.tag_id), relevance(model)))
+        .collect();
+    let threshold = hide_threshold();
+    for novel in novels {
+        let novel_id = novel.id;
+        let Some(tags) = novel.tags.as_mut() else { continue };
+        let score = |tag: &Tag| scores.get(&(novel_id, tag.id)).copied().unwrap_or_default();
+        tags.retain(|tag| score(tag) >= threshold);
+        tags.sort_by(|a, b| score(b).cmp(&score(a)).then_with(|| a.name.cmp(&b.name)));
+    }
+    Ok(())
+}
+
+async fn load_item<C: ConnectionTrait>(db: &C, novel_id: i32, tag_id: i32) -> Result<Model, (StatusCode, Json<serde_json::Value>)> {
+    Entity::find_by_id((novel_id, tag_id))
+        .one(db)
+        .await
+        .map_err(db_error)?
+        .ok_or_else(|| (StatusCode::NOT_FOUND, Json(json!({"error": "tag is not on this novel"}))))
+}
+
+async fn to_response<C: ConnectionTrait>(db: &C, model: Model, user_id: Option<i32>) -> Result<NovelTag, DbErr> {
+    let tag = tag::Entity::find_by_id(model.tag_id).one(db).await?.map(Into::into).unwrap_or_default();
+    let my_vote = match user_id {
+        Some(user_id) => novel_tag_vote::Entity::find_by_id((user_id, model.novel_id, model.tag_id))
+            .one(db)
+            .await?
+            .map(|vote| vote.up),
+        None => None,
+    };
+    let relevance = relevance(&model);
+    Ok(NovelTag {
+        tag,
+        upvotes: model.upvotes,
+        downvotes: model.downvotes,
+        relevance,
+        hidden: relevance < hide_threshold(),
+        proposed_by: model.proposed_by,
+        my_vote,
+    })
+}
+
+/// Every tag on the novel with its tallies, hidden ones included, most relevant first.
+pub async fn list(state: State<AppState>, principal: Option<Principal>, Path(id): Path<i32>) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let user_id = principal.and_then(|principal| principal.user_id);
+    let rows = Entity::find()
+        .filter(Column::NovelId.eq(id))
+        .find_also_related(tag::Entity)
+        .all(&state.db)
+        .await
+        .map_err(db_error)?;
+    let votes: HashMap<i32, bool> = match user_id {
+        Some(user_id) => novel_tag_vote::Entity::find()
+            .filter(novel_tag_vote::Column::UserId.eq(user_id))
+            .filter(novel_tag_vote::Column::NovelId.eq(id))
+            .all(&state.db)
+            .await
+            .map_err(db_error)?
+            .into_iter()
+            .map(|vote| (vote.tag_id, vote.up))
+            .collect(),
+        None => HashMap::new(),
+    };
+    let threshold = hide_threshold();
+    let mut responses: Vec<NovelTag> = rows.into_iter()
+        .map(|(model, tag)| {
+            let relevance = relevance(&model);
+            NovelTag {
+                tag: tag.map(Into::into).unwrap_or_default(),
+                upvotes: model.upvotes,
+                downvotes: model.downvotes,
+                relevance,
+                hidden: relevance < threshold,
+                proposed_by: model.proposed_by,
+                my_vote: votes.get(&model.tag_id).copied(),
+            }
+        })
+        .collect();
+    responses.sort_by(|a, b| b.relevance.cmp(&a.relevance).then_with(|| a.tag.name.cmp(&b.tag.name)));
+    Ok(Json(responses))
+}
+
+/// Puts an existing tag on the novel, counted as the proposer's upvote. Needs the
+/// `propose_tags` ability.
+pub async fn propose(state: State<AppState>, principal: Principal, Path(id): Path<i32>, Json(proposal): Json<TagProposal>) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let user = auth::current_user(&state.db, &principal).await?;
+    if !reputation::has_ability(&state.db, &user, Ability::ProposeTags).await.map_err(db_error)? {
+        return Err((StatusCode::FORBIDDEN, Json(json!({"error": "not enough reputation to propose tags"}))));
+    }
+    if novel::Entity::find_by_id(id).one(&state.db).await.map_err(db_error)?.is_none()
+        || tag::Entity::find_by_id(proposal.tag_id).one(&state.db).await.map_err(db_error)?.is_none() {
+        return Err((StatusCode::NOT_FOUND, Json(json!({"error": "not found"}))));
+    }
+    if Entity::find_by_id((id, proposal.tag_id)).one(&state.db).await.map_err(db_error)?.is_some() {
+        return Err((StatusCode::CONFLICT, Json(json!({"error": "tag is already on this novel; vote on it instead"}))));
+    }
+    let now = chrono::Utc::now().naive_utc();
+    let txn = state.db.begin().await.map_err(db_error)?;
+    ActiveModel {
+        novel_id: Set(id),
+        tag_id: Set(proposal.tag_id),
+        created_at: Set(now),
+        proposed_by: Set(Some(user.id)),
+        upvotes: Set(0),
+        downvotes: Set(0),
+    }
+    .insert(&txn)
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"failed to insert item": e.to_string()}))))?;
+    novel_tag_vote::ActiveModel {
+        user_id: Set(user.id),
+        novel_id: Set(id),
+        tag_id: Set(proposal.tag_id),
+        up: Set(true),
+        created_at: Set(now),
+    }
+    .insert(&txn)
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"failed to insert item": e.to_string()}))))?;
+    txn.commit().await.map_err(db_error)?;
+    let model = load_item(&state.db, id, proposal.tag_id).await?;
+    let resp = to_response(&state.db, model, Some(user.id)).await.map_err(db_error)?;
+    Ok((StatusCode::CREATED, Json(resp)))
+}
+
+/// Records or changes the caller's vote on a tag of the novel.
+pub async fn vote(state: State<AppState>, principal: Principal, Path((id, tag_id)): Path<(i32, i32)>, Json(vote): Json<TagVote>) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let user_id = require_user(&principal)?;
+    load_item(&state.db, id, tag_id).await?;
+    let active_model = novel_tag_vote::ActiveModel {
+        user_id: Set(user_id),
+        novel_id: Set(id),
+        tag_id: Set(tag_id),
+        up: Set(vote.up),
+        created_at: Set(chrono::Utc::now().naive_utc()),
+    };
+    novel_tag_vote::Entity::insert(active_model)
+        .on_conflict(OnConflict::columns([novel_tag_vote::Column::UserId, novel_tag_vote::Column::NovelId, novel_tag_vote::Column::TagId]).update_column(novel_tag_vote::Column::Up).to_owned())
+        .exec(&state.db)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"failed to insert item": e.to_string()}))))?;
+    let model = load_item(&state.db, id, tag_id).await?;
+    let resp = to_response(&state.db, model, Some(user_id)).await.map_err(db_error)?;
+    Ok(Json(resp))
+}
+
+pub async fn unvote(state: State<AppState>, principal: Principal, Path((id, tag_id)): Path<(i32, i32)>) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let user_id = require_user(&principal)?;
+    novel_tag_vote::Entity::delete_by_id((user_id, id, tag_id))
+        .exec(&state.db)
+        .await
+        .map_err(db_error)?;
+    let model = load_item(&state.db, id, tag_id).await?;
+    let resp = to_response(&state.db, model, Some(user_id)).await.map_err(db_error)?;
+    Ok(Json(resp))
+}
+
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/novels/{id}/tags", get(list))
+        .route("/novels/{id}/tags", post(propose))
+        .route("/novels/{id}/tags/{tag_id}/vote", put(vote))
+        .route("/novels/{id}/tags/{tag_id}/vote", delete(unvote))
+}
@@ -0,0 +1,158 @@
+use serde_json::json;
+use axum::{Router, extract::{Path, State}, http::StatusCode, routing::get, response::IntoResponse, Json};
+use serde::{Deserialize, Serialize};
+use sea_orm::{ConnectionTrait, EntityTrait, PaginatorTrait, QueryFilter, QueryOrder, QuerySelect};
+use sea_orm::prelude::*;
+use crate::app_state::AppState;
+use crate::models::{novel, novel_reading_list, reading_list, review, user};
+use crate::services::reputation;
+
+/// What anyone may see about a user. Never add account fields (email, role, 2FA) here;
+/// those belong on `me::Account`.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct PublicProfile {
+    pub username: String,
+    pub display_name: Option<String>,
+    pub avatar_url: Option<String>,
+    pub bio: Option<String>,
+    pub bio_html: Option<String>,
+    pub joined: DateTime,
+    pub reputation: i64,
+    pub stats: ProfileStats,
+    /// `None` when the user hides their reading lists.
+    pub reading_lists: Option<Vec<ProfileReadingList>>,
+    /// `None` when the user hides their reviews.
+    pub reviews: Option<Vec<ProfileReview>>,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct ProfileStats {
+    pub reviews: u64,
+    pub reading_lists: u64,
+    pub novels_tracked: u64,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct ProfileNovel {
+    pub id: i32,
+    pub default_name: String,
+    pub cover_image_url: Option<String>,
+}
+
+impl From<novel::Model> for ProfileNovel {
+    fn from(model: novel::Model) -> Self {
+        Self { id: model.id, default_name: model.default_name, cover_image_url: model.cover_image_url }
+    }
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct ProfileReadingList {
+    pub id: i32,
+    pub status: reading_list::Status,
+    pub novels: Vec<ProfileNovel>,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct ProfileReview {
+    pub id: i32,
+    pub created_at: DateTime,
+    pub novel: Option<ProfileNovel>,
+    pub title: Option<String>,
+    pub rating: String,
+    pub spoiler: Option<bool>,
+    pub helpful_count: i32,
+}
+
+fn db_error(e: DbErr) -> (StatusCode, Json<serde_json::Value>) {
+    (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"error": e.to_string()})))
+}
+
+pub async fn profile_stats<C: ConnectionTrait>(db: &C, user_id: i32) -> Result<ProfileStats, DbErr> {
+    let reviews = review::Entity::find()
+        .filter(review::Column::UserId.eq(user_id))
+        .filter(review::Column::HiddenAt.is_null())
+        .count(db)
+        .await?;
+    let reading_lists = reading_list::Entity::find()
+        .filter(reading_list::Column::UserId.eq(user_id))
+        .count(db)
+        .await?;
+    let novels_tracked = novel_reading_list::Entity::find()
+        .inner_join(reading_list::Entity)
+        .filter(reading_list::Column::UserId.eq(user_id))
+        .select_only()
+        .column(novel_reading_list::Column::NovelId)
+        .distinct()
+        .count(db)
+        .await?;
+    Ok(ProfileStats { reviews, reading_lists, novels_tracked })
+}
+
+pub async fn build_profile<C: ConnectionTrait>(db: &C, model: user::Model) -> Result<PublicProfile, DbErr> {
+    let stats = profile_stats(db, model.id).await?;
+    let reputation = reputation::for_user(db, &model).await?.score;
+    let reading_lists = if model.show_reading_lists {
+        let lists = reading_list::Entity::find()
+            .filter(reading_list::Column::UserId.eq(model.id))
+            .order_by_asc(reading_list::Column::Id)
+            .find_with_related(novel::Entity)
+            .all(db)
+            .await?;
+        Some(lists.into_iter().map(|(list, novels)| ProfileReadingList {
+            id: list.id,
+            status: list.status,
+            novels: novels.into_iter().map(Into::into).collect(),
+        }).collect())
+    } else {
+        None
+    };
+    let reviews = if model.show_reviews {
+        let reviews = review::Entity::find()
+            .filter(review::Column::UserId.eq(model.id))
+            .filter(review::Column::HiddenAt.is_null())
+            .order_by_desc(review::Column::CreatedAt)
+            .find_also_related(novel::Entity)
+            .all(db)
+            .await?;
+        Some(reviews.into_iter().map(|(review, novel)| ProfileReview {
+            id: review.id,
+            created_at: review.created_at,
+            novel: novel.map(Into::into),
+            title: review.title,
+            rating: review.rating,
+            spoiler: review.spoiler,
+            helpful_count: review.helpful_count,
+        }).collect())
+    } else {
+        None
+    };
+    Ok(PublicProfile {
+        username: model.username,
+        display_name: model.display_name,
+        avatar_url: model.avatar_url,
+        bio: model.bio,
+        bio_html: model.bio_html,
+        joined: model.created_at,
+        reputation,
+        stats,
+        reading_lists,
+        reviews,
+    })
+}
+
+pub async fn read_one(state: State<AppState>, Path(username): Path<String>) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let model = user::Entity::find()
+        .filter(user::Column::Username.eq(username))
+        .filter(user::Column::DeletionScheduledFor.is_null())
+        .one(&state.db)
+        .await
+        .map_err(db_error)?
+        .ok_or_else(|| (StatusCode::NOT_FOUND, Json(json!({"error": "not found"}))))?;
+    let resp = build_profile(&state.db, model).await.map_err(db_error)?;
+    Ok(Json(resp))
+}
+
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/profiles/{username}", get(read_one))
+}
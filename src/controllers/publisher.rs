@@ -1,10 +1,11 @@
 use serde_json::json;
-use axum::{Router, extract::{Path, State}, http::StatusCode, routing::{delete, get, patch, post, put}, response::IntoResponse, Json};
+use axum::{Router, extract::{Path, State}, http::{StatusCode, Uri}, routing::{delete, get, patch, post, put}, response::{IntoResponse, Response}, Json};
 use serde::{Deserialize, Serialize};
 use sea_orm::{ActiveModelTrait, ModelTrait, EntityTrait, Set, IntoActiveModel, ConnectionTrait};
 use sea_orm::EntityLoaderTrait;
 use sea_orm::prelude::*;
 use crate::app_state::AppState;
+use crate::services::slugs::{self, Resource};
 use crate::models::publisher::{ActiveModel, Entity, Model, ModelEx, };
 use super::{novel::Novel as Novel, };
 #[derive(Clone, Debug, Default, Deserialize, Serialize)]
@@ -16,6 +17,7 @@ pub struct Publisher {
     pub description: Option<String>,
     pub name: String,
     pub novels: Option<Vec<Novel>>,
+    pub slug: Option<String>,
     pub website: Option<String>
     
 }
@@ -30,6 +32,7 @@ impl From<Model> for Publisher {
             description: model.description,
             name: model.name,
             novels: None,
+            slug: model.slug,
             website: model.website
             
         }
@@ -46,6 +49,7 @@ impl From<ModelEx> for Publisher {
             description: model.description,
             name: model.name,
             novels: Some(model.novels.into_iter().map(Novel::from).collect()),
+            slug: model.slug,
             website: model.website,
             
         }
@@ -147,8 +151,13 @@ pub async fn list(state: State<AppState>) -> Result<impl IntoResponse, (StatusCo
 }
 
 pub async fn create(state: State<AppState>, Json(create): Json<PublisherCreate>) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let source = create.name.clone();
     let active_model:ActiveModel = create.into();
-    let model = active_model.insert(&state.db)
+    let model = slugs::insert_with(&state.db, Resource::Publisher, &source, |slug| {
+        let mut active_model = active_model.clone();
+        active_model.slug = Set(Some(slug));
+        active_model.insert(&state.db)
+    })
         .await
         .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"failed to insert item": e.to_string()}))))?;
         let resp: Publisher = model.into();
@@ -163,7 +172,10 @@ pub async fn patch_one(state: State<AppState>, Path(id): Path<i32>, Json(patch):
     let model = active_model.update(&state.db)
         .await
         .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"failed to update item": e.to_string()}))))?;
-    let resp: Publisher = model.into();
+    let slug = slugs::assign(&state.db, Resource::Publisher, model.id, &model.name)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"error": e.to_string()}))))?;
+    let resp: Publisher = Model { slug: Some(slug), ..model }.into();
     Ok(Json(resp))
 }
 
@@ -173,7 +185,10 @@ pub async fn put_one(state: State<AppState>, Path(id): Path<i32>, Json(update):
     let model = active_model.update(&state.db)
         .await
         .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"failed to update item": e.to_string()}))))?;
-    let resp: Publisher = model.into();
+    let slug = slugs::assign(&state.db, Resource::Publisher, model.id, &model.name)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"error": e.to_string()}))))?;
+    let resp: Publisher = Model { slug: Some(slug), ..model }.into();
     Ok(Json(resp))
 }
 
@@ -197,10 +212,17 @@ pub async fn read_one(state: State<AppState>, Path(id): Path<i32>) -> Result<imp
     Ok(Json(resp))
 }
 
+/// Serves the row a slug names. Old slugs redirect to the current one.
+pub async fn read_by_slug(state: State<AppState>, uri: Uri, Path(slug): Path<String>) -> Result<Response, (StatusCode, Json<serde_json::Value>)> {
+    let db = state.db.clone();
+    slugs::serve(&db, Resource::Publisher, &slug, &uri, |id| read_one(state, Path(id))).await
+}
+
 pub fn routes() -> Router<AppState> {
     Router::new()
         .route("/publishers", get(list))
         .route("/publishers", post(create))
+        .route("/publishers/by-slug/{slug}", get(read_by_slug))
         .route("/publishers/{id}", get(read_one))
         .route("/publishers/{id}", delete(remove))
         .route("/publishers/{id}", patch(patch_one))
@@ -1,26 +1,33 @@
 use serde_json::json;
-use axum::{Router, extract::{Path, State}, http::StatusCode, routing::{delete, get, patch, post, put}, response::IntoResponse, Json};
+use axum::{Router, extract::{Path, Query, State}, http::StatusCode, routing::{delete, get, patch, post, put}, response::IntoResponse, Json};
 use serde::{Deserialize, Serialize};
-use sea_orm::{ActiveModelTrait, ModelTrait, EntityTrait, Set, IntoActiveModel, ConnectionTrait};
+use sea_orm::{ActiveModelTrait, ModelTrait, EntityTrait, Set, IntoActiveModel, ConnectionTrait, Condition};
 use sea_orm::EntityLoaderTrait;
 use sea_orm::prelude::*;
 use crate::app_state::AppState;
-use crate::models::reading_list::{ActiveModel, Entity, Model, ModelEx, Status};
-use super::{novel::Novel as Novel, user::User as User, };
+use crate::models::reading_list::{ActiveModel, Column, Entity, Model, ModelEx, Status, Visibility};
+use crate::models::user_preference;
+use crate::services::{auth::Principal, markdown, preferences::{self, ListOptions}};
+use super::{novel::Novel as Novel, };
 #[derive(Clone, Debug, Default, Deserialize, Serialize)]
 pub struct ReadingList {
     pub id: i32,
     pub created_at: DateTime,
     pub last_updated: DateTime,
     pub completed_date: Option<String>,
-    pub current_chapter: Option<i8>,
+    pub current_chapter: Option<i32>,
+    pub description: Option<String>,
+    pub description_html: Option<String>,
     pub last_read: Option<String>,
+    pub name: Option<String>,
     pub notes: Option<String>,
     pub novel: Vec<Novel>,
     pub personal_rating: Option<String>,
     pub started_date: Option<String>,
     pub status: Status,
-    pub user: User
+    #[serde(default)]
+    pub visibility: Visibility,
+    pub user_id: i32
     
 }
 
@@ -32,13 +39,17 @@ impl From<Model> for ReadingList {
             last_updated: model.last_updated,
             completed_date: model.completed_date,
             current_chapter: model.current_chapter,
+            description: model.description,
+            description_html: model.description_html,
             last_read: model.last_read,
+            name: model.name,
             notes: model.notes,
             novel: vec![].into(),
             personal_rating: model.personal_rating,
             started_date: model.started_date,
             status: model.status,
-            user: User::default()
+            visibility: model.visibility,
+            user_id: model.user_id
             
         }
     }
@@ -52,13 +63,17 @@ impl From<ModelEx> for ReadingList {
             last_updated: model.last_updated,
             completed_date: model.completed_date,
             current_chapter: model.current_chapter,
+            description: model.description,
+            description_html: model.description_html,
             last_read: model.last_read,
+            name: model.name,
             notes: model.notes,
             novel: model.novel.into_iter().map(Novel::from).collect(),
             personal_rating: model.personal_rating,
             started_date: model.started_date,
             status: model.status,
-            user: model.user.into_option().map(Into::into).unwrap_or_default(),
+            visibility: model.visibility,
+            user_id: model.user_id,
             
         }
     }
@@ -67,14 +82,17 @@ impl From<ModelEx> for ReadingList {
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct ReadingListCreate {
     pub completed_date: Option<String>,
-    pub current_chapter: Option<i8>,
+    pub current_chapter: Option<i32>,
+    pub description: Option<String>,
     pub last_read: Option<String>,
+    pub name: Option<String>,
     pub notes: Option<String>,
     pub novel: Vec<Novel>,
     pub personal_rating: Option<String>,
     pub started_date: Option<String>,
     pub status: Status,
-    pub user: User
+    #[serde(default)]
+    pub visibility: Visibility
     
 }
 
@@ -83,12 +101,15 @@ impl From<ReadingListCreate> for ActiveModel {
         ActiveModel {
             completed_date: Set(source.completed_date.clone()),
             current_chapter: Set(source.current_chapter.clone()),
+            description: Set(source.description.clone()),
+            description_html: Set(markdown::render_opt(source.description.as_deref())),
             last_read: Set(source.last_read.clone()),
+            name: Set(source.name.clone()),
             notes: Set(source.notes.clone()),
             personal_rating: Set(source.personal_rating.clone()),
             started_date: Set(source.started_date.clone()),
             status: Set(source.status.clone()),
-            user_id: Set(source.user.id.clone()),..Default::default()
+            visibility: Set(source.visibility),..Default::default()
         }
     }
 }
@@ -96,29 +117,36 @@ impl From<ReadingListCreate> for ActiveModel {
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct ReadingListUpdate {
     pub completed_date: Option<String>,
-    pub current_chapter: Option<i8>,
+    pub current_chapter: Option<i32>,
+    pub description: Option<String>,
     pub last_read: Option<String>,
+    pub name: Option<String>,
     pub notes: Option<String>,
     pub novel: Vec<Novel>,
     pub personal_rating: Option<String>,
     pub started_date: Option<String>,
     pub status: Status,
-    pub user: User
+    #[serde(default)]
+    pub visibility: Visibility
     
 }
 
 impl ReadingListUpdate {
-    fn into_active_model(self, id:i32) -> ActiveModel {
+    fn into_active_model(self, id:i32, user_id: i32) -> ActiveModel {
         ActiveModel {
             id: Set(id),
             completed_date: Set(self.completed_date.clone()),
             current_chapter: Set(self.current_chapter.clone()),
+            description: Set(self.description.clone()),
+            description_html: Set(markdown::render_opt(self.description.as_deref())),
             last_read: Set(self.last_read.clone()),
+            name: Set(self.name.clone()),
             notes: Set(self.notes.clone()),
             personal_rating: Set(self.personal_rating.clone()),
             started_date: Set(self.started_date.clone()),
             status: Set(self.status.clone()),
-            user_id: Set(self.user.id.clone()),
+            visibility: Set(self.visibility),
+            user_id: Set(user_id),
             ..Default::default()
         }
     }
@@ -127,14 +155,16 @@ impl ReadingListUpdate {
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct ReadingListPatch {
     pub completed_date: Option<String>,
-    pub current_chapter: Option<i8>,
+    pub current_chapter: Option<i32>,
+    pub description: Option<String>,
     pub last_read: Option<String>,
+    pub name: Option<String>,
     pub notes: Option<String>,
     pub novel: Option<Vec<Novel>>,
     pub personal_rating: Option<String>,
     pub started_date: Option<String>,
     pub status: Option<Status>,
-    pub user: Option<User>
+    pub visibility: Option<Visibility>
     
 }
 
@@ -144,8 +174,13 @@ impl ReadingListPatch {
             active_model.completed_date = Set(self.completed_date.clone());
         }if self.current_chapter.is_some() {
             active_model.current_chapter = Set(self.current_chapter.clone());
+        }if self.description.is_some() {
+            active_model.description = Set(self.description.clone());
+            active_model.description_html = Set(markdown::render_opt(self.description.as_deref()));
         }if self.last_read.is_some() {
             active_model.last_read = Set(self.last_read.clone());
+        }if self.name.is_some() {
+            active_model.name = Set(self.name.clone());
         }if self.notes.is_some() {
             active_model.notes = Set(self.notes.clone());
         }if self.personal_rating.is_some() {
@@ -154,8 +189,8 @@ impl ReadingListPatch {
             active_model.started_date = Set(self.started_date.clone());
         }if let Some(value) = &self.status {
             active_model.status = Set(value.clone());
-        }if let Some(value) = &self.user {
-            active_model.user_id = Set(value.id.clone());
+        }if let Some(value) = &self.visibility {
+            active_model.visibility = Set(*value);
         }
     }
 }
@@ -174,20 +209,64 @@ where
         .ok_or_else(|| (StatusCode::NOT_FOUND, Json(json!({"error": "not found"}))))
 }
 
-pub async fn list(state: State<AppState>) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+fn require_user(principal: &Principal) -> Result<i32, (StatusCode, Json<serde_json::Value>)> {
+    principal.user_id.ok_or_else(|| (StatusCode::FORBIDDEN, Json(json!({"error": "api key is not tied to a user"}))))
+}
+
+async fn load_owned<C: ConnectionTrait>(db: &C, id: i32, principal: &Principal) -> Result<Model, (StatusCode, Json<serde_json::Value>)> {
+    let user_id = require_user(principal)?;
+    let model = load_item(db, id).await?;
+    if model.user_id != user_id {
+        return Err((StatusCode::FORBIDDEN, Json(json!({"error": "not your reading list"}))));
+    }
+    Ok(model)
+}
+
+/// Public lists and the caller's own. Unlisted lists are read through `/lists/{id}?token=`.
+fn readable_by(principal: Option<&Principal>) -> Condition {
+    let condition = Condition::any().add(Column::Visibility.eq(Visibility::Public));
+    match principal.and_then(|principal| principal.user_id) {
+        Some(user_id) => condition.add(Column::UserId.eq(user_id)),
+        None => condition,
+    }
+}
+
+/// Drops the novels the reader's content preferences hide.
+async fn hide_novels<C: ConnectionTrait>(db: &C, preference: Option<&user_preference::Model>, lists: &mut [ReadingList]) -> Result<(), DbErr> {
+    let Some(preference) = preference else {
+        return Ok(());
+    };
+    let novel_ids = lists.iter().flat_map(|list| list.novel.iter().map(|novel| novel.id)).collect();
+    let allowed = preferences::allowed_novel_ids(db, preference, novel_ids).await?;
+    for list in lists {
+        list.novel.retain(|novel| allowed.contains(&novel.id));
+    }
+    Ok(())
+}
+
+pub async fn list(state: State<AppState>, principal: Option<Principal>, Query(options): Query<ListOptions>) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
     let models = Entity::load()
+        .filter(readable_by(principal.as_ref()))
         .with(crate::models::novel::Entity)
-        .with(crate::models::user::Entity)
         .all(&state.db)
         .await
         .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"error": e.to_string()}))))?;
-    let responses: Vec<ReadingList> = models.into_iter().map(Into::into).collect();
+    let mut responses: Vec<ReadingList> = models.into_iter().map(Into::into).collect();
+    let preference = preferences::for_request(&state.db, principal.as_ref(), &options)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"error": e.to_string()}))))?;
+    hide_novels(&state.db, preference.as_ref(), &mut responses)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"error": e.to_string()}))))?;
 
     Ok(Json(responses))
 }
 
-pub async fn create(state: State<AppState>, Json(create): Json<ReadingListCreate>) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
-    let active_model:ActiveModel = create.into();
+/// The list belongs to the caller.
+pub async fn create(state: State<AppState>, principal: Principal, Json(create): Json<ReadingListCreate>) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let user_id = require_user(&principal)?;
+    let mut active_model:ActiveModel = create.into();
+    active_model.user_id = Set(user_id);
     let model = active_model.insert(&state.db)
         .await
         .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"failed to insert item": e.to_string()}))))?;
@@ -196,8 +275,8 @@ pub async fn create(state: State<AppState>, Json(create): Json<ReadingListCreate
 
 }
 
-pub async fn patch_one(state: State<AppState>, Path(id): Path<i32>, Json(patch): Json<ReadingListPatch> ) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
-    let model = load_item(&state.db, id).await?;
+pub async fn patch_one(state: State<AppState>, principal: Principal, Path(id): Path<i32>, Json(patch): Json<ReadingListPatch> ) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let model = load_owned(&state.db, id, &principal).await?;
     let mut active_model = model.into_active_model();
     patch.patch_active_model(&mut active_model);
     let model = active_model.update(&state.db)
@@ -207,9 +286,9 @@ pub async fn patch_one(state: State<AppState>, Path(id): Path<i32>, Json(patch):
     Ok(Json(resp))
 }
 
-pub async fn put_one(state: State<AppState>, Path(id): Path<i32>, Json(update): Json<ReadingListUpdate>) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
-    let _ = load_item(&state.db, id).await?;
-    let active_model = update.into_active_model(id);
+pub async fn put_one(state: State<AppState>, principal: Principal, Path(id): Path<i32>, Json(update): Json<ReadingListUpdate>) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let existing = load_owned(&state.db, id, &principal).await?;
+    let active_model = update.into_active_model(id, existing.user_id);
     let model = active_model.update(&state.db)
         .await
         .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"failed to update item": e.to_string()}))))?;
@@ -217,24 +296,30 @@ pub async fn put_one(state: State<AppState>, Path(id): Path<i32>, Json(update):
     Ok(Json(resp))
 }
 
-pub async fn remove(state: State<AppState>, Path(id): Path<i32>) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
-    let model = load_item(&state.db, id).await?;
+pub async fn remove(state: State<AppState>, principal: Principal, Path(id): Path<i32>) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let model = load_owned(&state.db, id, &principal).await?;
     model.delete(&state.db)
         .await
         .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"error": e.to_string()}))))?;
     Ok(StatusCode::NO_CONTENT)
 }
 
-pub async fn read_one(state: State<AppState>, Path(id): Path<i32>) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+pub async fn read_one(state: State<AppState>, principal: Option<Principal>, Path(id): Path<i32>, Query(options): Query<ListOptions>) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
     let model = Entity::load()
         .filter_by_id(id)
+        .filter(readable_by(principal.as_ref()))
         .with(crate::models::novel::Entity)
-        .with(crate::models::user::Entity)
         .one(&state.db)
         .await
         .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"error": e.to_string()}))))?
         .ok_or_else(|| (StatusCode::NOT_FOUND, Json(json!({"error": "not found"}))))?;
-    let resp: ReadingList = model.into();
+    let mut resp: ReadingList = model.into();
+    let preference = preferences::for_request(&state.db, principal.as_ref(), &options)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"error": e.to_string()}))))?;
+    hide_novels(&state.db, preference.as_ref(), std::slice::from_mut(&mut resp))
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"error": e.to_string()}))))?;
     Ok(Json(resp))
 }
 
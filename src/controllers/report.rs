@@ -0,0 +1,324 @@
+use serde_json::json;
+use axum::{Router, extract::{Query, State}, http::StatusCode, routing::{get, post}, response::IntoResponse, Json};
+use serde::{Deserialize, Serialize};
+use sea_orm::{ActiveModelTrait, ConnectionTrait, EntityTrait, IntoActiveModel, PaginatorTrait, QueryFilter, QueryOrder, QuerySelect, Set, TransactionTrait, sea_query::Expr};
+use sea_orm::prelude::*;
+use crate::app_state::AppState;
+use crate::models::{comment, moderation_log, novel, review, user};
+use crate::models::moderation_log::Action;
+use crate::models::report::{ActiveModel, Column, Entity, Model, Reason, Status, TargetType};
+use crate::models::user::Role;
+use crate::services::{auth::{self, Principal}, moderation};
+
+const DEFAULT_LIMIT: u64 = 50;
+const MAX_LIMIT: u64 = 200;
+const MAX_BULK: usize = 100;
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct Report {
+    pub id: i32,
+    pub created_at: DateTime,
+    pub reporter_id: Option<i32>,
+    pub target_type: TargetType,
+    pub target_id: i32,
+    pub reason: Reason,
+    pub details: Option<String>,
+    pub status: Status,
+    pub resolved_by: Option<i32>,
+    pub resolved_at: Option<DateTime>,
+}
+
+impl From<Model> for Report {
+    fn from(model: Model) -> Self {
+        Self {
+            id: model.id,
+            created_at: model.created_at,
+            reporter_id: model.reporter_id,
+            target_type: model.target_type,
+            target_id: model.target_id,
+            reason: model.reason,
+            details: model.details,
+            status: model.status,
+            resolved_by: model.resolved_by,
+            resolved_at: model.resolved_at,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ReportCreate {
+    pub target_type: TargetType,
+    pub target_id: i32,
+    pub reason: Reason,
+    pub details: Option<String>,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct ReportFilter {
+    /// Defaults to open reports.
+    pub status: Option<Status>,
+    pub target_type: Option<TargetType>,
+    pub limit: Option<u64>,
+    pub offset: Option<u64>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct BulkAction {
+    pub report_ids: Vec<i32>,
+    pub action: Action,
+    pub note: Option<String>,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct AuditEntry {
+    pub id: i32,
+    pub created_at: DateTime,
+    pub moderator_id: Option<i32>,
+    pub action: Action,
+    pub target_type: TargetType,
+    pub target_id: i32,
+    pub offender_id: Option<i32>,
+    pub report_id: Option<i32>,
+    pub note: Option<String>,
+}
+
+impl From<moderation_log::Model> for AuditEntry {
+    fn from(model: moderation_log::Model) -> Self {
+        Self {
+            id: model.id,
+            created_at: model.created_at,
+            moderator_id: model.moderator_id,
+            action: model.action,
+            target_type: model.target_type,
+            target_id: model.target_id,
+            offender_id: model.offender_id,
+            report_id: model.report_id,
+            note: model.note,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct AuditFilter {
+    pub target_type: Option<TargetType>,
+    pub target_id: Option<i32>,
+    pub moderator_id: Option<i32>,
+    pub limit: Option<u64>,
+    pub offset: Option<u64>,
+}
+
+fn db_error(e: DbErr) -> (StatusCode, Json<serde_json::Value>) {
+    (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"error": e.to_string()})))
+}
+
+fn unprocessable(message: String) -> (StatusCode, Json<serde_json::Value>) {
+    (StatusCode::UNPROCESSABLE_ENTITY, Json(json!({"error": message})))
+}
+
+/// Whether the target is still there to be reported; deleted comments and accounts awaiting
+/// deletion count as gone.
+async fn target_exists<C: ConnectionTrait>(db: &C, target_type: TargetType, id: i32) -> Result<bool, DbErr> {
+    let count = match target_type {
+        TargetType::Review => review::Entity::find_by_id(id).count(db).await?,
+        TargetType::Comment => comment::Entity::find_by_id(id).filter(comment::Column::DeletedAt.is_null()).count(db).await?,
+        TargetType::Novel => novel::Entity::find_by_id(id).count(db).await?,
+        TargetType::User => user::Entity::find_by_id(id).filter(user::Column::DeletionScheduledFor.is_null()).count(db).await?,
+    };
+    Ok(count > 0)
+}
+
+/// The account behind a target: the reported user, or the author of the reported review or comment.
+async fn target_user<C: ConnectionTrait>(db: &C, target_type: TargetType, id: i32) -> Result<Option<i32>, DbErr> {
+    Ok(match target_type {
+        TargetType::User => Some(id),
+        TargetType::Review => review::Entity::find_by_id(id).one(db).await?.and_then(|model| model.user_id),
+        TargetType::Comment => comment::Entity::find_by_id(id).one(db).await?.and_then(|model| model.user_id),
+        TargetType::Novel => None,
+    })
+}
+
+/// Carries out one action for one report and leaves the audit entry, naming the user it counts
+/// against. Deleting or hiding a target that is already gone is not an error, so several reports
+/// about the same target can be handled together.
+async fn apply<C: ConnectionTrait>(db: &C, moderator: &user::Model, report: &Model, action: Action, note: Option<String>) -> Result<(), (StatusCode, Json<serde_json::Value>)> {
+    let now = chrono::Utc::now().naive_utc();
+    let mut target = (report.target_type, report.target_id);
+    let offender_id = match action {
+        Action::Dismiss => None,
+        _ => target_user(db, report.target_type, report.target_id).await.map_err(db_error)?,
+    };
+    match action {
+        Action::Dismiss => {}
+        Action::HideReview => {
+            if report.target_type != TargetType::Review {
+                return Err(unprocessable(format!("report {} is not about a review", report.id)));
+            }
+            review::Entity::update_many()
+                .col_expr(review::Column::HiddenAt, Expr::value(now))
+                .filter(review::Column::Id.eq(report.target_id))
+                .filter(review::Column::HiddenAt.is_null())
+                .exec(db)
+                .await
+                .map_err(db_error)?;
+        }
+        Action::BanUser => {
+            let offender = match offender_id {
+                Some(user_id) => user::Entity::find_by_id(user_id).one(db).await.map_err(db_error)?,
+                None => None,
+            }
+            .ok_or_else(|| unprocessable(format!("report {} has no user to ban", report.id)))?;
+            if offender.role != Role::User {
+                return Err(unprocessable(format!("report {} is about a moderator or admin", report.id)));
+            }
+            target = (TargetType::User, offender.id);
+            moderation::ban(db, offender).await.map_err(db_error)?;
+        }
+        Action::Delete => match report.target_type {
+            TargetType::Review => {
+                review::Entity::delete_by_id(report.target_id).exec(db).await.map_err(db_error)?;
+            }
+            TargetType::Comment => {
+                comment::Entity::update_many()
+                    .col_expr(comment::Column::DeletedAt, Expr::value(now))
+                    .filter(comment::Column::Id.eq(report.target_id))
+                    .filter(comment::Column::DeletedAt.is_null())
+                    .exec(db)
+                    .await
+                    .map_err(db_error)?;
+            }
+            TargetType::Novel => {
+                novel::Entity::delete_by_id(report.target_id).exec(db).await.map_err(db_error)?;
+            }
+            TargetType::User => {
+                return Err(unprocessable(format!("report {} is about a user; ban them instead", report.id)));
+            }
+        },
+    }
+    moderation::record(db, moderator.id, action, target, offender_id, Some(report.id), note).await.map_err(db_error)?;
+    let mut active_model = report.clone().into_active_model();
+    active_model.status = Set(if action == Action::Dismiss { Status::Dismissed } else { Status::Actioned });
+    active_model.resolved_by = Set(Some(moderator.id));
+    active_model.resolved_at = Set(Some(now));
+    active_model.update(db)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"failed to update item": e.to_string()}))))?;
+    Ok(())
+}
+
+/// Flags content for the moderators. One open report per user and target.
+pub async fn create(state: State<AppState>, principal: Principal, Json(create): Json<ReportCreate>) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let reporter = auth::current_user(&state.db, &principal).await?;
+    if !target_exists(&state.db, create.target_type, create.target_id).await.map_err(db_error)? {
+        return Err((StatusCode::NOT_FOUND, Json(json!({"error": "not found"}))));
+    }
+    let duplicate = Entity::find()
+        .filter(Column::ReporterId.eq(reporter.id))
+        .filter(Column::TargetType.eq(create.target_type))
+        .filter(Column::TargetId.eq(create.target_id))
+        .filter(Column::Status.eq(Status::Open))
+        .count(&state.db)
+        .await
+        .map_err(db_error)?;
+    if duplicate > 0 {
+        return Err((StatusCode::CONFLICT, Json(json!({"error": "you already reported this"}))));
+    }
+    let model = ActiveModel {
+        reporter_id: Set(Some(reporter.id)),
+        target_type: Set(create.target_type),
+        target_id: Set(create.target_id),
+        reason: Set(create.reason),
+        details: Set(create.details.filter(|details| !details.trim().is_empty())),
+        status: Set(Status::Open),
+        ..Default::default()
+    }
+    .insert(&state.db)
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"failed to insert item": e.to_string()}))))?;
+    let resp: Report = model.into();
+    Ok((StatusCode::CREATED, Json(resp)))
+}
+
+/// The moderation queue, oldest first so nothing waits forever.
+pub async fn queue(state: State<AppState>, principal: Principal, Query(filter): Query<ReportFilter>) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    auth::require_role(&state.db, &principal, &[Role::Admin, Role::Moderator]).await?;
+    let mut query = Entity::find().filter(Column::Status.eq(filter.status.unwrap_or(Status::Open)));
+    if let Some(target_type) = filter.target_type {
+        query = query.filter(Column::TargetType.eq(target_type));
+    }
+    let models = query
+        .order_by_asc(Column::CreatedAt)
+        .order_by_asc(Column::Id)
+        .limit(filter.limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT))
+        .offset(filter.offset.unwrap_or(0))
+        .all(&state.db)
+        .await
+        .map_err(db_error)?;
+    let responses: Vec<Report> = models.into_iter().map(Into::into).collect();
+    Ok(Json(responses))
+}
+
+/// Applies one action to a batch of open reports. Either every report is handled or none is.
+pub async fn bulk_action(state: State<AppState>, principal: Principal, Json(bulk): Json<BulkAction>) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let moderator = auth::require_role(&state.db, &principal, &[Role::Admin, Role::Moderator]).await?;
+    if bulk.report_ids.is_empty() || bulk.report_ids.len() > MAX_BULK {
+        return Err(unprocessable(format!("between 1 and {MAX_BULK} reports can be handled at once")));
+    }
+    let txn = state.db.begin().await.map_err(db_error)?;
+    let reports = Entity::find()
+        .filter(Column::Id.is_in(bulk.report_ids.clone()))
+        .order_by_asc(Column::Id)
+        .all(&txn)
+        .await
+        .map_err(db_error)?;
+    if let Some(missing) = bulk.report_ids.iter().find(|id| !reports.iter().any(|report| report.id == **id)) {
+        return Err((StatusCode::NOT_FOUND, Json(json!({"error": format!("report {missing} not found")}))));
+    }
+    if let Some(resolved) = reports.iter().find(|report| report.status != Status::Open) {
+        return Err(unprocessable(format!("report {} is already resolved", resolved.id)));
+    }
+    for report in &reports {
+        apply(&txn, &moderator, report, bulk.action, bulk.note.clone()).await?;
+    }
+    txn.commit().await.map_err(db_error)?;
+    let models = Entity::find()
+        .filter(Column::Id.is_in(bulk.report_ids))
+        .order_by_asc(Column::Id)
+        .all(&state.db)
+        .await
+        .map_err(db_error)?;
+    let responses: Vec<Report> = models.into_iter().map(Into::into).collect();
+    Ok(Json(responses))
+}
+
+/// Every moderator action, newest first.
+pub async fn audit_log(state: State<AppState>, principal: Principal, Query(filter): Query<AuditFilter>) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    auth::require_role(&state.db, &principal, &[Role::Admin, Role::Moderator]).await?;
+    let mut query = moderation_log::Entity::find();
+    if let Some(target_type) = filter.target_type {
+        query = query.filter(moderation_log::Column::TargetType.eq(target_type));
+    }
+    if let Some(target_id) = filter.target_id {
+        query = query.filter(moderation_log::Column::TargetId.eq(target_id));
+    }
+    if let Some(moderator_id) = filter.moderator_id {
+        query = query.filter(moderation_log::Column::ModeratorId.eq(moderator_id));
+    }
+    let models = query
+        .order_by_desc(moderation_log::Column::CreatedAt)
+        .order_by_desc(moderation_log::Column::Id)
+        .limit(filter.limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT))
+        .offset(filter.offset.unwrap_or(0))
+        .all(&state.db)
+        .await
+        .map_err(db_error)?;
+    let responses: Vec<AuditEntry> = models.into_iter().map(Into::into).collect();
+    Ok(Json(responses))
+}
+
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/reports", post(create))
+        .route("/admin/reports", get(queue))
+        .route("/admin/reports/actions", post(bulk_action))
+        .route("/admin/audit-log", get(audit_log))
+}
@@ -1,11 +1,16 @@
 use serde_json::json;
-use axum::{Router, extract::{Path, State}, http::StatusCode, routing::{delete, get, patch, post, put}, response::IntoResponse, Json};
+use axum::{Router, extract::{Path, Query, State}, http::StatusCode, routing::{delete, get, patch, post, put}, response::IntoResponse, Json};
 use serde::{Deserialize, Serialize};
-use sea_orm::{ActiveModelTrait, ModelTrait, EntityTrait, Set, IntoActiveModel, ConnectionTrait};
+use sea_orm::{ActiveModelTrait, ModelTrait, EntityTrait, Order, QueryFilter, QueryOrder, Set, IntoActiveModel, ConnectionTrait, TransactionTrait, sea_query::{Expr, NullOrdering, OnConflict}};
 use sea_orm::EntityLoaderTrait;
 use sea_orm::prelude::*;
 use crate::app_state::AppState;
-use crate::models::review::{ActiveModel, Entity, Model, ModelEx, };
+use crate::models::review::{ActiveModel, Column, Entity, Model, ModelEx, };
+use crate::models::{review_vote, user};
+use crate::models::moderation_log::Action;
+use crate::models::report::TargetType;
+use crate::models::user::Role;
+use crate::services::{auth::{self, Principal}, markdown, moderation};
 use super::{novel::Novel as Novel, user::User as User, };
 #[derive(Clone, Debug, Default, Deserialize, Serialize)]
 pub struct Review {
@@ -13,11 +18,13 @@ pub struct Review {
     pub created_at: DateTime,
     pub last_updated: DateTime,
     pub content: String,
-    pub helpful_count: Option<i8>,
+    pub content_html: Option<String>,
+    pub helpful_count: i32,
     pub novel: Novel,
     pub rating: String,
     pub spoiler: Option<bool>,
     pub title: Option<String>,
+    pub unhelpful_count: i32,
     pub user: User
     
 }
@@ -29,11 +36,13 @@ impl From<Model> for Review {
             created_at: model.created_at,
             last_updated: model.last_updated,
             content: model.content,
+            content_html: model.content_html,
             helpful_count: model.helpful_count,
             novel: Novel::default(),
             rating: model.rating,
             spoiler: model.spoiler,
             title: model.title,
+            unhelpful_count: model.unhelpful_count,
             user: User::default()
             
         }
@@ -47,26 +56,27 @@ impl From<ModelEx> for Review {
             created_at: model.created_at,
             last_updated: model.last_updated,
             content: model.content,
+            content_html: model.content_html,
             helpful_count: model.helpful_count,
             novel: model.novel.into_option().map(Into::into).unwrap_or_default(),
             rating: model.rating,
             spoiler: model.spoiler,
             title: model.title,
+            unhelpful_count: model.unhelpful_count,
             user: model.user.into_option().map(Into::into).unwrap_or_default(),
             
         }
     }
 }
 
+/// The author is the signed-in user.
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct ReviewCreate {
     pub content: String,
-    pub helpful_count: Option<i8>,
     pub novel: Novel,
     pub rating: String,
     pub spoiler: Option<bool>,
-    pub title: Option<String>,
-    pub user: User
+    pub title: Option<String>
     
 }
 
@@ -74,24 +84,23 @@ impl From<ReviewCreate> for ActiveModel {
     fn from(source: ReviewCreate) -> Self {
         ActiveModel {
             content: Set(source.content.clone()),
-            helpful_count: Set(source.helpful_count.clone()),
+            content_html: Set(Some(markdown::render(&source.content))),
             novel_id: Set(source.novel.id.clone()),rating: Set(source.rating.clone()),
             spoiler: Set(source.spoiler.clone()),
             title: Set(source.title.clone()),
-            user_id: Set(source.user.id.clone()),..Default::default()
+            ..Default::default()
         }
     }
 }
 
+/// A review keeps its author; there is no moving it to another user.
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct ReviewUpdate {
     pub content: String,
-    pub helpful_count: Option<i8>,
     pub novel: Novel,
     pub rating: String,
     pub spoiler: Option<bool>,
-    pub title: Option<String>,
-    pub user: User
+    pub title: Option<String>
     
 }
 
@@ -100,12 +109,11 @@ impl ReviewUpdate {
         ActiveModel {
             id: Set(id),
             content: Set(self.content.clone()),
-            helpful_count: Set(self.helpful_count.clone()),
+            content_html: Set(Some(markdown::render(&self.content))),
             novel_id: Set(self.novel.id.clone()),
             rating: Set(self.rating.clone()),
             spoiler: Set(self.spoiler.clone()),
             title: Set(self.title.clone()),
-            user_id: Set(self.user.id.clone()),
             ..Default::default()
         }
     }
@@ -114,12 +122,10 @@ impl ReviewUpdate {
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct ReviewPatch {
     pub content: Option<String>,
-    pub helpful_count: Option<i8>,
     pub novel: Option<Novel>,
     pub rating: Option<String>,
     pub spoiler: Option<bool>,
-    pub title: Option<String>,
-    pub user: Option<User>
+    pub title: Option<String>
     
 }
 
@@ -127,8 +133,7 @@ impl ReviewPatch {
     pub fn patch_active_model(&self, active_model: &mut ActiveModel) {
         if let Some(value) = &self.content {
             active_model.content = Set(value.clone());
-        }if self.helpful_count.is_some() {
-            active_model.helpful_count = Set(self.helpful_count.clone());
+            active_model.content_html = Set(Some(markdown::render(value)));
         }if let Some(value) = &self.novel {
             active_model.novel_id = Set(value.id.clone());
         }if let Some(value) = &self.rating {
@@ -137,12 +142,37 @@ impl ReviewPatch {
             active_model.spoiler = Set(self.spoiler.clone());
         }if self.title.is_some() {
             active_model.title = Set(self.title.clone());
-        }if let Some(value) = &self.user {
-            active_model.user_id = Set(value.id.clone());
         }
     }
 }
 
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReviewSort {
+    #[default]
+    Newest,
+    Helpful,
+    HighestRating,
+    LowestRating,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct ReviewFilter {
+    pub novel_id: Option<i32>,
+    #[serde(default)]
+    pub sort: ReviewSort,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ReviewVote {
+    pub helpful: bool,
+}
+
+/// Helpful minus unhelpful votes.
+const NET_HELPFUL: &str = r#""review"."helpful_count" - "review"."unhelpful_count""#;
+/// Ratings are free text; the leading number ("4", "4.5/5") is what gets compared.
+const NUMERIC_RATING: &str = r#"substring("review"."rating" FROM '^\s*([0-9]+(?:\.[0-9]+)?)')::float8"#;
+
 async fn load_item<C>(
     db: &C,
     id: i32,
@@ -157,8 +187,35 @@ where
         .ok_or_else(|| (StatusCode::NOT_FOUND, Json(json!({"error": "not found"}))))
 }
 
-pub async fn list(state: State<AppState>) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
-    let models = Entity::load()
+/// Loads a review the caller may change: their own, or any review for moderators.
+async fn load_writable(state: &AppState, principal: &Principal, id: i32) -> Result<(Model, user::Model), (StatusCode, Json<serde_json::Value>)> {
+    let caller = auth::current_user(&state.db, principal).await?;
+    let model = load_item(&state.db, id).await?;
+    if model.user_id != Some(caller.id) && !matches!(caller.role, Role::Moderator | Role::Admin) {
+        return Err((StatusCode::FORBIDDEN, Json(json!({"error": "not your review"}))));
+    }
+    Ok((model, caller))
+}
+
+pub async fn list(state: State<AppState>, Query(filter): Query<ReviewFilter>) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let mut query = Entity::load().filter(Column::HiddenAt.is_null());
+    if let Some(novel_id) = filter.novel_id {
+        query = query.filter(Column::NovelId.eq(novel_id));
+    }
+    query = match filter.sort {
+        ReviewSort::Newest => query.order_by_desc(Column::CreatedAt),
+        ReviewSort::Helpful => query
+            .order_by(Expr::cust(NET_HELPFUL), Order::Desc)
+            .order_by_desc(Column::HelpfulCount)
+            .order_by_desc(Column::CreatedAt),
+        ReviewSort::HighestRating => query
+            .order_by_with_nulls(Expr::cust(NUMERIC_RATING), Order::Desc, NullOrdering::Last)
+            .order_by_desc(Column::CreatedAt),
+        ReviewSort::LowestRating => query
+            .order_by_with_nulls(Expr::cust(NUMERIC_RATING), Order::Asc, NullOrdering::Last)
+            .order_by_desc(Column::CreatedAt),
+    };
+    let models = query
         .with(crate::models::novel::Entity)
         .with(crate::models::user::Entity)
         .all(&state.db)
@@ -169,8 +226,10 @@ pub async fn list(state: State<AppState>) -> Result<impl IntoResponse, (StatusCo
     Ok(Json(responses))
 }
 
-pub async fn create(state: State<AppState>, Json(create): Json<ReviewCreate>) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
-    let active_model:ActiveModel = create.into();
+pub async fn create(state: State<AppState>, principal: Principal, Json(create): Json<ReviewCreate>) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let author = auth::current_user(&state.db, &principal).await?;
+    let mut active_model:ActiveModel = create.into();
+    active_model.user_id = Set(Some(author.id));
     let model = active_model.insert(&state.db)
         .await
         .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"failed to insert item": e.to_string()}))))?;
@@ -179,8 +238,8 @@ pub async fn create(state: State<AppState>, Json(create): Json<ReviewCreate>) ->
 
 }
 
-pub async fn patch_one(state: State<AppState>, Path(id): Path<i32>, Json(patch): Json<ReviewPatch> ) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
-    let model = load_item(&state.db, id).await?;
+pub async fn patch_one(state: State<AppState>, principal: Principal, Path(id): Path<i32>, Json(patch): Json<ReviewPatch> ) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let (model, _) = load_writable(&state, &principal, id).await?;
     let mut active_model = model.into_active_model();
     patch.patch_active_model(&mut active_model);
     let model = active_model.update(&state.db)
@@ -190,8 +249,8 @@ pub async fn patch_one(state: State<AppState>, Path(id): Path<i32>, Json(patch):
     Ok(Json(resp))
 }
 
-pub async fn put_one(state: State<AppState>, Path(id): Path<i32>, Json(update): Json<ReviewUpdate>) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
-    let _ = load_item(&state.db, id).await?;
+pub async fn put_one(state: State<AppState>, principal: Principal, Path(id): Path<i32>, Json(update): Json<ReviewUpdate>) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let _ = load_writable(&state, &principal, id).await?;
     let active_model = update.into_active_model(id);
     let model = active_model.update(&state.db)
         .await
@@ -200,9 +259,22 @@ pub async fn put_one(state: State<AppState>, Path(id): Path<i32>, Json(update):
     Ok(Json(resp))
 }
 
-pub async fn remove(state: State<AppState>, Path(id): Path<i32>) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
-    let model = load_item(&state.db, id).await?;
-    model.delete(&state.db)
+/// A moderator deleting someone else's review leaves an audit entry.
+pub async fn remove(state: State<AppState>, principal: Principal, Path(id): Path<i32>) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let (model, caller) = load_writable(&state, &principal, id).await?;
+    let author_id = model.user_id;
+    let txn = state.db.begin()
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"error": e.to_string()}))))?;
+    model.delete(&txn)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"error": e.to_string()}))))?;
+    if author_id != Some(caller.id) {
+        moderation::record(&txn, caller.id, Action::Delete, (TargetType::Review, id), author_id, None, None)
+            .await
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"error": e.to_string()}))))?;
+    }
+    txn.commit()
         .await
         .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"error": e.to_string()}))))?;
     Ok(StatusCode::NO_CONTENT)
@@ -211,6 +283,7 @@ pub async fn remove(state: State<AppState>, Path(id): Path<i32>) -> Result<impl
 pub async fn read_one(state: State<AppState>, Path(id): Path<i32>) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
     let model = Entity::load()
         .filter_by_id(id)
+        .filter(Column::HiddenAt.is_null())
         .with(crate::models::novel::Entity)
         .with(crate::models::user::Entity)
         .one(&state.db)
@@ -221,6 +294,40 @@ pub async fn read_one(state: State<AppState>, Path(id): Path<i32>) -> Result<imp
     Ok(Json(resp))
 }
 
+/// Records or changes the signed-in user's vote. Authors cannot vote on their own reviews.
+pub async fn vote(state: State<AppState>, principal: Principal, Path(id): Path<i32>, Json(vote): Json<ReviewVote>) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let user_id = principal.user_id
+        .ok_or_else(|| (StatusCode::FORBIDDEN, Json(json!({"error": "api key is not tied to a user"}))))?;
+    let model = load_item(&state.db, id).await?;
+    if model.user_id == Some(user_id) {
+        return Err((StatusCode::UNPROCESSABLE_ENTITY, Json(json!({"error": "cannot vote on your own review"}))));
+    }
+    let active_model = review_vote::ActiveModel {
+        user_id: Set(user_id),
+        review_id: Set(id),
+        helpful: Set(vote.helpful),
+        created_at: Set(chrono::Utc::now().naive_utc()),
+    };
+    review_vote::Entity::insert(active_model)
+        .on_conflict(OnConflict::columns([review_vote::Column::UserId, review_vote::Column::ReviewId]).update_column(review_vote::Column::Helpful).to_owned())
+        .exec(&state.db)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"failed to insert item": e.to_string()}))))?;
+    let resp: Review = load_item(&state.db, id).await?.into();
+    Ok(Json(resp))
+}
+
+pub async fn unvote(state: State<AppState>, principal: Principal, Path(id): Path<i32>) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let user_id = principal.user_id
+        .ok_or_else(|| (StatusCode::FORBIDDEN, Json(json!({"error": "api key is not tied to a user"}))))?;
+    review_vote::Entity::delete_by_id((user_id, id))
+        .exec(&state.db)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"error": e.to_string()}))))?;
+    let resp: Review = load_item(&state.db, id).await?.into();
+    Ok(Json(resp))
+}
+
 pub fn routes() -> Router<AppState> {
     Router::new()
         .route("/reviews", get(list))
@@ -229,4 +336,6 @@ pub fn routes() -> Router<AppState> {
         .route("/reviews/{id}", delete(remove))
         .route("/reviews/{id}", patch(patch_one))
         .route("/reviews/{id}", put(put_one))
+        .route("/reviews/{id}/vote", put(vote))
+        .route("/reviews/{id}/vote", delete(unvote))
 }
\ No newline at end of file
@@ -5,6 +5,7 @@ use sea_orm::{ActiveModelTrait, ModelTrait, EntityTrait, Set, IntoActiveModel, C
 use sea_orm::EntityLoaderTrait;
 use sea_orm::prelude::*;
 use crate::app_state::AppState;
+use crate::services::notifications;
 use crate::models::source::{ActiveModel, Entity, Model, ModelEx, Status};
 use super::{chapter::Chapter as Chapter, group::Group as Group, novel::Novel as Novel, };
 #[derive(Clone, Debug, Default, Deserialize, Serialize)]
@@ -12,7 +13,7 @@ pub struct Source {
     pub id: i32,
     pub created_at: DateTime,
     pub last_updated: DateTime,
-    pub chapter_count: Option<i8>,
+    pub chapter_count: Option<i32>,
     pub chapters: Option<Vec<Chapter>>,
     pub completely_translated: Option<bool>,
     pub group: Option<Group>,
@@ -69,7 +70,7 @@ impl From<ModelEx> for Source {
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct SourceCreate {
-    pub chapter_count: Option<i8>,
+    pub chapter_count: Option<i32>,
     pub chapters: Option<Vec<Chapter>>,
     pub completely_translated: Option<bool>,
     pub group: Option<Group>,
@@ -99,7 +100,7 @@ impl From<SourceCreate> for ActiveModel {
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct SourceUpdate {
-    pub chapter_count: Option<i8>,
+    pub chapter_count: Option<i32>,
     pub chapters: Option<Vec<Chapter>>,
     pub completely_translated: Option<bool>,
     pub group: Option<Group>,
@@ -132,7 +133,7 @@ impl SourceUpdate {
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct SourcePatch {
-    pub chapter_count: Option<i8>,
+    pub chapter_count: Option<i32>,
     pub chapters: Option<Vec<Chapter>>,
     pub completely_translated: Option<bool>,
     pub group: Option<Group>,
@@ -201,6 +202,7 @@ pub async fn create(state: State<AppState>, Json(create): Json<SourceCreate>) ->
     let model = active_model.insert(&state.db)
         .await
         .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"failed to insert item": e.to_string()}))))?;
+        notifications::log_failure(notifications::new_source(&state.db, &model).await, "new source");
         let resp: Source = model.into();
         Ok(Json(resp))
 
@@ -0,0 +1,47 @@
+use serde_json::json;
+use axum::{Router, extract::{Query, State}, http::StatusCode, routing::get, response::IntoResponse, Json};
+use serde::{Deserialize, Serialize};
+use sea_orm::prelude::*;
+use crate::app_state::AppState;
+use crate::models::user::Role;
+use crate::services::{activity, auth::{self, Principal}};
+
+const MAX_DAYS: i32 = 366;
+const MAX_WEEKS: i32 = 52;
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct RangeFilter {
+    pub days: Option<i32>,
+    pub weeks: Option<i32>,
+}
+
+fn db_error(e: DbErr) -> (StatusCode, Json<serde_json::Value>) {
+    (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"error": e.to_string()})))
+}
+
+pub async fn active_users(state: State<AppState>, principal: Principal) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    auth::require_role(&state.db, &principal, &[Role::Admin]).await?;
+    let resp = activity::active_users(&state.db).await.map_err(db_error)?;
+    Ok(Json(resp))
+}
+
+pub async fn registrations(state: State<AppState>, principal: Principal, Query(filter): Query<RangeFilter>) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    auth::require_role(&state.db, &principal, &[Role::Admin]).await?;
+    let days = filter.days.unwrap_or(30).clamp(1, MAX_DAYS);
+    let resp = activity::registrations(&state.db, days).await.map_err(db_error)?;
+    Ok(Json(resp))
+}
+
+pub async fn retention(state: State<AppState>, principal: Principal, Query(filter): Query<RangeFilter>) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    auth::require_role(&state.db, &principal, &[Role::Admin]).await?;
+    let weeks = filter.weeks.unwrap_or(8).clamp(1, MAX_WEEKS);
+    let resp = activity::retention(&state.db, weeks).await.map_err(db_error)?;
+    Ok(Json(resp))
+}
+
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/admin/stats/active-users", get(active_users))
+        .route("/admin/stats/registrations", get(registrations))
+        .route("/admin/stats/retention", get(retention))
+}
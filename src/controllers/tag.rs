@@ -1,11 +1,13 @@
 use serde_json::json;
-use axum::{Router, extract::{Path, State}, http::StatusCode, routing::{delete, get, patch, post, put}, response::IntoResponse, Json};
-use serde::{Deserialize, Serialize};
-use sea_orm::{ActiveModelTrait, ModelTrait, EntityTrait, Set, IntoActiveModel, ConnectionTrait};
+use axum::{Router, extract::{Path, Query, State}, http::{StatusCode, Uri}, routing::{delete, get, patch, post, put}, response::{IntoResponse, Response}, Json};
+use serde::{Deserialize, Deserializer, Serialize};
+use sea_orm::{ActiveModelTrait, ModelTrait, EntityTrait, QueryFilter, QueryOrder, Set, IntoActiveModel, ConnectionTrait, TransactionTrait};
 use sea_orm::EntityLoaderTrait;
 use sea_orm::prelude::*;
 use crate::app_state::AppState;
-use crate::models::tag::{ActiveModel, Entity, Model, ModelEx, Category};
+use crate::models::tag::{self, ActiveModel, Entity, Model, ModelEx, Category};
+use crate::models::{tag_alias, user::Role};
+use crate::services::{auth::{self, Principal}, slugs::{self, Resource}, tags};
 use super::{novel::Novel as Novel, };
 #[derive(Clone, Debug, Default, Deserialize, Serialize)]
 pub struct Tag {
@@ -16,8 +18,9 @@ pub struct Tag {
     pub description: Option<String>,
     pub name: String,
     pub novels: Option<Vec<Novel>>,
+    pub parent_id: Option<i32>,
     pub slug: String,
-    pub usage_count: Option<i8>
+    pub usage_count: Option<i32>
     
 }
 
@@ -31,6 +34,7 @@ impl From<Model> for Tag {
             description: model.description,
             name: model.name,
             novels: None,
+            parent_id: model.parent_id,
             slug: model.slug,
             usage_count: model.usage_count
             
@@ -48,6 +52,7 @@ impl From<ModelEx> for Tag {
             description: model.description,
             name: model.name,
             novels: Some(model.novels.into_iter().map(Novel::from).collect()),
+            parent_id: model.parent_id,
             slug: model.slug,
             usage_count: model.usage_count,
             
@@ -61,8 +66,10 @@ pub struct TagCreate {
     pub description: Option<String>,
     pub name: String,
     pub novels: Option<Vec<Novel>>,
-    pub slug: String,
-    pub usage_count: Option<i8>
+    pub parent_id: Option<i32>,
+    /// Generated from the name when left out.
+    pub slug: Option<String>,
+    pub usage_count: Option<i32>
     
 }
 
@@ -72,7 +79,7 @@ impl From<TagCreate> for ActiveModel {
             category: Set(source.category.clone()),
             description: Set(source.description.clone()),
             name: Set(source.name.clone()),
-            slug: Set(source.slug.clone()),
+            parent_id: Set(source.parent_id),
             usage_count: Set(source.usage_count.clone()),
             ..Default::default()
         }
@@ -85,8 +92,10 @@ pub struct TagUpdate {
     pub description: Option<String>,
     pub name: String,
     pub novels: Option<Vec<Novel>>,
-    pub slug: String,
-    pub usage_count: Option<i8>
+    pub parent_id: Option<i32>,
+    /// Generated from the name when left out.
+    pub slug: Option<String>,
+    pub usage_count: Option<i32>
     
 }
 
@@ -97,7 +106,7 @@ impl TagUpdate {
             category: Set(self.category.clone()),
             description: Set(self.description.clone()),
             name: Set(self.name.clone()),
-            slug: Set(self.slug.clone()),
+            parent_id: Set(self.parent_id),
             usage_count: Set(self.usage_count.clone()),
             ..Default::default()
         }
@@ -110,11 +119,19 @@ pub struct TagPatch {
     pub description: Option<String>,
     pub name: Option<String>,
     pub novels: Option<Vec<Novel>>,
+    /// Absent leaves the parent as is, `null` makes the tag a root.
+    #[serde(default, deserialize_with = "present")]
+    pub parent_id: Option<Option<i32>>,
     pub slug: Option<String>,
-    pub usage_count: Option<i8>
+    pub usage_count: Option<i32>
     
 }
 
+/// Tells a field sent as `null` (`Some(None)`) from one left out (`None`, via `#[serde(default)]`).
+fn present<'de, D: Deserializer<'de>, T: Deserialize<'de>>(deserializer: D) -> Result<Option<Option<T>>, D::Error> {
+    Option::<T>::deserialize(deserializer).map(Some)
+}
+
 impl TagPatch {
     pub fn patch_active_model(&self, active_model: &mut ActiveModel) {
         if self.category.is_some() {
@@ -123,14 +140,67 @@ impl TagPatch {
             active_model.description = Set(self.description.clone());
         }if let Some(value) = &self.name {
             active_model.name = Set(value.clone());
-        }if let Some(value) = &self.slug {
-            active_model.slug = Set(value.clone());
+        }if let Some(value) = self.parent_id {
+            active_model.parent_id = Set(value);
         }if self.usage_count.is_some() {
             active_model.usage_count = Set(self.usage_count.clone());
         }
     }
 }
 
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct TagAlias {
+    pub id: i32,
+    pub created_at: DateTime,
+    pub tag_id: i32,
+    pub name: String,
+}
+
+impl From<tag_alias::Model> for TagAlias {
+    fn from(model: tag_alias::Model) -> Self {
+        Self {
+            id: model.id,
+            created_at: model.created_at,
+            tag_id: model.tag_id,
+            name: model.name,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct TagAliasCreate {
+    pub name: String,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct TagMerge {
+    /// The tag that survives the merge.
+    pub into: i32,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ResolveOptions {
+    pub name: String,
+}
+
+const MODERATORS: &[Role] = &[Role::Admin, Role::Moderator];
+
+fn db_error(e: DbErr) -> (StatusCode, Json<serde_json::Value>) {
+    (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"error": e.to_string()})))
+}
+
+/// A parent must exist and must not be the tag itself or anything below it.
+async fn check_parent<C: ConnectionTrait>(db: &C, id: Option<i32>, parent_id: Option<i32>) -> Result<(), (StatusCode, Json<serde_json::Value>)> {
+    let Some(parent_id) = parent_id else { return Ok(()) };
+    if Entity::find_by_id(parent_id).one(db).await.map_err(db_error)?.is_none() {
+        return Err((StatusCode::UNPROCESSABLE_ENTITY, Json(json!({"error": "parent tag does not exist"}))));
+    }
+    if let Some(id) = id && tags::with_descendants(db, id).await.map_err(db_error)?.contains(&parent_id) {
+        return Err((StatusCode::UNPROCESSABLE_ENTITY, Json(json!({"error": "a tag cannot sit under itself or its descendants"}))));
+    }
+    Ok(())
+}
+
 async fn load_item<C>(
     db: &C,
     id: i32,
@@ -156,9 +226,16 @@ pub async fn list(state: State<AppState>) -> Result<impl IntoResponse, (StatusCo
     Ok(Json(responses))
 }
 
-pub async fn create(state: State<AppState>, Json(create): Json<TagCreate>) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+pub async fn create(state: State<AppState>, principal: Principal, Json(create): Json<TagCreate>) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    auth::require_role(&state.db, &principal, MODERATORS).await?;
+    check_parent(&state.db, None, create.parent_id).await?;
+    let source = create.slug.clone().unwrap_or_else(|| create.name.clone());
     let active_model:ActiveModel = create.into();
-    let model = active_model.insert(&state.db)
+    let model = slugs::insert_with(&state.db, Resource::Tag, &source, |slug| {
+        let mut active_model = active_model.clone();
+        active_model.slug = Set(slug);
+        active_model.insert(&state.db)
+    })
         .await
         .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"failed to insert item": e.to_string()}))))?;
         let resp: Tag = model.into();
@@ -166,28 +243,42 @@ pub async fn create(state: State<AppState>, Json(create): Json<TagCreate>) -> Re
 
 }
 
-pub async fn patch_one(state: State<AppState>, Path(id): Path<i32>, Json(patch): Json<TagPatch> ) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+pub async fn patch_one(state: State<AppState>, principal: Principal, Path(id): Path<i32>, Json(patch): Json<TagPatch> ) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    auth::require_role(&state.db, &principal, MODERATORS).await?;
     let model = load_item(&state.db, id).await?;
+    check_parent(&state.db, Some(id), patch.parent_id.flatten()).await?;
     let mut active_model = model.into_active_model();
     patch.patch_active_model(&mut active_model);
-    let model = active_model.update(&state.db)
+    let mut model = active_model.update(&state.db)
         .await
         .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"failed to update item": e.to_string()}))))?;
+    if patch.name.is_some() || patch.slug.is_some() {
+        model.slug = slugs::assign(&state.db, Resource::Tag, id, patch.slug.as_deref().unwrap_or(&model.name))
+            .await
+            .map_err(db_error)?;
+    }
     let resp: Tag = model.into();
     Ok(Json(resp))
 }
 
-pub async fn put_one(state: State<AppState>, Path(id): Path<i32>, Json(update): Json<TagUpdate>) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+pub async fn put_one(state: State<AppState>, principal: Principal, Path(id): Path<i32>, Json(update): Json<TagUpdate>) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    auth::require_role(&state.db, &principal, MODERATORS).await?;
     let _ = load_item(&state.db, id).await?;
+    check_parent(&state.db, Some(id), update.parent_id).await?;
+    let slug = update.slug.clone();
     let active_model = update.into_active_model(id);
-    let model = active_model.update(&state.db)
+    let mut model = active_model.update(&state.db)
         .await
         .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"failed to update item": e.to_string()}))))?;
+    model.slug = slugs::assign(&state.db, Resource::Tag, id, slug.as_deref().unwrap_or(&model.name))
+        .await
+        .map_err(db_error)?;
     let resp: Tag = model.into();
     Ok(Json(resp))
 }
 
-pub async fn remove(state: State<AppState>, Path(id): Path<i32>) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+pub async fn remove(state: State<AppState>, principal: Principal, Path(id): Path<i32>) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    auth::require_role(&state.db, &principal, MODERATORS).await?;
     let model = load_item(&state.db, id).await?;
     model.delete(&state.db)
         .await
@@ -207,12 +298,113 @@ pub async fn read_one(state: State<AppState>, Path(id): Path<i32>) -> Result<imp
     Ok(Json(resp))
 }
 
+/// The tags directly under this one.
+pub async fn children(state: State<AppState>, Path(id): Path<i32>) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    load_item(&state.db, id).await?;
+    let models = Entity::find()
+        .filter(tag::Column::ParentId.eq(id))
+        .order_by_asc(tag::Column::Name)
+        .all(&state.db)
+        .await
+        .map_err(db_error)?;
+    let responses: Vec<Tag> = models.into_iter().map(Into::into).collect();
+    Ok(Json(responses))
+}
+
+/// Finds the canonical tag for a name, slug or alias.
+pub async fn resolve(state: State<AppState>, Query(options): Query<ResolveOptions>) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let model = tags::resolve(&state.db, &options.name)
+        .await
+        .map_err(db_error)?
+        .ok_or_else(|| (StatusCode::NOT_FOUND, Json(json!({"error": "not found"}))))?;
+    let resp: Tag = model.into();
+    Ok(Json(resp))
+}
+
+pub async fn list_aliases(state: State<AppState>, Path(id): Path<i32>) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    load_item(&state.db, id).await?;
+    let models = tag_alias::Entity::find()
+        .filter(tag_alias::Column::TagId.eq(id))
+        .order_by_asc(tag_alias::Column::Name)
+        .all(&state.db)
+        .await
+        .map_err(db_error)?;
+    let responses: Vec<TagAlias> = models.into_iter().map(Into::into).collect();
+    Ok(Json(responses))
+}
+
+/// Adds another name for the tag. Names are unique across tags and aliases, ignoring case.
+pub async fn create_alias(state: State<AppState>, principal: Principal, Path(id): Path<i32>, Json(create): Json<TagAliasCreate>) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    auth::require_role(&state.db, &principal, MODERATORS).await?;
+    load_item(&state.db, id).await?;
+    let name = create.name.trim();
+    if name.is_empty() {
+        return Err((StatusCode::UNPROCESSABLE_ENTITY, Json(json!({"error": "name must not be empty"}))));
+    }
+    if tags::name_taken(&state.db, name).await.map_err(db_error)? {
+        return Err((StatusCode::CONFLICT, Json(json!({"error": "name is already used by a tag"}))));
+    }
+    let model = tag_alias::ActiveModel {
+        created_at: Set(chrono::Utc::now().naive_utc()),
+        tag_id: Set(id),
+        name: Set(name.to_string()),
+        ..Default::default()
+    }
+    .insert(&state.db)
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"failed to insert item": e.to_string()}))))?;
+    let resp: TagAlias = model.into();
+    Ok((StatusCode::CREATED, Json(resp)))
+}
+
+pub async fn remove_alias(state: State<AppState>, principal: Principal, Path((id, alias_id)): Path<(i32, i32)>) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    auth::require_role(&state.db, &principal, MODERATORS).await?;
+    let result = tag_alias::Entity::delete_many()
+        .filter(tag_alias::Column::Id.eq(alias_id))
+        .filter(tag_alias::Column::TagId.eq(id))
+        .exec(&state.db)
+        .await
+        .map_err(db_error)?;
+    if result.rows_affected == 0 {
+        return Err((StatusCode::NOT_FOUND, Json(json!({"error": "not found"}))));
+    }
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Folds this tag into `into` and returns the surviving tag.
+pub async fn merge(state: State<AppState>, principal: Principal, Path(id): Path<i32>, Json(merge): Json<TagMerge>) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    auth::require_role(&state.db, &principal, MODERATORS).await?;
+    let source = load_item(&state.db, id).await?;
+    let target = load_item(&state.db, merge.into).await?;
+    if tags::with_descendants(&state.db, source.id).await.map_err(db_error)?.contains(&target.id) {
+        return Err((StatusCode::UNPROCESSABLE_ENTITY, Json(json!({"error": "cannot merge a tag into itself or its descendants"}))));
+    }
+    let txn = state.db.begin().await.map_err(db_error)?;
+    tags::merge(&txn, &source, &target).await.map_err(db_error)?;
+    txn.commit().await.map_err(db_error)?;
+    let resp: Tag = load_item(&state.db, target.id).await?.into();
+    Ok(Json(resp))
+}
+
+/// Serves the tag a slug names. Old slugs redirect to the current one.
+pub async fn read_by_slug(state: State<AppState>, uri: Uri, Path(slug): Path<String>) -> Result<Response, (StatusCode, Json<serde_json::Value>)> {
+    let db = state.db.clone();
+    slugs::serve(&db, Resource::Tag, &slug, &uri, |id| read_one(state, Path(id))).await
+}
+
 pub fn routes() -> Router<AppState> {
     Router::new()
         .route("/tags", get(list))
         .route("/tags", post(create))
+        .route("/tags/resolve", get(resolve))
+        .route("/tags/by-slug/{slug}", get(read_by_slug))
         .route("/tags/{id}", get(read_one))
         .route("/tags/{id}", delete(remove))
         .route("/tags/{id}", patch(patch_one))
         .route("/tags/{id}", put(put_one))
+        .route("/tags/{id}/children", get(children))
+        .route("/tags/{id}/aliases", get(list_aliases))
+        .route("/tags/{id}/aliases", post(create_alias))
+        .route("/tags/{id}/aliases/{alias_id}", delete(remove_alias))
+        .route("/tags/{id}/merge", post(merge))
 }
\ No newline at end of file
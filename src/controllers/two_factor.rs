@@ -0,0 +1,196 @@
+use serde_json::json;
+use axum::{Router, extract::State, http::StatusCode, routing::{get, post, put}, response::IntoResponse, Json};
+use serde::{Deserialize, Serialize};
+use sea_orm::{ActiveModelTrait, EntityTrait, Iterable, PaginatorTrait, QueryFilter, Set, IntoActiveModel, sea_query::OnConflict};
+use sea_orm::prelude::*;
+use crate::app_state::AppState;
+use crate::models::{two_factor_policy, user::Role, user_recovery_code};
+use crate::services::{account, auth::{self, Principal}, password, totp};
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct TwoFactorStatus {
+    pub enabled: bool,
+    pub enabled_at: Option<DateTime>,
+    pub pending_enrollment: bool,
+    pub required_by_role: bool,
+    pub recovery_codes_remaining: u64,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Enrollment {
+    pub secret: String,
+    /// Render as a QR code for authenticator apps.
+    pub provisioning_uri: String,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CodeConfirm {
+    pub code: String,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RecoveryCodes {
+    pub recovery_codes: Vec<String>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Disable {
+    pub password: String,
+    pub totp_code: Option<String>,
+    pub recovery_code: Option<String>,
+}
+
+/// Whether a role must enroll before it gets a usable session.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Policy {
+    pub role: Role,
+    pub required: bool,
+}
+
+fn db_error(e: DbErr) -> (StatusCode, Json<serde_json::Value>) {
+    (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"error": e.to_string()})))
+}
+
+pub async fn status(state: State<AppState>, principal: Principal) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let user = auth::current_user(&state.db, &principal).await?;
+    let remaining = user_recovery_code::Entity::find()
+        .filter(user_recovery_code::Column::UserId.eq(user.id))
+        .filter(user_recovery_code::Column::UsedAt.is_null())
+        .count(&state.db)
+        .await
+        .map_err(db_error)?;
+    let resp = TwoFactorStatus {
+        enabled: user.totp_enabled_at.is_some(),
+        enabled_at: user.totp_enabled_at,
+        pending_enrollment: user.totp_enabled_at.is_none() && user.totp_secret.is_some(),
+        required_by_role: totp::required_for(&state.db, user.role).await.map_err(db_error)?,
+        recovery_codes_remaining: remaining,
+    };
+    Ok(Json(resp))
+}
+
+/// Starts (or restarts) enrollment with a fresh secret; 2FA is only enforced after `activate`.
+pub async fn enroll(state: State<AppState>, principal: Principal) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let user = auth::current_user(&state.db, &principal).await?;
+    if user.totp_enabled_at.is_some() {
+        return Err((StatusCode::CONFLICT, Json(json!({"error": "two-factor authentication is already enabled"}))));
+    }
+    let secret = totp::generate_secret();
+    let provisioning_uri = totp::provisioning_uri(&secret, &user.username);
+    let mut active_model = user.into_active_model();
+    active_model.totp_secret = Set(Some(secret.clone()));
+    active_model.totp_last_step = Set(None);
+    active_model.update(&state.db)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"failed to update item": e.to_string()}))))?;
+    Ok(Json(Enrollment { secret, provisioning_uri }))
+}
+
+pub async fn activate(state: State<AppState>, principal: Principal, Json(confirm): Json<CodeConfirm>) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let user = auth::current_user(&state.db, &principal).await?;
+    if user.totp_enabled_at.is_some() {
+        return Err((StatusCode::CONFLICT, Json(json!({"error": "two-factor authentication is already enabled"}))));
+    }
+    let secret = user.totp_secret.clone()
+        .ok_or_else(|| (StatusCode::BAD_REQUEST, Json(json!({"error": "start enrollment first"}))))?;
+    let step = totp::verify(&secret, &confirm.code, chrono::Utc::now().timestamp(), user.totp_last_step)
+        .ok_or_else(|| (StatusCode::BAD_REQUEST, Json(json!({"error": "invalid two-factor code"}))))?;
+    let user_id = user.id;
+    let mut active_model = user.into_active_model();
+    active_model.totp_enabled_at = Set(Some(chrono::Utc::now().naive_utc()));
+    active_model.totp_last_step = Set(Some(step));
+    active_model.update(&state.db)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"failed to update item": e.to_string()}))))?;
+    let recovery_codes = account::replace_recovery_codes(&state.db, user_id).await.map_err(db_error)?;
+    Ok(Json(RecoveryCodes { recovery_codes }))
+}
+
+pub async fn regenerate_recovery_codes(state: State<AppState>, principal: Principal, Json(confirm): Json<CodeConfirm>) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let user = auth::current_user(&state.db, &principal).await?;
+    if user.totp_enabled_at.is_none() {
+        return Err((StatusCode::BAD_REQUEST, Json(json!({"error": "two-factor authentication is not enabled"}))));
+    }
+    if !account::check_second_factor(&state.db, &user, Some(&confirm.code), None).await.map_err(db_error)? {
+        return Err((StatusCode::BAD_REQUEST, Json(json!({"error": "invalid two-factor code"}))));
+    }
+    let recovery_codes = account::replace_recovery_codes(&state.db, user.id).await.map_err(db_error)?;
+    Ok(Json(RecoveryCodes { recovery_codes }))
+}
+
+pub async fn disable(state: State<AppState>, principal: Principal, Json(disable): Json<Disable>) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let user = auth::current_user(&state.db, &principal).await?;
+    if totp::required_for(&state.db, user.role).await.map_err(db_error)? {
+        return Err((StatusCode::FORBIDDEN, Json(json!({"error": "your role requires two-factor authentication"}))));
+    }
+    if !password::verify(&disable.password, &user.password_hash) {
+        return Err((StatusCode::UNAUTHORIZED, Json(json!({"error": "invalid credentials"}))));
+    }
+    if user.totp_enabled_at.is_some() {
+        let passed = account::check_second_factor(&state.db, &user, disable.totp_code.as_deref(), disable.recovery_code.as_deref())
+            .await
+            .map_err(db_error)?;
+        if !passed {
+            return Err((StatusCode::BAD_REQUEST, Json(json!({"error": "invalid two-factor code"}))));
+        }
+    }
+    let user_id = user.id;
+    let mut active_model = user.into_active_model();
+    active_model.totp_secret = Set(None);
+    active_model.totp_enabled_at = Set(None);
+    active_model.totp_last_step = Set(None);
+    active_model.update(&state.db)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"failed to update item": e.to_string()}))))?;
+    user_recovery_code::Entity::delete_many()
+        .filter(user_recovery_code::Column::UserId.eq(user_id))
+        .exec(&state.db)
+        .await
+        .map_err(db_error)?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Every role, including ones without a stored row, which are not required.
+async fn load_policy<C: ConnectionTrait>(db: &C) -> Result<Vec<Policy>, DbErr> {
+    let stored = two_factor_policy::Entity::find().all(db).await?;
+    Ok(Role::iter()
+        .map(|role| Policy {
+            role,
+            required: stored.iter().any(|policy| policy.role == role && policy.required),
+        })
+        .collect())
+}
+
+pub async fn read_policy(state: State<AppState>, principal: Principal) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    auth::require_role(&state.db, &principal, &[Role::Admin]).await?;
+    let policy = load_policy(&state.db).await.map_err(db_error)?;
+    Ok(Json(policy))
+}
+
+/// Sessions issued before the change keep their scopes; the policy applies from the next login.
+pub async fn update_policy(state: State<AppState>, principal: Principal, Json(update): Json<Policy>) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    auth::require_role(&state.db, &principal, &[Role::Admin]).await?;
+    let active_model = two_factor_policy::ActiveModel {
+        role: Set(update.role),
+        required: Set(update.required),
+        ..Default::default()
+    };
+    two_factor_policy::Entity::insert(active_model)
+        .on_conflict(OnConflict::column(two_factor_policy::Column::Role).update_column(two_factor_policy::Column::Required).to_owned())
+        .exec(&state.db)
+        .await
+        .map_err(db_error)?;
+    let policy = load_policy(&state.db).await.map_err(db_error)?;
+    Ok(Json(policy))
+}
+
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/auth/2fa", get(status))
+        .route("/auth/2fa/enroll", post(enroll))
+        .route("/auth/2fa/activate", post(activate))
+        .route("/auth/2fa/recovery-codes", post(regenerate_recovery_codes))
+        .route("/auth/2fa/disable", post(disable))
+        .route("/admin/two-factor-policy", get(read_policy))
+        .route("/admin/two-factor-policy", put(update_policy))
+}
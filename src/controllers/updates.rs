@@ -0,0 +1,208 @@
+use serde_json::json;
+use axum::{Router, extract::{Query, State}, http::StatusCode, routing::{get, post}, response::IntoResponse, Json};
+use serde::{Deserialize, Serialize};
+use sea_orm::{ConnectionTrait, DbBackend, EntityTrait, FromQueryResult, QueryFilter, Statement};
+use sea_orm::prelude::*;
+use std::collections::HashMap;
+use crate::app_state::AppState;
+use crate::models::{library_entry::Status, novel, source, user_preference};
+use crate::services::{auth::Principal, preferences::{self, ListOptions}};
+use super::profile::ProfileNovel;
+
+/// Chapters of the user's library novels that are newer than the last read chapter and not
+/// marked read. Dropped entries are ignored. `$1` is the user, `$2` an optional novel and `$3`
+/// the chapter languages to keep, as in `user_preference`; empty keeps all.
+const UNREAD: &str = r#"
+    unread AS (
+        SELECT le.id AS entry_id, le.novel_id, c.id AS chapter_id, c.source_id, c.number, c.title, c.created_at
+        FROM public.library_entry le
+        JOIN public.chapter_novel cn ON cn.novel_id = le.novel_id
+        JOIN public.chapter c ON c.id = cn.chapter_id
+        LEFT JOIN public.chapter last_read ON last_read.id = le.last_read_chapter_id
+        WHERE le.user_id = $1
+            AND ($2::int IS NULL OR le.novel_id = $2)
+            AND le.status <> 'dropped'
+            AND (last_read.id IS NULL OR c.created_at > last_read.created_at)
+            AND (cardinality($3::TEXT[]) = 0 OR COALESCE(c.language = ANY($3::TEXT[]), FALSE))
+            AND NOT EXISTS (
+                SELECT 1 FROM public.library_chapter_read r
+                WHERE r.library_entry_id = le.id AND r.chapter_id = c.id
+            )
+    )"#;
+
+#[derive(Clone, Debug, Serialize)]
+pub struct NovelUpdate {
+    pub novel: ProfileNovel,
+    pub status: Status,
+    pub last_read_chapter_id: Option<i32>,
+    pub unread_count: i64,
+    pub newest_at: DateTime,
+    /// The newest unread chapter from each source (translation group release).
+    pub newest_by_source: Vec<SourceChapter>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct SourceChapter {
+    pub source_id: i32,
+    pub source_name: Option<String>,
+    pub chapter_id: i32,
+    pub number: String,
+    pub title: String,
+    pub created_at: DateTime,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct MarkRead {
+    /// Limit to one novel; the whole library when absent.
+    pub novel_id: Option<i32>,
+}
+
+#[derive(Clone, Debug, Default, Serialize, FromQueryResult)]
+pub struct MarkedRead {
+    pub novels: i64,
+    pub chapters: i64,
+}
+
+#[derive(Debug, FromQueryResult)]
+struct UnreadNovel {
+    novel_id: i32,
+    status: Status,
+    last_read_chapter_id: Option<i32>,
+    unread_count: i64,
+    newest_at: DateTime,
+}
+
+#[derive(Debug, FromQueryResult)]
+struct UnreadChapter {
+    novel_id: i32,
+    source_id: i32,
+    chapter_id: i32,
+    number: String,
+    title: String,
+    created_at: DateTime,
+}
+
+/// Novels with unread chapters, most recently updated first. With content preferences, hidden
+/// novels and chapters in other languages are left out.
+pub async fn find_unread<C: ConnectionTrait>(db: &C, user_id: i32, preference: Option<&user_preference::Model>) -> Result<Vec<NovelUpdate>, DbErr> {
+    let languages = preference.map(|preference| preference.chapter_languages.clone()).unwrap_or_default();
+    let mut novels = UnreadNovel::find_by_statement(Statement::from_sql_and_values(DbBackend::Postgres, format!(r#"
+        WITH {UNREAD}
+        SELECT u.novel_id, le.status, le.last_read_chapter_id, COUNT(*) AS unread_count, MAX(u.created_at) AS newest_at
+        FROM unread u
+        JOIN public.library_entry le ON le.id = u.entry_id
+        GROUP BY u.novel_id, le.status, le.last_read_chapter_id
+        ORDER BY newest_at DESC"#), [user_id.into(), Option::<i32>::None.into(), languages.clone().into()]))
+        .all(db)
+        .await?;
+    if let Some(preference) = preference {
+        let allowed = preferences::allowed_novel_ids(db, preference, novels.iter().map(|novel| novel.novel_id).collect()).await?;
+        novels.retain(|novel| allowed.contains(&novel.novel_id));
+    }
+    if novels.is_empty() {
+        return Ok(vec![]);
+    }
+    let chapters = UnreadChapter::find_by_statement(Statement::from_sql_and_values(DbBackend::Postgres, format!(r#"
+        WITH {UNREAD}
+        SELECT DISTINCT ON (novel_id, source_id) novel_id, source_id, chapter_id, number, title, created_at
+        FROM unread
+        ORDER BY novel_id, source_id, created_at DESC, chapter_id DESC"#), [user_id.into(), Option::<i32>::None.into(), languages.into()]))
+        .all(db)
+        .await?;
+    let novel_ids: Vec<i32> = novels.iter().map(|novel| novel.novel_id).collect();
+    let mut novel_models: HashMap<i32, novel::Model> = novel::Entity::find()
+        .filter(novel::Column::Id.is_in(novel_ids))
+        .all(db)
+        .await?
+        .into_iter()
+        .map(|model| (model.id, model))
+        .collect();
+    let source_ids: Vec<i32> = chapters.iter().map(|chapter| chapter.source_id).collect();
+    let source_names: HashMap<i32, String> = source::Entity::find()
+        .filter(source::Column::Id.is_in(source_ids))
+        .all(db)
+        .await?
+        .into_iter()
+        .map(|model| (model.id, model.name))
+        .collect();
+    let mut by_novel: HashMap<i32, Vec<SourceChapter>> = HashMap::new();
+    for chapter in chapters {
+        by_novel.entry(chapter.novel_id).or_default().push(SourceChapter {
+            source_id: chapter.source_id,
+            source_name: source_names.get(&chapter.source_id).cloned(),
+            chapter_id: chapter.chapter_id,
+            number: chapter.number,
+            title: chapter.title,
+            created_at: chapter.created_at,
+        });
+    }
+    Ok(novels.into_iter().map(|unread| {
+        let mut newest_by_source = by_novel.remove(&unread.novel_id).unwrap_or_default();
+        newest_by_source.sort_by_key(|update| std::cmp::Reverse(update.created_at));
+        NovelUpdate {
+            novel: novel_models.remove(&unread.novel_id).map(Into::into).unwrap_or_default(),
+            status: unread.status,
+            last_read_chapter_id: unread.last_read_chapter_id,
+            unread_count: unread.unread_count,
+            newest_at: unread.newest_at,
+            newest_by_source,
+        }
+    }).collect())
+}
+
+/// Marks every unread chapter as read, for the whole library or a single novel, and moves
+/// each entry's last read position to its newest chapter.
+pub async fn mark_all_read<C: ConnectionTrait>(db: &C, user_id: i32, novel_id: Option<i32>) -> Result<MarkedRead, DbErr> {
+    let stmt = Statement::from_sql_and_values(DbBackend::Postgres, format!(r#"
+        WITH {UNREAD},
+        marked AS (
+            INSERT INTO public.library_chapter_read (library_entry_id, chapter_id, read_at)
+            SELECT entry_id, chapter_id, CURRENT_TIMESTAMP FROM unread
+            ON CONFLICT DO NOTHING
+            RETURNING 1
+        ),
+        newest AS (
+            SELECT DISTINCT ON (entry_id) entry_id, chapter_id
+            FROM unread
+            ORDER BY entry_id, created_at DESC, chapter_id DESC
+        ),
+        moved AS (
+            UPDATE public.library_entry le
+            SET last_read_chapter_id = newest.chapter_id,
+                last_read_at = CURRENT_TIMESTAMP,
+                status = CASE WHEN le.status = 'plan_to_read' THEN 'reading' ELSE le.status END
+            FROM newest
+            WHERE le.id = newest.entry_id
+            RETURNING 1
+        )
+        SELECT (SELECT COUNT(*) FROM moved) AS novels, (SELECT COUNT(*) FROM marked) AS chapters"#),
+        [user_id.into(), novel_id.into(), Vec::<String>::new().into()]);
+    Ok(MarkedRead::find_by_statement(stmt).one(db).await?.unwrap_or_default())
+}
+
+fn db_error(e: DbErr) -> (StatusCode, Json<serde_json::Value>) {
+    (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"error": e.to_string()})))
+}
+
+fn require_user(principal: &Principal) -> Result<i32, (StatusCode, Json<serde_json::Value>)> {
+    principal.user_id.ok_or_else(|| (StatusCode::FORBIDDEN, Json(json!({"error": "api key is not tied to a user"}))))
+}
+
+pub async fn list(state: State<AppState>, principal: Principal, Query(options): Query<ListOptions>) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let user_id = require_user(&principal)?;
+    let preference = preferences::for_request(&state.db, Some(&principal), &options).await.map_err(db_error)?;
+    let resp = find_unread(&state.db, user_id, preference.as_ref()).await.map_err(db_error)?;
+    Ok(Json(resp))
+}
+
+pub async fn read_all(state: State<AppState>, principal: Principal, Query(filter): Query<MarkRead>) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let user_id = require_user(&principal)?;
+    let resp = mark_all_read(&state.db, user_id, filter.novel_id).await.map_err(db_error)?;
+    Ok(Json(resp))
+}
+
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/me/updates", get(list))
+        .route("/me/updates/read", post(read_all))
+}
@@ -1,11 +1,12 @@
 use serde_json::json;
 use axum::{Router, extract::{Path, State}, http::StatusCode, routing::{delete, get, patch, post, put}, response::IntoResponse, Json};
 use serde::{Deserialize, Serialize};
-use sea_orm::{ActiveModelTrait, ModelTrait, EntityTrait, Set, IntoActiveModel, ConnectionTrait};
+use sea_orm::{ActiveModelTrait, EntityTrait, Set, IntoActiveModel, ConnectionTrait};
 use sea_orm::EntityLoaderTrait;
 use sea_orm::prelude::*;
 use crate::app_state::AppState;
-use crate::models::user::{ActiveModel, Entity, Model, ModelEx, };
+use crate::models::user::{ActiveModel, Entity, Model, ModelEx, Role};
+use crate::services::{account, account_data, auth::{self, Principal}, markdown};
 use super::{reading_list::ReadingList as ReadingList, review::Review as Review, };
 #[derive(Clone, Debug, Default, Deserialize, Serialize)]
 pub struct User {
@@ -14,13 +15,16 @@ pub struct User {
     pub last_updated: DateTime,
     pub avatar_url: Option<String>,
     pub bio: Option<String>,
+    pub bio_html: Option<String>,
     pub display_name: Option<String>,
     pub email: String,
+    pub email_verified_at: Option<DateTime>,
     pub joined_date: Option<String>,
-    pub last_active: Option<String>,
-    pub password_hash: String,
+    pub last_active: Option<DateTime>,
     pub reading_lists: Option<Vec<ReadingList>>,
     pub reviews: Option<Vec<Review>>,
+    pub role: Role,
+    pub two_factor_enabled: bool,
     pub username: String
     
 }
@@ -33,13 +37,16 @@ impl From<Model> for User {
             last_updated: model.last_updated,
             avatar_url: model.avatar_url,
             bio: model.bio,
+            bio_html: model.bio_html,
             display_name: model.display_name,
             email: model.email,
+            email_verified_at: model.email_verified_at,
             joined_date: model.joined_date,
             last_active: model.last_active,
-            password_hash: model.password_hash,
             reading_lists: vec![].into(),
             reviews: vec![].into(),
+            role: model.role,
+            two_factor_enabled: model.totp_enabled_at.is_some(),
             username: model.username
             
         }
@@ -54,19 +61,24 @@ impl From<ModelEx> for User {
             last_updated: model.last_updated,
             avatar_url: model.avatar_url,
             bio: model.bio,
+            bio_html: model.bio_html,
             display_name: model.display_name,
             email: model.email,
+            email_verified_at: model.email_verified_at,
             joined_date: model.joined_date,
             last_active: model.last_active,
-            password_hash: model.password_hash,
             reading_lists: Some(model.reading_lists.into_iter().map(ReadingList::from).collect()),
-            reviews: Some(model.reviews.into_iter().map(Review::from).collect()),
+            reviews: Some(model.reviews.into_iter().filter(|review| review.hidden_at.is_none()).map(Review::from).collect()),
+            role: model.role,
+            two_factor_enabled: model.totp_enabled_at.is_some(),
             username: model.username,
             
         }
     }
 }
 
+/// An account an admin opens for someone. It has no password until its owner sets one through
+/// the password reset mail sent to `email`.
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct UserCreate {
     pub avatar_url: Option<String>,
@@ -74,8 +86,6 @@ pub struct UserCreate {
     pub display_name: Option<String>,
     pub email: String,
     pub joined_date: Option<String>,
-    pub last_active: Option<String>,
-    pub password_hash: String,
     pub reading_lists: Option<Vec<ReadingList>>,
     pub reviews: Option<Vec<Review>>,
     pub username: String
@@ -87,26 +97,24 @@ impl From<UserCreate> for ActiveModel {
         ActiveModel {
             avatar_url: Set(source.avatar_url.clone()),
             bio: Set(source.bio.clone()),
+            bio_html: Set(markdown::render_opt(source.bio.as_deref())),
             display_name: Set(source.display_name.clone()),
             email: Set(source.email.clone()),
             joined_date: Set(source.joined_date.clone()),
-            last_active: Set(source.last_active.clone()),
-            password_hash: Set(source.password_hash.clone()),
+            password_hash: Set(String::new()),
             username: Set(source.username.clone()),
             ..Default::default()
         }
     }
 }
 
+/// Email and password are left to their owner, through `/me/email` and the password reset.
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct UserUpdate {
     pub avatar_url: Option<String>,
     pub bio: Option<String>,
     pub display_name: Option<String>,
-    pub email: String,
     pub joined_date: Option<String>,
-    pub last_active: Option<String>,
-    pub password_hash: String,
     pub reading_lists: Option<Vec<ReadingList>>,
     pub reviews: Option<Vec<Review>>,
     pub username: String
@@ -119,28 +127,25 @@ impl UserUpdate {
             id: Set(id),
             avatar_url: Set(self.avatar_url.clone()),
             bio: Set(self.bio.clone()),
+            bio_html: Set(markdown::render_opt(self.bio.as_deref())),
             display_name: Set(self.display_name.clone()),
-            email: Set(self.email.clone()),
             joined_date: Set(self.joined_date.clone()),
-            last_active: Set(self.last_active.clone()),
-            password_hash: Set(self.password_hash.clone()),
             username: Set(self.username.clone()),
             ..Default::default()
         }
     }
 }
 
+/// Like `UserUpdate`, without email and password.
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct UserPatch {
     pub avatar_url: Option<String>,
     pub bio: Option<String>,
     pub display_name: Option<String>,
-    pub email: Option<String>,
     pub joined_date: Option<String>,
-    pub last_active: Option<String>,
-    pub password_hash: Option<String>,
     pub reading_lists: Option<Vec<ReadingList>>,
     pub reviews: Option<Vec<Review>>,
+    pub role: Option<Role>,
     pub username: Option<String>
     
 }
@@ -151,16 +156,13 @@ impl UserPatch {
             active_model.avatar_url = Set(self.avatar_url.clone());
         }if self.bio.is_some() {
             active_model.bio = Set(self.bio.clone());
+            active_model.bio_html = Set(markdown::render_opt(self.bio.as_deref()));
         }if self.display_name.is_some() {
             active_model.display_name = Set(self.display_name.clone());
-        }if let Some(value) = &self.email {
-            active_model.email = Set(value.clone());
         }if self.joined_date.is_some() {
             active_model.joined_date = Set(self.joined_date.clone());
-        }if self.last_active.is_some() {
-            active_model.last_active = Set(self.last_active.clone());
-        }if let Some(value) = &self.password_hash {
-            active_model.password_hash = Set(value.clone());
+        }if let Some(value) = &self.role {
+            active_model.role = Set(*value);
         }if let Some(value) = &self.username {
             active_model.username = Set(value.clone());
         }
@@ -181,7 +183,9 @@ where
         .ok_or_else(|| (StatusCode::NOT_FOUND, Json(json!({"error": "not found"}))))
 }
 
-pub async fn list(state: State<AppState>) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+/// The full account view is for admins; everyone else gets `/profiles/{username}`.
+pub async fn list(state: State<AppState>, principal: Principal) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    auth::require_role(&state.db, &principal, &[Role::Admin]).await?;
     let models = Entity::load()
         .with(crate::models::reading_list::Entity)
         .with(crate::models::review::Entity)
@@ -193,17 +197,22 @@ pub async fn list(state: State<AppState>) -> Result<impl IntoResponse, (StatusCo
     Ok(Json(responses))
 }
 
-pub async fn create(state: State<AppState>, Json(create): Json<UserCreate>) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+pub async fn create(state: State<AppState>, principal: Principal, Json(create): Json<UserCreate>) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    auth::require_role(&state.db, &principal, &[Role::Admin]).await?;
     let active_model:ActiveModel = create.into();
     let model = active_model.insert(&state.db)
         .await
         .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"failed to insert item": e.to_string()}))))?;
+    account::send_password_reset(&state, &model)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"error": e.to_string()}))))?;
         let resp: User = model.into();
         Ok(Json(resp))
 
 }
 
-pub async fn patch_one(state: State<AppState>, Path(id): Path<i32>, Json(patch): Json<UserPatch> ) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+pub async fn patch_one(state: State<AppState>, principal: Principal, Path(id): Path<i32>, Json(patch): Json<UserPatch> ) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    auth::require_role(&state.db, &principal, &[Role::Admin]).await?;
     let model = load_item(&state.db, id).await?;
     let mut active_model = model.into_active_model();
     patch.patch_active_model(&mut active_model);
@@ -214,7 +223,8 @@ pub async fn patch_one(state: State<AppState>, Path(id): Path<i32>, Json(patch):
     Ok(Json(resp))
 }
 
-pub async fn put_one(state: State<AppState>, Path(id): Path<i32>, Json(update): Json<UserUpdate>) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+pub async fn put_one(state: State<AppState>, principal: Principal, Path(id): Path<i32>, Json(update): Json<UserUpdate>) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    auth::require_role(&state.db, &principal, &[Role::Admin]).await?;
     let _ = load_item(&state.db, id).await?;
     let active_model = update.into_active_model(id);
     let model = active_model.update(&state.db)
@@ -224,15 +234,18 @@ pub async fn put_one(state: State<AppState>, Path(id): Path<i32>, Json(update):
     Ok(Json(resp))
 }
 
-pub async fn remove(state: State<AppState>, Path(id): Path<i32>) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+/// Deletes right away, like a due scheduled deletion; users delete themselves through `DELETE /me`.
+pub async fn remove(state: State<AppState>, principal: Principal, Path(id): Path<i32>) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    auth::require_role(&state.db, &principal, &[Role::Admin]).await?;
     let model = load_item(&state.db, id).await?;
-    model.delete(&state.db)
+    account_data::purge_user(&state.db, model.id)
         .await
         .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"error": e.to_string()}))))?;
     Ok(StatusCode::NO_CONTENT)
 }
 
-pub async fn read_one(state: State<AppState>, Path(id): Path<i32>) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+pub async fn read_one(state: State<AppState>, principal: Principal, Path(id): Path<i32>) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    auth::require_role(&state.db, &principal, &[Role::Admin]).await?;
     let model = Entity::load()
         .filter_by_id(id)
         .with(crate::models::reading_list::Entity)
@@ -0,0 +1,46 @@
+use sea_orm::entity::prelude::*;
+
+
+
+#[sea_orm::model]
+#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel)]
+#[sea_orm(table_name = "api_key")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i32,
+    pub created_at: DateTime,
+    pub last_updated: DateTime,
+    pub name: String
+    ,
+    pub key_prefix: String
+    ,
+    #[sea_orm(unique)]
+    pub key_hash: String
+    ,
+    pub scopes: String
+    ,
+    pub user_id: Option<i32>,
+#[sea_orm(belongs_to, from = "user_id", to = "id")]
+    pub user: HasOne<super::user::Entity>
+    ,
+    pub group_id: Option<i32>,
+#[sea_orm(belongs_to, from = "group_id", to = "id")]
+    pub group: HasOne<super::group::Entity>
+    ,
+    pub expires_at: Option<DateTime>
+    ,
+    pub last_used_at: Option<DateTime>
+    ,
+    pub revoked_at: Option<DateTime>
+    ,
+    pub session: bool
+    
+}
+
+impl Model {
+    pub fn scope_list(&self) -> Vec<String> {
+        self.scopes.split_whitespace().map(str::to_string).collect()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -9,9 +9,9 @@ pub struct Model {
     #[sea_orm(primary_key, auto_increment = false)]
     pub novel_id: i32,
     #[sea_orm(belongs_to, from = "artist_id", to = "id")]
-    pub artist: Option<super::artist::Entity>,
+    pub artist: HasOne<super::artist::Entity>,
     #[sea_orm(belongs_to, from = "novel_id", to = "id")]
-    pub novel: Option<super::novel::Entity>,
+    pub novel: HasOne<super::novel::Entity>,
 }
 
 impl ActiveModelBehavior for ActiveModel {}
\ No newline at end of file
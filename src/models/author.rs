@@ -12,6 +12,8 @@ pub struct Model {
     pub last_updated: DateTime,
     pub bio: Option<String>
     ,
+    pub bio_html: Option<String>
+    ,
     pub country: Option<String>
     ,
     pub name: String
@@ -21,6 +23,9 @@ pub struct Model {
     #[sea_orm(has_many, via = "author_novel" )]
     pub novels: HasMany<super::novel::Entity>
     ,
+    #[sea_orm(unique)]
+    pub slug: Option<String>
+    ,
     pub website: Option<String>
     
 }
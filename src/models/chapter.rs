@@ -21,7 +21,7 @@ pub struct Model {
     ,
     pub number: String
     ,
-    pub part: Option<i8>
+    pub part: Option<i32>
     ,
     pub release_date: Option<String>
     ,
@@ -32,9 +32,9 @@ pub source_id: i32,
     ,
     pub title: String
     ,
-    pub views: Option<i8>
+    pub views: Option<i32>
     ,
-    pub volume: Option<i8>
+    pub volume: Option<i32>
     
 }
 
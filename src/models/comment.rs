@@ -0,0 +1,37 @@
+use sea_orm::entity::prelude::*;
+
+/// A comment on a review or a chapter; exactly one of `review_id` and `chapter_id` is set.
+/// Replies point at their `parent_id` and at the top-level comment of the thread in `root_id`.
+#[sea_orm::model]
+#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel)]
+#[sea_orm(table_name = "comment")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i32,
+    pub created_at: DateTime,
+    pub last_updated: DateTime,
+    pub user_id: Option<i32>,
+#[sea_orm(belongs_to, from = "user_id", to = "id")]
+    pub user: HasOne<super::user::Entity>
+    ,
+    pub review_id: Option<i32>
+    ,
+    pub chapter_id: Option<i32>
+    ,
+    pub parent_id: Option<i32>
+    ,
+    pub root_id: Option<i32>
+    ,
+    pub content: String
+    ,
+    pub content_html: Option<String>
+    ,
+    pub spoiler: bool
+    ,
+    pub edited_at: Option<DateTime>
+    ,
+    pub deleted_at: Option<DateTime>
+    
+}
+
+impl ActiveModelBehavior for ActiveModel {}
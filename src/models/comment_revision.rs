@@ -0,0 +1,18 @@
+use sea_orm::entity::prelude::*;
+
+/// A previous version of a comment, saved whenever it is edited.
+#[sea_orm::model]
+#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel)]
+#[sea_orm(table_name = "comment_revision")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i32,
+    pub comment_id: i32,
+    #[sea_orm(belongs_to, from = "comment_id", to = "id")]
+    pub comment: HasOne<super::comment::Entity>,
+    pub content: String,
+    pub spoiler: bool,
+    pub created_at: DateTime,
+}
+
+impl ActiveModelBehavior for ActiveModel {}
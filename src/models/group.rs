@@ -23,6 +23,8 @@ pub struct Model {
     pub last_updated: DateTime,
     pub description: Option<String>
     ,
+    pub description_html: Option<String>
+    ,
     pub discord_url: Option<String>
     ,
     pub founded_date: Option<String>
@@ -31,12 +33,15 @@ pub struct Model {
     ,
     pub logo_url: Option<String>
     ,
-    pub member_count: Option<i8>
+    pub member_count: Option<i32>
     ,
     pub name: String
     ,
     pub patreon_url: Option<String>
     ,
+    #[sea_orm(unique)]
+    pub slug: Option<String>
+    ,
     #[sea_orm(has_many)]
     pub sources: HasMany<super::source::Entity>
     ,
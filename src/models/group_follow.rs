@@ -0,0 +1,18 @@
+use sea_orm::entity::prelude::*;
+
+#[sea_orm::model]
+#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel)]
+#[sea_orm(table_name = "group_follow")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub user_id: i32,
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub group_id: i32,
+    pub created_at: DateTime,
+    #[sea_orm(belongs_to, from = "user_id", to = "id")]
+    pub user: HasOne<super::user::Entity>,
+    #[sea_orm(belongs_to, from = "group_id", to = "id")]
+    pub group: HasOne<super::group::Entity>,
+}
+
+impl ActiveModelBehavior for ActiveModel {}
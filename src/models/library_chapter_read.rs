@@ -0,0 +1,18 @@
+use sea_orm::entity::prelude::*;
+
+#[sea_orm::model]
+#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel)]
+#[sea_orm(table_name = "library_chapter_read")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub library_entry_id: i32,
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub chapter_id: i32,
+    pub read_at: DateTime,
+    #[sea_orm(belongs_to, from = "library_entry_id", to = "id")]
+    pub library_entry: HasOne<super::library_entry::Entity>,
+    #[sea_orm(belongs_to, from = "chapter_id", to = "id")]
+    pub chapter: HasOne<super::chapter::Entity>,
+}
+
+impl ActiveModelBehavior for ActiveModel {}
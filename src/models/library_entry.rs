@@ -0,0 +1,46 @@
+use sea_orm::entity::prelude::*;
+pub use super::reading_list::Status;
+
+
+
+#[sea_orm::model]
+#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel)]
+#[sea_orm(table_name = "library_entry")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i32,
+    pub created_at: DateTime,
+    pub last_updated: DateTime,
+    pub user_id: i32,
+#[sea_orm(belongs_to, from = "user_id", to = "id")]
+    pub user: HasOne<super::user::Entity>
+    ,
+    pub novel_id: i32,
+#[sea_orm(belongs_to, from = "novel_id", to = "id")]
+    pub novel: HasOne<super::novel::Entity>
+    ,
+    pub status: Status
+    ,
+    pub last_read_chapter_id: Option<i32>,
+#[sea_orm(belongs_to, from = "last_read_chapter_id", to = "id")]
+    pub last_read_chapter: HasOne<super::chapter::Entity>
+    ,
+    pub last_read_at: Option<DateTime>
+    ,
+    pub personal_rating: Option<String>
+    ,
+    pub notes: Option<String>
+    ,
+    pub started_date: Option<String>
+    ,
+    pub completed_date: Option<String>
+    ,
+    /// Set by the database whenever the status becomes completed.
+    pub completed_at: Option<DateTime>
+    ,
+    /// Set by the database whenever the status becomes dropped.
+    pub dropped_at: Option<DateTime>
+    
+}
+
+impl ActiveModelBehavior for ActiveModel {}
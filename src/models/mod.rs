@@ -1,18 +1,38 @@
 
 
 pub mod enums;
+pub mod api_key;
 pub mod artist;
 pub mod author;
 pub mod chapter;
+pub mod comment;
+pub mod comment_revision;
 pub mod group;
+pub mod group_follow;
+pub mod library_chapter_read;
+pub mod library_entry;
+pub mod moderation_log;
+pub mod notification;
+pub mod notification_mute;
+pub mod notification_preference;
 pub mod novel;
+pub mod novel_tag_vote;
 pub mod publisher;
 pub mod reading_list;
+pub mod reading_list_follow;
+pub mod report;
 pub mod review;
+pub mod review_vote;
 pub mod source;
 pub mod tag;
+pub mod tag_alias;
+pub mod two_factor_policy;
 pub mod r#type;
 pub mod user;
+pub mod user_activity;
+pub mod user_preference;
+pub mod user_recovery_code;
+pub mod user_token;
 pub mod artist_novel;
 pub mod author_novel;
 pub mod chapter_novel;
@@ -0,0 +1,45 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+use super::report::TargetType;
+
+
+#[derive(PartialEq, Clone, Copy, Debug, Default, Eq, EnumIter, DeriveActiveEnum, Serialize, Deserialize)]
+#[sea_orm(rs_type = "String", db_type = "String(StringLen::None)", enum_name = "action")]
+pub enum Action{
+        #[default]
+        #[sea_orm(string_value = "hide_review")]
+        HideReview,
+        #[sea_orm(string_value = "ban_user")]
+        BanUser,
+        #[sea_orm(string_value = "delete")]
+        Delete,
+        /// The report was closed without touching the target.
+        #[sea_orm(string_value = "dismiss")]
+        Dismiss
+}
+/// One moderator action, kept for the audit trail even after the target is gone.
+#[sea_orm::model]
+#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel)]
+#[sea_orm(table_name = "moderation_log")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i32,
+    pub created_at: DateTime,
+    pub moderator_id: Option<i32>
+    ,
+    pub action: Action
+    ,
+    pub target_type: TargetType
+    ,
+    pub target_id: i32
+    ,
+    pub report_id: Option<i32>
+    ,
+    /// Whose content or account the action hit; counts against their reputation.
+    pub offender_id: Option<i32>
+    ,
+    pub note: Option<String>
+    
+}
+
+impl ActiveModelBehavior for ActiveModel {}
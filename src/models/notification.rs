@@ -0,0 +1,50 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+
+#[derive(PartialEq, Clone, Copy, Debug, Default, Eq, EnumIter, DeriveActiveEnum, Serialize, Deserialize)]
+#[sea_orm(rs_type = "String", db_type = "String(StringLen::None)", enum_name = "kind")]
+pub enum Kind{
+        /// A novel in the user's library got a new chapter.
+        #[default]
+        #[sea_orm(string_value = "new_chapter")]
+        NewChapter,
+        /// A followed group started translating a novel.
+        #[sea_orm(string_value = "new_source")]
+        NewSource,
+        /// Someone replied to one of the user's reviews.
+        #[sea_orm(string_value = "review_reply")]
+        ReviewReply
+}
+#[sea_orm::model]
+#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel)]
+#[sea_orm(table_name = "notification")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i32,
+    pub created_at: DateTime,
+    pub user_id: i32,
+#[sea_orm(belongs_to, from = "user_id", to = "id")]
+    pub user: HasOne<super::user::Entity>
+    ,
+    pub kind: Kind
+    ,
+    pub title: String
+    ,
+    pub novel_id: Option<i32>
+    ,
+    pub chapter_id: Option<i32>
+    ,
+    pub source_id: Option<i32>
+    ,
+    pub group_id: Option<i32>
+    ,
+    pub review_id: Option<i32>
+    ,
+    pub actor_id: Option<i32>
+    ,
+    pub read_at: Option<DateTime>
+    
+}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,47 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+
+#[derive(PartialEq, Clone, Copy, Debug, Default, Eq, EnumIter, DeriveActiveEnum, Serialize, Deserialize)]
+#[sea_orm(rs_type = "String", db_type = "String(StringLen::None)", enum_name = "digest_frequency")]
+pub enum DigestFrequency{
+        #[default]
+        #[sea_orm(string_value = "never")]
+        Never,
+        #[sea_orm(string_value = "daily")]
+        Daily,
+        #[sea_orm(string_value = "weekly")]
+        Weekly
+}
+
+/// Which kinds of notification the user wants. A missing row means all of them.
+#[sea_orm::model]
+#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel)]
+#[sea_orm(table_name = "notification_preference")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i32,
+    pub created_at: DateTime,
+    pub last_updated: DateTime,
+    #[sea_orm(unique)]
+    pub user_id: i32,
+#[sea_orm(belongs_to, from = "user_id", to = "id")]
+    pub user: HasOne<super::user::Entity>
+    ,
+    pub new_chapter: bool
+    ,
+    pub new_source: bool
+    ,
+    pub review_reply: bool
+    ,
+    pub digest_frequency: DigestFrequency
+    ,
+    pub digest_sent_at: Option<DateTime>
+    ,
+    /// Long-lived, unlike `user_token`: it sits in every digest mail.
+    #[sea_orm(unique)]
+    pub unsubscribe_token: Option<String>
+    
+}
+
+impl ActiveModelBehavior for ActiveModel {}
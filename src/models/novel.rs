@@ -46,20 +46,23 @@ pub struct Model {
     ,
     pub description: Option<String>
     ,
+    pub description_html: Option<String>
+    ,
     pub english_publisher: Option<String>
     ,
+    /// Derived from the novel's genre tags by a trigger; read-only.
     pub genres: Option<String>
     ,
     pub licensed: Option<bool>
     ,
     pub native_name: Option<String>
     ,
-    pub original_language: String
+    pub original_language: Option<String>
     ,
     #[sea_orm(has_many, via = "novel_publisher" )]
     pub publishers: HasMany<super::publisher::Entity>
     ,
-    pub rating_count: Option<i8>
+    pub rating_count: Option<i32>
     ,
     #[sea_orm(has_many, via = "novel_reading_list" )]
     pub reading_lists: HasMany<super::reading_list::Entity>
@@ -69,6 +72,9 @@ pub struct Model {
     #[sea_orm(has_many)]
     pub reviews: HasMany<super::review::Entity>
     ,
+    #[sea_orm(unique)]
+    pub slug: Option<String>
+    ,
     #[sea_orm(has_many)]
     pub sources: HasMany<super::source::Entity>
     ,
@@ -77,16 +83,16 @@ pub struct Model {
     #[sea_orm(has_many, via = "novel_tag" )]
     pub tags: HasMany<super::tag::Entity>
     ,
-    pub total_chapters: Option<i8>
+    pub total_chapters: Option<i32>
     ,
     #[sea_orm(unique)]
 pub type_id: i32,
 #[sea_orm(belongs_to, from = "type_id", to = "id")]
     pub r#type: HasOne<super::r#type::Entity>
     ,
-    pub views: Option<i8>
+    pub views: Option<i32>
     ,
-    pub year: Option<i8>
+    pub year: Option<i32>
     
 }
 
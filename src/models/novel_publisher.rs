@@ -9,9 +9,9 @@ pub struct Model {
     #[sea_orm(primary_key, auto_increment = false)]
     pub publisher_id: i32,
     #[sea_orm(belongs_to, from = "novel_id", to = "id")]
-    pub novel: Option<super::novel::Entity>,
+    pub novel: HasOne<super::novel::Entity>,
     #[sea_orm(belongs_to, from = "publisher_id", to = "id")]
-    pub publisher: Option<super::publisher::Entity>,
+    pub publisher: HasOne<super::publisher::Entity>,
 }
 
 impl ActiveModelBehavior for ActiveModel {}
\ No newline at end of file
@@ -8,10 +8,13 @@ pub struct Model {
     pub novel_id: i32,
     #[sea_orm(primary_key, auto_increment = false)]
     pub reading_list_id: i32,
+    pub position: i32,
+    pub blurb: Option<String>,
+    pub blurb_html: Option<String>,
     #[sea_orm(belongs_to, from = "novel_id", to = "id")]
-    pub novel: Option<super::novel::Entity>,
+    pub novel: HasOne<super::novel::Entity>,
     #[sea_orm(belongs_to, from = "reading_list_id", to = "id")]
-    pub reading_list: Option<super::reading_list::Entity>,
+    pub reading_list: HasOne<super::reading_list::Entity>,
 }
 
 impl ActiveModelBehavior for ActiveModel {}
\ No newline at end of file
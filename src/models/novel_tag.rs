@@ -8,10 +8,15 @@ pub struct Model {
     pub novel_id: i32,
     #[sea_orm(primary_key, auto_increment = false)]
     pub tag_id: i32,
+    pub created_at: DateTime,
+    /// Who suggested the tag; `None` for tags set by editors.
+    pub proposed_by: Option<i32>,
+    pub upvotes: i32,
+    pub downvotes: i32,
     #[sea_orm(belongs_to, from = "novel_id", to = "id")]
-    pub novel: Option<super::novel::Entity>,
+    pub novel: HasOne<super::novel::Entity>,
     #[sea_orm(belongs_to, from = "tag_id", to = "id")]
-    pub tag: Option<super::tag::Entity>,
+    pub tag: HasOne<super::tag::Entity>,
 }
 
 impl ActiveModelBehavior for ActiveModel {}
\ No newline at end of file
@@ -0,0 +1,21 @@
+use sea_orm::entity::prelude::*;
+
+/// One user's up or down vote on a tag of a novel. A trigger keeps `novel_tag.upvotes` and
+/// `novel_tag.downvotes` in step with these rows.
+#[sea_orm::model]
+#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel)]
+#[sea_orm(table_name = "novel_tag_vote")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub user_id: i32,
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub novel_id: i32,
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub tag_id: i32,
+    pub up: bool,
+    pub created_at: DateTime,
+    #[sea_orm(belongs_to, from = "user_id", to = "id")]
+    pub user: HasOne<super::user::Entity>,
+}
+
+impl ActiveModelBehavior for ActiveModel {}
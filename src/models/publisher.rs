@@ -19,6 +19,9 @@ pub struct Model {
     #[sea_orm(has_many, via = "novel_publisher" )]
     pub novels: HasMany<super::novel::Entity>
     ,
+    #[sea_orm(unique)]
+    pub slug: Option<String>
+    ,
     pub website: Option<String>
     
 }
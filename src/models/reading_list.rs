@@ -17,6 +17,19 @@ pub enum Status{
         #[sea_orm(string_value = "dropped")]
         Dropped
 }
+
+#[derive(PartialEq, Clone, Copy, Debug, Default, Eq, EnumIter, DeriveActiveEnum, Serialize, Deserialize)]
+#[sea_orm(rs_type = "String", db_type = "String(StringLen::None)", enum_name = "visibility")]
+pub enum Visibility{
+        #[default]
+        #[sea_orm(string_value = "private")]
+        Private,
+        /// Readable by anyone holding the share token, never listed.
+        #[sea_orm(string_value = "unlisted")]
+        Unlisted,
+        #[sea_orm(string_value = "public")]
+        Public
+}
 #[sea_orm::model]
 #[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel)]
 #[sea_orm(table_name = "reading_list")]
@@ -27,10 +40,16 @@ pub struct Model {
     pub last_updated: DateTime,
     pub completed_date: Option<String>
     ,
-    pub current_chapter: Option<i8>
+    pub current_chapter: Option<i32>
+    ,
+    pub description: Option<String>
+    ,
+    pub description_html: Option<String>
     ,
     pub last_read: Option<String>
     ,
+    pub name: Option<String>
+    ,
     pub notes: Option<String>
     ,
     #[sea_orm(has_many, via = "novel_reading_list" )]
@@ -38,10 +57,15 @@ pub struct Model {
     ,
     pub personal_rating: Option<String>
     ,
+    #[sea_orm(unique)]
+    pub share_token: Option<String>
+    ,
     pub started_date: Option<String>
     ,
     pub status: Status
     ,
+    pub visibility: Visibility
+    ,
     #[sea_orm(unique)]
 pub user_id: i32,
 #[sea_orm(belongs_to, from = "user_id", to = "id")]
@@ -0,0 +1,72 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+
+#[derive(PartialEq, Clone, Copy, Debug, Default, Eq, EnumIter, DeriveActiveEnum, Serialize, Deserialize)]
+#[sea_orm(rs_type = "String", db_type = "String(StringLen::None)", enum_name = "target_type")]
+pub enum TargetType{
+        #[default]
+        #[sea_orm(string_value = "review")]
+        Review,
+        #[sea_orm(string_value = "comment")]
+        Comment,
+        #[sea_orm(string_value = "novel")]
+        Novel,
+        #[sea_orm(string_value = "user")]
+        User
+}
+
+#[derive(PartialEq, Clone, Copy, Debug, Default, Eq, EnumIter, DeriveActiveEnum, Serialize, Deserialize)]
+#[sea_orm(rs_type = "String", db_type = "String(StringLen::None)", enum_name = "reason")]
+pub enum Reason{
+        #[default]
+        #[sea_orm(string_value = "spam")]
+        Spam,
+        /// Unmarked spoilers.
+        #[sea_orm(string_value = "spoiler")]
+        Spoiler,
+        #[sea_orm(string_value = "abuse")]
+        Abuse
+}
+
+#[derive(PartialEq, Clone, Copy, Debug, Default, Eq, EnumIter, DeriveActiveEnum, Serialize, Deserialize)]
+#[sea_orm(rs_type = "String", db_type = "String(StringLen::None)", enum_name = "status")]
+pub enum Status{
+        #[default]
+        #[sea_orm(string_value = "open")]
+        Open,
+        /// A moderator acted on the target.
+        #[sea_orm(string_value = "actioned")]
+        Actioned,
+        #[sea_orm(string_value = "dismissed")]
+        Dismissed
+}
+/// A user flagging a review, comment, novel or profile. `target_id` is not a foreign key so the
+/// report outlives a deleted target.
+#[sea_orm::model]
+#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel)]
+#[sea_orm(table_name = "report")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i32,
+    pub created_at: DateTime,
+    pub last_updated: DateTime,
+    pub reporter_id: Option<i32>
+    ,
+    pub target_type: TargetType
+    ,
+    pub target_id: i32
+    ,
+    pub reason: Reason
+    ,
+    pub details: Option<String>
+    ,
+    pub status: Status
+    ,
+    pub resolved_by: Option<i32>
+    ,
+    pub resolved_at: Option<DateTime>
+    
+}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -12,7 +12,12 @@ pub struct Model {
     pub last_updated: DateTime,
     pub content: String
     ,
-    pub helpful_count: Option<i8>
+    pub content_html: Option<String>
+    ,
+    pub helpful_count: i32
+    ,
+    /// Set when a moderator hid the review; hidden reviews are left out of public listings.
+    pub hidden_at: Option<DateTime>
     ,
     #[sea_orm(unique)]
 pub novel_id: i32,
@@ -25,8 +30,10 @@ pub novel_id: i32,
     ,
     pub title: Option<String>
     ,
+    pub unhelpful_count: i32
+    ,
     #[sea_orm(unique)]
-pub user_id: i32,
+pub user_id: Option<i32>,
 #[sea_orm(belongs_to, from = "user_id", to = "id")]
     pub user: HasOne<super::user::Entity>
     
@@ -0,0 +1,21 @@
+use sea_orm::entity::prelude::*;
+
+/// One user's helpful/unhelpful vote on a review. A trigger keeps `review.helpful_count` and
+/// `review.unhelpful_count` in step with these rows.
+#[sea_orm::model]
+#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel)]
+#[sea_orm(table_name = "review_vote")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub user_id: i32,
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub review_id: i32,
+    pub helpful: bool,
+    pub created_at: DateTime,
+    #[sea_orm(belongs_to, from = "user_id", to = "id")]
+    pub user: HasOne<super::user::Entity>,
+    #[sea_orm(belongs_to, from = "review_id", to = "id")]
+    pub review: HasOne<super::review::Entity>,
+}
+
+impl ActiveModelBehavior for ActiveModel {}
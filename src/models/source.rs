@@ -23,7 +23,7 @@ pub struct Model {
     pub id: i32,
     pub created_at: DateTime,
     pub last_updated: DateTime,
-    pub chapter_count: Option<i8>
+    pub chapter_count: Option<i32>
     ,
     #[sea_orm(has_many)]
     pub chapters: HasMany<super::chapter::Entity>
@@ -33,13 +33,16 @@ pub struct Model {
     ,
     pub name: String
     ,
+    /// The broader tag this one sits under, e.g. "Xianxia" for "Cultivation".
+    pub parent_id: Option<i32>
+    ,
     #[sea_orm(has_many, via = "novel_tag" )]
     pub novels: HasMany<super::novel::Entity>
     ,
     #[sea_orm(unique)]
     pub slug: String
     ,
-    pub usage_count: Option<i8>
+    pub usage_count: Option<i32>
     
 }
 
@@ -0,0 +1,17 @@
+use sea_orm::entity::prelude::*;
+
+/// Another name for a tag. Searching by an alias finds the canonical tag.
+#[sea_orm::model]
+#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel)]
+#[sea_orm(table_name = "tag_alias")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i32,
+    pub created_at: DateTime,
+    pub tag_id: i32,
+    #[sea_orm(belongs_to, from = "tag_id", to = "id")]
+    pub tag: HasOne<super::tag::Entity>,
+    pub name: String,
+}
+
+impl ActiveModelBehavior for ActiveModel {}
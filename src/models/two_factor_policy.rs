@@ -0,0 +1,17 @@
+use sea_orm::entity::prelude::*;
+use super::user::Role;
+
+/// Whether a role must have two-factor authentication; see `services::totp::required_for`.
+#[sea_orm::model]
+#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel)]
+#[sea_orm(table_name = "two_factor_policy")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub role: Role,
+    pub created_at: DateTime,
+    pub last_updated: DateTime,
+    pub required: bool
+    
+}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -1,7 +1,18 @@
 use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
 
 
-
+#[derive(PartialEq, Clone, Copy, Debug, Default, Eq, EnumIter, DeriveActiveEnum, Serialize, Deserialize)]
+#[sea_orm(rs_type = "String", db_type = "String(StringLen::None)", enum_name = "role")]
+pub enum Role{
+        #[default]
+        #[sea_orm(string_value = "user")]
+        User,
+        #[sea_orm(string_value = "moderator")]
+        Moderator,
+        #[sea_orm(string_value = "admin")]
+        Admin
+}
 #[sea_orm::model]
 #[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel)]
 #[sea_orm(table_name = "user")]
@@ -12,16 +23,22 @@ pub struct Model {
     pub last_updated: DateTime,
     pub avatar_url: Option<String>
     ,
+    pub banned_at: Option<DateTime>
+    ,
     pub bio: Option<String>
     ,
+    pub bio_html: Option<String>
+    ,
     pub display_name: Option<String>
     ,
     #[sea_orm(unique)]
     pub email: String
     ,
+    pub email_verified_at: Option<DateTime>
+    ,
     pub joined_date: Option<String>
     ,
-    pub last_active: Option<String>
+    pub last_active: Option<DateTime>
     ,
     pub password_hash: String
     ,
@@ -31,6 +48,20 @@ pub struct Model {
     #[sea_orm(has_many)]
     pub reviews: HasMany<super::review::Entity>
     ,
+    pub role: Role
+    ,
+    pub show_reading_lists: bool
+    ,
+    pub show_reviews: bool
+    ,
+    pub deletion_scheduled_for: Option<DateTime>
+    ,
+    pub totp_secret: Option<String>
+    ,
+    pub totp_enabled_at: Option<DateTime>
+    ,
+    pub totp_last_step: Option<i64>
+    ,
     #[sea_orm(unique)]
     pub username: String
     
@@ -0,0 +1,29 @@
+use sea_orm::entity::prelude::*;
+
+
+
+/// Content filters applied to listings for the signed-in user. An empty list means "no restriction".
+#[sea_orm::model]
+#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel)]
+#[sea_orm(table_name = "user_preference")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i32,
+    pub created_at: DateTime,
+    pub last_updated: DateTime,
+    #[sea_orm(unique)]
+    pub user_id: i32,
+#[sea_orm(belongs_to, from = "user_id", to = "id")]
+    pub user: HasOne<super::user::Entity>
+    ,
+    pub excluded_tag_ids: Vec<i32>
+    ,
+    pub original_languages: Vec<String>
+    ,
+    pub chapter_languages: Vec<String>
+    ,
+    pub hide_licensed: bool
+    
+}
+
+impl ActiveModelBehavior for ActiveModel {}
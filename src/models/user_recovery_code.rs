@@ -0,0 +1,23 @@
+use sea_orm::entity::prelude::*;
+
+
+
+#[sea_orm::model]
+#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel)]
+#[sea_orm(table_name = "user_recovery_code")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i32,
+    pub created_at: DateTime,
+    pub last_updated: DateTime,
+    pub user_id: i32,
+#[sea_orm(belongs_to, from = "user_id", to = "id")]
+    pub user: HasOne<super::user::Entity>
+    ,
+    pub code_hash: String
+    ,
+    pub used_at: Option<DateTime>
+    
+}
+
+impl ActiveModelBehavior for ActiveModel {}
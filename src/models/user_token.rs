@@ -0,0 +1,36 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+
+#[derive(PartialEq, Clone, Copy, Debug, Eq, EnumIter, DeriveActiveEnum, Serialize, Deserialize)]
+#[sea_orm(rs_type = "String", db_type = "String(StringLen::None)", enum_name = "purpose")]
+pub enum Purpose{
+        #[sea_orm(string_value = "email_verification")]
+        EmailVerification,
+        #[sea_orm(string_value = "password_reset")]
+        PasswordReset
+}
+#[sea_orm::model]
+#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel)]
+#[sea_orm(table_name = "user_token")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i32,
+    pub created_at: DateTime,
+    pub last_updated: DateTime,
+    pub user_id: i32,
+#[sea_orm(belongs_to, from = "user_id", to = "id")]
+    pub user: HasOne<super::user::Entity>
+    ,
+    pub purpose: Purpose
+    ,
+    #[sea_orm(unique)]
+    pub token_hash: String
+    ,
+    pub expires_at: DateTime
+    ,
+    pub used_at: Option<DateTime>
+    
+}
+
+impl ActiveModelBehavior for ActiveModel {}
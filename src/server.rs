@@ -1,9 +1,10 @@
 use log::{info, error};
 use sea_orm::{Database, DatabaseConnection};
 use sqlx::postgres::PgPoolOptions;
-use std::{env, error::Error};
+use std::{env, error::Error, net::SocketAddr};
 use tokio::net::TcpListener;
 use crate::app_state::AppState;
+use crate::services::{account_data, digest, mail, markdown, rate_limit::{self, RateLimitConfig, RateLimiter}, slugs};
 use static_serve::embed_assets;
 
 embed_assets!("admin/dist", compress = true);
@@ -23,6 +24,8 @@ pub async fn run() -> Result<(), Box<dyn Error>> {
     env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info")).init();
 
     let db= init_db().await?;
+    if let Err(e) = markdown::backfill(&db).await { error!("❌ Failed to render existing Markdown: {e}"); }
+    if let Err(e) = slugs::backfill(&db).await { error!("❌ Failed to generate slugs: {e}"); }
     let port_env = env::var("PORT").unwrap_or_else(|_| "8080".to_string());
     let requested_port: u16 = port_env.parse().unwrap_or(8080);
 
@@ -30,10 +33,14 @@ pub async fn run() -> Result<(), Box<dyn Error>> {
     let actual_port = listener.local_addr()?.port();
     info!("Server starting on port {}", actual_port);
 
-    let state = AppState {  db};
+    let app_url = env::var("APP_URL").unwrap_or_else(|_| format!("http://localhost:{}", actual_port));
+    tokio::spawn(account_data::purge_loop(db.clone()));
+    let state = AppState {  db, rate_limiter: RateLimiter::new(RateLimitConfig::from_env()), mailer: mail::from_env()?, app_url };
+    tokio::spawn(digest::digest_loop(state.clone()));
+    tokio::spawn(rate_limit::sweep_loop(state.rate_limiter.clone()));
     let app = crate::controllers::routes("/api",state);
     
     let app = app.merge(static_router());
-    axum::serve(listener, app).await?;
+    axum::serve(listener, app.into_make_service_with_connect_info::<SocketAddr>()).await?;
     Ok(())
 }
\ No newline at end of file
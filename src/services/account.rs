@@ -0,0 +1,140 @@
+use chrono::{Duration, Utc};
+use log::warn;
+use sea_orm::{ActiveModelTrait, ColumnTrait, Condition, ConnectionTrait, DbErr, EntityTrait, QueryFilter, Set, sea_query::Expr};
+use crate::app_state::AppState;
+use crate::models::{user, user_recovery_code};
+use crate::models::user_token::{ActiveModel, Column, Entity, Purpose};
+use crate::services::{auth, mail::Mail, totp};
+
+fn ttl(purpose: Purpose) -> Duration {
+    match purpose {
+        Purpose::EmailVerification => Duration::hours(48),
+        Purpose::PasswordReset => Duration::hours(1),
+    }
+}
+
+/// Stores the hash of a new single-use token and returns the plaintext for the mail link.
+pub async fn issue_token<C: ConnectionTrait>(db: &C, user_id: i32, purpose: Purpose) -> Result<String, DbErr> {
+    let token = auth::generate_key();
+    ActiveModel {
+        user_id: Set(user_id),
+        purpose: Set(purpose),
+        token_hash: Set(auth::hash_key(&token)),
+        expires_at: Set((Utc::now() + ttl(purpose)).naive_utc()),
+        ..Default::default()
+    }
+    .insert(db)
+    .await?;
+    Ok(token)
+}
+
+/// Marks the token as used and returns its user, or `None` if it is unknown, expired or spent.
+/// The conditional update makes concurrent redemptions of the same token fail for all but one.
+pub async fn consume_token<C: ConnectionTrait>(db: &C, token: &str, purpose: Purpose) -> Result<Option<i32>, DbErr> {
+    let now = Utc::now().naive_utc();
+    let Some(model) = Entity::find()
+        .filter(Column::TokenHash.eq(auth::hash_key(token)))
+        .filter(Column::Purpose.eq(purpose))
+        .one(db)
+        .await? else { return Ok(None) };
+    if model.used_at.is_some() || model.expires_at <= now {
+        return Ok(None);
+    }
+    let result = Entity::update_many()
+        .col_expr(Column::UsedAt, Expr::value(now))
+        .filter(Column::Id.eq(model.id))
+        .filter(Column::UsedAt.is_null())
+        .exec(db)
+        .await?;
+    Ok((result.rows_affected == 1).then_some(model.user_id))
+}
+
+/// Spends every outstanding token of `purpose`, e.g. older reset links once a password changed.
+pub async fn revoke_tokens<C: ConnectionTrait>(db: &C, user_id: i32, purpose: Purpose) -> Result<(), DbErr> {
+    Entity::update_many()
+        .col_expr(Column::UsedAt, Expr::value(Utc::now().naive_utc()))
+        .filter(Column::UserId.eq(user_id))
+        .filter(Column::Purpose.eq(purpose))
+        .filter(Column::UsedAt.is_null())
+        .exec(db)
+        .await?;
+    Ok(())
+}
+
+/// Mail delivery failures are logged rather than surfaced, so the request that triggered
+/// the mail still succeeds and the user can ask for it again.
+async fn deliver(state: &AppState, mail: Mail) {
+    let to = mail.to.clone();
+    if let Err(e) = state.mailer.send(mail).await {
+        warn!("failed to send mail to {to}: {e}");
+    }
+}
+
+pub async fn send_verification(state: &AppState, user: &user::Model) -> Result<(), DbErr> {
+    let token = issue_token(&state.db, user.id, Purpose::EmailVerification).await?;
+    let body = format!(
+        "Hi {},\n\nConfirm your email address by opening the link below:\n{}/verify-email?token={}\n\nThe link expires in 48 hours.",
+        user.username, state.app_url, token
+    );
+    deliver(state, Mail { to: user.email.clone(), subject: "Confirm your email address".to_string(), body, list_unsubscribe: None }).await;
+    Ok(())
+}
+
+pub async fn send_password_reset(state: &AppState, user: &user::Model) -> Result<(), DbErr> {
+    let token = issue_token(&state.db, user.id, Purpose::PasswordReset).await?;
+    let body = format!(
+        "Hi {},\n\nSomeone asked to reset your password. If that was you, open the link below:\n{}/reset-password?token={}\n\nThe link expires in one hour. If you did not ask for this, ignore this mail.",
+        user.username, state.app_url, token
+    );
+    deliver(state, Mail { to: user.email.clone(), subject: "Reset your password".to_string(), body, list_unsubscribe: None }).await;
+    Ok(())
+}
+
+/// Replaces all recovery codes of a user and returns the new plaintext codes.
+pub async fn replace_recovery_codes<C: ConnectionTrait>(db: &C, user_id: i32) -> Result<Vec<String>, DbErr> {
+    user_recovery_code::Entity::delete_many()
+        .filter(user_recovery_code::Column::UserId.eq(user_id))
+        .exec(db)
+        .await?;
+    let codes = totp::generate_recovery_codes();
+    let models = codes.iter().map(|code| user_recovery_code::ActiveModel {
+        user_id: Set(user_id),
+        code_hash: Set(auth::hash_key(&totp::normalize_recovery_code(code))),
+        ..Default::default()
+    });
+    user_recovery_code::Entity::insert_many(models).exec(db).await?;
+    Ok(codes)
+}
+
+async fn consume_recovery_code<C: ConnectionTrait>(db: &C, user_id: i32, code: &str) -> Result<bool, DbErr> {
+    let result = user_recovery_code::Entity::update_many()
+        .col_expr(user_recovery_code::Column::UsedAt, Expr::value(Utc::now().naive_utc()))
+        .filter(user_recovery_code::Column::UserId.eq(user_id))
+        .filter(user_recovery_code::Column::CodeHash.eq(auth::hash_key(&totp::normalize_recovery_code(code))))
+        .filter(user_recovery_code::Column::UsedAt.is_null())
+        .exec(db)
+        .await?;
+    Ok(result.rows_affected > 0)
+}
+
+/// Accepts either a current TOTP code or an unused recovery code. A matched TOTP step is
+/// remembered on the user so the same code cannot be used twice, even by two concurrent
+/// requests: only the one that moves `totp_last_step` forward passes.
+pub async fn check_second_factor<C: ConnectionTrait>(db: &C, user: &user::Model, totp_code: Option<&str>, recovery_code: Option<&str>) -> Result<bool, DbErr> {
+    if let (Some(code), Some(secret)) = (totp_code, user.totp_secret.as_deref())
+        && let Some(step) = totp::verify(secret, code, Utc::now().timestamp(), user.totp_last_step) {
+        let result = user::Entity::update_many()
+            .col_expr(user::Column::TotpLastStep, Expr::value(step))
+            .filter(user::Column::Id.eq(user.id))
+            .filter(Condition::any()
+                .add(user::Column::TotpLastStep.is_null())
+                .add(user::Column::TotpLastStep.lt(step)))
+            .exec(db)
+            .await?;
+        return Ok(result.rows_affected == 1);
+    }
+    if let Some(code) = recovery_code {
+        return consume_recovery_code(db, user.id, code).await;
+    }
+    Ok(false)
+}
@@ -0,0 +1,567 @@
+use chrono::{Duration, Utc};
+use log::{error, info, warn};
+use sea_orm::{ActiveModelTrait, ColumnTrait, ConnectionTrait, DatabaseConnection, DbErr, EntityTrait, IntoActiveModel, QueryFilter, QueryOrder, Set, TransactionTrait, sea_query::Expr};
+use sea_orm::prelude::DateTime;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::env;
+use crate::models::{
+    api_key, comment, comment_revision, group_follow, library_chapter_read, library_entry, notification_mute, notification_preference, novel,
+    novel_reading_list, novel_tag_vote, reading_list, reading_list_follow, report, review, review_vote, user, user_preference,
+};
+use crate::services::auth;
+
+const PURGE_INTERVAL_SECS: u64 = 60 * 60;
+
+/// Days between a deletion request and the hard delete, from `ACCOUNT_DELETION_GRACE_DAYS`.
+pub fn grace_period() -> Duration {
+    let days = env::var("ACCOUNT_DELETION_GRACE_DAYS").ok().and_then(|v| v.parse().ok()).unwrap_or(30);
+    Duration::days(days)
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct Export {
+    pub exported_at: DateTime,
+    pub account: ExportedAccount,
+    pub reading_lists: Vec<ExportedReadingList>,
+    pub library: Vec<ExportedLibraryEntry>,
+    pub follows: ExportedFollows,
+    pub reviews: Vec<ExportedReview>,
+    pub review_votes: Vec<ExportedReviewVote>,
+    pub comments: Vec<ExportedComment>,
+    pub reports: Vec<ExportedReport>,
+    pub tag_votes: Vec<ExportedTagVote>,
+    pub content_preferences: Option<ExportedPreferences>,
+    pub notification_settings: Option<ExportedNotificationSettings>,
+    pub notification_mutes: Vec<ExportedMute>,
+    pub activity: ExportedActivity,
+}
+
+/// Every column of the `user` row except credentials (password hash, TOTP secret).
+#[derive(Clone, Debug, Serialize)]
+pub struct ExportedAccount {
+    pub id: i32,
+    pub created_at: DateTime,
+    pub last_updated: DateTime,
+    pub username: String,
+    pub email: String,
+    pub email_verified_at: Option<DateTime>,
+    pub display_name: Option<String>,
+    pub avatar_url: Option<String>,
+    pub bio: Option<String>,
+    pub joined_date: Option<String>,
+    pub last_active: Option<DateTime>,
+    pub role: user::Role,
+    pub two_factor_enabled_at: Option<DateTime>,
+    pub show_reading_lists: bool,
+    pub show_reviews: bool,
+    pub deletion_scheduled_for: Option<DateTime>,
+}
+
+impl From<user::Model> for ExportedAccount {
+    fn from(model: user::Model) -> Self {
+        Self {
+            id: model.id,
+            created_at: model.created_at,
+            last_updated: model.last_updated,
+            username: model.username,
+            email: model.email,
+            email_verified_at: model.email_verified_at,
+            display_name: model.display_name,
+            avatar_url: model.avatar_url,
+            bio: model.bio,
+            joined_date: model.joined_date,
+            last_active: model.last_active,
+            role: model.role,
+            two_factor_enabled_at: model.totp_enabled_at,
+            show_reading_lists: model.show_reading_lists,
+            show_reviews: model.show_reviews,
+            deletion_scheduled_for: model.deletion_scheduled_for,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct ExportedNovel {
+    pub id: i32,
+    pub default_name: String,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct ExportedListNovel {
+    #[serde(flatten)]
+    pub novel: ExportedNovel,
+    pub position: i32,
+    pub blurb: Option<String>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct ExportedReadingList {
+    pub id: i32,
+    pub created_at: DateTime,
+    pub last_updated: DateTime,
+    pub name: Option<String>,
+    pub description: Option<String>,
+    pub visibility: reading_list::Visibility,
+    pub status: reading_list::Status,
+    pub current_chapter: Option<i32>,
+    pub last_read: Option<String>,
+    pub started_date: Option<String>,
+    pub completed_date: Option<String>,
+    pub personal_rating: Option<String>,
+    pub notes: Option<String>,
+    pub novels: Vec<ExportedListNovel>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct ExportedLibraryEntry {
+    pub id: i32,
+    pub created_at: DateTime,
+    pub last_updated: DateTime,
+    pub novel: Option<ExportedNovel>,
+    pub status: library_entry::Status,
+    pub last_read_chapter_id: Option<i32>,
+    pub last_read_at: Option<DateTime>,
+    pub personal_rating: Option<String>,
+    pub notes: Option<String>,
+    pub started_date: Option<String>,
+    pub completed_date: Option<String>,
+    pub completed_at: Option<DateTime>,
+    pub dropped_at: Option<DateTime>,
+    pub chapters_read: Vec<ExportedChapterRead>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct ExportedChapterRead {
+    pub chapter_id: i32,
+    pub read_at: DateTime,
+}
+
+/// Reading lists and groups the user follows.
+#[derive(Clone, Debug, Serialize)]
+pub struct ExportedFollows {
+    pub reading_lists: Vec<ExportedFollow>,
+    pub groups: Vec<ExportedFollow>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct ExportedFollow {
+    pub id: i32,
+    pub followed_at: DateTime,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct ExportedReview {
+    pub id: i32,
+    pub created_at: DateTime,
+    pub last_updated: DateTime,
+    pub novel: Option<ExportedNovel>,
+    pub title: Option<String>,
+    pub content: String,
+    pub rating: String,
+    pub spoiler: Option<bool>,
+    pub helpful_count: i32,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct ExportedReviewVote {
+    pub review_id: i32,
+    pub helpful: bool,
+    pub created_at: DateTime,
+}
+
+/// Deleted comments are included with their content, which is kept for moderation.
+#[derive(Clone, Debug, Serialize)]
+pub struct ExportedComment {
+    pub id: i32,
+    pub created_at: DateTime,
+    pub last_updated: DateTime,
+    pub review_id: Option<i32>,
+    pub chapter_id: Option<i32>,
+    pub parent_id: Option<i32>,
+    pub content: String,
+    pub spoiler: bool,
+    pub edited_at: Option<DateTime>,
+    pub deleted_at: Option<DateTime>,
+    /// Earlier versions, oldest first.
+    pub revisions: Vec<ExportedCommentRevision>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct ExportedCommentRevision {
+    pub content: String,
+    pub spoiler: bool,
+    pub created_at: DateTime,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct ExportedReport {
+    pub id: i32,
+    pub created_at: DateTime,
+    pub target_type: report::TargetType,
+    pub target_id: i32,
+    pub reason: report::Reason,
+    pub details: Option<String>,
+    pub status: report::Status,
+    pub resolved_at: Option<DateTime>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct ExportedTagVote {
+    pub novel_id: i32,
+    pub tag_id: i32,
+    pub up: bool,
+    pub created_at: DateTime,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct ExportedPreferences {
+    pub excluded_tag_ids: Vec<i32>,
+    pub original_languages: Vec<String>,
+    pub chapter_languages: Vec<String>,
+    pub hide_licensed: bool,
+}
+
+/// Includes the digest settings; the unsubscribe token is left out like other credentials.
+#[derive(Clone, Debug, Serialize)]
+pub struct ExportedNotificationSettings {
+    pub new_chapter: bool,
+    pub new_source: bool,
+    pub review_reply: bool,
+    pub digest_frequency: notification_preference::DigestFrequency,
+    pub digest_sent_at: Option<DateTime>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct ExportedMute {
+    pub novel_id: i32,
+    pub created_at: DateTime,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct ExportedActivity {
+    pub last_active: Option<DateTime>,
+    pub api_keys: Vec<ExportedApiKey>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct ExportedApiKey {
+    pub created_at: DateTime,
+    pub name: String,
+    pub key_prefix: String,
+    pub scopes: Vec<String>,
+    pub session: bool,
+    pub expires_at: Option<DateTime>,
+    pub last_used_at: Option<DateTime>,
+    pub revoked_at: Option<DateTime>,
+}
+
+fn exported_novel(model: novel::Model) -> ExportedNovel {
+    ExportedNovel { id: model.id, default_name: model.default_name }
+}
+
+pub async fn export<C: ConnectionTrait>(db: &C, model: user::Model) -> Result<Export, DbErr> {
+    let lists = reading_list::Entity::find()
+        .filter(reading_list::Column::UserId.eq(model.id))
+        .order_by_asc(reading_list::Column::Id)
+        .all(db)
+        .await?;
+    let mut list_novels: HashMap<i32, Vec<ExportedListNovel>> = HashMap::new();
+    for (entry, novel) in novel_reading_list::Entity::find()
+        .filter(novel_reading_list::Column::ReadingListId.is_in(lists.iter().map(|list| list.id)))
+        .order_by_asc(novel_reading_list::Column::Position)
+        .order_by_asc(novel_reading_list::Column::NovelId)
+        .find_also_related(novel::Entity)
+        .all(db)
+        .await?
+    {
+        let Some(novel) = novel else { continue };
+        list_novels.entry(entry.reading_list_id).or_default().push(ExportedListNovel {
+            novel: exported_novel(novel),
+            position: entry.position,
+            blurb: entry.blurb,
+        });
+    }
+    let reading_lists = lists
+        .into_iter()
+        .map(|list| ExportedReadingList {
+            id: list.id,
+            created_at: list.created_at,
+            last_updated: list.last_updated,
+            novels: list_novels.remove(&list.id).unwrap_or_default(),
+            name: list.name,
+            description: list.description,
+            visibility: list.visibility,
+            status: list.status,
+            current_chapter: list.current_chapter,
+            last_read: list.last_read,
+            started_date: list.started_date,
+            completed_date: list.completed_date,
+            personal_rating: list.personal_rating,
+            notes: list.notes,
+        })
+        .collect();
+    let mut chapters_read: HashMap<i32, Vec<ExportedChapterRead>> = HashMap::new();
+    for read in library_chapter_read::Entity::find()
+        .inner_join(library_entry::Entity)
+        .filter(library_entry::Column::UserId.eq(model.id))
+        .order_by_asc(library_chapter_read::Column::ReadAt)
+        .order_by_asc(library_chapter_read::Column::ChapterId)
+        .all(db)
+        .await?
+    {
+        chapters_read.entry(read.library_entry_id).or_default().push(ExportedChapterRead { chapter_id: read.chapter_id, read_at: read.read_at });
+    }
+    let library = library_entry::Entity::find()
+        .filter(library_entry::Column::UserId.eq(model.id))
+        .order_by_asc(library_entry::Column::Id)
+        .find_also_related(novel::Entity)
+        .all(db)
+        .await?
+        .into_iter()
+        .map(|(entry, novel)| ExportedLibraryEntry {
+            id: entry.id,
+            created_at: entry.created_at,
+            last_updated: entry.last_updated,
+            novel: novel.map(exported_novel),
+            status: entry.status,
+            last_read_chapter_id: entry.last_read_chapter_id,
+            last_read_at: entry.last_read_at,
+            personal_rating: entry.personal_rating,
+            notes: entry.notes,
+            started_date: entry.started_date,
+            completed_date: entry.completed_date,
+            completed_at: entry.completed_at,
+            dropped_at: entry.dropped_at,
+            chapters_read: chapters_read.remove(&entry.id).unwrap_or_default(),
+        })
+        .collect();
+    let follows = ExportedFollows {
+        reading_lists: reading_list_follow::Entity::find()
+            .filter(reading_list_follow::Column::UserId.eq(model.id))
+            .order_by_asc(reading_list_follow::Column::CreatedAt)
+            .all(db)
+            .await?
+            .into_iter()
+            .map(|follow| ExportedFollow { id: follow.reading_list_id, followed_at: follow.created_at })
+            .collect(),
+        groups: group_follow::Entity::find()
+            .filter(group_follow::Column::UserId.eq(model.id))
+            .order_by_asc(group_follow::Column::CreatedAt)
+            .all(db)
+            .await?
+            .into_iter()
+            .map(|follow| ExportedFollow { id: follow.group_id, followed_at: follow.created_at })
+            .collect(),
+    };
+    let reviews = review::Entity::find()
+        .filter(review::Column::UserId.eq(model.id))
+        .order_by_asc(review::Column::Id)
+        .find_also_related(novel::Entity)
+        .all(db)
+        .await?
+        .into_iter()
+        .map(|(review, novel)| ExportedReview {
+            id: review.id,
+            created_at: review.created_at,
+            last_updated: review.last_updated,
+            novel: novel.map(exported_novel),
+            title: review.title,
+            content: review.content,
+            rating: review.rating,
+            spoiler: review.spoiler,
+            helpful_count: review.helpful_count,
+        })
+        .collect();
+    let review_votes = review_vote::Entity::find()
+        .filter(review_vote::Column::UserId.eq(model.id))
+        .order_by_asc(review_vote::Column::CreatedAt)
+        .all(db)
+        .await?
+        .into_iter()
+        .map(|vote| ExportedReviewVote { review_id: vote.review_id, helpful: vote.helpful, created_at: vote.created_at })
+        .collect();
+    let mut revisions: HashMap<i32, Vec<ExportedCommentRevision>> = HashMap::new();
+    for revision in comment_revision::Entity::find()
+        .inner_join(comment::Entity)
+        .filter(comment::Column::UserId.eq(model.id))
+        .order_by_asc(comment_revision::Column::Id)
+        .all(db)
+        .await?
+    {
+        revisions.entry(revision.comment_id).or_default().push(ExportedCommentRevision {
+            content: revision.content,
+            spoiler: revision.spoiler,
+            created_at: revision.created_at,
+        });
+    }
+    let comments = comment::Entity::find()
+        .filter(comment::Column::UserId.eq(model.id))
+        .order_by_asc(comment::Column::Id)
+        .all(db)
+        .await?
+        .into_iter()
+        .map(|comment| ExportedComment {
+            id: comment.id,
+            created_at: comment.created_at,
+            last_updated: comment.last_updated,
+            review_id: comment.review_id,
+            chapter_id: comment.chapter_id,
+            parent_id: comment.parent_id,
+            content: comment.content,
+            spoiler: comment.spoiler,
+            edited_at: comment.edited_at,
+            deleted_at: comment.deleted_at,
+            revisions: revisions.remove(&comment.id).unwrap_or_default(),
+        })
+        .collect();
+    let reports = report::Entity::find()
+        .filter(report::Column::ReporterId.eq(model.id))
+        .order_by_asc(report::Column::Id)
+        .all(db)
+        .await?
+        .into_iter()
+        .map(|report| ExportedReport {
+            id: report.id,
+            created_at: report.created_at,
+            target_type: report.target_type,
+            target_id: report.target_id,
+            reason: report.reason,
+            details: report.details,
+            status: report.status,
+            resolved_at: report.resolved_at,
+        })
+        .collect();
+    let tag_votes = novel_tag_vote::Entity::find()
+        .filter(novel_tag_vote::Column::UserId.eq(model.id))
+        .order_by_asc(novel_tag_vote::Column::CreatedAt)
+        .all(db)
+        .await?
+        .into_iter()
+        .map(|vote| ExportedTagVote { novel_id: vote.novel_id, tag_id: vote.tag_id, up: vote.up, created_at: vote.created_at })
+        .collect();
+    let content_preferences = user_preference::Entity::find()
+        .filter(user_preference::Column::UserId.eq(model.id))
+        .one(db)
+        .await?
+        .map(|preference| ExportedPreferences {
+            excluded_tag_ids: preference.excluded_tag_ids,
+            original_languages: preference.original_languages,
+            chapter_languages: preference.chapter_languages,
+            hide_licensed: preference.hide_licensed,
+        });
+    let notification_settings = notification_preference::Entity::find()
+        .filter(notification_preference::Column::UserId.eq(model.id))
+        .one(db)
+        .await?
+        .map(|preference| ExportedNotificationSettings {
+            new_chapter: preference.new_chapter,
+            new_source: preference.new_source,
+            review_reply: preference.review_reply,
+            digest_frequency: preference.digest_frequency,
+            digest_sent_at: preference.digest_sent_at,
+        });
+    let notification_mutes = notification_mute::Entity::find()
+        .filter(notification_mute::Column::UserId.eq(model.id))
+        .order_by_asc(notification_mute::Column::CreatedAt)
+        .all(db)
+        .await?
+        .into_iter()
+        .map(|mute| ExportedMute { novel_id: mute.novel_id, created_at: mute.created_at })
+        .collect();
+    let api_keys = api_key::Entity::find()
+        .filter(api_key::Column::UserId.eq(model.id))
+        .order_by_asc(api_key::Column::Id)
+        .all(db)
+        .await?
+        .into_iter()
+        .map(|key| ExportedApiKey {
+            created_at: key.created_at,
+            scopes: key.scope_list(),
+            name: key.name,
+            key_prefix: key.key_prefix,
+            session: key.session,
+            expires_at: key.expires_at,
+            last_used_at: key.last_used_at,
+            revoked_at: key.revoked_at,
+        })
+        .collect();
+    Ok(Export {
+        exported_at: Utc::now().naive_utc(),
+        activity: ExportedActivity { last_active: model.last_active, api_keys },
+        account: model.into(),
+        reading_lists,
+        library,
+        follows,
+        reviews,
+        review_votes,
+        comments,
+        reports,
+        tag_votes,
+        content_preferences,
+        notification_settings,
+        notification_mutes,
+    })
+}
+
+/// Revokes every key and session right away; the row itself is removed by `purge_due`.
+pub async fn schedule_deletion<C: ConnectionTrait>(db: &C, model: user::Model) -> Result<DateTime, DbErr> {
+    let user_id = model.id;
+    let due = (Utc::now() + grace_period()).naive_utc();
+    let mut active_model = model.into_active_model();
+    active_model.deletion_scheduled_for = Set(Some(due));
+    active_model.update(db).await?;
+    auth::revoke_user_keys(db, user_id).await?;
+    Ok(due)
+}
+
+pub async fn cancel_deletion<C: ConnectionTrait>(db: &C, model: user::Model) -> Result<user::Model, DbErr> {
+    let mut active_model = model.into_active_model();
+    active_model.deletion_scheduled_for = Set(None);
+    active_model.update(db).await
+}
+
+/// Hard-deletes one account. Reviews are kept but detached from the user.
+pub async fn purge_user(db: &DatabaseConnection, user_id: i32) -> Result<(), DbErr> {
+    let txn = db.begin().await?;
+    review::Entity::update_many()
+        .col_expr(review::Column::UserId, Expr::value(Option::<i32>::None))
+        .filter(review::Column::UserId.eq(user_id))
+        .exec(&txn)
+        .await?;
+    reading_list::Entity::delete_many()
+        .filter(reading_list::Column::UserId.eq(user_id))
+        .exec(&txn)
+        .await?;
+    user::Entity::delete_by_id(user_id).exec(&txn).await?;
+    txn.commit().await
+}
+
+/// One account that fails to purge is logged and retried on the next run without holding up the rest.
+pub async fn purge_due(db: &DatabaseConnection) -> Result<usize, DbErr> {
+    let due = user::Entity::find()
+        .filter(user::Column::DeletionScheduledFor.lte(Utc::now().naive_utc()))
+        .all(db)
+        .await?;
+    let mut count = 0;
+    for model in &due {
+        match purge_user(db, model.id).await {
+            Ok(()) => count += 1,
+            Err(e) => warn!("failed to purge user {}: {e}", model.id),
+        }
+    }
+    Ok(count)
+}
+
+/// Runs `purge_due` hourly for the lifetime of the server.
+pub async fn purge_loop(db: DatabaseConnection) {
+    let mut interval = tokio::time::interval(std::time::Duration::from_secs(PURGE_INTERVAL_SECS));
+    loop {
+        interval.tick().await;
+        match purge_due(&db).await {
+            Ok(0) => {}
+            Ok(count) => info!("🗑️ Purged {count} deleted account(s)"),
+            Err(e) => error!("❌ Failed to purge deleted accounts: {e}"),
+        }
+    }
+}
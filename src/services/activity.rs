@@ -0,0 +1,114 @@
+use sea_orm::{ColumnTrait, ConnectionTrait, DbBackend, DbErr, EntityTrait, FromQueryResult, QueryFilter, Set, Statement, sea_query::{Expr, OnConflict}};
+use sea_orm::prelude::{Date, DateTime};
+use serde::Serialize;
+use crate::models::{user, user_activity};
+
+/// Marks the user as active now. Callers throttle this; see `auth::lookup_key`.
+pub async fn record<C: ConnectionTrait>(db: &C, user_id: i32, now: DateTime) -> Result<(), DbErr> {
+    user::Entity::update_many()
+        .col_expr(user::Column::LastActive, Expr::value(now))
+        .filter(user::Column::Id.eq(user_id))
+        .exec(db)
+        .await?;
+    let activity = user_activity::ActiveModel {
+        user_id: Set(user_id),
+        day: Set(now.date()),
+    };
+    user_activity::Entity::insert(activity)
+        .on_conflict(OnConflict::columns([user_activity::Column::UserId, user_activity::Column::Day]).do_nothing().to_owned())
+        .do_nothing()
+        .exec(db)
+        .await?;
+    Ok(())
+}
+
+#[derive(Clone, Debug, Default, Serialize, FromQueryResult)]
+pub struct ActiveUsers {
+    pub as_of: Date,
+    pub daily: i64,
+    pub weekly: i64,
+    pub monthly: i64,
+}
+
+#[derive(Clone, Debug, Default, Serialize, FromQueryResult)]
+pub struct DailyCount {
+    pub day: Date,
+    pub count: i64,
+}
+
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct Cohort {
+    /// Monday of the registration week.
+    pub week: Date,
+    pub size: i64,
+    /// Users of the cohort active in each week after registration; index 0 is the registration week.
+    pub retained: Vec<i64>,
+}
+
+#[derive(Clone, Debug, FromQueryResult)]
+struct CohortRow {
+    week: Date,
+    size: i64,
+    offset: i32,
+    active: i64,
+}
+
+/// Distinct users active today, in the last 7 days and in the last 30 days.
+pub async fn active_users<C: ConnectionTrait>(db: &C) -> Result<ActiveUsers, DbErr> {
+    let stmt = Statement::from_string(DbBackend::Postgres, r#"
+        SELECT CURRENT_DATE AS as_of,
+            COUNT(DISTINCT user_id) FILTER (WHERE day = CURRENT_DATE) AS daily,
+            COUNT(DISTINCT user_id) FILTER (WHERE day > CURRENT_DATE - 7) AS weekly,
+            COUNT(DISTINCT user_id) AS monthly
+        FROM public.user_activity
+        WHERE day > CURRENT_DATE - 30"#);
+    Ok(ActiveUsers::find_by_statement(stmt).one(db).await?.unwrap_or_default())
+}
+
+/// New accounts per day for the last `days` days, oldest first, including empty days.
+pub async fn registrations<C: ConnectionTrait>(db: &C, days: i32) -> Result<Vec<DailyCount>, DbErr> {
+    let stmt = Statement::from_sql_and_values(DbBackend::Postgres, r#"
+        SELECT series.day::date AS day, COUNT(u.id) AS count
+        FROM generate_series(CURRENT_DATE - ($1::int - 1), CURRENT_DATE, INTERVAL '1 day') AS series(day)
+        LEFT JOIN public.user u ON u.created_at::date = series.day::date
+        GROUP BY series.day
+        ORDER BY series.day"#, [days.into()]);
+    DailyCount::find_by_statement(stmt).all(db).await
+}
+
+/// Weekly registration cohorts for the last `weeks` weeks and how many of each came back
+/// in the following weeks.
+pub async fn retention<C: ConnectionTrait>(db: &C, weeks: i32) -> Result<Vec<Cohort>, DbErr> {
+    let stmt = Statement::from_sql_and_values(DbBackend::Postgres, r#"
+        WITH cohort AS (
+            SELECT id AS user_id, date_trunc('week', created_at)::date AS week
+            FROM public.user
+            WHERE created_at >= date_trunc('week', CURRENT_DATE) - ($1::int - 1) * INTERVAL '1 week'
+        ),
+        size AS (
+            SELECT week, COUNT(*) AS size FROM cohort GROUP BY week
+        ),
+        active AS (
+            SELECT c.week, ((date_trunc('week', a.day)::date - c.week) / 7)::int AS "offset", COUNT(DISTINCT a.user_id) AS active
+            FROM cohort c
+            JOIN public.user_activity a ON a.user_id = c.user_id AND a.day >= c.week
+            GROUP BY 1, 2
+        )
+        SELECT s.week, s.size, COALESCE(a."offset", 0) AS "offset", COALESCE(a.active, 0) AS active
+        FROM size s
+        LEFT JOIN active a ON a.week = s.week
+        ORDER BY s.week, 3"#, [weeks.into()]);
+    let rows = CohortRow::find_by_statement(stmt).all(db).await?;
+    let mut cohorts: Vec<Cohort> = Vec::new();
+    for row in rows {
+        if cohorts.last().is_none_or(|cohort| cohort.week != row.week) {
+            let elapsed = (chrono::Utc::now().date_naive() - row.week).num_weeks() as usize;
+            cohorts.push(Cohort { week: row.week, size: row.size, retained: vec![0; elapsed + 1] });
+        }
+        let cohort = cohorts.last_mut().expect("cohort pushed above");
+        if let Some(slot) = cohort.retained.get_mut(row.offset as usize) {
+            *slot = row.active;
+        }
+    }
+    Ok(cohorts)
+}
@@ -0,0 +1,277 @@
+use axum::{Json, extract::{OptionalFromRequestParts, FromRequestParts, Request, State}, http::{Method, StatusCode, header::AUTHORIZATION, request::Parts}, middleware::Next, response::Response};
+use sea_orm::{ActiveModelTrait, ColumnTrait, ConnectionTrait, DbErr, EntityTrait, QueryFilter, Set, IntoActiveModel, sea_query::Expr};
+use serde_json::json;
+use sha2::{Digest, Sha256};
+use crate::app_state::AppState;
+use crate::models::api_key::{ActiveModel, Column, Entity, Model};
+use crate::models::user::{self, Role};
+use crate::services::{activity, rate_limit};
+
+pub type AuthError = (StatusCode, Json<serde_json::Value>);
+
+/// Resources that can be named in a scope, one per controller route prefix.
+pub const RESOURCES: &[&str] = &[
+    "admin", "api-keys", "artists", "authors", "chapters", "comments", "groups", "library", "lists",
+    "me", "novels", "profiles", "publishers", "reading-lists", "reports", "reviews", "sources", "tags",
+    "types", "users",
+];
+
+const KEY_PREFIX: &str = "nu_";
+const LAST_USED_RESOLUTION_SECS: i64 = 60;
+const SESSION_TTL_DAYS: i64 = 30;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Action {
+    Read,
+    Write,
+}
+
+impl Action {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Action::Read => "read",
+            Action::Write => "write",
+        }
+    }
+
+    pub fn for_method(method: &Method) -> Self {
+        match *method {
+            Method::GET | Method::HEAD | Method::OPTIONS => Action::Read,
+            _ => Action::Write,
+        }
+    }
+}
+
+/// The caller behind a validated API key, stored in the request extensions.
+#[derive(Clone, Debug)]
+pub struct Principal {
+    pub key_id: i32,
+    pub user_id: Option<i32>,
+    pub group_id: Option<i32>,
+    pub scopes: Vec<String>,
+}
+
+impl Principal {
+    /// A scope is `<resource>:<action>`; either side may be `*`, and a bare `*` grants everything.
+    pub fn allows(&self, resource: &str, action: Action) -> bool {
+        self.scopes.iter().any(|scope| {
+            if scope == "*" {
+                return true;
+            }
+            let Some((res, act)) = scope.split_once(':') else { return false };
+            (res == "*" || res == resource) && (act == "*" || act == action.as_str())
+        })
+    }
+
+    /// Whether every permission granted by `scope` is also granted to this principal.
+    pub fn covers(&self, scope: &str) -> bool {
+        if scope == "*" {
+            return self.scopes.iter().any(|s| s == "*");
+        }
+        let Some((res, act)) = scope.split_once(':') else { return false };
+        let resources: Vec<&str> = if res == "*" { RESOURCES.to_vec() } else { vec![res] };
+        let actions: &[Action] = match act {
+            "*" => &[Action::Read, Action::Write],
+            "read" => &[Action::Read],
+            _ => &[Action::Write],
+        };
+        resources.iter().all(|r| actions.iter().all(|a| self.allows(r, *a)))
+    }
+
+    pub fn require(&self, resource: &str, action: Action) -> Result<(), AuthError> {
+        if self.allows(resource, action) {
+            Ok(())
+        } else {
+            Err((StatusCode::FORBIDDEN, Json(json!({"error": format!("missing scope {}:{}", resource, action.as_str())}))))
+        }
+    }
+}
+
+impl From<Model> for Principal {
+    fn from(model: Model) -> Self {
+        Self {
+            key_id: model.id,
+            user_id: model.user_id,
+            group_id: model.group_id,
+            scopes: model.scope_list(),
+        }
+    }
+}
+
+impl<S: Send + Sync> FromRequestParts<S> for Principal {
+    type Rejection = AuthError;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        parts.extensions.get::<Principal>()
+            .cloned()
+            .ok_or_else(|| (StatusCode::UNAUTHORIZED, Json(json!({"error": "authentication required"}))))
+    }
+}
+
+impl<S: Send + Sync> OptionalFromRequestParts<S> for Principal {
+    type Rejection = AuthError;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Option<Self>, Self::Rejection> {
+        Ok(parts.extensions.get::<Principal>().cloned())
+    }
+}
+
+pub fn validate_scopes(scopes: &[String]) -> Result<(), AuthError> {
+    for scope in scopes {
+        if scope == "*" {
+            continue;
+        }
+        let valid = match scope.split_once(':') {
+            Some((res, act)) => (res == "*" || RESOURCES.contains(&res)) && matches!(act, "*" | "read" | "write"),
+            None => false,
+        };
+        if !valid {
+            return Err((StatusCode::UNPROCESSABLE_ENTITY, Json(json!({"error": format!("invalid scope {}", scope)}))));
+        }
+    }
+    Ok(())
+}
+
+/// Returns a fresh plaintext key; only its hash is ever stored.
+pub fn generate_key() -> String {
+    let bytes: [u8; 24] = rand::random();
+    format!("{}{}", KEY_PREFIX, hex::encode(bytes))
+}
+
+pub fn hash_key(key: &str) -> String {
+    hex::encode(Sha256::digest(key.as_bytes()))
+}
+
+pub fn display_prefix(key: &str) -> String {
+    key.chars().take(KEY_PREFIX.len() + 8).collect()
+}
+
+/// Login sessions are API keys flagged as `session`, so they share validation and revocation.
+pub async fn issue_session<C: ConnectionTrait>(db: &C, user_id: i32, scopes: Vec<String>) -> Result<(String, Model), DbErr> {
+    let key = generate_key();
+    let model = ActiveModel {
+        name: Set("session".to_string()),
+        key_prefix: Set(display_prefix(&key)),
+        key_hash: Set(hash_key(&key)),
+        scopes: Set(scopes.join(" ")),
+        user_id: Set(Some(user_id)),
+        expires_at: Set(Some((chrono::Utc::now() + chrono::Duration::days(SESSION_TTL_DAYS)).naive_utc())),
+        session: Set(true),
+        ..Default::default()
+    }
+    .insert(db)
+    .await?;
+    Ok((key, model))
+}
+
+/// Revokes every API key and session of a user, e.g. once their password changed or they were banned.
+pub async fn revoke_user_keys<C: ConnectionTrait>(db: &C, user_id: i32) -> Result<(), DbErr> {
+    Entity::update_many()
+        .col_expr(Column::RevokedAt, Expr::value(chrono::Utc::now().naive_utc()))
+        .filter(Column::UserId.eq(user_id))
+        .filter(Column::RevokedAt.is_null())
+        .exec(db)
+        .await?;
+    Ok(())
+}
+
+/// Loads a key's user, refusing users that are gone or banned.
+async fn active_user<C: ConnectionTrait>(db: &C, user_id: i32) -> Result<user::Model, AuthError> {
+    let user = user::Entity::find_by_id(user_id)
+        .one(db)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"error": e.to_string()}))))?
+        .ok_or_else(|| (StatusCode::UNAUTHORIZED, Json(json!({"error": "user no longer exists"}))))?;
+    if user.banned_at.is_some() {
+        return Err((StatusCode::FORBIDDEN, Json(json!({"error": "account is banned"}))));
+    }
+    Ok(user)
+}
+
+/// Loads the user behind a principal; group-only keys and banned users are refused.
+pub async fn current_user<C: ConnectionTrait>(db: &C, principal: &Principal) -> Result<user::Model, AuthError> {
+    let user_id = principal.user_id
+        .ok_or_else(|| (StatusCode::FORBIDDEN, Json(json!({"error": "this endpoint needs a user account"}))))?;
+    active_user(db, user_id).await
+}
+
+pub async fn require_role<C: ConnectionTrait>(db: &C, principal: &Principal, roles: &[Role]) -> Result<user::Model, AuthError> {
+    let user = current_user(db, principal).await?;
+    if roles.contains(&user.role) {
+        Ok(user)
+    } else {
+        Err((StatusCode::FORBIDDEN, Json(json!({"error": "insufficient role"}))))
+    }
+}
+
+fn bearer_token(request: &Request) -> Result<Option<String>, AuthError> {
+    let Some(value) = request.headers().get(AUTHORIZATION) else { return Ok(None) };
+    let value = value.to_str()
+        .map_err(|_| (StatusCode::UNAUTHORIZED, Json(json!({"error": "malformed authorization header"}))))?;
+    let token = value.strip_prefix("Bearer ")
+        .or_else(|| value.strip_prefix("ApiKey "))
+        .ok_or_else(|| (StatusCode::UNAUTHORIZED, Json(json!({"error": "unsupported authorization scheme"}))))?;
+    Ok(Some(token.trim().to_string()))
+}
+
+/// Finds a usable key. Keys of banned users are refused even if the ban missed revoking them.
+pub async fn lookup_key(state: &AppState, token: &str) -> Result<Model, AuthError> {
+    let model = Entity::find()
+        .filter(Column::KeyHash.eq(hash_key(token)))
+        .one(&state.db)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"error": e.to_string()}))))?
+        .ok_or_else(|| (StatusCode::UNAUTHORIZED, Json(json!({"error": "invalid api key"}))))?;
+    let now = chrono::Utc::now().naive_utc();
+    if model.revoked_at.is_some() {
+        return Err((StatusCode::UNAUTHORIZED, Json(json!({"error": "api key revoked"}))));
+    }
+    if model.expires_at.is_some_and(|expires_at| expires_at <= now) {
+        return Err((StatusCode::UNAUTHORIZED, Json(json!({"error": "api key expired"}))));
+    }
+    if let Some(user_id) = model.user_id {
+        active_user(&state.db, user_id).await?;
+    }
+    let stale = model.last_used_at
+        .is_none_or(|last_used_at| (now - last_used_at).num_seconds() >= LAST_USED_RESOLUTION_SECS);
+    if stale {
+        let mut active_model = model.clone().into_active_model();
+        active_model.last_used_at = Set(Some(now));
+        active_model.update(&state.db)
+            .await
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"error": e.to_string()}))))?;
+        if let Some(user_id) = model.user_id {
+            activity::record(&state.db, user_id, now)
+                .await
+                .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"error": e.to_string()}))))?;
+        }
+    }
+    Ok(model)
+}
+
+/// The resource a request's scope is checked against: the first path segment, except for
+/// comments nested under what they discuss, such as `/reviews/{id}/comments`.
+fn scope_resource(path: &str) -> &str {
+    let mut segments = path.trim_start_matches('/').split('/');
+    let first = segments.next().unwrap_or_default();
+    match segments.nth(1) {
+        Some("comments") => "comments",
+        _ => first,
+    }
+}
+
+/// Validates an `Authorization: Bearer <key>` header when present and checks the key's
+/// scope against the resource and method of the request. Anonymous requests pass through.
+pub async fn authenticate(State(state): State<AppState>, mut request: Request, next: Next) -> Result<Response, AuthError> {
+    let Some(token) = bearer_token(&request)? else { return Ok(next.run(request).await) };
+    let principal = match lookup_key(&state, &token).await {
+        Ok(model) => Principal::from(model),
+        Err(error) => return rate_limit::charge_failed_auth(&state, &request).map(Ok).unwrap_or(Err(error)),
+    };
+    let resource = scope_resource(request.uri().path());
+    if RESOURCES.contains(&resource) {
+        principal.require(resource, Action::for_method(request.method()))?;
+    }
+    request.extensions_mut().insert(principal);
+    Ok(next.run(request).await)
+}
@@ -0,0 +1,174 @@
+use chrono::{Duration, Utc};
+use log::{error, info, warn};
+use sea_orm::{ActiveModelTrait, ColumnTrait, ConnectionTrait, DbBackend, DbErr, EntityTrait, FromQueryResult, IntoActiveModel, PaginatorTrait, QueryFilter, Set, Statement, sea_query::Expr};
+use std::env;
+use sea_orm::prelude::DateTime;
+use crate::app_state::AppState;
+use crate::models::notification_preference::{self, DigestFrequency};
+use crate::models::user;
+use crate::services::mail::Mail;
+
+/// How often due digests are looked for, overridable through `DIGEST_INTERVAL_SECS`.
+const DIGEST_INTERVAL_SECS: u64 = 60 * 60;
+
+fn interval_secs() -> u64 {
+    env::var("DIGEST_INTERVAL_SECS").ok().and_then(|v| v.parse().ok()).filter(|secs| *secs > 0).unwrap_or(DIGEST_INTERVAL_SECS)
+}
+
+fn period(frequency: DigestFrequency) -> Option<Duration> {
+    match frequency {
+        DigestFrequency::Never => None,
+        DigestFrequency::Daily => Some(Duration::days(1)),
+        DigestFrequency::Weekly => Some(Duration::weeks(1)),
+    }
+}
+
+#[derive(Clone, Debug, FromQueryResult)]
+struct DigestChapter {
+    novel_id: i32,
+    novel_name: String,
+    source_name: String,
+    number: String,
+    title: String,
+    content_url: Option<String>,
+}
+
+/// Chapters released since `since` for the novels in the user's library, skipping dropped and
+/// muted novels, grouped by novel.
+async fn new_chapters<C: ConnectionTrait>(db: &C, user_id: i32, since: DateTime) -> Result<Vec<DigestChapter>, DbErr> {
+    DigestChapter::find_by_statement(Statement::from_sql_and_values(DbBackend::Postgres, r#"
+        SELECT n.id AS novel_id, n.default_name AS novel_name, s.name AS source_name, c.number, c.title, c.content_url
+        FROM public.library_entry le
+        JOIN public.novel n ON n.id = le.novel_id
+        JOIN public.source s ON s.novel_id = n.id
+        JOIN public.chapter c ON c.source_id = s.id
+        WHERE le.user_id = $1
+            AND le.status <> 'dropped'
+            AND c.created_at > $2
+            AND NOT EXISTS (
+                SELECT 1 FROM public.notification_mute m
+                WHERE m.user_id = le.user_id AND m.novel_id = n.id
+            )
+        ORDER BY n.default_name, n.id, c.created_at, c.id"#, [user_id.into(), since.into()]))
+        .all(db)
+        .await
+}
+
+fn body(user: &user::Model, chapters: &[DigestChapter], unsubscribe_url: &str) -> String {
+    let mut body = format!("Hi {},\n\nNew chapters for the novels in your reading list:\n", user.username);
+    let mut current = None;
+    for chapter in chapters {
+        if current != Some(chapter.novel_id) {
+            body.push_str(&format!("\n{}\n", chapter.novel_name));
+            current = Some(chapter.novel_id);
+        }
+        let title = if chapter.title.is_empty() { String::new() } else { format!(" — {}", chapter.title) };
+        let link = chapter.content_url.as_deref().map(|url| format!("\n    {url}")).unwrap_or_default();
+        body.push_str(&format!("  • {} chapter {}{}{}\n", chapter.source_name, chapter.number, title, link));
+    }
+    body.push_str(&format!("\nTo stop receiving these mails, open:\n{unsubscribe_url}\n"));
+    body
+}
+
+/// Returns the user's unsubscribe token, creating it on first use.
+async fn unsubscribe_token<C: ConnectionTrait>(db: &C, preference: &notification_preference::Model) -> Result<String, DbErr> {
+    if let Some(token) = &preference.unsubscribe_token {
+        return Ok(token.clone());
+    }
+    let token = hex::encode(rand::random::<[u8; 24]>());
+    let mut active_model = preference.clone().into_active_model();
+    active_model.unsubscribe_token = Set(Some(token.clone()));
+    active_model.update(db).await?;
+    Ok(token)
+}
+
+/// Mails one digest if it is due. The send time only moves forward when delivery succeeded,
+/// so a failed mail is retried on the next run with the same chapters.
+async fn send_one(state: &AppState, preference: notification_preference::Model, now: DateTime) -> Result<bool, DbErr> {
+    let Some(period) = period(preference.digest_frequency) else { return Ok(false) };
+    if preference.digest_sent_at.is_some_and(|sent_at| sent_at + period > now) {
+        return Ok(false);
+    }
+    let Some(user) = user::Entity::find_by_id(preference.user_id)
+        .filter(user::Column::EmailVerifiedAt.is_not_null())
+        .filter(user::Column::DeletionScheduledFor.is_null())
+        .one(&state.db)
+        .await? else { return Ok(false) };
+    let since = preference.digest_sent_at.unwrap_or(now - period);
+    let chapters = new_chapters(&state.db, user.id, since).await?;
+    let mut sent = false;
+    if !chapters.is_empty() {
+        let token = unsubscribe_token(&state.db, &preference).await?;
+        let unsubscribe_url = format!("{}/api/digest/unsubscribe?token={}", state.app_url, token);
+        let subject = match preference.digest_frequency {
+            DigestFrequency::Weekly => "Your weekly NovelUpdates digest",
+            _ => "Your daily NovelUpdates digest",
+        };
+        let mail = Mail {
+            to: user.email.clone(),
+            subject: subject.to_string(),
+            body: body(&user, &chapters, &unsubscribe_url),
+            list_unsubscribe: Some(unsubscribe_url),
+        };
+        if let Err(e) = state.mailer.send(mail).await {
+            warn!("failed to send digest to {}: {e}", user.email);
+            return Ok(false);
+        }
+        sent = true;
+    }
+    notification_preference::Entity::update_many()
+        .col_expr(notification_preference::Column::DigestSentAt, Expr::value(now))
+        .filter(notification_preference::Column::Id.eq(preference.id))
+        .exec(&state.db)
+        .await?;
+    Ok(sent)
+}
+
+/// Sends every digest that is due and returns how many mails went out.
+pub async fn send_due(state: &AppState) -> Result<u64, DbErr> {
+    let now = Utc::now().naive_utc();
+    let preferences = notification_preference::Entity::find()
+        .filter(notification_preference::Column::DigestFrequency.ne(DigestFrequency::Never))
+        .all(&state.db)
+        .await?;
+    let mut count = 0;
+    for preference in preferences {
+        let user_id = preference.user_id;
+        match send_one(state, preference, now).await {
+            Ok(true) => count += 1,
+            Ok(false) => {}
+            Err(e) => warn!("failed to build digest for user {user_id}: {e}"),
+        }
+    }
+    Ok(count)
+}
+
+pub async fn token_exists<C: ConnectionTrait>(db: &C, token: &str) -> Result<bool, DbErr> {
+    let count = notification_preference::Entity::find()
+        .filter(notification_preference::Column::UnsubscribeToken.eq(token))
+        .count(db)
+        .await?;
+    Ok(count > 0)
+}
+
+/// Turns the digest off for whoever holds the token. Returns false for unknown tokens.
+pub async fn unsubscribe<C: ConnectionTrait>(db: &C, token: &str) -> Result<bool, DbErr> {
+    let result = notification_preference::Entity::update_many()
+        .col_expr(notification_preference::Column::DigestFrequency, Expr::value(DigestFrequency::Never))
+        .filter(notification_preference::Column::UnsubscribeToken.eq(token))
+        .exec(db)
+        .await?;
+    Ok(result.rows_affected > 0)
+}
+
+pub async fn digest_loop(state: AppState) {
+    let mut interval = tokio::time::interval(std::time::Duration::from_secs(interval_secs()));
+    loop {
+        interval.tick().await;
+        match send_due(&state).await {
+            Ok(0) => {}
+            Ok(count) => info!("📬 Sent {count} digest mail(s)"),
+            Err(e) => error!("❌ Failed to send digests: {e}"),
+        }
+    }
+}
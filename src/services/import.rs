@@ -0,0 +1,346 @@
+use quick_xml::{Reader, events::Event};
+use sea_orm::{ColumnTrait, Condition, ConnectionTrait, DbErr, EntityTrait, ExprTrait, QueryFilter, QuerySelect, sea_query::{Expr, Func}};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use crate::models::{library_entry::Status, novel};
+use crate::services::slugs;
+
+/// Titles at or above this score are imported without asking the user.
+pub const MATCH_THRESHOLD: f64 = 0.85;
+/// Candidates below this score are not worth suggesting.
+const CANDIDATE_THRESHOLD: f64 = 0.5;
+const MAX_CANDIDATES: usize = 3;
+/// Most novels scored against one title that has no exact match.
+const CANDIDATE_POOL: u64 = 200;
+/// Longest words of a title used to narrow the candidate pool.
+const POOL_WORDS: usize = 4;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Format {
+    /// NovelUpdates reading-list CSV.
+    Csv,
+    /// NovelUpdates reading-list page saved as HTML.
+    Html,
+    /// MyAnimeList XML export.
+    Mal,
+}
+
+/// One row of an uploaded export, before matching.
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct ImportedEntry {
+    pub title: String,
+    pub status: Status,
+    /// Chapter number of the last read chapter, as written in the export.
+    pub progress: Option<String>,
+    pub rating: Option<String>,
+    pub notes: Option<String>,
+    pub started_date: Option<String>,
+    pub completed_date: Option<String>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct Candidate {
+    pub novel_id: i32,
+    pub default_name: String,
+    pub confidence: f64,
+}
+
+pub fn parse(format: Format, body: &str, default_status: Status) -> Result<Vec<ImportedEntry>, String> {
+    match format {
+        Format::Csv => parse_csv(body),
+        Format::Html => Ok(parse_html(body, default_status)),
+        Format::Mal => parse_mal(body),
+    }
+}
+
+fn non_empty(value: &str) -> Option<String> {
+    let value = value.trim();
+    (!value.is_empty()).then(|| value.to_string())
+}
+
+/// Maps the status names used by NovelUpdates and MAL (text or MAL's numeric codes).
+pub fn parse_status(value: &str, has_progress: bool) -> Status {
+    let key: String = value.chars().filter(|c| c.is_alphanumeric()).collect::<String>().to_lowercase();
+    match key.as_str() {
+        "reading" | "current" | "1" => Status::Reading,
+        "completed" | "complete" | "2" => Status::Completed,
+        "onhold" | "paused" | "3" => Status::OnHold,
+        "dropped" | "4" => Status::Dropped,
+        "plantoread" | "planned" | "6" => Status::PlanToRead,
+        _ if has_progress => Status::Reading,
+        _ => Status::PlanToRead,
+    }
+}
+
+/// Extracts the chapter number from progress strings such as `c45`, `v2c10`, `45/120` or `45`.
+pub fn parse_progress(value: &str) -> Option<String> {
+    let value = value.trim().to_lowercase();
+    let tail = value.rfind('c')
+        .map(|index| &value[index + 1..])
+        .filter(|tail| tail.trim_start().starts_with(|c: char| c.is_ascii_digit()))
+        .unwrap_or(&value);
+    let start = tail.find(|c: char| c.is_ascii_digit())?;
+    let number: String = tail[start..].chars().take_while(|c| c.is_ascii_digit() || *c == '.').collect();
+    let number = number.trim_end_matches('.');
+    (!number.trim_start_matches('0').is_empty()).then(|| number.to_string())
+}
+
+fn parse_csv(body: &str) -> Result<Vec<ImportedEntry>, String> {
+    let mut reader = csv::ReaderBuilder::new().flexible(true).from_reader(body.as_bytes());
+    let headers: Vec<String> = reader.headers()
+        .map_err(|e| e.to_string())?
+        .iter()
+        .map(|header| header.trim().to_lowercase())
+        .collect();
+    let column = |names: &[&str]| headers.iter().position(|header| names.contains(&header.as_str()));
+    let title = column(&["title", "name", "novel", "series"]).ok_or("csv has no title column")?;
+    let status = column(&["status", "list", "reading list"]);
+    let progress = column(&["progress", "chapter", "chapters read", "last read", "read"]);
+    let rating = column(&["rating", "score"]);
+    let notes = column(&["notes", "note", "comments", "comment"]);
+    let started = column(&["started", "start date", "started date"]);
+    let completed = column(&["completed", "finish date", "completed date"]);
+    let mut entries = vec![];
+    for record in reader.records() {
+        let record = record.map_err(|e| e.to_string())?;
+        let field = |index: Option<usize>| index.and_then(|index| record.get(index)).and_then(non_empty);
+        let Some(title) = field(Some(title)) else { continue };
+        let progress = field(progress).and_then(|value| parse_progress(&value));
+        entries.push(ImportedEntry {
+            title,
+            status: parse_status(&field(status).unwrap_or_default(), progress.is_some()),
+            progress,
+            rating: field(rating),
+            notes: field(notes),
+            started_date: field(started),
+            completed_date: field(completed),
+        });
+    }
+    Ok(entries)
+}
+
+fn decode_entities(text: &str) -> String {
+    text.replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&#039;", "'")
+        .replace("&apos;", "'")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&nbsp;", " ")
+        .replace("&amp;", "&")
+}
+
+fn strip_tags(html: &str) -> String {
+    let mut text = String::with_capacity(html.len());
+    let mut in_tag = false;
+    for c in html.chars() {
+        match c {
+            '<' => in_tag = true,
+            '>' => { in_tag = false; text.push(' '); }
+            _ if !in_tag => text.push(c),
+            _ => {}
+        }
+    }
+    decode_entities(&text)
+}
+
+/// NovelUpdates reading-list pages have one table row per novel, with a link to `/series/<slug>/`
+/// holding the title and the progress (`c45`, `v2c10`) somewhere in the same row. A saved page
+/// only covers one list, so every row gets `default_status`.
+fn parse_html(body: &str, default_status: Status) -> Vec<ImportedEntry> {
+    let mut entries = vec![];
+    let mut seen = HashSet::new();
+    let lower = body.to_ascii_lowercase();
+    for (row_start, _) in lower.match_indices("<tr") {
+        let row_end = lower[row_start..].find("</tr>").map_or(body.len(), |end| row_start + end);
+        let row = &body[row_start..row_end];
+        let row_lower = &lower[row_start..row_end];
+        let Some(link) = row_lower.find("/series/") else { continue };
+        let Some(text_start) = row_lower[link..].find('>').map(|i| link + i + 1) else { continue };
+        let Some(text_end) = row_lower[text_start..].find("</a>").map(|i| text_start + i) else { continue };
+        let Some(title) = non_empty(&strip_tags(&row[text_start..text_end])) else { continue };
+        if !seen.insert(title.clone()) {
+            continue;
+        }
+        let rest = strip_tags(&row[text_end..]);
+        let progress = rest.split_whitespace()
+            .filter(|word| word.to_lowercase().trim_start_matches(|c: char| c == 'v' || c.is_ascii_digit()).starts_with('c'))
+            .find_map(parse_progress);
+        entries.push(ImportedEntry { title, status: default_status.clone(), progress, ..Default::default() });
+    }
+    entries
+}
+
+fn parse_mal(body: &str) -> Result<Vec<ImportedEntry>, String> {
+    let mut reader = Reader::from_str(body);
+    reader.config_mut().trim_text(true);
+    let mut entries = vec![];
+    let mut current: Option<Vec<(String, String)>> = None;
+    let mut field: Option<String> = None;
+    loop {
+        match reader.read_event().map_err(|e| e.to_string())? {
+            Event::Start(e) => {
+                let name = String::from_utf8_lossy(e.name().as_ref()).to_string();
+                if name == "manga" {
+                    current = Some(vec![]);
+                } else if current.is_some() {
+                    field = Some(name);
+                }
+            }
+            Event::Text(e) => {
+                if let (Some(fields), Some(name)) = (current.as_mut(), field.as_ref()) {
+                    fields.push((name.clone(), e.unescape().map_err(|e| e.to_string())?.to_string()));
+                }
+            }
+            Event::CData(e) => {
+                if let (Some(fields), Some(name)) = (current.as_mut(), field.as_ref()) {
+                    fields.push((name.clone(), String::from_utf8_lossy(&e.into_inner()).to_string()));
+                }
+            }
+            Event::End(e) => {
+                if e.name().as_ref() == b"manga"
+                    && let Some(entry) = current.take().and_then(mal_entry) {
+                    entries.push(entry);
+                }
+                field = None;
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+    Ok(entries)
+}
+
+fn mal_entry(fields: Vec<(String, String)>) -> Option<ImportedEntry> {
+    let get = |name: &str| fields.iter().find(|(key, _)| key == name).and_then(|(_, value)| non_empty(value));
+    let date = |name: &str| get(name).filter(|value| !value.starts_with("0000"));
+    let progress = get("my_read_chapters").and_then(|value| parse_progress(&value));
+    Some(ImportedEntry {
+        title: get("manga_title")?,
+        status: parse_status(&get("my_status").unwrap_or_default(), progress.is_some()),
+        progress,
+        rating: get("my_score").filter(|score| score != "0"),
+        notes: get("my_comments"),
+        started_date: date("my_start_date"),
+        completed_date: date("my_finish_date"),
+    })
+}
+
+/// Lower-cases and keeps only letters and digits, so punctuation and spacing do not matter.
+pub fn normalize_title(title: &str) -> String {
+    title.chars()
+        .map(|c| if c.is_alphanumeric() { c.to_lowercase().next().unwrap_or(c) } else { ' ' })
+        .collect::<String>()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn bigrams(text: &str) -> Vec<(char, char)> {
+    let chars: Vec<char> = text.chars().filter(|c| !c.is_whitespace()).collect();
+    chars.windows(2).map(|pair| (pair[0], pair[1])).collect()
+}
+
+/// Sørensen–Dice coefficient over character bigrams of the normalized titles; 1.0 for equal titles.
+pub fn similarity(a: &str, b: &str) -> f64 {
+    if a == b {
+        return 1.0;
+    }
+    let (a, b) = (bigrams(a), bigrams(b));
+    if a.is_empty() || b.is_empty() {
+        return 0.0;
+    }
+    let mut remaining = b.clone();
+    let mut shared = 0;
+    for pair in &a {
+        if let Some(index) = remaining.iter().position(|other| other == pair) {
+            remaining.swap_remove(index);
+            shared += 1;
+        }
+    }
+    (2 * shared) as f64 / (a.len() + b.len()) as f64
+}
+
+/// Every name a novel is known by, normalized.
+fn novel_names(model: &novel::Model) -> Vec<String> {
+    let mut names = vec![normalize_title(&model.default_name)];
+    if let Some(native_name) = &model.native_name {
+        names.push(normalize_title(native_name));
+    }
+    if let Some(alternative_names) = &model.alternative_names {
+        names.extend(alternative_names.split([',', ';', '\n', '|']).map(normalize_title));
+    }
+    names.retain(|name| !name.is_empty());
+    names
+}
+
+/// Best matching novels for a title, highest confidence first.
+pub fn candidates(novels: &[novel::Model], title: &str) -> Vec<Candidate> {
+    let title = normalize_title(title);
+    let mut scored: Vec<Candidate> = novels.iter()
+        .filter_map(|model| {
+            let confidence = novel_names(model).iter().map(|name| similarity(&title, name)).fold(0.0, f64::max);
+            (confidence >= CANDIDATE_THRESHOLD).then(|| Candidate {
+                novel_id: model.id,
+                default_name: model.default_name.clone(),
+                confidence: (confidence * 100.0).round() / 100.0,
+            })
+        })
+        .collect();
+    scored.sort_by(|a, b| b.confidence.total_cmp(&a.confidence));
+    scored.truncate(MAX_CANDIDATES);
+    scored
+}
+
+/// Novels whose name, ignoring case, or slug equals one of the titles, keyed by title. A name
+/// match wins over a slug match.
+pub async fn exact_matches<C: ConnectionTrait>(db: &C, titles: &[String]) -> Result<HashMap<String, Candidate>, DbErr> {
+    let names: Vec<String> = titles.iter().map(|title| title.trim().to_lowercase()).collect();
+    let slugs: Vec<String> = titles.iter().map(|title| slugs::slugify(title)).collect();
+    let novels = novel::Entity::find()
+        .filter(Condition::any()
+            .add(Expr::expr(Func::lower(Expr::col((novel::Entity, novel::Column::DefaultName)))).is_in(names))
+            .add(novel::Column::Slug.is_in(slugs)))
+        .all(db)
+        .await?;
+    let candidate = |model: &novel::Model| Candidate { novel_id: model.id, default_name: model.default_name.clone(), confidence: 1.0 };
+    Ok(titles.iter()
+        .filter_map(|title| {
+            let name = title.trim().to_lowercase();
+            let slug = slugs::slugify(title);
+            novels.iter()
+                .find(|model| model.default_name.to_lowercase() == name)
+                .or_else(|| novels.iter().find(|model| model.slug.as_deref() == Some(slug.as_str())))
+                .map(|model| (title.clone(), candidate(model)))
+        })
+        .collect())
+}
+
+/// Novels sharing one of the longest words of `title` in any of their names, so fuzzy matching
+/// scores a bounded set instead of the whole catalogue.
+pub async fn candidate_pool<C: ConnectionTrait>(db: &C, title: &str) -> Result<Vec<novel::Model>, DbErr> {
+    let title = normalize_title(title);
+    let mut words: Vec<&str> = title.split(' ').filter(|word| word.chars().count() >= 3).collect();
+    words.sort_by_key(|word| std::cmp::Reverse(word.chars().count()));
+    words.dedup();
+    words.truncate(POOL_WORDS);
+    if words.is_empty() && !title.is_empty() {
+        words.push(&title);
+    }
+    if words.is_empty() {
+        return Ok(vec![]);
+    }
+    let mut condition = Condition::any();
+    for word in words {
+        let pattern = format!("%{word}%");
+        for column in [novel::Column::DefaultName, novel::Column::NativeName, novel::Column::AlternativeNames] {
+            condition = condition.add(Expr::expr(Func::lower(Expr::col((novel::Entity, column)))).like(pattern.clone()));
+        }
+    }
+    novel::Entity::find()
+        .filter(condition)
+        .limit(CANDIDATE_POOL)
+        .all(db)
+        .await
+}
@@ -0,0 +1,99 @@
+use log::info;
+use pulldown_cmark::{Event, Options, Parser, Tag, TagEnd, TextMergeStream, html};
+use sea_orm::{ConnectionTrait, DbBackend, DbErr, FromQueryResult, Statement};
+
+const SPOILER_MARKER: &str = "||";
+const SPOILER_OPEN: &str = r#"<span class="spoiler">"#;
+const SPOILER_CLOSE: &str = "</span>";
+
+/// Tables with user-authored Markdown: the table, the source column and the rendered column.
+const RENDERED_COLUMNS: &[(&str, &str, &str)] = &[
+    ("author", "bio", "bio_html"),
+    ("comment", "content", "content_html"),
+    ("group", "description", "description_html"),
+    ("novel", "description", "description_html"),
+    ("novel_reading_list", "blurb", "blurb_html"),
+    ("reading_list", "description", "description_html"),
+    ("review", "content", "content_html"),
+    ("user", "bio", "bio_html"),
+];
+
+/// Turns `||hidden||` pairs inside one run of text into spoiler spans. A marker without a
+/// partner is kept as typed, and spoilers cannot span formatting such as `**bold**`.
+fn with_spoilers(event: Event<'_>) -> Vec<Event<'_>> {
+    let Event::Text(text) = event else { return vec![event] };
+    if text.matches(SPOILER_MARKER).count() < 2 {
+        return vec![Event::Text(text)];
+    }
+    let parts: Vec<&str> = text.split(SPOILER_MARKER).collect();
+    let paired = (parts.len() - 1) / 2 * 2;
+    let mut events = Vec::with_capacity(parts.len() * 2);
+    for (index, part) in parts.iter().enumerate() {
+        if index > 0 {
+            events.push(match index {
+                index if index > paired => Event::Text(SPOILER_MARKER.into()),
+                index if index % 2 == 1 => Event::InlineHtml(SPOILER_OPEN.into()),
+                _ => Event::InlineHtml(SPOILER_CLOSE.into()),
+            });
+        }
+        if !part.is_empty() {
+            events.push(Event::Text(part.to_string().into()));
+        }
+    }
+    events
+}
+
+/// Renders Markdown to HTML that is safe to embed. Raw HTML passes the parser, so the output goes
+/// through ammonia's allow list, extended with the spoiler class.
+pub fn render(source: &str) -> String {
+    let mut in_code_block = false;
+    let parser = TextMergeStream::new(Parser::new_ext(source, Options::ENABLE_STRIKETHROUGH));
+    let events = parser.flat_map(|event| {
+        match &event {
+            Event::Start(Tag::CodeBlock(_)) => in_code_block = true,
+            Event::End(TagEnd::CodeBlock) => in_code_block = false,
+            _ => {}
+        }
+        if in_code_block { vec![event] } else { with_spoilers(event) }
+    });
+    let mut unsafe_html = String::with_capacity(source.len() * 3 / 2);
+    html::push_html(&mut unsafe_html, events);
+    ammonia::Builder::default()
+        .add_allowed_classes("span", &["spoiler"])
+        .clean(&unsafe_html)
+        .to_string()
+}
+
+pub fn render_opt(source: Option<&str>) -> Option<String> {
+    source.map(render)
+}
+
+#[derive(Clone, Debug, FromQueryResult)]
+struct Unrendered {
+    source: String,
+}
+
+/// Renders rows written before the HTML columns existed. Runs at startup and only touches rows
+/// whose rendered column is still empty. Rows are matched by their source rather than an id, as
+/// some tables (list blurbs) have none; equal sources render the same anyway.
+pub async fn backfill<C: ConnectionTrait>(db: &C) -> Result<u64, DbErr> {
+    let mut count = 0;
+    for (table, source, rendered) in RENDERED_COLUMNS {
+        let rows = Unrendered::find_by_statement(Statement::from_string(DbBackend::Postgres, format!(
+            "SELECT DISTINCT {source} AS source FROM public.{table} WHERE {source} IS NOT NULL AND {rendered} IS NULL"
+        )))
+        .all(db)
+        .await?;
+        for row in rows {
+            let result = db.execute_raw(Statement::from_sql_and_values(DbBackend::Postgres,
+                format!("UPDATE public.{table} SET {rendered} = $1 WHERE {source} = $2 AND {rendered} IS NULL"),
+                [render(&row.source).into(), row.source.into()],
+            )).await?;
+            count += result.rows_affected();
+        }
+    }
+    if count > 0 {
+        info!("📝 Rendered Markdown for {count} existing row(s)");
+    }
+    Ok(count)
+}
@@ -0,0 +1,18 @@
+pub mod account;
+pub mod account_data;
+pub mod activity;
+pub mod auth;
+pub mod digest;
+pub mod import;
+pub mod mail;
+pub mod markdown;
+pub mod moderation;
+pub mod notifications;
+pub mod password;
+pub mod preferences;
+pub mod rate_limit;
+pub mod reading_stats;
+pub mod reputation;
+pub mod slugs;
+pub mod tags;
+pub mod totp;
@@ -0,0 +1,46 @@
+use chrono::Utc;
+use sea_orm::{ActiveModelTrait, ConnectionTrait, DbErr, IntoActiveModel, Set};
+use crate::models::{moderation_log, user};
+use crate::models::moderation_log::Action;
+use crate::models::report::TargetType;
+use crate::services::auth;
+
+/// Writes one audit entry. Call it in the same transaction as the action it describes.
+pub async fn record<C: ConnectionTrait>(
+    db: &C,
+    moderator_id: i32,
+    action: Action,
+    target: (TargetType, i32),
+    offender_id: Option<i32>,
+    report_id: Option<i32>,
+    note: Option<String>,
+) -> Result<moderation_log::Model, DbErr> {
+    moderation_log::ActiveModel {
+        created_at: Set(Utc::now().naive_utc()),
+        moderator_id: Set(Some(moderator_id)),
+        action: Set(action),
+        target_type: Set(target.0),
+        target_id: Set(target.1),
+        offender_id: Set(offender_id),
+        report_id: Set(report_id),
+        note: Set(note),
+        ..Default::default()
+    }
+    .insert(db)
+    .await
+}
+
+/// Bans the user and revokes every key and session, like a scheduled deletion does.
+/// Banning an already banned user keeps the original date.
+pub async fn ban<C: ConnectionTrait>(db: &C, model: user::Model) -> Result<user::Model, DbErr> {
+    let user_id = model.id;
+    let model = if model.banned_at.is_none() {
+        let mut active_model = model.into_active_model();
+        active_model.banned_at = Set(Some(Utc::now().naive_utc()));
+        active_model.update(db).await?
+    } else {
+        model
+    };
+    auth::revoke_user_keys(db, user_id).await?;
+    Ok(model)
+}
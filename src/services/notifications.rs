@@ -0,0 +1,81 @@
+use log::warn;
+use sea_orm::{ConnectionTrait, DbBackend, DbErr, EntityTrait, Statement};
+use crate::models::{chapter, comment, review, source};
+
+/// Recipients must not have muted the novel and must not have turned the kind off;
+/// `$1` is the novel, the user is `recipient.user_id` and the preference column is spliced in.
+fn wanted(kind_column: &str) -> String {
+    format!(r#"
+        NOT EXISTS (
+            SELECT 1 FROM public.notification_mute m
+            WHERE m.user_id = recipient.user_id AND m.novel_id = $1
+        )
+        AND COALESCE((
+            SELECT p.{kind_column} FROM public.notification_preference p
+            WHERE p.user_id = recipient.user_id
+        ), TRUE)"#)
+}
+
+/// Notifies everyone with the novel of the chapter's source in their library, except readers
+/// who dropped it.
+pub async fn new_chapter<C: ConnectionTrait>(db: &C, chapter: &chapter::Model) -> Result<u64, DbErr> {
+    let sql = format!(r#"
+        INSERT INTO public.notification (user_id, kind, title, novel_id, chapter_id, source_id, group_id)
+        SELECT recipient.user_id, 'new_chapter', n.default_name || ': chapter ' || $2, n.id, $3, s.id, s.group_id
+        FROM public.source s
+        JOIN public.novel n ON n.id = s.novel_id
+        JOIN public.library_entry recipient ON recipient.novel_id = n.id
+        WHERE s.id = $4 AND n.id = $1 AND recipient.status <> 'dropped' AND {}"#, wanted("new_chapter"));
+    let novel_id = source_novel_id(db, chapter.source_id).await?;
+    let result = db.execute_raw(Statement::from_sql_and_values(DbBackend::Postgres, sql, [
+        novel_id.into(), chapter.number.clone().into(), chapter.id.into(), chapter.source_id.into(),
+    ])).await?;
+    Ok(result.rows_affected())
+}
+
+/// Notifies the followers of the source's group that it picked up a novel.
+pub async fn new_source<C: ConnectionTrait>(db: &C, source: &source::Model) -> Result<u64, DbErr> {
+    let sql = format!(r#"
+        INSERT INTO public.notification (user_id, kind, title, novel_id, source_id, group_id)
+        SELECT recipient.user_id, 'new_source', g.name || ' started translating ' || n.default_name, n.id, $2, g.id
+        FROM public.group g
+        JOIN public.group_follow recipient ON recipient.group_id = g.id
+        JOIN public.novel n ON n.id = $1
+        WHERE g.id = $3 AND {}"#, wanted("new_source"));
+    let result = db.execute_raw(Statement::from_sql_and_values(DbBackend::Postgres, sql, [
+        source.novel_id.into(), source.id.into(), source.group_id.into(),
+    ])).await?;
+    Ok(result.rows_affected())
+}
+
+/// Notifies the author of a review that someone commented on it, anywhere in its threads.
+pub async fn review_reply<C: ConnectionTrait>(db: &C, comment: &comment::Model) -> Result<u64, DbErr> {
+    let Some(review_id) = comment.review_id else { return Ok(0) };
+    let Some(review) = review::Entity::find_by_id(review_id).one(db).await? else { return Ok(0) };
+    let sql = format!(r#"
+        INSERT INTO public.notification (user_id, kind, title, novel_id, review_id, actor_id)
+        SELECT recipient.user_id, 'review_reply', COALESCE(actor.username, 'Someone') || ' replied to your review of ' || n.default_name, n.id, recipient.id, $2
+        FROM public.review recipient
+        JOIN public.novel n ON n.id = $1
+        LEFT JOIN public.user actor ON actor.id = $2
+        WHERE recipient.id = $3 AND recipient.user_id IS NOT NULL AND recipient.user_id IS DISTINCT FROM $2 AND {}"#, wanted("review_reply"));
+    let result = db.execute_raw(Statement::from_sql_and_values(DbBackend::Postgres, sql, [
+        review.novel_id.into(), comment.user_id.into(), review.id.into(),
+    ])).await?;
+    Ok(result.rows_affected())
+}
+
+async fn source_novel_id<C: ConnectionTrait>(db: &C, source_id: i32) -> Result<i32, DbErr> {
+    source::Entity::find_by_id(source_id)
+        .one(db)
+        .await?
+        .map(|source| source.novel_id)
+        .ok_or_else(|| DbErr::RecordNotFound(format!("source {source_id}")))
+}
+
+/// Notifications are a side effect; failing to create them must not fail the write that caused them.
+pub fn log_failure(result: Result<u64, DbErr>, event: &str) {
+    if let Err(e) = result {
+        warn!("failed to create {event} notifications: {e}");
+    }
+}
@@ -0,0 +1,94 @@
+use sea_orm::{ColumnTrait, Condition, ConnectionTrait, DbErr, EntityTrait, ExprTrait, QueryFilter, QuerySelect, sea_query::{Expr, Func, Query}};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use crate::models::{chapter, chapter_novel, novel, novel_tag, user_preference};
+use crate::services::auth::Principal;
+
+/// Query flags shared by listings that honour content preferences.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct ListOptions {
+    /// Skip the signed-in user's content preferences.
+    #[serde(default)]
+    pub unfiltered: bool,
+}
+
+/// Loads the preferences that apply to this request, if any.
+pub async fn for_request<C: ConnectionTrait>(db: &C, principal: Option<&Principal>, options: &ListOptions) -> Result<Option<user_preference::Model>, DbErr> {
+    let Some(user_id) = principal.and_then(|principal| principal.user_id) else { return Ok(None) };
+    if options.unfiltered {
+        return Ok(None);
+    }
+    user_preference::Entity::find()
+        .filter(user_preference::Column::UserId.eq(user_id))
+        .one(db)
+        .await
+}
+
+/// A novel with an unknown original language never matches a language filter and one with an
+/// unknown licensed flag counts as unlicensed. Every part is wrapped so it is never NULL, which
+/// keeps the outcome the same when `chapter_condition` negates it.
+pub fn novel_condition(preference: &user_preference::Model) -> Condition {
+    let mut condition = Condition::all();
+    if !preference.excluded_tag_ids.is_empty() {
+        condition = condition.add(novel::Column::Id.not_in_subquery(
+            Query::select()
+                .column(novel_tag::Column::NovelId)
+                .from(novel_tag::Entity)
+                .and_where(novel_tag::Column::TagId.is_in(preference.excluded_tag_ids.iter().copied()))
+                .to_owned(),
+        ));
+    }
+    if !preference.original_languages.is_empty() {
+        condition = condition.add(Expr::expr(Func::coalesce([
+            Expr::col((novel::Entity, novel::Column::OriginalLanguage)).is_in(preference.original_languages.iter().cloned()),
+            Expr::value(false),
+        ])));
+    }
+    if preference.hide_licensed {
+        condition = condition.add(
+            Func::coalesce([Expr::col((novel::Entity, novel::Column::Licensed)), Expr::value(false)]).eq(false),
+        );
+    }
+    condition
+}
+
+/// Chapters in an allowed language whose novels pass `novel_condition`. Chapters with an
+/// unknown language are left out once any language is chosen.
+pub fn chapter_condition(preference: &user_preference::Model) -> Condition {
+    let mut condition = Condition::all();
+    if !preference.chapter_languages.is_empty() {
+        condition = condition.add(Expr::expr(Func::coalesce([
+            Expr::col((chapter::Entity, chapter::Column::Language)).is_in(preference.chapter_languages.iter().cloned()),
+            Expr::value(false),
+        ])));
+    }
+    let novels = novel_condition(preference);
+    if !novels.is_empty() {
+        condition = condition.add(chapter::Column::Id.not_in_subquery(
+            Query::select()
+                .column(chapter_novel::Column::ChapterId)
+                .from(chapter_novel::Entity)
+                .inner_join(
+                    novel::Entity,
+                    Expr::col((novel::Entity, novel::Column::Id)).equals((chapter_novel::Entity, chapter_novel::Column::NovelId)),
+                )
+                .cond_where(novels.not())
+                .to_owned(),
+        ));
+    }
+    condition
+}
+
+/// The subset of `novel_ids` that passes `novel_condition`, for responses put together from
+/// several queries.
+pub async fn allowed_novel_ids<C: ConnectionTrait>(db: &C, preference: &user_preference::Model, novel_ids: Vec<i32>) -> Result<HashSet<i32>, DbErr> {
+    let ids: Vec<i32> = novel::Entity::find()
+        .select_only()
+        .column(novel::Column::Id)
+        .filter(novel::Column::Id.is_in(novel_ids))
+        .filter(novel_condition(preference))
+        .into_tuple()
+        .all(db)
+        .await?;
+    Ok(ids.into_iter().collect())
+}
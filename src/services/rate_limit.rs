@@ -0,0 +1,265 @@
+use axum::{Json, extract::{ConnectInfo, Request, State}, http::{HeaderMap, HeaderName, HeaderValue, StatusCode, header::RETRY_AFTER}, middleware::Next, response::{IntoResponse, Response}};
+use serde_json::json;
+use log::debug;
+use std::{collections::HashMap, env, net::{IpAddr, SocketAddr}, sync::{Arc, Mutex}, time::{Duration, Instant}};
+use crate::app_state::AppState;
+use crate::services::auth::{Action, Principal};
+
+const MAX_TRACKED_CLIENTS: usize = 10_000;
+/// Buckets refill completely within a minute, so one idle for that long can be forgotten.
+const IDLE_AFTER: Duration = Duration::from_secs(60);
+const SWEEP_INTERVAL_SECS: u64 = 60;
+
+#[derive(Clone, Debug)]
+pub struct RateLimitConfig {
+    pub enabled: bool,
+    pub reads_per_minute: u32,
+    pub writes_per_minute: u32,
+    pub trust_forwarded_for: bool,
+}
+
+impl RateLimitConfig {
+    /// Reads `RATE_LIMIT_ENABLED`, `RATE_LIMIT_READS_PER_MINUTE`, `RATE_LIMIT_WRITES_PER_MINUTE`
+    /// and `RATE_LIMIT_TRUST_FORWARDED_FOR`, falling back to the defaults below.
+    pub fn from_env() -> Self {
+        fn var<T: std::str::FromStr>(name: &str, default: T) -> T {
+            env::var(name).ok().and_then(|v| v.parse().ok()).unwrap_or(default)
+        }
+        Self {
+            enabled: var("RATE_LIMIT_ENABLED", true),
+            reads_per_minute: var("RATE_LIMIT_READS_PER_MINUTE", 300),
+            writes_per_minute: var("RATE_LIMIT_WRITES_PER_MINUTE", 60),
+            trust_forwarded_for: var("RATE_LIMIT_TRUST_FORWARDED_FOR", false),
+        }
+    }
+
+    fn budget(&self, action: Action) -> u32 {
+        match action {
+            Action::Read => self.reads_per_minute,
+            Action::Write => self.writes_per_minute,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum ClientKey {
+    User(i32),
+    ApiKey(i32),
+    Ip(IpAddr),
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Bucket {
+    tokens: f64,
+    /// Also when the client was last seen, as every check refills the bucket.
+    refilled_at: Instant,
+}
+
+/// Outcome of taking a token, used to fill the `RateLimit-*` headers.
+#[derive(Clone, Copy, Debug)]
+pub struct Decision {
+    pub allowed: bool,
+    pub limit: u32,
+    pub remaining: u32,
+    pub reset_secs: u64,
+    pub retry_after_secs: u64,
+}
+
+/// In-memory token buckets, one per client and action. Each bucket holds a minute's budget
+/// and refills continuously, so short bursts are allowed up to the per-minute limit.
+/// Idle buckets are dropped by `sweep_loop`; past `max_clients` the least recently seen go first.
+#[derive(Clone)]
+pub struct RateLimiter {
+    config: RateLimitConfig,
+    max_clients: usize,
+    buckets: Arc<Mutex<HashMap<(ClientKey, Action), Bucket>>>,
+}
+
+impl RateLimiter {
+    pub fn new(config: RateLimitConfig) -> Self {
+        Self::with_max_clients(config, MAX_TRACKED_CLIENTS)
+    }
+
+    fn with_max_clients(config: RateLimitConfig, max_clients: usize) -> Self {
+        Self { config, max_clients, buckets: Arc::new(Mutex::new(HashMap::new())) }
+    }
+
+    pub fn check(&self, client: ClientKey, action: Action) -> Decision {
+        let capacity = f64::from(self.config.budget(action));
+        let rate = capacity / 60.0;
+        let now = Instant::now();
+        let key = (client, action);
+        let mut buckets = self.buckets.lock().unwrap_or_else(|e| e.into_inner());
+        if buckets.len() >= self.max_clients && !buckets.contains_key(&key) {
+            // A tenth at a time, so a flood of new clients doesn't scan the map on every request
+            evict_least_recent(&mut buckets, (self.max_clients / 10).max(1));
+        }
+        let bucket = buckets.entry(key).or_insert(Bucket { tokens: capacity, refilled_at: now });
+        bucket.tokens = (bucket.tokens + now.duration_since(bucket.refilled_at).as_secs_f64() * rate).min(capacity);
+        bucket.refilled_at = now;
+        let allowed = bucket.tokens >= 1.0;
+        if allowed {
+            bucket.tokens -= 1.0;
+        }
+        let secs_until = |tokens: f64| if rate > 0.0 { (tokens.max(0.0) / rate).ceil() as u64 } else { 60 };
+        Decision {
+            allowed,
+            limit: self.config.budget(action),
+            remaining: bucket.tokens.floor() as u32,
+            reset_secs: secs_until(capacity - bucket.tokens),
+            retry_after_secs: secs_until(1.0 - bucket.tokens),
+        }
+    }
+
+    /// Drops buckets that have been idle long enough to be full again. Returns how many went.
+    pub fn sweep(&self) -> usize {
+        let now = Instant::now();
+        let mut buckets = self.buckets.lock().unwrap_or_else(|e| e.into_inner());
+        let before = buckets.len();
+        buckets.retain(|_, b| now.duration_since(b.refilled_at) < IDLE_AFTER);
+        before - buckets.len()
+    }
+}
+
+fn evict_least_recent(buckets: &mut HashMap<(ClientKey, Action), Bucket>, count: usize) {
+    let mut seen: Vec<Instant> = buckets.values().map(|b| b.refilled_at).collect();
+    let count = count.min(seen.len());
+    if count == 0 {
+        return;
+    }
+    let (_, cutoff, _) = seen.select_nth_unstable(count - 1);
+    let cutoff = *cutoff;
+    buckets.retain(|_, b| b.refilled_at > cutoff);
+}
+
+/// Runs `sweep` every minute for the lifetime of the server, keeping it out of the request path.
+pub async fn sweep_loop(limiter: RateLimiter) {
+    let mut interval = tokio::time::interval(Duration::from_secs(SWEEP_INTERVAL_SECS));
+    loop {
+        interval.tick().await;
+        let swept = limiter.sweep();
+        if swept > 0 {
+            debug!("Dropped {swept} idle rate limit bucket(s)");
+        }
+    }
+}
+
+fn client_key(request: &Request, trust_forwarded_for: bool) -> Option<ClientKey> {
+    if let Some(principal) = request.extensions().get::<Principal>() {
+        return Some(principal.user_id.map(ClientKey::User).unwrap_or(ClientKey::ApiKey(principal.key_id)));
+    }
+    ip_key(request, trust_forwarded_for)
+}
+
+fn ip_key(request: &Request, trust_forwarded_for: bool) -> Option<ClientKey> {
+    if trust_forwarded_for {
+        let forwarded = request.headers().get("x-forwarded-for")
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.split(',').next())
+            .and_then(|v| v.trim().parse().ok());
+        if let Some(ip) = forwarded {
+            return Some(ClientKey::Ip(ip));
+        }
+    }
+    request.extensions().get::<ConnectInfo<SocketAddr>>().map(|ConnectInfo(addr)| ClientKey::Ip(addr.ip()))
+}
+
+fn set_headers(headers: &mut HeaderMap, decision: &Decision) {
+    let pairs = [
+        ("ratelimit-limit", decision.limit as u64),
+        ("ratelimit-remaining", decision.remaining as u64),
+        ("ratelimit-reset", decision.reset_secs),
+    ];
+    for (name, value) in pairs {
+        headers.insert(HeaderName::from_static(name), HeaderValue::from(value));
+    }
+}
+
+fn rejection(decision: &Decision) -> Response {
+    let mut response = (StatusCode::TOO_MANY_REQUESTS, Json(json!({"error": "rate limit exceeded"}))).into_response();
+    set_headers(response.headers_mut(), decision);
+    response.headers_mut().insert(RETRY_AFTER, HeaderValue::from(decision.retry_after_secs));
+    response
+}
+
+/// Charges a request whose key `auth::authenticate` refused to the client's address, so guessing
+/// keys is throttled like anonymous traffic. Returns the response to send once that runs out.
+pub fn charge_failed_auth(state: &AppState, request: &Request) -> Option<Response> {
+    let limiter = &state.rate_limiter;
+    if !limiter.config.enabled {
+        return None;
+    }
+    let client = ip_key(request, limiter.config.trust_forwarded_for)?;
+    let decision = limiter.check(client, Action::for_method(request.method()));
+    (!decision.allowed).then(|| rejection(&decision))
+}
+
+/// Must be layered inside `auth::authenticate` so the principal is already known; requests with
+/// a refused key never get here and are charged by `charge_failed_auth` instead.
+pub async fn limit(State(state): State<AppState>, request: Request, next: Next) -> Response {
+    let limiter = &state.rate_limiter;
+    if !limiter.config.enabled {
+        return next.run(request).await;
+    }
+    let Some(client) = client_key(&request, limiter.config.trust_forwarded_for) else { return next.run(request).await };
+    let decision = limiter.check(client, Action::for_method(request.method()));
+    if !decision.allowed {
+        return rejection(&decision);
+    }
+    let mut response = next.run(request).await;
+    set_headers(response.headers_mut(), &decision);
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn limiter(max_clients: usize) -> RateLimiter {
+        let config = RateLimitConfig { enabled: true, reads_per_minute: 3, writes_per_minute: 1, trust_forwarded_for: false };
+        RateLimiter::with_max_clients(config, max_clients)
+    }
+
+    fn ip(last: u8) -> ClientKey {
+        ClientKey::Ip(IpAddr::from([10, 0, 0, last]))
+    }
+
+    fn tracked(limiter: &RateLimiter, client: ClientKey) -> bool {
+        limiter.buckets.lock().unwrap().contains_key(&(client, Action::Read))
+    }
+
+    #[test]
+    fn full_map_evicts_the_least_recently_seen() {
+        let limiter = limiter(10);
+        for last in 0..10 {
+            limiter.check(ip(last), Action::Read);
+            std::thread::sleep(Duration::from_millis(1));
+        }
+        limiter.check(ip(0), Action::Read);
+        limiter.check(ip(10), Action::Read);
+        assert!(tracked(&limiter, ip(0)));
+        assert!(!tracked(&limiter, ip(1)));
+        assert!(tracked(&limiter, ip(10)));
+        assert_eq!(limiter.buckets.lock().unwrap().len(), 10);
+    }
+
+    #[test]
+    fn known_clients_never_trigger_an_eviction() {
+        let limiter = limiter(2);
+        assert!(limiter.check(ip(0), Action::Write).allowed);
+        assert!(!limiter.check(ip(0), Action::Write).allowed);
+        limiter.check(ip(1), Action::Read);
+        assert!(!limiter.check(ip(0), Action::Write).allowed);
+    }
+
+    #[test]
+    fn sweep_drops_only_idle_buckets() {
+        let limiter = limiter(10);
+        limiter.check(ip(0), Action::Read);
+        limiter.check(ip(1), Action::Read);
+        limiter.buckets.lock().unwrap().get_mut(&(ip(1), Action::Read)).unwrap().refilled_at -= IDLE_AFTER;
+        assert_eq!(limiter.sweep(), 1);
+        assert!(tracked(&limiter, ip(0)));
+        assert!(!tracked(&limiter, ip(1)));
+    }
+}
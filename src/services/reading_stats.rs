@@ -0,0 +1,252 @@
+use chrono::{Datelike, NaiveDate};
+use sea_orm::{ConnectionTrait, DbBackend, DbErr, FromQueryResult, Statement};
+use sea_orm::prelude::{Date, DateTime};
+use serde::Serialize;
+
+const TOP_LIMIT: i64 = 10;
+
+/// Which reads and status changes count. `None` bounds mean all time.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Period {
+    pub from: Option<DateTime>,
+    pub until: Option<DateTime>,
+}
+
+impl Period {
+    /// January 1st of `year` up to, but not including, January 1st of the next year.
+    pub fn year(year: i32) -> Option<Self> {
+        let start = |year| NaiveDate::from_ymd_opt(year, 1, 1).and_then(|day| day.and_hms_opt(0, 0, 0));
+        Some(Self { from: Some(start(year)?), until: Some(start(year + 1)?) })
+    }
+}
+
+#[derive(Clone, Debug, Default, Serialize, FromQueryResult)]
+pub struct DayCount {
+    pub day: Date,
+    pub chapters: i64,
+}
+
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct MonthCount {
+    /// First day of the month.
+    pub month: Date,
+    pub chapters: i64,
+}
+
+#[derive(Clone, Debug, Default, Serialize, FromQueryResult)]
+pub struct TopEntry {
+    pub name: String,
+    pub novels: i64,
+}
+
+#[derive(Clone, Debug, Default, FromQueryResult)]
+struct Totals {
+    chapters_read: i64,
+    novels_completed: i64,
+    novels_dropped: i64,
+    average_rating: Option<f64>,
+}
+
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct Streaks {
+    /// Consecutive days with a read chapter, ending today or yesterday.
+    pub current: i64,
+    pub longest: i64,
+    pub longest_start: Option<Date>,
+}
+
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct ReadingStats {
+    pub chapters_read: i64,
+    pub novels_completed: i64,
+    pub novels_dropped: i64,
+    /// Mean of the numeric personal ratings; ratings that are not numbers are ignored.
+    pub average_rating: Option<f64>,
+    /// Only days with at least one read chapter, oldest first.
+    pub per_day: Vec<DayCount>,
+    pub per_month: Vec<MonthCount>,
+    pub top_tags: Vec<TopEntry>,
+    pub top_languages: Vec<TopEntry>,
+    pub streaks: Streaks,
+}
+
+/// Reads of the user's chapters inside the period; `$1` is the user, `$2`/`$3` the bounds.
+const READS: &str = r#"
+    SELECT r.read_at, le.novel_id
+    FROM public.library_chapter_read r
+    JOIN public.library_entry le ON le.id = r.library_entry_id
+    WHERE le.user_id = $1
+        AND ($2::timestamp IS NULL OR r.read_at >= $2)
+        AND ($3::timestamp IS NULL OR r.read_at < $3)"#;
+
+/// Novels the user read in the period or finished in it; the base for the top tags and languages.
+const NOVELS_READ: &str = r#"
+    SELECT le.novel_id
+    FROM public.library_entry le
+    WHERE le.user_id = $1
+        AND (
+            EXISTS (
+                SELECT 1 FROM public.library_chapter_read r
+                WHERE r.library_entry_id = le.id
+                    AND ($2::timestamp IS NULL OR r.read_at >= $2)
+                    AND ($3::timestamp IS NULL OR r.read_at < $3)
+            )
+            OR (le.completed_at IS NOT NULL
+                AND ($2::timestamp IS NULL OR le.completed_at >= $2)
+                AND ($3::timestamp IS NULL OR le.completed_at < $3))
+        )"#;
+
+fn statement(sql: String, user_id: i32, period: Period) -> Statement {
+    Statement::from_sql_and_values(DbBackend::Postgres, sql, [user_id.into(), period.from.into(), period.until.into()])
+}
+
+/// Completions and drops are dated by when the entry last got that status, so a novel counts in
+/// the period it was finished even if it is being re-read now. Ratings are averaged over the
+/// entries updated in the period.
+async fn totals<C: ConnectionTrait>(db: &C, user_id: i32, period: Period) -> Result<Totals, DbErr> {
+    let sql = format!(r#"
+        WITH reads AS ({READS}),
+        entries AS (
+            SELECT
+                le.personal_rating,
+                ($2::timestamp IS NULL OR le.last_updated >= $2) AND ($3::timestamp IS NULL OR le.last_updated < $3) AS updated,
+                le.completed_at IS NOT NULL AND ($2::timestamp IS NULL OR le.completed_at >= $2) AND ($3::timestamp IS NULL OR le.completed_at < $3) AS completed,
+                le.dropped_at IS NOT NULL AND ($2::timestamp IS NULL OR le.dropped_at >= $2) AND ($3::timestamp IS NULL OR le.dropped_at < $3) AS dropped
+            FROM public.library_entry le
+            WHERE le.user_id = $1
+        )
+        SELECT
+            (SELECT COUNT(*) FROM reads) AS chapters_read,
+            (SELECT COUNT(*) FROM entries WHERE completed) AS novels_completed,
+            (SELECT COUNT(*) FROM entries WHERE dropped) AS novels_dropped,
+            (SELECT AVG(substring(personal_rating FROM '^\s*([0-9]+(?:\.[0-9]+)?)')::float8) FROM entries WHERE updated) AS average_rating"#);
+    Ok(Totals::find_by_statement(statement(sql, user_id, period)).one(db).await?.unwrap_or_default())
+}
+
+async fn per_day<C: ConnectionTrait>(db: &C, user_id: i32, period: Period) -> Result<Vec<DayCount>, DbErr> {
+    let sql = format!(r#"
+        SELECT read_at::date AS day, COUNT(*) AS chapters
+        FROM ({READS}) reads
+        GROUP BY 1
+        ORDER BY 1"#);
+    DayCount::find_by_statement(statement(sql, user_id, period)).all(db).await
+}
+
+async fn top_tags<C: ConnectionTrait>(db: &C, user_id: i32, period: Period) -> Result<Vec<TopEntry>, DbErr> {
+    let sql = format!(r#"
+        SELECT t.name, COUNT(*) AS novels
+        FROM ({NOVELS_READ}) read_novels
+        JOIN public.novel_tag nt ON nt.novel_id = read_novels.novel_id
+        JOIN public.tag t ON t.id = nt.tag_id
+        GROUP BY t.id, t.name
+        ORDER BY 2 DESC, 1
+        LIMIT {TOP_LIMIT}"#);
+    TopEntry::find_by_statement(statement(sql, user_id, period)).all(db).await
+}
+
+async fn top_languages<C: ConnectionTrait>(db: &C, user_id: i32, period: Period) -> Result<Vec<TopEntry>, DbErr> {
+    let sql = format!(r#"
+        SELECT n.original_language AS name, COUNT(*) AS novels
+        FROM ({NOVELS_READ}) read_novels
+        JOIN public.novel n ON n.id = read_novels.novel_id
+        WHERE n.original_language IS NOT NULL
+        GROUP BY 1
+        ORDER BY 2 DESC, 1
+        LIMIT {TOP_LIMIT}"#);
+    TopEntry::find_by_statement(statement(sql, user_id, period)).all(db).await
+}
+
+fn per_month(days: &[DayCount]) -> Vec<MonthCount> {
+    let mut months: Vec<MonthCount> = Vec::new();
+    for day in days {
+        let month = day.day.with_day(1).unwrap_or(day.day);
+        match months.last_mut() {
+            Some(last) if last.month == month => last.chapters += day.chapters,
+            _ => months.push(MonthCount { month, chapters: day.chapters }),
+        }
+    }
+    months
+}
+
+/// `days` must be sorted and free of duplicates.
+fn streaks(days: &[Date], today: Date) -> Streaks {
+    let mut result = Streaks::default();
+    let mut start = None;
+    let mut length = 0;
+    let mut previous: Option<Date> = None;
+    for &day in days {
+        if previous.and_then(|previous| previous.succ_opt()) == Some(day) {
+            length += 1;
+        } else {
+            start = Some(day);
+            length = 1;
+        }
+        if length > result.longest {
+            result.longest = length;
+            result.longest_start = start;
+        }
+        previous = Some(day);
+    }
+    if previous.is_some_and(|last| last == today || last.succ_opt() == Some(today)) {
+        result.current = length;
+    }
+    result
+}
+
+pub async fn for_user<C: ConnectionTrait>(db: &C, user_id: i32, period: Period) -> Result<ReadingStats, DbErr> {
+    let totals = totals(db, user_id, period).await?;
+    let per_day = per_day(db, user_id, period).await?;
+    let days: Vec<Date> = per_day.iter().map(|count| count.day).collect();
+    Ok(ReadingStats {
+        chapters_read: totals.chapters_read,
+        novels_completed: totals.novels_completed,
+        novels_dropped: totals.novels_dropped,
+        average_rating: totals.average_rating.map(|rating| (rating * 100.0).round() / 100.0),
+        per_month: per_month(&per_day),
+        top_tags: top_tags(db, user_id, period).await?,
+        top_languages: top_languages(db, user_id, period).await?,
+        streaks: streaks(&days, chrono::Utc::now().date_naive()),
+        per_day,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(year: i32, month: u32, day: u32) -> Date {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    #[test]
+    fn streaks_of_no_days_are_empty() {
+        let result = streaks(&[], date(2025, 3, 10));
+        assert_eq!((result.current, result.longest, result.longest_start), (0, 0, None));
+    }
+
+    #[test]
+    fn longest_streak_keeps_the_first_of_equal_runs() {
+        let days = [date(2025, 1, 1), date(2025, 1, 2), date(2025, 1, 5), date(2025, 1, 6), date(2025, 1, 9)];
+        let result = streaks(&days, date(2025, 3, 10));
+        assert_eq!((result.current, result.longest, result.longest_start), (0, 2, Some(date(2025, 1, 1))));
+    }
+
+    #[test]
+    fn current_streak_ends_today_or_yesterday() {
+        let days = [date(2025, 2, 27), date(2025, 2, 28), date(2025, 3, 1)];
+        assert_eq!(streaks(&days, date(2025, 3, 1)).current, 3);
+        assert_eq!(streaks(&days, date(2025, 3, 2)).current, 3);
+        assert_eq!(streaks(&days, date(2025, 3, 3)).current, 0);
+    }
+
+    #[test]
+    fn per_month_sums_days_of_the_same_month() {
+        let days = [
+            DayCount { day: date(2024, 12, 31), chapters: 2 },
+            DayCount { day: date(2025, 1, 1), chapters: 3 },
+            DayCount { day: date(2025, 1, 20), chapters: 4 },
+        ];
+        let months: Vec<(Date, i64)> = per_month(&days).into_iter().map(|count| (count.month, count.chapters)).collect();
+        assert_eq!(months, vec![(date(2024, 12, 1), 2), (date(2025, 1, 1), 7)]);
+    }
+}
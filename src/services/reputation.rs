@@ -0,0 +1,80 @@
+use sea_orm::{ConnectionTrait, DbBackend, DbErr, FromQueryResult, Statement};
+use serde::{Deserialize, Serialize};
+use std::env;
+use crate::models::user::{self, Role};
+
+/// Points per helpful vote on one of the user's visible reviews. Unhelpful votes cost nothing;
+/// disagreeing with a review is not misconduct.
+const HELPFUL_VOTE_POINTS: i64 = 1;
+/// Points lost per moderation action against the user's content, such as a hidden review.
+const MODERATION_PENALTY: i64 = 20;
+const BAN_PENALTY: i64 = 100;
+
+/// Something a plain user earns with reputation. Moderators and admins have all of them. Only
+/// abilities something checks belong here; editing without review waits for an edit queue.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Ability {
+    ProposeTags,
+}
+
+impl Ability {
+    const ALL: [Ability; 1] = [Ability::ProposeTags];
+
+    /// The score needed, from `REPUTATION_PROPOSE_TAGS`.
+    pub fn threshold(self) -> i64 {
+        let (var, default) = match self {
+            Ability::ProposeTags => ("REPUTATION_PROPOSE_TAGS", 10),
+        };
+        env::var(var).ok().and_then(|v| v.parse().ok()).unwrap_or(default)
+    }
+}
+
+#[derive(Clone, Debug, Default, FromQueryResult)]
+struct Tally {
+    helpful_votes: i64,
+    moderation_actions: i64,
+    bans: i64,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct Reputation {
+    pub score: i64,
+    pub helpful_votes: i64,
+    /// Points lost to moderation, as a positive number.
+    pub penalties: i64,
+    pub abilities: Vec<Ability>,
+}
+
+/// The score is computed on read so votes and moderation count immediately. The tree has no
+/// moderated metadata edits or release submissions yet; they belong in here once it does.
+pub async fn for_user<C: ConnectionTrait>(db: &C, user: &user::Model) -> Result<Reputation, DbErr> {
+    let tally = Tally::find_by_statement(Statement::from_sql_and_values(DbBackend::Postgres, r#"
+        SELECT
+            COALESCE((
+                SELECT SUM(r.helpful_count) FROM public.review r
+                WHERE r.user_id = $1 AND r.hidden_at IS NULL
+            ), 0)::bigint AS helpful_votes,
+            (SELECT COUNT(*) FROM public.moderation_log l WHERE l.offender_id = $1 AND l.action NOT IN ('ban_user', 'dismiss')) AS moderation_actions,
+            (SELECT COUNT(*) FROM public.moderation_log l WHERE l.offender_id = $1 AND l.action = 'ban_user') AS bans"#,
+        [user.id.into()]))
+        .one(db)
+        .await?
+        .unwrap_or_default();
+    let penalties = tally.moderation_actions * MODERATION_PENALTY + tally.bans * BAN_PENALTY;
+    let score = tally.helpful_votes * HELPFUL_VOTE_POINTS - penalties;
+    Ok(Reputation { score, helpful_votes: tally.helpful_votes, penalties, abilities: abilities(user.role, score) })
+}
+
+pub fn abilities(role: Role, score: i64) -> Vec<Ability> {
+    Ability::ALL.into_iter()
+        .filter(|ability| role != Role::User || score >= ability.threshold())
+        .collect()
+}
+
+pub async fn has_ability<C: ConnectionTrait>(db: &C, user: &user::Model, ability: Ability) -> Result<bool, DbErr> {
+    if user.role != Role::User {
+        return Ok(true);
+    }
+    Ok(for_user(db, user).await?.abilities.contains(&ability))
+}
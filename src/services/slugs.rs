@@ -0,0 +1,257 @@
+use axum::{Json, http::{StatusCode, Uri}, response::{IntoResponse, Redirect, Response}};
+use deunicode::deunicode;
+use log::info;
+use sea_orm::{ConnectionTrait, DbBackend, DbErr, FromQueryResult, SqlErr, Statement};
+use serde_json::json;
+use std::collections::HashSet;
+
+/// Longest generated slug, before any collision suffix.
+const MAX_LEN: usize = 80;
+/// Used when a name has nothing that survives transliteration, such as a row of emoji.
+const FALLBACK: &str = "untitled";
+/// Tries at taking a free slug, for when a concurrent write takes it first.
+const ATTEMPTS: usize = 3;
+
+/// The resources that get a slug from their name.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Resource {
+    Artist,
+    Author,
+    Group,
+    Novel,
+    Publisher,
+    Tag,
+}
+
+impl Resource {
+    const ALL: [Resource; 6] = [Resource::Artist, Resource::Author, Resource::Group, Resource::Novel, Resource::Publisher, Resource::Tag];
+
+    /// The table, which is also what `slug_history.resource` stores.
+    pub fn table(self) -> &'static str {
+        match self {
+            Resource::Artist => "artist",
+            Resource::Author => "author",
+            Resource::Group => "group",
+            Resource::Novel => "novel",
+            Resource::Publisher => "publisher",
+            Resource::Tag => "tag",
+        }
+    }
+
+    fn name_column(self) -> &'static str {
+        match self {
+            Resource::Novel => "default_name",
+            _ => "name",
+        }
+    }
+}
+
+/// Where a slug points.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Found {
+    Current(i32),
+    /// An old slug; the row now lives at this one.
+    Moved(String),
+}
+
+#[derive(Clone, Debug, FromQueryResult)]
+struct Slug {
+    slug: String,
+}
+
+#[derive(Clone, Debug, FromQueryResult)]
+struct Row {
+    id: i32,
+    slug: Option<String>,
+}
+
+#[derive(Clone, Debug, FromQueryResult)]
+struct Unslugged {
+    id: i32,
+    source: Option<String>,
+}
+
+/// Lowercase ASCII words joined by hyphens, e.g. "Martial Peak" and "武炼巅峰" become
+/// `martial-peak` and `wu-lian-dian-feng`.
+pub fn slugify(name: &str) -> String {
+    let mut slug = String::new();
+    for c in deunicode(name).to_lowercase().chars() {
+        if c.is_ascii_alphanumeric() {
+            slug.push(c);
+        } else if !slug.is_empty() && !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+    slug.truncate(MAX_LEN);
+    let slug = slug.trim_end_matches('-');
+    if slug.is_empty() { FALLBACK.to_string() } else { slug.to_string() }
+}
+
+/// Whether `slug` is `base` or `base` with a collision suffix.
+fn fits(slug: &str, base: &str) -> bool {
+    match slug.strip_prefix(base) {
+        Some("") => true,
+        Some(rest) => rest.strip_prefix('-').is_some_and(|n| !n.is_empty() && n.bytes().all(|b| b.is_ascii_digit())),
+        None => false,
+    }
+}
+
+/// `base`, or `base-2`, `base-3` and so on, skipping slugs other rows use now or used to.
+async fn unique<C: ConnectionTrait>(db: &C, resource: Resource, base: &str, id: Option<i32>) -> Result<String, DbErr> {
+    let taken: HashSet<String> = Slug::find_by_statement(Statement::from_sql_and_values(DbBackend::Postgres, format!(r#"
+        SELECT slug FROM public.{table} WHERE id IS DISTINCT FROM $2 AND (slug = $1 OR slug LIKE $1 || '-%')
+        UNION
+        SELECT slug FROM public.slug_history WHERE resource = $3 AND target_id IS DISTINCT FROM $2 AND (slug = $1 OR slug LIKE $1 || '-%')"#,
+        table = resource.table()),
+        [base.into(), id.into(), resource.table().into()]))
+        .all(db)
+        .await?
+        .into_iter()
+        .map(|row| row.slug)
+        .collect();
+    if !taken.contains(base) {
+        return Ok(base.to_string());
+    }
+    Ok((2..).map(|n| format!("{base}-{n}")).find(|slug| !taken.contains(slug)).unwrap_or_default())
+}
+
+/// Whether a write failed because another row holds the slug it meant to take.
+fn taken_meanwhile(error: &DbErr) -> bool {
+    matches!(error.sql_err(), Some(SqlErr::UniqueConstraintViolation(message)) if message.contains("slug"))
+}
+
+/// Runs `insert` with a free slug from `source` for a row that does not exist yet. Finding a
+/// free slug and taking it are separate statements, so when a concurrent insert took it in
+/// between, a new one is found and `insert` runs again. Must not run inside a transaction, which
+/// the failed statement would abort.
+pub async fn insert_with<C, T, F, Fut>(db: &C, resource: Resource, source: &str, mut insert: F) -> Result<T, DbErr>
+where
+    C: ConnectionTrait,
+    F: FnMut(String) -> Fut,
+    Fut: Future<Output = Result<T, DbErr>>,
+{
+    let base = slugify(source);
+    let mut attempt = 1;
+    loop {
+        let slug = unique(db, resource, &base, None).await?;
+        match insert(slug).await {
+            Err(error) if attempt < ATTEMPTS && taken_meanwhile(&error) => attempt += 1,
+            result => return result,
+        }
+    }
+}
+
+/// Points an old slug at a row, taking it over from whatever it pointed at before.
+pub async fn redirect<C: ConnectionTrait>(db: &C, resource: Resource, slug: &str, id: i32) -> Result<(), DbErr> {
+    db.execute_raw(Statement::from_sql_and_values(DbBackend::Postgres, r#"
+        INSERT INTO public.slug_history (resource, slug, target_id) VALUES ($1, $2, $3)
+        ON CONFLICT (resource, slug) DO UPDATE SET target_id = EXCLUDED.target_id, created_at = CURRENT_TIMESTAMP"#,
+        [resource.table().into(), slug.into(), id.into()],
+    )).await?;
+    Ok(())
+}
+
+/// Gives an existing row a slug from `source`, its name or a slug the client asked for. The
+/// current slug stays while it still fits; otherwise it moves to the history so old links keep
+/// working. Returns the row's slug. Like `insert_with`, it tries another slug when a concurrent
+/// write took the free one first.
+pub async fn assign<C: ConnectionTrait>(db: &C, resource: Resource, id: i32, source: &str) -> Result<String, DbErr> {
+    let base = slugify(source);
+    let current = Row::find_by_statement(Statement::from_sql_and_values(DbBackend::Postgres,
+        format!("SELECT id, slug FROM public.{} WHERE id = $1", resource.table()),
+        [id.into()]))
+        .one(db)
+        .await?
+        .and_then(|row| row.slug);
+    if let Some(current) = &current && fits(current, &base) {
+        return Ok(current.clone());
+    }
+    let mut attempt = 1;
+    let slug = loop {
+        let slug = unique(db, resource, &base, Some(id)).await?;
+        let updated = db.execute_raw(Statement::from_sql_and_values(DbBackend::Postgres,
+            format!("UPDATE public.{} SET slug = $1 WHERE id = $2", resource.table()),
+            [slug.clone().into(), id.into()],
+        )).await;
+        match updated {
+            Ok(_) => break slug,
+            Err(error) if attempt < ATTEMPTS && taken_meanwhile(&error) => attempt += 1,
+            Err(error) => return Err(error),
+        }
+    };
+    if let Some(current) = &current {
+        redirect(db, resource, current, id).await?;
+    }
+    db.execute_raw(Statement::from_sql_and_values(DbBackend::Postgres,
+        "DELETE FROM public.slug_history WHERE resource = $1 AND slug = $2",
+        [resource.table().into(), slug.clone().into()],
+    )).await?;
+    Ok(slug)
+}
+
+/// Looks a slug up among current slugs, then among old ones. Ignores case.
+pub async fn find<C: ConnectionTrait>(db: &C, resource: Resource, slug: &str) -> Result<Option<Found>, DbErr> {
+    let slug = slug.to_lowercase();
+    let current = Row::find_by_statement(Statement::from_sql_and_values(DbBackend::Postgres,
+        format!("SELECT id, slug FROM public.{} WHERE slug = $1", resource.table()),
+        [slug.clone().into()]))
+        .one(db)
+        .await?;
+    if let Some(row) = current {
+        return Ok(Some(Found::Current(row.id)));
+    }
+    let moved = Row::find_by_statement(Statement::from_sql_and_values(DbBackend::Postgres, format!(r#"
+        SELECT t.id, t.slug FROM public.slug_history h
+        JOIN public.{} t ON t.id = h.target_id
+        WHERE h.resource = $1 AND h.slug = $2 AND t.slug IS NOT NULL"#, resource.table()),
+        [resource.table().into(), slug.into()]))
+        .one(db)
+        .await?;
+    Ok(moved.and_then(|row| row.slug).map(Found::Moved))
+}
+
+/// Answers `GET /{resource}/by-slug/{slug}` with `read` for the row the slug names. Old slugs
+/// redirect to the current one, keeping the query string.
+pub async fn serve<C, F, Fut, R>(db: &C, resource: Resource, slug: &str, uri: &Uri, read: F) -> Result<Response, (StatusCode, Json<serde_json::Value>)>
+where
+    C: ConnectionTrait,
+    F: FnOnce(i32) -> Fut,
+    Fut: Future<Output = Result<R, (StatusCode, Json<serde_json::Value>)>>,
+    R: IntoResponse,
+{
+    let found = find(db, resource, slug)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"error": e.to_string()}))))?;
+    match found {
+        Some(Found::Current(id)) => Ok(read(id).await?.into_response()),
+        Some(Found::Moved(current)) => {
+            let location = match uri.query() {
+                Some(query) => format!("{current}?{query}"),
+                None => current,
+            };
+            Ok(Redirect::permanent(&location).into_response())
+        }
+        None => Err((StatusCode::NOT_FOUND, Json(json!({"error": "not found"})))),
+    }
+}
+
+/// Slugs rows created before slugs existed. Runs at startup and only touches rows without one.
+pub async fn backfill<C: ConnectionTrait>(db: &C) -> Result<u64, DbErr> {
+    let mut count = 0;
+    for resource in Resource::ALL {
+        let rows = Unslugged::find_by_statement(Statement::from_string(DbBackend::Postgres, format!(
+            "SELECT id, {} AS source FROM public.{} WHERE slug IS NULL ORDER BY id",
+            resource.name_column(), resource.table()
+        )))
+        .all(db)
+        .await?;
+        for row in rows {
+            assign(db, resource, row.id, row.source.as_deref().unwrap_or_default()).await?;
+            count += 1;
+        }
+    }
+    if count > 0 {
+        info!("🔗 Generated slugs for {count} existing row(s)");
+    }
+    Ok(count)
+}
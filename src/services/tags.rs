@@ -0,0 +1,83 @@
+use sea_orm::{ConnectionTrait, DbBackend, DbErr, EntityTrait, ExprTrait, FromQueryResult, QueryFilter, Statement, Value, sea_query::{Expr, Func}};
+use crate::models::{tag, tag_alias};
+use super::slugs::{self, Resource};
+
+#[derive(Clone, Debug, FromQueryResult)]
+struct TagId {
+    id: i32,
+}
+
+/// The tag and everything below it, at any depth. `UNION` keeps a cycle from recursing forever.
+pub async fn with_descendants<C: ConnectionTrait>(db: &C, tag_id: i32) -> Result<Vec<i32>, DbErr> {
+    let rows = TagId::find_by_statement(Statement::from_sql_and_values(DbBackend::Postgres, r#"
+        WITH RECURSIVE tree AS (
+            SELECT id FROM public.tag WHERE id = $1
+            UNION
+            SELECT t.id FROM public.tag t JOIN tree ON t.parent_id = tree.id
+        )
+        SELECT id FROM tree"#, [tag_id.into()]))
+        .all(db)
+        .await?;
+    Ok(rows.into_iter().map(|row| row.id).collect())
+}
+
+/// Finds the canonical tag for a name, slug or alias, ignoring case.
+pub async fn resolve<C: ConnectionTrait>(db: &C, name: &str) -> Result<Option<tag::Model>, DbErr> {
+    let name = name.trim().to_lowercase();
+    let direct = tag::Entity::find()
+        .filter(
+            Expr::expr(Func::lower(Expr::col(tag::Column::Name))).eq(name.as_str())
+                .or(Expr::expr(Func::lower(Expr::col(tag::Column::Slug))).eq(name.as_str())),
+        )
+        .one(db)
+        .await?;
+    if direct.is_some() {
+        return Ok(direct);
+    }
+    let Some(alias) = tag_alias::Entity::find()
+        .filter(Expr::expr(Func::lower(Expr::col(tag_alias::Column::Name))).eq(name.as_str()))
+        .one(db)
+        .await? else { return Ok(None) };
+    tag::Entity::find_by_id(alias.tag_id).one(db).await
+}
+
+/// Whether a name already points at a tag, as its name, slug or one of its aliases.
+pub async fn name_taken<C: ConnectionTrait>(db: &C, name: &str) -> Result<bool, DbErr> {
+    Ok(resolve(db, name).await?.is_some())
+}
+
+/// Folds `source` into `target`: novels, votes, children, aliases, old slugs and excluded-tag
+/// preferences move over, the source is deleted and its name and slug then lead to the target.
+/// A novel tagged with both keeps the target's row and gains the source's votes from users who
+/// had not voted on the target. `target` must not sit below `source`. Run it inside a
+/// transaction.
+pub async fn merge<C: ConnectionTrait>(db: &C, source: &tag::Model, target: &tag::Model) -> Result<(), DbErr> {
+    let statements: [(&str, Vec<Value>); 6] = [
+        (r#"
+            INSERT INTO public.novel_tag (novel_id, tag_id, created_at, proposed_by)
+            SELECT novel_id, $2, created_at, proposed_by FROM public.novel_tag WHERE tag_id = $1
+            ON CONFLICT (novel_id, tag_id) DO NOTHING"#, vec![source.id.into(), target.id.into()]),
+        (r#"
+            INSERT INTO public.novel_tag_vote (user_id, novel_id, tag_id, up, created_at)
+            SELECT user_id, novel_id, $2, up, created_at FROM public.novel_tag_vote WHERE tag_id = $1
+            ON CONFLICT (user_id, novel_id, tag_id) DO NOTHING"#, vec![source.id.into(), target.id.into()]),
+        ("UPDATE public.tag SET parent_id = $2 WHERE parent_id = $1", vec![source.id.into(), target.id.into()]),
+        ("UPDATE public.tag_alias SET tag_id = $2 WHERE tag_id = $1", vec![source.id.into(), target.id.into()]),
+        ("UPDATE public.slug_history SET target_id = $2 WHERE resource = 'tag' AND target_id = $1", vec![source.id.into(), target.id.into()]),
+        (r#"
+            UPDATE public.user_preference SET excluded_tag_ids = ARRAY(SELECT DISTINCT unnest(array_replace(excluded_tag_ids, $1, $2)))
+            WHERE $1 = ANY(excluded_tag_ids)"#, vec![source.id.into(), target.id.into()]),
+    ];
+    for (sql, values) in statements {
+        db.execute_raw(Statement::from_sql_and_values(DbBackend::Postgres, sql, values)).await?;
+    }
+    tag::Entity::delete_by_id(source.id).exec(db).await?;
+    slugs::redirect(db, Resource::Tag, &source.slug, target.id).await?;
+    if !name_taken(db, &source.name).await? {
+        db.execute_raw(Statement::from_sql_and_values(DbBackend::Postgres,
+            "INSERT INTO public.tag_alias (tag_id, name) VALUES ($1, $2)",
+            [target.id.into(), source.name.clone().into()],
+        )).await?;
+    }
+    Ok(())
+}
@@ -0,0 +1,78 @@
+use data_encoding::BASE32_NOPAD;
+use hmac::{Hmac, Mac};
+use sha1::Sha1;
+use sea_orm::{ConnectionTrait, DbErr, EntityTrait};
+use crate::models::{two_factor_policy, user::Role};
+
+pub const ISSUER: &str = "NovelUpdates";
+const DIGITS: u32 = 6;
+const PERIOD_SECS: i64 = 30;
+/// Codes from one step before or after the current one are accepted to absorb clock drift.
+const SKEW_STEPS: i64 = 1;
+pub const RECOVERY_CODE_COUNT: usize = 10;
+
+pub fn generate_secret() -> String {
+    let bytes: [u8; 20] = rand::random();
+    BASE32_NOPAD.encode(&bytes)
+}
+
+fn percent_encode(value: &str) -> String {
+    value.bytes().map(|b| match b {
+        b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => (b as char).to_string(),
+        _ => format!("%{:02X}", b),
+    }).collect()
+}
+
+/// The `otpauth://` URI authenticator apps read from a QR code.
+pub fn provisioning_uri(secret: &str, account: &str) -> String {
+    format!(
+        "otpauth://totp/{issuer}:{account}?secret={secret}&issuer={issuer}&algorithm=SHA1&digits={DIGITS}&period={PERIOD_SECS}",
+        issuer = percent_encode(ISSUER),
+        account = percent_encode(account),
+    )
+}
+
+fn hotp(key: &[u8], counter: i64) -> u32 {
+    let mut mac = Hmac::<Sha1>::new_from_slice(key).expect("hmac accepts keys of any length");
+    mac.update(&counter.to_be_bytes());
+    let digest = mac.finalize().into_bytes();
+    let offset = (digest[digest.len() - 1] & 0x0f) as usize;
+    let binary = u32::from_be_bytes([digest[offset] & 0x7f, digest[offset + 1], digest[offset + 2], digest[offset + 3]]);
+    binary % 10u32.pow(DIGITS)
+}
+
+/// Checks `code` against the steps around `now` and returns the matching step. Steps at or
+/// before `last_step` are refused so an observed code cannot be replayed.
+pub fn verify(secret: &str, code: &str, now: i64, last_step: Option<i64>) -> Option<i64> {
+    let code = code.trim().replace(' ', "");
+    if code.len() != DIGITS as usize {
+        return None;
+    }
+    let code: u32 = code.parse().ok()?;
+    let key = BASE32_NOPAD.decode(secret.as_bytes()).ok()?;
+    let current = now / PERIOD_SECS;
+    (current - SKEW_STEPS..=current + SKEW_STEPS)
+        .filter(|step| last_step.is_none_or(|last| *step > last))
+        .find(|step| hotp(&key, *step) == code)
+}
+
+/// Plaintext recovery codes, shown to the user once and stored hashed.
+pub fn generate_recovery_codes() -> Vec<String> {
+    (0..RECOVERY_CODE_COUNT)
+        .map(|_| {
+            let code = hex::encode(rand::random::<[u8; 5]>());
+            format!("{}-{}", &code[..5], &code[5..])
+        })
+        .collect()
+}
+
+pub fn normalize_recovery_code(code: &str) -> String {
+    code.trim().to_lowercase()
+}
+
+/// Whether `role` must have two-factor authentication before it gets a usable session, from
+/// the `two_factor_policy` table admins edit.
+pub async fn required_for<C: ConnectionTrait>(db: &C, role: Role) -> Result<bool, DbErr> {
+    let policy = two_factor_policy::Entity::find_by_id(role).one(db).await?;
+    Ok(policy.is_some_and(|policy| policy.required))
+}
@@ -0,0 +1,256 @@
+mod common;
+
+use common::TestContext;
+use serde_json::json;
+use serial_test::serial;
+
+#[tokio::test]
+#[serial]
+async fn test_registration_and_email_verification() {
+    let ctx = TestContext::new().await;
+    let base_url = "http://localhost:8080/api/auth";
+
+    // REGISTER - Creates the account and mails a verification link
+    let register_response = ctx
+        .client
+        .post(format!("{}/register", base_url))
+        .json(&json!({
+            "username": "reader",
+            "email": "reader@example.com",
+            "password": "correct horse"
+        }))
+        .send()
+        .await
+        .expect("Failed to register");
+
+    assert_eq!(register_response.status(), 201);
+
+    let registered: serde_json::Value = register_response.json().await.expect("Failed to parse registration");
+    assert!(registered["id"].is_i64());
+    assert_eq!(registered["email_verified"], false);
+    assert_eq!(ctx.sent_mails("reader@example.com").len(), 1);
+
+    // Registering the same email again conflicts
+    let duplicate_response = ctx
+        .client
+        .post(format!("{}/register", base_url))
+        .json(&json!({
+            "username": "someone_else",
+            "email": "reader@example.com",
+            "password": "correct horse"
+        }))
+        .send()
+        .await
+        .expect("Failed to register");
+
+    assert_eq!(duplicate_response.status(), 409);
+
+    // VERIFY - The mailed token verifies the address once
+    let token = ctx.last_mail_token("reader@example.com");
+    let verify_response = ctx
+        .client
+        .post(format!("{}/verify-email", base_url))
+        .json(&json!({ "token": token }))
+        .send()
+        .await
+        .expect("Failed to verify email");
+
+    assert_eq!(verify_response.status(), 204);
+
+    let reuse_response = ctx
+        .client
+        .post(format!("{}/verify-email", base_url))
+        .json(&json!({ "token": token }))
+        .send()
+        .await
+        .expect("Failed to verify email");
+
+    assert_eq!(reuse_response.status(), 400);
+
+    let session: serde_json::Value = ctx
+        .client
+        .post(format!("{}/login", base_url))
+        .json(&json!({ "login": "reader", "password": "correct horse" }))
+        .send()
+        .await
+        .expect("Failed to login")
+        .json()
+        .await
+        .expect("Failed to parse session");
+
+    let me: serde_json::Value = ctx
+        .client
+        .get("http://localhost:8080/api/me")
+        .header("Authorization", format!("Bearer {}", session["token"].as_str().expect("No session token")))
+        .send()
+        .await
+        .expect("Failed to get me")
+        .json()
+        .await
+        .expect("Failed to parse me");
+
+    assert!(!me["email_verified_at"].is_null());
+}
+
+#[tokio::test]
+#[serial]
+async fn test_password_reset_flow() {
+    let ctx = TestContext::new().await;
+    let base_url = "http://localhost:8080/api/auth";
+
+    ctx.client
+        .post(format!("{}/register", base_url))
+        .json(&json!({
+            "username": "forgetful",
+            "email": "forgetful@example.com",
+            "password": "first password"
+        }))
+        .send()
+        .await
+        .expect("Failed to register");
+
+    let session: serde_json::Value = ctx
+        .client
+        .post(format!("{}/login", base_url))
+        .json(&json!({ "login": "forgetful", "password": "first password" }))
+        .send()
+        .await
+        .expect("Failed to login")
+        .json()
+        .await
+        .expect("Failed to parse session");
+
+    let old_auth = format!("Bearer {}", session["token"].as_str().expect("No session token"));
+
+    // Unknown addresses get the same answer and no mail
+    let unknown_response = ctx
+        .client
+        .post(format!("{}/password-reset", base_url))
+        .json(&json!({ "email": "nobody@example.com" }))
+        .send()
+        .await
+        .expect("Failed to request reset");
+
+    assert_eq!(unknown_response.status(), 202);
+    assert!(ctx.sent_mails("nobody@example.com").is_empty());
+
+    let request_response = ctx
+        .client
+        .post(format!("{}/password-reset", base_url))
+        .json(&json!({ "email": "forgetful@example.com" }))
+        .send()
+        .await
+        .expect("Failed to request reset");
+
+    assert_eq!(request_response.status(), 202);
+
+    let token = ctx.last_mail_token("forgetful@example.com");
+
+    // Too short passwords are rejected without spending the token
+    let short_response = ctx
+        .client
+        .post(format!("{}/password-reset/confirm", base_url))
+        .json(&json!({ "token": token, "password": "short" }))
+        .send()
+        .await
+        .expect("Failed to confirm reset");
+
+    assert_eq!(short_response.status(), 422);
+
+    let confirm_response = ctx
+        .client
+        .post(format!("{}/password-reset/confirm", base_url))
+        .json(&json!({ "token": token, "password": "second password" }))
+        .send()
+        .await
+        .expect("Failed to confirm reset");
+
+    assert_eq!(confirm_response.status(), 204);
+
+    // Sessions opened with the old password are signed out
+    let stale_response = ctx
+        .client
+        .get("http://localhost:8080/api/me")
+        .header("Authorization", &old_auth)
+        .send()
+        .await
+        .expect("Failed to get me");
+
+    assert_eq!(stale_response.status(), 401);
+
+    let reuse_response = ctx
+        .client
+        .post(format!("{}/password-reset/confirm", base_url))
+        .json(&json!({ "token": token, "password": "third password" }))
+        .send()
+        .await
+        .expect("Failed to confirm reset");
+
+    assert_eq!(reuse_response.status(), 400);
+}
+
+#[tokio::test]
+#[serial]
+async fn test_email_change_needs_the_password_and_a_new_verification() {
+    let ctx = TestContext::new().await;
+    let auth = ctx.register_and_login("mover").await;
+    ctx.register_and_login("squatter").await;
+    let old_token = ctx.last_mail_token("mover@example.com");
+
+    let wrong_password = ctx
+        .client
+        .put("http://localhost:8080/api/me/email")
+        .header("Authorization", &auth)
+        .json(&json!({ "email": "moved@example.com", "password": "not my password" }))
+        .send()
+        .await
+        .expect("Failed to change email");
+
+    assert_eq!(wrong_password.status(), 401);
+
+    let conflict_response = ctx
+        .client
+        .put("http://localhost:8080/api/me/email")
+        .header("Authorization", &auth)
+        .json(&json!({ "email": "squatter@example.com", "password": "long enough password" }))
+        .send()
+        .await
+        .expect("Failed to change email");
+
+    assert_eq!(conflict_response.status(), 409);
+
+    let change_response = ctx
+        .client
+        .put("http://localhost:8080/api/me/email")
+        .header("Authorization", &auth)
+        .json(&json!({ "email": "moved@example.com", "password": "long enough password" }))
+        .send()
+        .await
+        .expect("Failed to change email");
+
+    assert_eq!(change_response.status(), 200);
+    let account: serde_json::Value = change_response.json().await.expect("Failed to parse account");
+    assert_eq!(account["email"], "moved@example.com");
+    assert!(account["email_verified_at"].is_null());
+
+    // The link mailed to the old address no longer verifies the account
+    let stale_response = ctx
+        .client
+        .post("http://localhost:8080/api/auth/verify-email")
+        .json(&json!({ "token": old_token }))
+        .send()
+        .await
+        .expect("Failed to verify email");
+
+    assert_eq!(stale_response.status(), 400);
+
+    let verify_response = ctx
+        .client
+        .post("http://localhost:8080/api/auth/verify-email")
+        .json(&json!({ "token": ctx.last_mail_token("moved@example.com") }))
+        .send()
+        .await
+        .expect("Failed to verify email");
+
+    assert!(verify_response.status().is_success());
+}
@@ -0,0 +1,238 @@
+mod common;
+
+use common::TestContext;
+use serde_json::json;
+use serial_test::serial;
+
+#[tokio::test]
+#[serial]
+async fn test_api_key_scopes_and_revocation() {
+    let ctx = TestContext::new().await;
+    let base_url = "http://localhost:8080/api/api-keys";
+
+    // The key is bound to the caller's account
+    let auth = ctx.register_and_login("release_bot").await;
+
+    // CREATE - Issue a read-only key
+    let create_payload = json!({
+        "name": "novel reader",
+        "scopes": ["novels:read"]
+    });
+
+    let create_response = ctx
+        .client
+        .post(base_url)
+        .header("Authorization", &auth)
+        .json(&create_payload)
+        .send()
+        .await
+        .expect("Failed to create api key");
+
+    assert_eq!(create_response.status(), 201);
+
+    let created_key: serde_json::Value = create_response
+        .json()
+        .await
+        .expect("Failed to parse created api key");
+
+    let key_id = created_key["id"].as_i64().expect("No api key ID");
+    let key = created_key["key"].as_str().expect("No plaintext key").to_string();
+    assert!(key.starts_with(created_key["key_prefix"].as_str().unwrap()));
+    assert!(created_key["user_id"].is_i64());
+
+    // Reads within scope succeed
+    let read_response = ctx
+        .client
+        .get("http://localhost:8080/api/novels")
+        .header("Authorization", format!("Bearer {}", key))
+        .send()
+        .await
+        .expect("Failed to list novels");
+
+    assert_eq!(read_response.status(), 200);
+
+    // Writes outside scope are forbidden
+    let write_response = ctx
+        .client
+        .post("http://localhost:8080/api/chapters")
+        .header("Authorization", format!("Bearer {}", key))
+        .json(&json!({}))
+        .send()
+        .await
+        .expect("Failed to post chapter");
+
+    assert_eq!(write_response.status(), 403);
+
+    // The listing never exposes key material
+    let list_response = ctx
+        .client
+        .get(base_url)
+        .header("Authorization", &auth)
+        .send()
+        .await
+        .expect("Failed to list api keys");
+
+    let keys: serde_json::Value = list_response.json().await.expect("Failed to parse api keys");
+    assert_eq!(keys.as_array().expect("Not an array").len(), 1);
+    assert!(keys[0].get("key").is_none());
+    assert!(keys[0].get("key_hash").is_none());
+    assert!(!keys[0]["last_used_at"].is_null());
+
+    // REVOKE - The key stops working
+    let revoke_response = ctx
+        .client
+        .delete(format!("{}/{}", base_url, key_id))
+        .header("Authorization", &auth)
+        .send()
+        .await
+        .expect("Failed to revoke api key");
+
+    assert_eq!(revoke_response.status(), 204);
+
+    let rejected_response = ctx
+        .client
+        .get("http://localhost:8080/api/novels")
+        .header("Authorization", format!("Bearer {}", key))
+        .send()
+        .await
+        .expect("Failed to list novels");
+
+    assert_eq!(rejected_response.status(), 401);
+}
+
+#[tokio::test]
+#[serial]
+async fn test_api_key_rejects_unknown_scope() {
+    let ctx = TestContext::new().await;
+    let auth = ctx.register_and_login("scoped").await;
+
+    let response = ctx
+        .client
+        .post("http://localhost:8080/api/api-keys")
+        .header("Authorization", &auth)
+        .json(&json!({ "name": "bad", "scopes": ["novels:delete"] }))
+        .send()
+        .await
+        .expect("Failed to create api key");
+
+    assert_eq!(response.status(), 422);
+}
+
+#[tokio::test]
+#[serial]
+async fn test_comment_writes_need_the_comments_scope() {
+    let ctx = TestContext::new().await;
+    let auth = ctx.register_and_login("commenter_bot").await;
+
+    let mut keys = vec![];
+    for scopes in [json!(["reviews:write", "chapters:write"]), json!(["comments:write"])] {
+        let created: serde_json::Value = ctx
+            .client
+            .post("http://localhost:8080/api/api-keys")
+            .header("Authorization", &auth)
+            .json(&json!({ "name": "comment scopes", "scopes": scopes }))
+            .send()
+            .await
+            .expect("Failed to create api key")
+            .json()
+            .await
+            .expect("Failed to parse created api key");
+        keys.push(format!("Bearer {}", created["key"].as_str().expect("No plaintext key")));
+    }
+
+    // Comments nested under a review or chapter are checked against `comments`, not the parent
+    for url in [
+        "http://localhost:8080/api/reviews/999999/comments",
+        "http://localhost:8080/api/chapters/999999/comments",
+    ] {
+        let parent_scope = ctx
+            .client
+            .post(url)
+            .header("Authorization", &keys[0])
+            .json(&json!({ "content": "First!" }))
+            .send()
+            .await
+            .expect("Failed to post comment");
+
+        assert_eq!(parent_scope.status(), 403);
+
+        let comments_scope = ctx
+            .client
+            .post(url)
+            .header("Authorization", &keys[1])
+            .json(&json!({ "content": "First!" }))
+            .send()
+            .await
+            .expect("Failed to post comment");
+
+        assert_eq!(comments_scope.status(), 404);
+    }
+}
+
+#[tokio::test]
+#[serial]
+async fn test_api_keys_require_authentication() {
+    let ctx = TestContext::new().await;
+
+    let create_response = ctx
+        .client
+        .post("http://localhost:8080/api/api-keys")
+        .json(&json!({ "name": "anonymous", "scopes": ["*"] }))
+        .send()
+        .await
+        .expect("Failed to create api key");
+
+    assert_eq!(create_response.status(), 401);
+
+    let list_response = ctx
+        .client
+        .get("http://localhost:8080/api/api-keys")
+        .send()
+        .await
+        .expect("Failed to list api keys");
+
+    assert_eq!(list_response.status(), 401);
+}
+
+#[tokio::test]
+#[serial]
+async fn test_refused_keys_are_rate_limited() {
+    let ctx = TestContext::new().await;
+
+    // Every write with a bad key is charged to the client's address until its bucket is empty
+    let mut last_response = None;
+    for _ in 0..61 {
+        let response = ctx
+            .client
+            .post("http://localhost:8080/api/novels")
+            .header("Authorization", "Bearer nu_not_a_real_key")
+            .json(&json!({}))
+            .send()
+            .await
+            .expect("Failed to post novel");
+
+        if response.status() == 429 {
+            last_response = Some(response);
+            break;
+        }
+        assert_eq!(response.status(), 401);
+    }
+
+    let limited = last_response.expect("Bucket was never exhausted");
+    let headers = limited.headers();
+    assert_eq!(headers["ratelimit-limit"], "60");
+    assert_eq!(headers["ratelimit-remaining"], "0");
+    assert!(headers["ratelimit-reset"].to_str().unwrap().parse::<u64>().unwrap() > 0);
+    assert!(headers["retry-after"].to_str().unwrap().parse::<u64>().unwrap() >= 1);
+
+    // Reads have their own bucket and report it on ordinary responses
+    let read_response = ctx
+        .client
+        .get("http://localhost:8080/api/novels")
+        .send()
+        .await
+        .expect("Failed to list novels");
+
+    assert_eq!(read_response.status(), 200);
+    assert_eq!(read_response.headers()["ratelimit-limit"], "300");
+}
@@ -1,24 +1,35 @@
+// Each test binary compiles this module and uses only some of its helpers.
+#![allow(dead_code)]
+
 use std::collections::HashMap;
 use testcontainers::{ContainerAsync, GenericImage, ImageExt};
 use testcontainers_modules::{postgres::Postgres, testcontainers::runners::AsyncRunner};
 use sqlx::postgres::PgPoolOptions;
 use reqwest::Client;
 use serde_json::json;
+use sha2::{Digest, Sha256};
 use tokio::process::{Child, Command};
 use std::env;
+use std::path::PathBuf;
 
 pub struct TestContext {
-    pub postgres_container: ContainerAsync<Postgres>,
-    pub keycloak_container: ContainerAsync<GenericImage>,
+    pub postgres_container: Option<ContainerAsync<Postgres>>,
+    pub keycloak_container: Option<ContainerAsync<GenericImage>>,
     pub database_url: String,
     pub keycloak_url: String,
     pub client: Client,
     pub access_token: Option<String>,
     pub app_process: Option<Child>,
+    pub mail_file: PathBuf,
 }
 
 impl TestContext {
     pub async fn new() -> Self {
+        // Without Docker, `TEST_DATABASE_URL` points at a running Postgres server instead
+        if let Ok(server_url) = env::var("TEST_DATABASE_URL") {
+            return Self::on_server(&server_url).await;
+        }
+
         // Start Postgres container
         let postgres_container = Postgres::default()
             .start()
@@ -75,13 +86,14 @@ impl TestContext {
             .expect("Failed to create HTTP client");
 
         let mut context = Self {
-            postgres_container,
-            keycloak_container,
+            postgres_container: Some(postgres_container),
+            keycloak_container: Some(keycloak_container),
             database_url: database_url.clone(),
             keycloak_url: keycloak_url.clone(),
             client,
             access_token: None,
             app_process: None,
+            mail_file: env::temp_dir().join(format!("novelupdates-mail-{}.jsonl", postgres_port)),
         };
 
         // Setup Keycloak realm and client
@@ -93,18 +105,67 @@ impl TestContext {
         context
     }
 
+    /// Runs against a fresh database on an existing Postgres server, without containers. There
+    /// is no Keycloak then, so `get_auth_header` is unavailable and tests log in with
+    /// `register_and_login`.
+    async fn on_server(server_url: &str) -> Self {
+        let database = format!("novelupdates_test_{:016x}", rand::random::<u64>());
+        let server = PgPoolOptions::new()
+            .max_connections(1)
+            .connect(server_url)
+            .await
+            .expect("Failed to connect to Postgres server");
+        sqlx::query(&format!("CREATE DATABASE {}", database))
+            .execute(&server)
+            .await
+            .expect("Failed to create test database");
+
+        let base = server_url.rsplit_once('/').map_or(server_url, |(base, _)| base);
+        let database_url = format!("{}/{}", base, database);
+        let pool = PgPoolOptions::new()
+            .max_connections(5)
+            .connect(&database_url)
+            .await
+            .expect("Failed to connect to database");
+
+        sqlx::migrate!("./migrations")
+            .run(&pool)
+            .await
+            .expect("Failed to run migrations");
+
+        let client = Client::builder()
+            .cookie_store(true)
+            .build()
+            .expect("Failed to create HTTP client");
+
+        let mut context = Self {
+            postgres_container: None,
+            keycloak_container: None,
+            database_url,
+            keycloak_url: String::new(),
+            client,
+            access_token: None,
+            app_process: None,
+            mail_file: env::temp_dir().join(format!("{}-mail.jsonl", database)),
+        };
+        context.start_app_server().await;
+        context
+    }
+
     async fn start_app_server(&mut self) {
         // Set environment variables for the app
         let issuer = format!("{}/realms/test-realm", self.keycloak_url);
         let app_url = "http://localhost:8080".to_string();
 
-        let mut cmd = Command::new("cargo");
-        cmd.arg("run")
-            .env("DATABASE_URL", &self.database_url)
+        let mut cmd = Command::new(env!("CARGO_BIN_EXE_novelupdates"));
+        cmd.env("DATABASE_URL", &self.database_url)
             .env("APP_URL", &app_url)
             .env("ISSUER", &issuer)
             .env("CLIENT_ID", "test-client")
             .env("CLIENT_SECRET", "test-secret")
+            .env("MAIL_TRANSPORT", "file")
+            .env("MAIL_FILE", &self.mail_file)
+            .env("DIGEST_INTERVAL_SECS", "1")
             .stdout(std::process::Stdio::null())
             .stderr(std::process::Stdio::null())
             .kill_on_drop(true);
@@ -270,6 +331,200 @@ impl TestContext {
     pub fn get_auth_header(&self) -> String {
         format!("Bearer {}", self.access_token.as_ref().unwrap())
     }
+
+    /// Registers `username` with a fixed password and returns an `Authorization` header for a fresh session.
+    pub async fn register_and_login(&self, username: &str) -> String {
+        self.client
+            .post("http://localhost:8080/api/auth/register")
+            .json(&json!({
+                "username": username,
+                "email": format!("{}@example.com", username),
+                "password": "long enough password"
+            }))
+            .send()
+            .await
+            .expect("Failed to register");
+
+        let session: serde_json::Value = self
+            .client
+            .post("http://localhost:8080/api/auth/login")
+            .json(&json!({ "login": username, "password": "long enough password" }))
+            .send()
+            .await
+            .expect("Failed to login")
+            .json()
+            .await
+            .expect("Failed to parse session");
+
+        format!("Bearer {}", session["token"].as_str().expect("No session token"))
+    }
+
+    /// Sets a user's role straight in the database, since only admins can change roles.
+    pub async fn set_role(&self, username: &str, role: &str) {
+        let pool = PgPoolOptions::new()
+            .max_connections(1)
+            .connect(&self.database_url)
+            .await
+            .expect("Failed to connect to database");
+
+        sqlx::query("UPDATE public.user SET role = $1 WHERE username = $2")
+            .bind(role)
+            .bind(username)
+            .execute(&pool)
+            .await
+            .expect("Failed to set role");
+    }
+
+    /// Inserts an API key for a user straight into the database, like one minted while the user
+    /// was being banned, and returns the key.
+    pub async fn insert_api_key(&self, username: &str) -> String {
+        let pool = PgPoolOptions::new()
+            .max_connections(1)
+            .connect(&self.database_url)
+            .await
+            .expect("Failed to connect to database");
+
+        let key = format!("nu_{:032x}", rand::random::<u128>());
+        sqlx::query(
+            "INSERT INTO public.api_key (name, key_prefix, key_hash, scopes, user_id)
+             SELECT 'inserted', $1, $2, '*', id FROM public.user WHERE username = $3",
+        )
+        .bind(&key[..11])
+        .bind(hex::encode(Sha256::digest(key.as_bytes())))
+        .bind(username)
+        .execute(&pool)
+        .await
+        .expect("Failed to insert api key");
+        key
+    }
+
+    /// Inserts a novel straight into the database with a type of its own, so tests only name the
+    /// fields they care about instead of building the whole create payload.
+    pub async fn insert_novel(&self, name: &str, original_language: Option<&str>) -> i32 {
+        let pool = PgPoolOptions::new()
+            .max_connections(1)
+            .connect(&self.database_url)
+            .await
+            .expect("Failed to connect to database");
+
+        let type_id: i32 = sqlx::query_scalar("INSERT INTO public.type (name) VALUES ('Web Novel') RETURNING id")
+            .fetch_one(&pool)
+            .await
+            .expect("Failed to insert type");
+        sqlx::query_scalar("INSERT INTO public.novel (default_name, original_language, type_id) VALUES ($1, $2, $3) RETURNING id")
+            .bind(name)
+            .bind(original_language)
+            .bind(type_id)
+            .fetch_one(&pool)
+            .await
+            .expect("Failed to insert novel")
+    }
+
+    /// Inserts a source of a novel by a new group straight into the database, bypassing the
+    /// notifications creating one through the API sends.
+    pub async fn insert_source(&self, novel_id: i32) -> i32 {
+        let pool = PgPoolOptions::new()
+            .max_connections(1)
+            .connect(&self.database_url)
+            .await
+            .expect("Failed to connect to database");
+
+        let group_id: i32 = sqlx::query_scalar("INSERT INTO public.group (name) VALUES ('Test Translations') RETURNING id")
+            .fetch_one(&pool)
+            .await
+            .expect("Failed to insert group");
+        sqlx::query_scalar(
+            "INSERT INTO public.source (name, language, novel_id, group_id) VALUES ('Test Source', 'en', $1, $2) RETURNING id",
+        )
+        .bind(novel_id)
+        .bind(group_id)
+        .fetch_one(&pool)
+        .await
+        .expect("Failed to insert source")
+    }
+
+    /// Inserts a chapter of a novel straight into the database, in a new source, bypassing the
+    /// notifications creating one through the API sends.
+    pub async fn insert_chapter(&self, novel_id: i32, number: &str) -> i32 {
+        let source_id = self.insert_source(novel_id).await;
+        let pool = PgPoolOptions::new()
+            .max_connections(1)
+            .connect(&self.database_url)
+            .await
+            .expect("Failed to connect to database");
+
+        let chapter_id: i32 = sqlx::query_scalar(
+            "INSERT INTO public.chapter (number, title, language, source_id, content_url)
+             VALUES ($1, '', 'en', $2, 'https://example.com/chapter') RETURNING id",
+        )
+        .bind(number)
+        .bind(source_id)
+        .fetch_one(&pool)
+        .await
+        .expect("Failed to insert chapter");
+        sqlx::query("INSERT INTO public.chapter_novel (chapter_id, novel_id) VALUES ($1, $2)")
+            .bind(chapter_id)
+            .bind(novel_id)
+            .execute(&pool)
+            .await
+            .expect("Failed to link chapter");
+        chapter_id
+    }
+
+    /// Moves the reads of a chapter `days` days into the past, since the API records reads as
+    /// happening now.
+    pub async fn backdate_reads(&self, chapter_id: i32, days: i32) {
+        let pool = PgPoolOptions::new()
+            .max_connections(1)
+            .connect(&self.database_url)
+            .await
+            .expect("Failed to connect to database");
+
+        sqlx::query("UPDATE public.library_chapter_read SET read_at = read_at - make_interval(days => $2) WHERE chapter_id = $1")
+            .bind(chapter_id)
+            .bind(days)
+            .execute(&pool)
+            .await
+            .expect("Failed to backdate reads");
+    }
+
+    /// Inserts a review by a user straight into the database, since creating one through the API
+    /// needs the full novel and user.
+    pub async fn insert_review(&self, username: &str, novel_id: i32) -> i32 {
+        let pool = PgPoolOptions::new()
+            .max_connections(1)
+            .connect(&self.database_url)
+            .await
+            .expect("Failed to connect to database");
+
+        sqlx::query_scalar(
+            "INSERT INTO public.review (content, rating, novel_id, user_id)
+             SELECT 'Worth reading.', '5', $1, id FROM public.user WHERE username = $2 RETURNING id",
+        )
+        .bind(novel_id)
+        .bind(username)
+        .fetch_one(&pool)
+        .await
+        .expect("Failed to insert review")
+    }
+
+    /// Returns the mails written by the app's file mail sender, oldest first.
+    pub fn sent_mails(&self, to: &str) -> Vec<serde_json::Value> {
+        std::fs::read_to_string(&self.mail_file)
+            .unwrap_or_default()
+            .lines()
+            .filter_map(|line| serde_json::from_str::<serde_json::Value>(line).ok())
+            .filter(|mail| mail["to"] == to)
+            .collect()
+    }
+
+    /// Extracts the `token` query parameter from the newest mail sent to `to`.
+    pub fn last_mail_token(&self, to: &str) -> String {
+        let mails = self.sent_mails(to);
+        let body = mails.last().expect("No mail sent")["body"].as_str().unwrap().to_string();
+        let start = body.find("token=").expect("No token in mail") + "token=".len();
+        body[start..].split_whitespace().next().unwrap().to_string()
+    }
 }
 
 impl Drop for TestContext {
@@ -278,5 +533,6 @@ impl Drop for TestContext {
         if let Some(mut process) = self.app_process.take() {
             let _ = process.start_kill();
         }
+        let _ = std::fs::remove_file(&self.mail_file);
     }
 }
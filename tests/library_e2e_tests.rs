@@ -0,0 +1,621 @@
+mod common;
+
+use common::TestContext;
+use serde_json::json;
+use serial_test::serial;
+
+#[tokio::test]
+#[serial]
+async fn test_library_requires_authentication() {
+    let ctx = TestContext::new().await;
+
+    let response = ctx
+        .client
+        .get("http://localhost:8080/api/library")
+        .send()
+        .await
+        .expect("Failed to list library");
+
+    assert_eq!(response.status(), 401);
+}
+
+#[tokio::test]
+#[serial]
+async fn test_library_entries_for_unknown_novels() {
+    let ctx = TestContext::new().await;
+    let auth = ctx.register_and_login("reader").await;
+
+    // An empty library to start with
+    let entries: serde_json::Value = ctx
+        .client
+        .get("http://localhost:8080/api/library")
+        .header("Authorization", &auth)
+        .send()
+        .await
+        .expect("Failed to list library")
+        .json()
+        .await
+        .expect("Failed to parse library");
+
+    assert_eq!(entries, json!([]));
+
+    // Novels and chapters must exist
+    let put_response = ctx
+        .client
+        .put("http://localhost:8080/api/library/999999")
+        .header("Authorization", &auth)
+        .json(&json!({ "status": "Reading" }))
+        .send()
+        .await
+        .expect("Failed to put entry");
+
+    assert_eq!(put_response.status(), 404);
+
+    let read_response = ctx
+        .client
+        .post("http://localhost:8080/api/library/999999/chapters/1/read")
+        .header("Authorization", &auth)
+        .send()
+        .await
+        .expect("Failed to mark read");
+
+    assert_eq!(read_response.status(), 404);
+
+    let missing = ctx
+        .client
+        .get("http://localhost:8080/api/library/999999")
+        .header("Authorization", &auth)
+        .send()
+        .await
+        .expect("Failed to read entry");
+
+    assert_eq!(missing.status(), 404);
+}
+
+#[tokio::test]
+#[serial]
+async fn test_chapter_reads_on_a_new_novel_share_one_entry() {
+    let ctx = TestContext::new().await;
+    let auth = ctx.register_and_login("eager_reader").await;
+    let novel_id = ctx.insert_novel("Binged Novel", None).await;
+    let mut chapter_ids = Vec::new();
+    for number in ["1", "2", "3", "4"] {
+        chapter_ids.push(ctx.insert_chapter(novel_id, number).await);
+    }
+
+    // Several first reads at once add the novel to the library only once
+    let reads = chapter_ids.iter().map(|chapter_id| {
+        ctx.client
+            .post(format!("http://localhost:8080/api/library/{}/chapters/{}/read", novel_id, chapter_id))
+            .header("Authorization", &auth)
+            .send()
+    });
+    for response in futures_util::future::join_all(reads).await {
+        assert_eq!(response.expect("Failed to mark read").status(), 200);
+    }
+
+    let entries: serde_json::Value = ctx
+        .client
+        .get("http://localhost:8080/api/library")
+        .header("Authorization", &auth)
+        .send()
+        .await
+        .expect("Failed to list library")
+        .json()
+        .await
+        .expect("Failed to parse library");
+
+    assert_eq!(entries.as_array().unwrap().len(), 1);
+    assert_eq!(entries[0]["status"], "Reading");
+    assert_eq!(entries[0]["chapters_read"], 4);
+
+    // Reading on from plan-to-read moves the entry to reading
+    let other_id = ctx.insert_novel("Planned Novel", None).await;
+    let other_chapter = ctx.insert_chapter(other_id, "1").await;
+    let planned = ctx
+        .client
+        .put(format!("http://localhost:8080/api/library/{}", other_id))
+        .header("Authorization", &auth)
+        .json(&json!({ "status": "PlanToRead" }))
+        .send()
+        .await
+        .expect("Failed to put entry");
+
+    assert_eq!(planned.status(), 201);
+
+    let entry: serde_json::Value = ctx
+        .client
+        .post(format!("http://localhost:8080/api/library/{}/chapters/{}/read", other_id, other_chapter))
+        .header("Authorization", &auth)
+        .send()
+        .await
+        .expect("Failed to mark read")
+        .json()
+        .await
+        .expect("Failed to parse entry");
+
+    assert_eq!(entry["status"], "Reading");
+    assert_eq!(entry["read_chapter_ids"], json!([other_chapter]));
+}
+
+#[tokio::test]
+#[serial]
+async fn test_updates_for_empty_library() {
+    let ctx = TestContext::new().await;
+    let auth = ctx.register_and_login("caughtup").await;
+
+    let updates: serde_json::Value = ctx
+        .client
+        .get("http://localhost:8080/api/me/updates")
+        .header("Authorization", &auth)
+        .send()
+        .await
+        .expect("Failed to get updates")
+        .json()
+        .await
+        .expect("Failed to parse updates");
+
+    assert_eq!(updates, json!([]));
+
+    let marked: serde_json::Value = ctx
+        .client
+        .post("http://localhost:8080/api/me/updates/read")
+        .header("Authorization", &auth)
+        .send()
+        .await
+        .expect("Failed to mark all read")
+        .json()
+        .await
+        .expect("Failed to parse result");
+
+    assert_eq!(marked["novels"], 0);
+    assert_eq!(marked["chapters"], 0);
+}
+
+#[tokio::test]
+#[serial]
+async fn test_import_reports_unmatched_entries() {
+    let ctx = TestContext::new().await;
+    let auth = ctx.register_and_login("migrant").await;
+
+    let csv = "Title,Status,Progress\nA Novel Nobody Has Added,Reading,c12\nAnother Missing One,Plan to Read,\n";
+    let report: serde_json::Value = ctx
+        .client
+        .post("http://localhost:8080/api/me/import?format=csv")
+        .header("Authorization", &auth)
+        .body(csv)
+        .send()
+        .await
+        .expect("Failed to import")
+        .json()
+        .await
+        .expect("Failed to parse report");
+
+    assert_eq!(report["total"], 2);
+    assert_eq!(report["imported"], json!([]));
+    let unmatched = report["unmatched"].as_array().expect("No unmatched list");
+    assert_eq!(unmatched.len(), 2);
+    assert_eq!(unmatched[0]["entry"]["status"], "Reading");
+    assert_eq!(unmatched[0]["entry"]["progress"], "12");
+
+    let mal = r#"<?xml version="1.0" encoding="UTF-8"?>
+<myanimelist>
+  <manga>
+    <manga_title><![CDATA[Unknown Manga]]></manga_title>
+    <my_read_chapters>3</my_read_chapters>
+    <my_status>On-Hold</my_status>
+  </manga>
+</myanimelist>"#;
+    let report: serde_json::Value = ctx
+        .client
+        .post("http://localhost:8080/api/me/import?format=mal&dry_run=true")
+        .header("Authorization", &auth)
+        .body(mal)
+        .send()
+        .await
+        .expect("Failed to import")
+        .json()
+        .await
+        .expect("Failed to parse report");
+
+    assert_eq!(report["dry_run"], true);
+    assert_eq!(report["unmatched"][0]["entry"]["title"], "Unknown Manga");
+    assert_eq!(report["unmatched"][0]["entry"]["status"], "OnHold");
+
+    // A CSV without a title column cannot be read
+    let bad = ctx
+        .client
+        .post("http://localhost:8080/api/me/import?format=csv")
+        .header("Authorization", &auth)
+        .body("Foo,Bar\n1,2\n")
+        .send()
+        .await
+        .expect("Failed to import");
+
+    assert_eq!(bad.status(), 400);
+}
+
+#[tokio::test]
+#[serial]
+async fn test_import_matches_novels() {
+    let ctx = TestContext::new().await;
+    let auth = ctx.register_and_login("switcher").await;
+    let exact_id = ctx.insert_novel("The Beginning After The End", Some("ko")).await;
+    let fuzzy_id = ctx.insert_novel("Lord of the Mysteries", Some("zh")).await;
+
+    // Names match ignoring case; near misses are scored
+    let csv = "Title,Status,Progress\nthe beginning after the end,Reading,\n\"Mysteries, Lord of the\",Completed,\n";
+    let report: serde_json::Value = ctx
+        .client
+        .post("http://localhost:8080/api/me/import?format=csv")
+        .header("Authorization", &auth)
+        .body(csv)
+        .send()
+        .await
+        .expect("Failed to import")
+        .json()
+        .await
+        .expect("Failed to parse report");
+
+    let imported = report["imported"].as_array().expect("No imported list");
+    assert_eq!(imported.len(), 2);
+    assert_eq!(imported[0]["novel_id"], exact_id);
+    assert_eq!(imported[0]["confidence"], 1.0);
+    assert_eq!(imported[1]["novel_id"], fuzzy_id);
+    assert!(imported[1]["confidence"].as_f64().unwrap() < 1.0);
+    assert_eq!(report["unmatched"], json!([]));
+
+    let library: serde_json::Value = ctx
+        .client
+        .get("http://localhost:8080/api/library")
+        .header("Authorization", &auth)
+        .send()
+        .await
+        .expect("Failed to list library")
+        .json()
+        .await
+        .expect("Failed to parse library");
+
+    assert_eq!(library.as_array().unwrap().len(), 2);
+}
+
+#[tokio::test]
+#[serial]
+async fn test_reading_list_export_formats() {
+    let ctx = TestContext::new().await;
+    let auth = ctx.register_and_login("backup").await;
+
+    let json_response = ctx
+        .client
+        .get("http://localhost:8080/api/me/reading-lists/export?format=json")
+        .header("Authorization", &auth)
+        .send()
+        .await
+        .expect("Failed to export");
+
+    assert_eq!(json_response.status(), 200);
+    assert!(json_response.headers()["content-disposition"].to_str().unwrap().ends_with(".json\""));
+    let entries: serde_json::Value = json_response.json().await.expect("Failed to parse export");
+    assert_eq!(entries, json!([]));
+
+    // Entries come from the library, whether or not the novel sits in a reading list
+    let novel_id = ctx.insert_novel("Backed Up Novel", Some("ko")).await;
+    let put_response = ctx
+        .client
+        .put(format!("http://localhost:8080/api/library/{}", novel_id))
+        .header("Authorization", &auth)
+        .json(&json!({ "status": "Reading", "personal_rating": "4", "notes": "Slow start" }))
+        .send()
+        .await
+        .expect("Failed to put entry");
+
+    assert_eq!(put_response.status(), 201);
+
+    let entries: serde_json::Value = ctx
+        .client
+        .get("http://localhost:8080/api/me/reading-lists/export?format=json")
+        .header("Authorization", &auth)
+        .send()
+        .await
+        .expect("Failed to export")
+        .json()
+        .await
+        .expect("Failed to parse export");
+
+    assert_eq!(entries.as_array().unwrap().len(), 1);
+    assert_eq!(entries[0]["novel_id"], novel_id);
+    assert_eq!(entries[0]["title"], "Backed Up Novel");
+    assert_eq!(entries[0]["status"], "Reading");
+    assert_eq!(entries[0]["notes"], "Slow start");
+
+    let csv = ctx
+        .client
+        .get("http://localhost:8080/api/me/reading-lists/export?format=csv")
+        .header("Authorization", &auth)
+        .send()
+        .await
+        .expect("Failed to export")
+        .text()
+        .await
+        .expect("Failed to read export");
+
+    assert!(csv.starts_with("Title,Status,Progress"));
+    assert!(csv.contains("Backed Up Novel,Reading,,4,Slow start"));
+
+    let mal = ctx
+        .client
+        .get("http://localhost:8080/api/me/reading-lists/export?format=mal")
+        .header("Authorization", &auth)
+        .send()
+        .await
+        .expect("Failed to export")
+        .text()
+        .await
+        .expect("Failed to read export");
+
+    assert!(mal.contains("<myanimelist>"));
+    assert!(mal.contains("<manga_title><![CDATA[Backed Up Novel]]></manga_title>"));
+    assert!(mal.trim_end().ends_with("</myanimelist>"));
+}
+
+#[tokio::test]
+#[serial]
+async fn test_list_endpoints_for_unknown_lists() {
+    let ctx = TestContext::new().await;
+    let auth = ctx.register_and_login("curator").await;
+
+    let browse = ctx
+        .client
+        .get("http://localhost:8080/api/lists?sort=recent")
+        .send()
+        .await
+        .expect("Failed to browse lists");
+
+    assert_eq!(browse.status(), 200);
+    let lists: serde_json::Value = browse.json().await.expect("Failed to parse lists");
+    assert!(lists.is_array());
+
+    let missing = ctx
+        .client
+        .get("http://localhost:8080/api/lists/999999")
+        .send()
+        .await
+        .expect("Failed to read list");
+
+    assert_eq!(missing.status(), 404);
+
+    let shared = ctx
+        .client
+        .get("http://localhost:8080/api/lists/shared/not-a-token")
+        .send()
+        .await
+        .expect("Failed to read shared list");
+
+    assert_eq!(shared.status(), 404);
+
+    let follow = ctx
+        .client
+        .post("http://localhost:8080/api/lists/999999/follow")
+        .header("Authorization", &auth)
+        .send()
+        .await
+        .expect("Failed to follow list");
+
+    assert_eq!(follow.status(), 404);
+
+    let followed: serde_json::Value = ctx
+        .client
+        .get("http://localhost:8080/api/lists/followed")
+        .header("Authorization", &auth)
+        .send()
+        .await
+        .expect("Failed to list followed lists")
+        .json()
+        .await
+        .expect("Failed to parse followed lists");
+
+    assert_eq!(followed, json!([]));
+}
+
+#[tokio::test]
+#[serial]
+async fn test_reading_list_visibility() {
+    let ctx = TestContext::new().await;
+    let owner_auth = ctx.register_and_login("lister").await;
+    let other_auth = ctx.register_and_login("onlooker").await;
+
+    let mut ids = Vec::new();
+    for visibility in ["Public", "Unlisted", "Private"] {
+        let created: serde_json::Value = ctx
+            .client
+            .post("http://localhost:8080/api/reading-lists")
+            .header("Authorization", &owner_auth)
+            .json(&json!({ "name": visibility, "novel": [], "status": "Reading", "visibility": visibility }))
+            .send()
+            .await
+            .expect("Failed to create reading list")
+            .json()
+            .await
+            .expect("Failed to parse reading list");
+
+        assert_eq!(created["visibility"], visibility);
+        ids.push(created["id"].as_i64().expect("No reading list ID"));
+    }
+    let (public_id, unlisted_id, private_id) = (ids[0], ids[1], ids[2]);
+
+    let share: serde_json::Value = ctx
+        .client
+        .post(format!("http://localhost:8080/api/lists/{}/share-token", unlisted_id))
+        .header("Authorization", &owner_auth)
+        .send()
+        .await
+        .expect("Failed to create share token")
+        .json()
+        .await
+        .expect("Failed to parse share token");
+
+    let token = share["share_token"].as_str().expect("No share token");
+
+    // Anonymous readers only see the public list
+    let listed: serde_json::Value = ctx
+        .client
+        .get("http://localhost:8080/api/reading-lists")
+        .send()
+        .await
+        .expect("Failed to list reading lists")
+        .json()
+        .await
+        .expect("Failed to parse reading lists");
+
+    let listed_ids: Vec<i64> = listed.as_array().unwrap().iter().map(|list| list["id"].as_i64().unwrap()).collect();
+    assert_eq!(listed_ids, vec![public_id]);
+
+    let browsed: serde_json::Value = ctx
+        .client
+        .get("http://localhost:8080/api/lists?sort=recent")
+        .send()
+        .await
+        .expect("Failed to browse lists")
+        .json()
+        .await
+        .expect("Failed to parse lists");
+
+    let browsed_ids: Vec<i64> = browsed.as_array().unwrap().iter().map(|list| list["id"].as_i64().unwrap()).collect();
+    assert_eq!(browsed_ids, vec![public_id]);
+
+    for (id, expected) in [(public_id, 200), (unlisted_id, 404), (private_id, 404)] {
+        for url in [
+            format!("http://localhost:8080/api/reading-lists/{}", id),
+            format!("http://localhost:8080/api/lists/{}", id),
+        ] {
+            let response = ctx.client.get(&url).send().await.expect("Failed to read list");
+            assert_eq!(response.status(), expected, "{}", url);
+        }
+    }
+
+    // The share token opens the unlisted list and nothing else
+    let with_token = ctx
+        .client
+        .get(format!("http://localhost:8080/api/lists/{}?token={}", unlisted_id, token))
+        .send()
+        .await
+        .expect("Failed to read list");
+
+    assert_eq!(with_token.status(), 200);
+
+    let shared = ctx
+        .client
+        .get(format!("http://localhost:8080/api/lists/shared/{}", token))
+        .send()
+        .await
+        .expect("Failed to read shared list");
+
+    assert_eq!(shared.status(), 200);
+
+    // The owner sees all three
+    let own: serde_json::Value = ctx
+        .client
+        .get("http://localhost:8080/api/reading-lists")
+        .header("Authorization", &owner_auth)
+        .send()
+        .await
+        .expect("Failed to list reading lists")
+        .json()
+        .await
+        .expect("Failed to parse reading lists");
+
+    assert_eq!(own.as_array().unwrap().len(), 3);
+
+    let private_response = ctx
+        .client
+        .get(format!("http://localhost:8080/api/lists/{}", private_id))
+        .header("Authorization", &owner_auth)
+        .send()
+        .await
+        .expect("Failed to read list");
+
+    assert_eq!(private_response.status(), 200);
+
+    // Other users cannot change or delete someone else's list
+    let patch_response = ctx
+        .client
+        .patch(format!("http://localhost:8080/api/reading-lists/{}", public_id))
+        .header("Authorization", &other_auth)
+        .json(&json!({ "name": "Mine now" }))
+        .send()
+        .await
+        .expect("Failed to patch reading list");
+
+    assert_eq!(patch_response.status(), 403);
+
+    let delete_response = ctx
+        .client
+        .delete(format!("http://localhost:8080/api/reading-lists/{}", public_id))
+        .header("Authorization", &other_auth)
+        .send()
+        .await
+        .expect("Failed to delete reading list");
+
+    assert_eq!(delete_response.status(), 403);
+
+    let anonymous_create = ctx
+        .client
+        .post("http://localhost:8080/api/reading-lists")
+        .json(&json!({ "name": "Nobody's", "novel": [], "status": "Reading" }))
+        .send()
+        .await
+        .expect("Failed to create reading list");
+
+    assert_eq!(anonymous_create.status(), 401);
+}
+
+#[tokio::test]
+#[serial]
+async fn test_list_descriptions_and_blurbs_are_rendered() {
+    let ctx = TestContext::new().await;
+    let auth = ctx.register_and_login("curator").await;
+    let novel_id = ctx.insert_novel("Curated Novel", None).await;
+
+    let created: serde_json::Value = ctx
+        .client
+        .post("http://localhost:8080/api/reading-lists")
+        .header("Authorization", &auth)
+        .json(&json!({ "name": "Picks", "description": "**Best** of the year <script>alert(1)</script>", "novel": [], "status": "Reading", "visibility": "Public" }))
+        .send()
+        .await
+        .expect("Failed to create reading list")
+        .json()
+        .await
+        .expect("Failed to parse reading list");
+
+    let html = created["description_html"].as_str().expect("No rendered description");
+    assert!(html.contains("<strong>Best</strong>"));
+    assert!(!html.contains("<script"));
+
+    let list: serde_json::Value = ctx
+        .client
+        .put(format!("http://localhost:8080/api/lists/{}/novels", created["id"]))
+        .header("Authorization", &auth)
+        .json(&json!([{ "novel_id": novel_id, "blurb": "Read it for ||the twist||" }]))
+        .send()
+        .await
+        .expect("Failed to put novels")
+        .json()
+        .await
+        .expect("Failed to parse list");
+
+    assert_eq!(list["description_html"], created["description_html"]);
+    assert!(list["novels"][0]["blurb_html"].as_str().unwrap().contains(r#"<span class="spoiler">the twist</span>"#));
+
+    let browsed: serde_json::Value = ctx
+        .client
+        .get("http://localhost:8080/api/lists")
+        .send()
+        .await
+        .expect("Failed to browse lists")
+        .json()
+        .await
+        .expect("Failed to parse lists");
+
+    assert_eq!(browsed[0]["description_html"], created["description_html"]);
+}
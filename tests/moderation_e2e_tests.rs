@@ -0,0 +1,173 @@
+mod common;
+
+use common::TestContext;
+use serde_json::json;
+use serial_test::serial;
+
+#[tokio::test]
+#[serial]
+async fn test_reports_and_moderation_queue() {
+    let ctx = TestContext::new().await;
+    let reporter = ctx.register_and_login("reporter").await;
+    let offender = ctx.register_and_login("offender").await;
+
+    let offender_account: serde_json::Value = ctx
+        .client
+        .get("http://localhost:8080/api/me")
+        .header("Authorization", &offender)
+        .send()
+        .await
+        .expect("Failed to load account")
+        .json()
+        .await
+        .expect("Failed to parse account");
+    let offender_id = offender_account["id"].as_i64().expect("No user id");
+
+    let anonymous = ctx
+        .client
+        .post("http://localhost:8080/api/reports")
+        .json(&json!({ "target_type": "User", "target_id": offender_id, "reason": "Spam" }))
+        .send()
+        .await
+        .expect("Failed to report");
+
+    assert_eq!(anonymous.status(), 401);
+
+    let missing = ctx
+        .client
+        .post("http://localhost:8080/api/reports")
+        .header("Authorization", &reporter)
+        .json(&json!({ "target_type": "Review", "target_id": 999999, "reason": "Spoiler" }))
+        .send()
+        .await
+        .expect("Failed to report");
+
+    assert_eq!(missing.status(), 404);
+
+    let report = ctx
+        .client
+        .post("http://localhost:8080/api/reports")
+        .header("Authorization", &reporter)
+        .json(&json!({
+            "target_type": "User",
+            "target_id": offender_id,
+            "reason": "Abuse",
+            "details": "Insults in every comment"
+        }))
+        .send()
+        .await
+        .expect("Failed to report");
+
+    assert_eq!(report.status(), 201);
+    let report: serde_json::Value = report.json().await.expect("Failed to parse report");
+    assert_eq!(report["status"], "Open");
+
+    let duplicate = ctx
+        .client
+        .post("http://localhost:8080/api/reports")
+        .header("Authorization", &reporter)
+        .json(&json!({ "target_type": "User", "target_id": offender_id, "reason": "Abuse" }))
+        .send()
+        .await
+        .expect("Failed to report");
+
+    assert_eq!(duplicate.status(), 409);
+
+    let queue = ctx
+        .client
+        .get("http://localhost:8080/api/admin/reports")
+        .header("Authorization", &reporter)
+        .send()
+        .await
+        .expect("Failed to load queue");
+
+    assert_eq!(queue.status(), 403);
+
+    let actions = ctx
+        .client
+        .post("http://localhost:8080/api/admin/reports/actions")
+        .header("Authorization", &reporter)
+        .json(&json!({ "report_ids": [report["id"]], "action": "BanUser" }))
+        .send()
+        .await
+        .expect("Failed to act on reports");
+
+    assert_eq!(actions.status(), 403);
+
+    let audit = ctx
+        .client
+        .get("http://localhost:8080/api/admin/audit-log")
+        .header("Authorization", &reporter)
+        .send()
+        .await
+        .expect("Failed to load audit log");
+
+    assert_eq!(audit.status(), 403);
+}
+
+#[tokio::test]
+#[serial]
+async fn test_banned_users_are_locked_out() {
+    let ctx = TestContext::new().await;
+    let moderator = ctx.register_and_login("ban_moderator").await;
+    ctx.set_role("ban_moderator", "moderator").await;
+    let reporter = ctx.register_and_login("ban_reporter").await;
+    let offender = ctx.register_and_login("ban_offender").await;
+
+    let offender_account: serde_json::Value = ctx
+        .client
+        .get("http://localhost:8080/api/me")
+        .header("Authorization", &offender)
+        .send()
+        .await
+        .expect("Failed to load account")
+        .json()
+        .await
+        .expect("Failed to parse account");
+
+    let report: serde_json::Value = ctx
+        .client
+        .post("http://localhost:8080/api/reports")
+        .header("Authorization", &reporter)
+        .json(&json!({ "target_type": "User", "target_id": offender_account["id"], "reason": "Abuse" }))
+        .send()
+        .await
+        .expect("Failed to report")
+        .json()
+        .await
+        .expect("Failed to parse report");
+
+    let ban = ctx
+        .client
+        .post("http://localhost:8080/api/admin/reports/actions")
+        .header("Authorization", &moderator)
+        .json(&json!({ "report_ids": [report["id"]], "action": "BanUser" }))
+        .send()
+        .await
+        .expect("Failed to ban");
+
+    assert_eq!(ban.status(), 200);
+
+    // The ban revokes the offender's session
+    let revoked = ctx
+        .client
+        .get("http://localhost:8080/api/me")
+        .header("Authorization", &offender)
+        .send()
+        .await
+        .expect("Failed to load account");
+
+    assert_eq!(revoked.status(), 401);
+
+    // A key that appeared after the ban is refused as well
+    let late_key = ctx.insert_api_key("ban_offender").await;
+    let refused = ctx
+        .client
+        .get("http://localhost:8080/api/me")
+        .header("Authorization", format!("Bearer {}", late_key))
+        .send()
+        .await
+        .expect("Failed to load account");
+
+    assert_eq!(refused.status(), 403);
+}
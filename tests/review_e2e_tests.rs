@@ -0,0 +1,285 @@
+mod common;
+
+use common::TestContext;
+use serde_json::json;
+use serial_test::serial;
+
+#[tokio::test]
+#[serial]
+async fn test_review_sorting_and_votes() {
+    let ctx = TestContext::new().await;
+    let auth = ctx.register_and_login("critic").await;
+
+    for sort in ["newest", "helpful", "highest_rating", "lowest_rating"] {
+        let response = ctx
+            .client
+            .get(format!("http://localhost:8080/api/reviews?novel_id=1&sort={}", sort))
+            .send()
+            .await
+            .expect("Failed to list reviews");
+
+        assert_eq!(response.status(), 200);
+    }
+
+    let anonymous = ctx
+        .client
+        .put("http://localhost:8080/api/reviews/999999/vote")
+        .json(&json!({ "helpful": true }))
+        .send()
+        .await
+        .expect("Failed to vote");
+
+    assert_eq!(anonymous.status(), 401);
+
+    let missing = ctx
+        .client
+        .put("http://localhost:8080/api/reviews/999999/vote")
+        .header("Authorization", &auth)
+        .json(&json!({ "helpful": true }))
+        .send()
+        .await
+        .expect("Failed to vote");
+
+    assert_eq!(missing.status(), 404);
+}
+
+#[tokio::test]
+#[serial]
+async fn test_comment_threads() {
+    let ctx = TestContext::new().await;
+    let auth = ctx.register_and_login("commenter").await;
+
+    let missing = ctx
+        .client
+        .get("http://localhost:8080/api/reviews/999999/comments")
+        .send()
+        .await
+        .expect("Failed to list comments");
+
+    assert_eq!(missing.status(), 404);
+
+    let anonymous = ctx
+        .client
+        .post("http://localhost:8080/api/chapters/999999/comments")
+        .json(&json!({ "content": "First!" }))
+        .send()
+        .await
+        .expect("Failed to comment");
+
+    assert_eq!(anonymous.status(), 401);
+
+    let empty = ctx
+        .client
+        .post("http://localhost:8080/api/chapters/999999/comments")
+        .header("Authorization", &auth)
+        .json(&json!({ "content": "   " }))
+        .send()
+        .await
+        .expect("Failed to comment");
+
+    assert_eq!(empty.status(), 422);
+
+    let unknown = ctx
+        .client
+        .patch("http://localhost:8080/api/comments/999999")
+        .header("Authorization", &auth)
+        .json(&json!({ "content": "edited" }))
+        .send()
+        .await
+        .expect("Failed to edit comment");
+
+    assert_eq!(unknown.status(), 404);
+}
+
+#[tokio::test]
+#[serial]
+async fn test_review_writes_belong_to_their_author() {
+    let ctx = TestContext::new().await;
+    let author_auth = ctx.register_and_login("reviewer").await;
+    let other_auth = ctx.register_and_login("bystander").await;
+    let moderator_auth = ctx.register_and_login("review_moderator").await;
+    ctx.set_role("review_moderator", "moderator").await;
+    let base_url = "http://localhost:8080/api/reviews";
+
+    let novel_id = ctx.insert_novel("Reviewed Novel", Some("ko")).await;
+    let novel: serde_json::Value = ctx
+        .client
+        .get(format!("http://localhost:8080/api/novels/{}", novel_id))
+        .send()
+        .await
+        .expect("Failed to get novel")
+        .json()
+        .await
+        .expect("Failed to parse novel");
+    let bystander: serde_json::Value = ctx
+        .client
+        .get("http://localhost:8080/api/me")
+        .header("Authorization", &other_auth)
+        .send()
+        .await
+        .expect("Failed to get me")
+        .json()
+        .await
+        .expect("Failed to parse account");
+
+    let anonymous_create = ctx
+        .client
+        .post(base_url)
+        .json(&json!({ "content": "Anonymous", "novel": novel, "rating": "1" }))
+        .send()
+        .await
+        .expect("Failed to create review");
+
+    assert_eq!(anonymous_create.status(), 401);
+
+    // The author comes from the session, not from the body
+    let create_response = ctx
+        .client
+        .post(base_url)
+        .header("Authorization", &author_auth)
+        .json(&json!({ "content": "Great", "novel": novel, "rating": "5", "user": { "id": bystander["id"] } }))
+        .send()
+        .await
+        .expect("Failed to create review");
+
+    assert_eq!(create_response.status(), 200);
+    let review_id = create_response.json::<serde_json::Value>().await.expect("Failed to parse review")["id"].as_i64().unwrap();
+
+    let author_of = |review: serde_json::Value| review["user"]["username"].as_str().map(str::to_string);
+    let read = || async {
+        ctx.client
+            .get(format!("{}/{}", base_url, review_id))
+            .send()
+            .await
+            .expect("Failed to get review")
+            .json::<serde_json::Value>()
+            .await
+            .expect("Failed to parse review")
+    };
+    assert_eq!(author_of(read().await).as_deref(), Some("reviewer"));
+
+    // Other users can neither edit nor delete it
+    let other_patch = ctx
+        .client
+        .patch(format!("{}/{}", base_url, review_id))
+        .header("Authorization", &other_auth)
+        .json(&json!({ "content": "Hijacked" }))
+        .send()
+        .await
+        .expect("Failed to patch review");
+
+    assert_eq!(other_patch.status(), 403);
+
+    let other_put = ctx
+        .client
+        .put(format!("{}/{}", base_url, review_id))
+        .header("Authorization", &other_auth)
+        .json(&json!({ "content": "Hijacked", "novel": novel, "rating": "1" }))
+        .send()
+        .await
+        .expect("Failed to put review");
+
+    assert_eq!(other_put.status(), 403);
+
+    let other_delete = ctx
+        .client
+        .delete(format!("{}/{}", base_url, review_id))
+        .header("Authorization", &other_auth)
+        .send()
+        .await
+        .expect("Failed to delete review");
+
+    assert_eq!(other_delete.status(), 403);
+
+    // Its author cannot hand it to someone else either
+    let author_patch = ctx
+        .client
+        .patch(format!("{}/{}", base_url, review_id))
+        .header("Authorization", &author_auth)
+        .json(&json!({ "title": "Mine", "user": { "id": bystander["id"] } }))
+        .send()
+        .await
+        .expect("Failed to patch review");
+
+    assert_eq!(author_patch.status(), 200);
+    let review = read().await;
+    assert_eq!(review["title"], "Mine");
+    assert_eq!(author_of(review).as_deref(), Some("reviewer"));
+
+    // So the author still cannot vote on it
+    let own_vote = ctx
+        .client
+        .put(format!("{}/{}/vote", base_url, review_id))
+        .header("Authorization", &author_auth)
+        .json(&json!({ "helpful": true }))
+        .send()
+        .await
+        .expect("Failed to vote");
+
+    assert_eq!(own_vote.status(), 422);
+
+    // Moderators may delete anyone's review
+    let moderator_delete = ctx
+        .client
+        .delete(format!("{}/{}", base_url, review_id))
+        .header("Authorization", &moderator_auth)
+        .send()
+        .await
+        .expect("Failed to delete review");
+
+    assert_eq!(moderator_delete.status(), 204);
+}
+
+#[tokio::test]
+#[serial]
+async fn test_comment_markdown_is_rendered_and_sanitized() {
+    let ctx = TestContext::new().await;
+    let auth = ctx.register_and_login("commenter").await;
+    ctx.register_and_login("reviewed").await;
+    let novel_id = ctx.insert_novel("Commented Novel", None).await;
+    let review_id = ctx.insert_review("reviewed", novel_id).await;
+
+    let created: serde_json::Value = ctx
+        .client
+        .post(format!("http://localhost:8080/api/reviews/{}/comments", review_id))
+        .header("Authorization", &auth)
+        .json(&json!({ "content": "*Agreed*, ||the ending|| <script>alert(1)</script>" }))
+        .send()
+        .await
+        .expect("Failed to comment")
+        .json()
+        .await
+        .expect("Failed to parse comment");
+
+    let html = created["content_html"].as_str().expect("No rendered comment");
+    assert!(html.contains("<em>Agreed</em>"));
+    assert!(html.contains(r#"<span class="spoiler">the ending</span>"#));
+    assert!(!html.contains("<script"));
+
+    // Edits are rendered again
+    let edited: serde_json::Value = ctx
+        .client
+        .patch(format!("http://localhost:8080/api/comments/{}", created["id"]))
+        .header("Authorization", &auth)
+        .json(&json!({ "content": "**Changed** my mind" }))
+        .send()
+        .await
+        .expect("Failed to edit comment")
+        .json()
+        .await
+        .expect("Failed to parse comment");
+
+    assert!(edited["content_html"].as_str().unwrap().contains("<strong>Changed</strong>"));
+
+    let page: serde_json::Value = ctx
+        .client
+        .get(format!("http://localhost:8080/api/reviews/{}/comments", review_id))
+        .send()
+        .await
+        .expect("Failed to list comments")
+        .json()
+        .await
+        .expect("Failed to parse comments");
+
+    assert_eq!(page["comments"][0]["content_html"], edited["content_html"]);
+}
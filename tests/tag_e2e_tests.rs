@@ -8,6 +8,8 @@ use serial_test::serial;
 #[serial]
 async fn test_tag_crud_operations() {
     let ctx = TestContext::new().await;
+    let auth = ctx.register_and_login("tag_editor").await;
+    ctx.set_role("tag_editor", "moderator").await;
     let base_url = "http://localhost:8080/api/tags";
 
     // CREATE - Create a new tag
@@ -18,27 +20,27 @@ async fn test_tag_crud_operations() {
     let create_response = ctx
         .client
         .post(base_url)
-        .header("Authorization", ctx.get_auth_header())
+        .header("Authorization", &auth)
         .json(&create_payload)
         .send()
         .await
         .expect("Failed to create tag");
 
-    assert_eq!(create_response.status(), 201);
+    assert_eq!(create_response.status(), 200);
 
     let created_tag: serde_json::Value = create_response
         .json()
         .await
         .expect("Failed to parse created tag");
 
-    let tag_id = created_tag["id"].as_str().expect("No tag ID");
+    let tag_id = created_tag["id"].as_i64().expect("No tag ID");
     assert_eq!(created_tag["name"], "Technology");
 
     // READ - Get the created tag
     let read_response = ctx
         .client
         .get(&format!("{}/{}", base_url, tag_id))
-        .header("Authorization", ctx.get_auth_header())
+        .header("Authorization", &auth)
         .send()
         .await
         .expect("Failed to get tag");
@@ -50,14 +52,14 @@ async fn test_tag_crud_operations() {
         .await
         .expect("Failed to parse tag");
 
-    assert_eq!(read_tag["id"], tag_id);
+    assert_eq!(read_tag["id"].as_i64(), Some(tag_id));
     assert_eq!(read_tag["name"], "Technology");
 
     // LIST - Get all tags
     let list_response = ctx
         .client
         .get(base_url)
-        .header("Authorization", ctx.get_auth_header())
+        .header("Authorization", &auth)
         .send()
         .await
         .expect("Failed to list tags");
@@ -79,7 +81,7 @@ async fn test_tag_crud_operations() {
     let update_response = ctx
         .client
         .put(&format!("{}/{}", base_url, tag_id))
-        .header("Authorization", ctx.get_auth_header())
+        .header("Authorization", &auth)
         .json(&update_payload)
         .send()
         .await
@@ -94,11 +96,42 @@ async fn test_tag_crud_operations() {
 
     assert_eq!(updated_tag["name"], "Tech & Innovation");
 
+    // PATCH - Leaving parent_id out keeps the parent, null clears it
+    let child: serde_json::Value = ctx
+        .client
+        .post(base_url)
+        .header("Authorization", &auth)
+        .json(&json!({ "name": "Gadgets", "parent_id": tag_id }))
+        .send()
+        .await
+        .expect("Failed to create child tag")
+        .json()
+        .await
+        .expect("Failed to parse child tag");
+
+    assert_eq!(child["parent_id"].as_i64(), Some(tag_id));
+
+    for (patch, parent_id) in [(json!({ "description": "Devices" }), json!(tag_id)), (json!({ "parent_id": null }), json!(null))] {
+        let patched: serde_json::Value = ctx
+            .client
+            .patch(format!("{}/{}", base_url, child["id"]))
+            .header("Authorization", &auth)
+            .json(&patch)
+            .send()
+            .await
+            .expect("Failed to patch tag")
+            .json()
+            .await
+            .expect("Failed to parse patched tag");
+
+        assert_eq!(patched["parent_id"], parent_id, "{}", patch);
+    }
+
     // DELETE - Delete the tag
     let delete_response = ctx
         .client
         .delete(&format!("{}/{}", base_url, tag_id))
-        .header("Authorization", ctx.get_auth_header())
+        .header("Authorization", &auth)
         .send()
         .await
         .expect("Failed to delete tag");
@@ -109,7 +142,7 @@ async fn test_tag_crud_operations() {
     let verify_response = ctx
         .client
         .get(&format!("{}/{}", base_url, tag_id))
-        .header("Authorization", ctx.get_auth_header())
+        .header("Authorization", &auth)
         .send()
         .await
         .expect("Failed to verify deletion");
@@ -121,6 +154,8 @@ async fn test_tag_crud_operations() {
 #[serial]
 async fn test_tag_bulk_operations() {
     let ctx = TestContext::new().await;
+    let auth = ctx.register_and_login("bulk_tagger").await;
+    ctx.set_role("bulk_tagger", "moderator").await;
     let base_url = "http://localhost:8080/api/tags";
 
     // Create multiple tags
@@ -132,21 +167,21 @@ async fn test_tag_bulk_operations() {
         let response = ctx
             .client
             .post(base_url)
-            .header("Authorization", ctx.get_auth_header())
+            .header("Authorization", &auth)
             .json(&payload)
             .send()
             .await
             .expect("Failed to create tag");
 
         let tag: serde_json::Value = response.json().await.expect("Failed to parse tag");
-        created_ids.push(tag["id"].as_str().unwrap().to_string());
+        created_ids.push(tag["id"].as_i64().unwrap());
     }
 
     // Verify all tags exist
     let list_response = ctx
         .client
         .get(base_url)
-        .header("Authorization", ctx.get_auth_header())
+        .header("Authorization", &auth)
         .send()
         .await
         .expect("Failed to list tags");
@@ -159,8 +194,261 @@ async fn test_tag_bulk_operations() {
         let _ = ctx
             .client
             .delete(&format!("{}/{}", base_url, id))
-            .header("Authorization", ctx.get_auth_header())
+            .header("Authorization", &auth)
             .send()
             .await;
     }
 }
+
+#[tokio::test]
+#[serial]
+async fn test_novel_tag_proposals_and_votes() {
+    let ctx = TestContext::new().await;
+    let auth = ctx.register_and_login("tagger").await;
+
+    let tags = ctx
+        .client
+        .get("http://localhost:8080/api/novels/999999/tags")
+        .send()
+        .await
+        .expect("Failed to list novel tags");
+
+    assert_eq!(tags.status(), 200);
+    assert_eq!(tags.json::<serde_json::Value>().await.expect("Failed to parse tags"), json!([]));
+
+    // New accounts lack the reputation to propose tags
+    let proposal = ctx
+        .client
+        .post("http://localhost:8080/api/novels/999999/tags")
+        .header("Authorization", &auth)
+        .json(&json!({ "tag_id": 1 }))
+        .send()
+        .await
+        .expect("Failed to propose tag");
+
+    assert_eq!(proposal.status(), 403);
+
+    let anonymous = ctx
+        .client
+        .put("http://localhost:8080/api/novels/999999/tags/1/vote")
+        .json(&json!({ "up": true }))
+        .send()
+        .await
+        .expect("Failed to vote");
+
+    assert_eq!(anonymous.status(), 401);
+
+    let missing = ctx
+        .client
+        .put("http://localhost:8080/api/novels/999999/tags/1/vote")
+        .header("Authorization", &auth)
+        .json(&json!({ "up": false }))
+        .send()
+        .await
+        .expect("Failed to vote");
+
+    assert_eq!(missing.status(), 404);
+}
+
+#[tokio::test]
+#[serial]
+async fn test_tag_hierarchy_aliases_and_merge() {
+    let ctx = TestContext::new().await;
+    let auth = ctx.register_and_login("tag_gardener").await;
+
+    let unknown = ctx
+        .client
+        .get("http://localhost:8080/api/tags/resolve?name=no-such-tag")
+        .send()
+        .await
+        .expect("Failed to resolve tag");
+
+    assert_eq!(unknown.status(), 404);
+
+    let children = ctx
+        .client
+        .get("http://localhost:8080/api/tags/999999/children")
+        .send()
+        .await
+        .expect("Failed to list children");
+
+    assert_eq!(children.status(), 404);
+
+    // An unknown tag filters every novel out rather than none
+    let novels = ctx
+        .client
+        .get("http://localhost:8080/api/novels?tag=no-such-tag&include_descendants=true")
+        .send()
+        .await
+        .expect("Failed to list novels");
+
+    assert_eq!(novels.status(), 200);
+    assert_eq!(novels.json::<serde_json::Value>().await.expect("Failed to parse novels"), json!([]));
+
+    // Tags, aliases and merges are for moderators
+    let created = ctx
+        .client
+        .post("http://localhost:8080/api/tags")
+        .header("Authorization", &auth)
+        .json(&json!({ "name": "Unreviewed" }))
+        .send()
+        .await
+        .expect("Failed to create tag");
+
+    assert_eq!(created.status(), 403);
+
+    for method in ["PUT", "PATCH", "DELETE"] {
+        let response = ctx
+            .client
+            .request(method.parse().unwrap(), "http://localhost:8080/api/tags/1")
+            .header("Authorization", &auth)
+            .json(&json!({ "name": "Renamed" }))
+            .send()
+            .await
+            .expect("Failed to write tag");
+
+        assert_eq!(response.status(), 403, "{}", method);
+    }
+
+    let alias = ctx
+        .client
+        .post("http://localhost:8080/api/tags/1/aliases")
+        .header("Authorization", &auth)
+        .json(&json!({ "name": "Cultivation" }))
+        .send()
+        .await
+        .expect("Failed to create alias");
+
+    assert_eq!(alias.status(), 403);
+
+    let merge = ctx
+        .client
+        .post("http://localhost:8080/api/tags/1/merge")
+        .header("Authorization", &auth)
+        .json(&json!({ "into": 2 }))
+        .send()
+        .await
+        .expect("Failed to merge tags");
+
+    assert_eq!(merge.status(), 403);
+
+    let anonymous = ctx
+        .client
+        .post("http://localhost:8080/api/tags/1/merge")
+        .json(&json!({ "into": 2 }))
+        .send()
+        .await
+        .expect("Failed to merge tags");
+
+    assert_eq!(anonymous.status(), 401);
+}
+
+#[tokio::test]
+#[serial]
+async fn test_tag_slugs_are_generated_and_redirect() {
+    let ctx = TestContext::new().await;
+    let auth = ctx.register_and_login("slug_editor").await;
+    ctx.set_role("slug_editor", "moderator").await;
+    let base_url = "http://localhost:8080/api/tags";
+
+    let mut slugs = Vec::new();
+    for _ in 0..2 {
+        let created: serde_json::Value = ctx
+            .client
+            .post(base_url)
+            .header("Authorization", &auth)
+            .json(&json!({ "name": "Xiānxiá Café" }))
+            .send()
+            .await
+            .expect("Failed to create tag")
+            .json()
+            .await
+            .expect("Failed to parse created tag");
+        slugs.push(created);
+    }
+
+    assert_eq!(slugs[0]["slug"], "xianxia-cafe");
+    assert_eq!(slugs[1]["slug"], "xianxia-cafe-2");
+    let tag_id = slugs[0]["id"].as_i64().expect("No tag ID");
+
+    let renamed: serde_json::Value = ctx
+        .client
+        .patch(format!("{}/{}", base_url, tag_id))
+        .header("Authorization", &auth)
+        .json(&json!({ "name": "Cultivation" }))
+        .send()
+        .await
+        .expect("Failed to rename tag")
+        .json()
+        .await
+        .expect("Failed to parse renamed tag");
+
+    assert_eq!(renamed["slug"], "cultivation");
+
+    // The old slug redirects to the new one
+    let by_old_slug = ctx
+        .client
+        .get(format!("{}/by-slug/xianxia-cafe", base_url))
+        .send()
+        .await
+        .expect("Failed to get tag by slug");
+
+    assert_eq!(by_old_slug.status(), 200);
+    assert!(by_old_slug.url().path().ends_with("/by-slug/cultivation"));
+    let tag: serde_json::Value = by_old_slug.json().await.expect("Failed to parse tag");
+    assert_eq!(tag["id"].as_i64(), Some(tag_id));
+
+    // The redirect keeps the query string
+    let with_query = ctx
+        .client
+        .get(format!("{}/by-slug/xianxia-cafe?unfiltered=true", base_url))
+        .send()
+        .await
+        .expect("Failed to get tag by slug");
+
+    assert_eq!(with_query.status(), 200);
+    assert!(with_query.url().path().ends_with("/by-slug/cultivation"));
+    assert_eq!(with_query.url().query(), Some("unfiltered=true"));
+
+    let missing = ctx
+        .client
+        .get(format!("{}/by-slug/no-such-tag", base_url))
+        .send()
+        .await
+        .expect("Failed to get tag by slug");
+
+    assert_eq!(missing.status(), 404);
+}
+
+#[tokio::test]
+#[serial]
+async fn test_novels_filter_by_genre_tag() {
+    let ctx = TestContext::new().await;
+    let auth = ctx.register_and_login("genre_editor").await;
+    ctx.set_role("genre_editor", "moderator").await;
+
+    for (name, category) in [("Wuxia", "Genre"), ("Harem", "Theme")] {
+        let created = ctx
+            .client
+            .post("http://localhost:8080/api/tags")
+            .header("Authorization", &auth)
+            .json(&json!({ "name": name, "category": category }))
+            .send()
+            .await
+            .expect("Failed to create tag");
+
+        assert_eq!(created.status(), 200);
+    }
+
+    for query in ["genre=wuxia", "genre=Harem", "tag=harem"] {
+        let novels = ctx
+            .client
+            .get(format!("http://localhost:8080/api/novels?{}", query))
+            .send()
+            .await
+            .expect("Failed to list novels");
+
+        assert_eq!(novels.status(), 200);
+        assert_eq!(novels.json::<serde_json::Value>().await.expect("Failed to parse novels"), json!([]));
+    }
+}
@@ -0,0 +1,197 @@
+mod common;
+
+use common::TestContext;
+use data_encoding::BASE32_NOPAD;
+use hmac::{Hmac, Mac};
+use serde_json::json;
+use serial_test::serial;
+use sha1::Sha1;
+
+fn totp_code(secret: &str, offset_steps: i64) -> String {
+    let key = BASE32_NOPAD.decode(secret.as_bytes()).unwrap();
+    let step = chrono::Utc::now().timestamp() / 30 + offset_steps;
+    let mut mac = Hmac::<Sha1>::new_from_slice(&key).unwrap();
+    mac.update(&step.to_be_bytes());
+    let digest = mac.finalize().into_bytes();
+    let offset = (digest[19] & 0x0f) as usize;
+    let binary = u32::from_be_bytes([digest[offset] & 0x7f, digest[offset + 1], digest[offset + 2], digest[offset + 3]]);
+    format!("{:06}", binary % 1_000_000)
+}
+
+#[tokio::test]
+#[serial]
+async fn test_two_factor_enrollment_and_login() {
+    let ctx = TestContext::new().await;
+    let base_url = "http://localhost:8080/api/auth";
+
+    ctx.client
+        .post(format!("{}/register", base_url))
+        .json(&json!({
+            "username": "careful",
+            "email": "careful@example.com",
+            "password": "hunter2hunter2"
+        }))
+        .send()
+        .await
+        .expect("Failed to register");
+
+    // LOGIN - Plain users get a full session without 2FA
+    let login_response = ctx
+        .client
+        .post(format!("{}/login", base_url))
+        .json(&json!({ "login": "careful", "password": "hunter2hunter2" }))
+        .send()
+        .await
+        .expect("Failed to login");
+
+    assert_eq!(login_response.status(), 200);
+    let session: serde_json::Value = login_response.json().await.expect("Failed to parse session");
+    assert_eq!(session["two_factor_enrollment_required"], false);
+    let auth = format!("Bearer {}", session["token"].as_str().unwrap());
+
+    // ENROLL - Returns a secret and an otpauth URI for the QR code
+    let enrollment: serde_json::Value = ctx
+        .client
+        .post(format!("{}/2fa/enroll", base_url))
+        .header("Authorization", &auth)
+        .send()
+        .await
+        .expect("Failed to enroll")
+        .json()
+        .await
+        .expect("Failed to parse enrollment");
+
+    let secret = enrollment["secret"].as_str().expect("No secret").to_string();
+    assert!(enrollment["provisioning_uri"].as_str().unwrap().starts_with("otpauth://totp/NovelUpdates:careful?"));
+
+    // ACTIVATE - A wrong code is refused, the current one enables 2FA
+    let wrong_response = ctx
+        .client
+        .post(format!("{}/2fa/activate", base_url))
+        .header("Authorization", &auth)
+        .json(&json!({ "code": "000000" }))
+        .send()
+        .await
+        .expect("Failed to activate");
+
+    assert_eq!(wrong_response.status(), 400);
+
+    let activate_response = ctx
+        .client
+        .post(format!("{}/2fa/activate", base_url))
+        .header("Authorization", &auth)
+        .json(&json!({ "code": totp_code(&secret, 0) }))
+        .send()
+        .await
+        .expect("Failed to activate");
+
+    assert_eq!(activate_response.status(), 200);
+    let codes: serde_json::Value = activate_response.json().await.expect("Failed to parse recovery codes");
+    let recovery_codes = codes["recovery_codes"].as_array().expect("No recovery codes");
+    assert_eq!(recovery_codes.len(), 10);
+    let recovery_code = recovery_codes[0].as_str().unwrap().to_string();
+
+    // Password alone is no longer enough
+    let password_only = ctx
+        .client
+        .post(format!("{}/login", base_url))
+        .json(&json!({ "login": "careful@example.com", "password": "hunter2hunter2" }))
+        .send()
+        .await
+        .expect("Failed to login");
+
+    assert_eq!(password_only.status(), 401);
+    let body: serde_json::Value = password_only.json().await.unwrap();
+    assert_eq!(body["two_factor_required"], true);
+
+    // The next TOTP step logs in; the step used for activation cannot be replayed
+    let with_code = ctx
+        .client
+        .post(format!("{}/login", base_url))
+        .json(&json!({ "login": "careful", "password": "hunter2hunter2", "totp_code": totp_code(&secret, 1) }))
+        .send()
+        .await
+        .expect("Failed to login");
+
+    assert_eq!(with_code.status(), 200);
+
+    // Recovery codes work exactly once
+    for expected in [200, 401] {
+        let with_recovery = ctx
+            .client
+            .post(format!("{}/login", base_url))
+            .json(&json!({ "login": "careful", "password": "hunter2hunter2", "recovery_code": recovery_code }))
+            .send()
+            .await
+            .expect("Failed to login");
+
+        assert_eq!(with_recovery.status(), expected);
+    }
+}
+
+#[tokio::test]
+#[serial]
+async fn test_admins_change_the_two_factor_policy() {
+    let ctx = TestContext::new().await;
+    let policy_url = "http://localhost:8080/api/admin/two-factor-policy";
+    let admin_auth = ctx.register_and_login("policy_admin").await;
+    ctx.set_role("policy_admin", "admin").await;
+    let user_auth = ctx.register_and_login("policy_user").await;
+
+    // Only admins see or change the policy
+    let forbidden_response = ctx
+        .client
+        .put(policy_url)
+        .header("Authorization", &user_auth)
+        .json(&json!({ "role": "User", "required": true }))
+        .send()
+        .await
+        .expect("Failed to update policy");
+
+    assert_eq!(forbidden_response.status(), 403);
+
+    let policy: serde_json::Value = ctx
+        .client
+        .get(policy_url)
+        .header("Authorization", &admin_auth)
+        .send()
+        .await
+        .expect("Failed to read policy")
+        .json()
+        .await
+        .expect("Failed to parse policy");
+
+    assert_eq!(policy, json!([
+        { "role": "User", "required": false },
+        { "role": "Moderator", "required": true },
+        { "role": "Admin", "required": true }
+    ]));
+
+    let update_response = ctx
+        .client
+        .put(policy_url)
+        .header("Authorization", &admin_auth)
+        .json(&json!({ "role": "User", "required": true }))
+        .send()
+        .await
+        .expect("Failed to update policy");
+
+    assert_eq!(update_response.status(), 200);
+    let policy: serde_json::Value = update_response.json().await.expect("Failed to parse policy");
+    assert_eq!(policy[0], json!({ "role": "User", "required": true }));
+
+    // The next login of a plain user has to enroll first
+    let session: serde_json::Value = ctx
+        .client
+        .post("http://localhost:8080/api/auth/login")
+        .json(&json!({ "login": "policy_user", "password": "long enough password" }))
+        .send()
+        .await
+        .expect("Failed to login")
+        .json()
+        .await
+        .expect("Failed to parse session");
+
+    assert_eq!(session["two_factor_enrollment_required"], true);
+    assert_eq!(session["scopes"], json!([]));
+}
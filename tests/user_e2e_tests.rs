@@ -0,0 +1,1182 @@
+mod common;
+
+use common::TestContext;
+use serde_json::json;
+use serial_test::serial;
+
+#[tokio::test]
+#[serial]
+async fn test_public_profile_hides_account_fields() {
+    let ctx = TestContext::new().await;
+    let auth = ctx.register_and_login("profiled").await;
+
+    let profile_response = ctx
+        .client
+        .get("http://localhost:8080/api/profiles/profiled")
+        .send()
+        .await
+        .expect("Failed to get profile");
+
+    assert_eq!(profile_response.status(), 200);
+
+    let profile: serde_json::Value = profile_response.json().await.expect("Failed to parse profile");
+    assert_eq!(profile["username"], "profiled");
+    assert!(profile.get("email").is_none());
+    assert!(profile.get("password_hash").is_none());
+    assert!(profile["reading_lists"].is_array());
+    assert!(profile["reviews"].is_array());
+
+    // The private view carries the account fields
+    let me: serde_json::Value = ctx
+        .client
+        .get("http://localhost:8080/api/me")
+        .header("Authorization", &auth)
+        .send()
+        .await
+        .expect("Failed to get me")
+        .json()
+        .await
+        .expect("Failed to parse me");
+
+    assert_eq!(me["email"], "profiled@example.com");
+    assert_eq!(me["privacy"]["show_reviews"], true);
+
+    // Hiding reviews removes them from the public profile only
+    let patch_response = ctx
+        .client
+        .patch("http://localhost:8080/api/me")
+        .header("Authorization", &auth)
+        .json(&json!({ "show_reviews": false, "bio": "Reads at night" }))
+        .send()
+        .await
+        .expect("Failed to patch me");
+
+    assert_eq!(patch_response.status(), 200);
+
+    let profile: serde_json::Value = ctx
+        .client
+        .get("http://localhost:8080/api/profiles/profiled")
+        .send()
+        .await
+        .expect("Failed to get profile")
+        .json()
+        .await
+        .expect("Failed to parse profile");
+
+    assert!(profile["reviews"].is_null());
+    assert!(profile["reading_lists"].is_array());
+    assert_eq!(profile["bio"], "Reads at night");
+}
+
+#[tokio::test]
+#[serial]
+async fn test_me_requires_authentication() {
+    let ctx = TestContext::new().await;
+
+    let response = ctx
+        .client
+        .get("http://localhost:8080/api/me")
+        .send()
+        .await
+        .expect("Failed to get me");
+
+    assert_eq!(response.status(), 401);
+
+    let missing = ctx
+        .client
+        .get("http://localhost:8080/api/profiles/nobody")
+        .send()
+        .await
+        .expect("Failed to get profile");
+
+    assert_eq!(missing.status(), 404);
+}
+
+#[tokio::test]
+#[serial]
+async fn test_user_accounts_are_admin_only() {
+    let ctx = TestContext::new().await;
+    let user_auth = ctx.register_and_login("nosy").await;
+    let admin_auth = ctx.register_and_login("user_admin").await;
+    ctx.set_role("user_admin", "admin").await;
+
+    let anonymous_response = ctx
+        .client
+        .get("http://localhost:8080/api/users")
+        .send()
+        .await
+        .expect("Failed to list users");
+
+    assert_eq!(anonymous_response.status(), 401);
+
+    let user_response = ctx
+        .client
+        .get("http://localhost:8080/api/users")
+        .header("Authorization", &user_auth)
+        .send()
+        .await
+        .expect("Failed to list users");
+
+    assert_eq!(user_response.status(), 403);
+
+    let users: serde_json::Value = ctx
+        .client
+        .get("http://localhost:8080/api/users")
+        .header("Authorization", &admin_auth)
+        .send()
+        .await
+        .expect("Failed to list users")
+        .json()
+        .await
+        .expect("Failed to parse users");
+
+    let nosy = users.as_array().unwrap().iter().find(|user| user["username"] == "nosy").expect("User not listed");
+    assert_eq!(nosy["email"], "nosy@example.com");
+    assert!(nosy.get("password_hash").is_none());
+
+    let read_response = ctx
+        .client
+        .get(format!("http://localhost:8080/api/users/{}", nosy["id"]))
+        .header("Authorization", &user_auth)
+        .send()
+        .await
+        .expect("Failed to get user");
+
+    assert_eq!(read_response.status(), 403);
+
+    // Only admins edit accounts, and not even they set emails or passwords
+    let anonymous_create = ctx
+        .client
+        .post("http://localhost:8080/api/users")
+        .json(&json!({ "username": "sneaky", "email": "sneaky@example.com" }))
+        .send()
+        .await
+        .expect("Failed to create user");
+
+    assert_eq!(anonymous_create.status(), 401);
+
+    let user_patch = ctx
+        .client
+        .patch(format!("http://localhost:8080/api/users/{}", nosy["id"]))
+        .header("Authorization", &user_auth)
+        .json(&json!({ "display_name": "Nosy" }))
+        .send()
+        .await
+        .expect("Failed to patch user");
+
+    assert_eq!(user_patch.status(), 403);
+
+    let user_put = ctx
+        .client
+        .put(format!("http://localhost:8080/api/users/{}", nosy["id"]))
+        .header("Authorization", &user_auth)
+        .json(&json!({ "username": "nosy" }))
+        .send()
+        .await
+        .expect("Failed to put user");
+
+    assert_eq!(user_put.status(), 403);
+
+    let admin_patch = ctx
+        .client
+        .patch(format!("http://localhost:8080/api/users/{}", nosy["id"]))
+        .header("Authorization", &admin_auth)
+        .json(&json!({ "display_name": "Renamed", "email": "attacker@example.com", "password_hash": "attacker" }))
+        .send()
+        .await
+        .expect("Failed to patch user");
+
+    assert_eq!(admin_patch.status(), 200);
+    let patched: serde_json::Value = admin_patch.json().await.expect("Failed to parse user");
+    assert_eq!(patched["display_name"], "Renamed");
+    assert_eq!(patched["email"], "nosy@example.com");
+
+    let login_response = ctx
+        .client
+        .post("http://localhost:8080/api/auth/login")
+        .json(&json!({ "login": "nosy", "password": "long enough password" }))
+        .send()
+        .await
+        .expect("Failed to login");
+
+    assert_eq!(login_response.status(), 200);
+
+    // Accounts an admin opens get a mail to set their password
+    let admin_create = ctx
+        .client
+        .post("http://localhost:8080/api/users")
+        .header("Authorization", &admin_auth)
+        .json(&json!({ "username": "invited", "email": "invited@example.com" }))
+        .send()
+        .await
+        .expect("Failed to create user");
+
+    assert_eq!(admin_create.status(), 200);
+    let invited_mails = ctx.sent_mails("invited@example.com");
+    assert_eq!(invited_mails.len(), 1);
+    assert_eq!(invited_mails[0]["subject"], "Reset your password");
+
+    // Only admins hard-delete accounts
+    let anonymous_delete = ctx
+        .client
+        .delete(format!("http://localhost:8080/api/users/{}", nosy["id"]))
+        .send()
+        .await
+        .expect("Failed to delete user");
+
+    assert_eq!(anonymous_delete.status(), 401);
+
+    let admin_delete = ctx
+        .client
+        .delete(format!("http://localhost:8080/api/users/{}", nosy["id"]))
+        .header("Authorization", &admin_auth)
+        .send()
+        .await
+        .expect("Failed to delete user");
+
+    assert_eq!(admin_delete.status(), 204);
+
+    let deleted_response = ctx
+        .client
+        .get("http://localhost:8080/api/profiles/nosy")
+        .send()
+        .await
+        .expect("Failed to get profile");
+
+    assert_eq!(deleted_response.status(), 404);
+}
+
+#[tokio::test]
+#[serial]
+async fn test_export_and_scheduled_deletion() {
+    let ctx = TestContext::new().await;
+    let auth = ctx.register_and_login("leaving").await;
+
+    // Leave a trace in each part of the site the export covers
+    let novel_id = ctx.insert_novel("Exported Novel", Some("zh")).await;
+    let chapter_id = ctx.insert_chapter(novel_id, "1").await;
+    ctx.register_and_login("export_reviewer").await;
+    let review_id = ctx.insert_review("export_reviewer", novel_id).await;
+    let writes = [
+        ("PUT", format!("library/{}", novel_id), json!({ "status": "Reading" })),
+        ("POST", format!("library/{}/chapters/{}/read", novel_id, chapter_id), json!({})),
+        ("PUT", format!("reviews/{}/vote", review_id), json!({ "helpful": true })),
+        ("POST", format!("reviews/{}/comments", review_id), json!({ "content": "First take" })),
+        ("POST", "reports".to_string(), json!({ "target_type": "Review", "target_id": review_id, "reason": "Spoiler" })),
+        ("PUT", "me/preferences".to_string(), json!({ "original_languages": ["zh"], "hide_licensed": true })),
+        ("PUT", "me/notifications/preferences".to_string(), json!({ "new_chapter": true, "new_source": false, "review_reply": true, "digest": "Weekly" })),
+        ("PUT", format!("me/notifications/mutes/{}", novel_id), json!({})),
+    ];
+    let mut comment_id = serde_json::Value::Null;
+    for (method, path, body) in writes {
+        let response = ctx
+            .client
+            .request(method.parse().unwrap(), format!("http://localhost:8080/api/{}", path))
+            .header("Authorization", &auth)
+            .json(&body)
+            .send()
+            .await
+            .expect("Failed to write");
+
+        assert!(response.status().is_success(), "{} {} returned {}", method, path, response.status());
+        if path.ends_with("/comments") {
+            comment_id = response.json::<serde_json::Value>().await.expect("Failed to parse comment")["id"].clone();
+        }
+    }
+    let edit = ctx
+        .client
+        .patch(format!("http://localhost:8080/api/comments/{}", comment_id))
+        .header("Authorization", &auth)
+        .json(&json!({ "content": "Second take" }))
+        .send()
+        .await
+        .expect("Failed to edit comment");
+
+    assert_eq!(edit.status(), 200);
+
+    // EXPORT - A downloadable archive without credentials
+    let export_response = ctx
+        .client
+        .get("http://localhost:8080/api/me/export")
+        .header("Authorization", &auth)
+        .send()
+        .await
+        .expect("Failed to export");
+
+    assert_eq!(export_response.status(), 200);
+    let disposition = export_response.headers()["content-disposition"].to_str().unwrap().to_string();
+    assert!(disposition.starts_with("attachment"));
+
+    let export: serde_json::Value = export_response.json().await.expect("Failed to parse export");
+    assert_eq!(export["account"]["username"], "leaving");
+    assert!(export["account"].get("password_hash").is_none());
+    assert!(export["reading_lists"].is_array());
+    assert!(export["activity"]["api_keys"].as_array().unwrap().len() >= 1);
+    assert_eq!(export["library"][0]["novel"]["id"], novel_id);
+    assert_eq!(export["library"][0]["chapters_read"][0]["chapter_id"], chapter_id);
+    assert_eq!(export["review_votes"][0]["review_id"], review_id);
+    assert_eq!(export["comments"][0]["content"], "Second take");
+    assert_eq!(export["comments"][0]["revisions"][0]["content"], "First take");
+    assert_eq!(export["reports"][0]["target_id"], review_id);
+    assert_eq!(export["content_preferences"]["original_languages"], json!(["zh"]));
+    assert_eq!(export["notification_settings"]["digest_frequency"], "Weekly");
+    assert!(export["notification_settings"].get("unsubscribe_token").is_none());
+    assert_eq!(export["notification_mutes"][0]["novel_id"], novel_id);
+    assert!(export["follows"]["reading_lists"].is_array());
+    assert!(export["follows"]["groups"].is_array());
+    assert!(export["tag_votes"].is_array());
+
+    // DELETE - Wrong password is refused
+    let wrong_response = ctx
+        .client
+        .delete("http://localhost:8080/api/me")
+        .header("Authorization", &auth)
+        .json(&json!({ "password": "not the password" }))
+        .send()
+        .await
+        .expect("Failed to delete");
+
+    assert_eq!(wrong_response.status(), 401);
+
+    let delete_response = ctx
+        .client
+        .delete("http://localhost:8080/api/me")
+        .header("Authorization", &auth)
+        .json(&json!({ "password": "long enough password" }))
+        .send()
+        .await
+        .expect("Failed to delete");
+
+    assert_eq!(delete_response.status(), 202);
+
+    // Sessions are revoked and the profile disappears during the grace period
+    let me_response = ctx
+        .client
+        .get("http://localhost:8080/api/me")
+        .header("Authorization", &auth)
+        .send()
+        .await
+        .expect("Failed to get me");
+
+    assert_eq!(me_response.status(), 401);
+
+    let profile_response = ctx
+        .client
+        .get("http://localhost:8080/api/profiles/leaving")
+        .send()
+        .await
+        .expect("Failed to get profile");
+
+    assert_eq!(profile_response.status(), 404);
+
+    // Logging in again cancels the deletion
+    ctx.register_and_login("leaving").await;
+
+    let profile_response = ctx
+        .client
+        .get("http://localhost:8080/api/profiles/leaving")
+        .send()
+        .await
+        .expect("Failed to get profile");
+
+    assert_eq!(profile_response.status(), 200);
+}
+
+#[tokio::test]
+#[serial]
+async fn test_last_active_is_tracked_and_stats_are_admin_only() {
+    let ctx = TestContext::new().await;
+    let auth = ctx.register_and_login("active").await;
+
+    let me: serde_json::Value = ctx
+        .client
+        .get("http://localhost:8080/api/me")
+        .header("Authorization", &auth)
+        .send()
+        .await
+        .expect("Failed to get me")
+        .json()
+        .await
+        .expect("Failed to parse me");
+
+    assert!(me["last_active"].is_string());
+
+    // Plain users cannot read the admin statistics
+    for path in ["active-users", "registrations", "retention"] {
+        let response = ctx
+            .client
+            .get(format!("http://localhost:8080/api/admin/stats/{}", path))
+            .header("Authorization", &auth)
+            .send()
+            .await
+            .expect("Failed to get stats");
+
+        assert_eq!(response.status(), 403);
+    }
+}
+
+#[tokio::test]
+#[serial]
+async fn test_content_preferences() {
+    let ctx = TestContext::new().await;
+    let auth = ctx.register_and_login("picky").await;
+
+    // Defaults are empty filters
+    let defaults: serde_json::Value = ctx
+        .client
+        .get("http://localhost:8080/api/me/preferences")
+        .header("Authorization", &auth)
+        .send()
+        .await
+        .expect("Failed to get preferences")
+        .json()
+        .await
+        .expect("Failed to parse preferences");
+
+    assert_eq!(defaults["excluded_tag_ids"], json!([]));
+    assert_eq!(defaults["hide_licensed"], false);
+
+    let put_response = ctx
+        .client
+        .put("http://localhost:8080/api/me/preferences")
+        .header("Authorization", &auth)
+        .json(&json!({
+            "excluded_tag_ids": [3, 7],
+            "original_languages": ["ko", "zh", " Brazilian Portuguese ", "ko"],
+            "chapter_languages": ["en"],
+            "hide_licensed": true
+        }))
+        .send()
+        .await
+        .expect("Failed to put preferences");
+
+    assert_eq!(put_response.status(), 200);
+    let saved: serde_json::Value = put_response.json().await.expect("Failed to parse preferences");
+    assert_eq!(saved["excluded_tag_ids"], json!([3, 7]));
+    assert_eq!(saved["original_languages"], json!(["ko", "zh", "Brazilian Portuguese"]));
+
+    // Listings apply the preferences, and can be asked not to
+    for url in [
+        "http://localhost:8080/api/novels",
+        "http://localhost:8080/api/novels?unfiltered=true",
+        "http://localhost:8080/api/chapters",
+    ] {
+        let response = ctx
+            .client
+            .get(url)
+            .header("Authorization", &auth)
+            .send()
+            .await
+            .expect("Failed to list");
+
+        assert_eq!(response.status(), 200);
+    }
+
+    // Novel pages too, including novels with no original language on record
+    for language in [Some("ja"), None] {
+        let novel_id = ctx.insert_novel("Hidden Novel", language).await;
+        let hidden = ctx
+            .client
+            .get(format!("http://localhost:8080/api/novels/{}", novel_id))
+            .header("Authorization", &auth)
+            .send()
+            .await
+            .expect("Failed to get novel");
+
+        assert_eq!(hidden.status(), 404);
+
+        let unfiltered = ctx
+            .client
+            .get(format!("http://localhost:8080/api/novels/{}?unfiltered=true", novel_id))
+            .header("Authorization", &auth)
+            .send()
+            .await
+            .expect("Failed to get novel");
+
+        assert_eq!(unfiltered.status(), 200);
+    }
+
+    // Languages may contain spaces
+    for language in ["ko", "Brazilian Portuguese"] {
+        let shown_id = ctx.insert_novel("Shown Novel", Some(language)).await;
+        let shown = ctx
+            .client
+            .get(format!("http://localhost:8080/api/novels/{}", shown_id))
+            .header("Authorization", &auth)
+            .send()
+            .await
+            .expect("Failed to get novel");
+
+        assert_eq!(shown.status(), 200, "{}", language);
+    }
+}
+
+#[tokio::test]
+#[serial]
+async fn test_personal_reading_stats() {
+    let ctx = TestContext::new().await;
+    let auth = ctx.register_and_login("bookworm").await;
+
+    let stats: serde_json::Value = ctx
+        .client
+        .get("http://localhost:8080/api/me/stats")
+        .header("Authorization", &auth)
+        .send()
+        .await
+        .expect("Failed to get stats")
+        .json()
+        .await
+        .expect("Failed to parse stats");
+
+    assert_eq!(stats["chapters_read"], 0);
+    assert_eq!(stats["per_day"], json!([]));
+    assert_eq!(stats["streaks"]["current"], 0);
+
+    let yearly = ctx
+        .client
+        .get("http://localhost:8080/api/me/stats?year=2025")
+        .header("Authorization", &auth)
+        .send()
+        .await
+        .expect("Failed to get yearly stats");
+
+    assert_eq!(yearly.status(), 200);
+
+    let anonymous = ctx
+        .client
+        .get("http://localhost:8080/api/me/stats")
+        .send()
+        .await
+        .expect("Failed to get stats");
+
+    assert_eq!(anonymous.status(), 401);
+}
+
+#[tokio::test]
+#[serial]
+async fn test_reading_stats_over_several_days() {
+    let ctx = TestContext::new().await;
+    let auth = ctx.register_and_login("steady_reader").await;
+    let novel_id = ctx.insert_novel("Long Novel", Some("ko")).await;
+    let entry = ctx
+        .client
+        .put(format!("http://localhost:8080/api/library/{}", novel_id))
+        .header("Authorization", &auth)
+        .json(&json!({ "status": "Reading" }))
+        .send()
+        .await
+        .expect("Failed to put library entry");
+
+    assert_eq!(entry.status(), 201);
+
+    // One chapter today, one yesterday and one three days ago
+    for (number, days_ago) in [("1", 3), ("2", 1), ("3", 0)] {
+        let chapter_id = ctx.insert_chapter(novel_id, number).await;
+        let read = ctx
+            .client
+            .post(format!("http://localhost:8080/api/library/{}/chapters/{}/read", novel_id, chapter_id))
+            .header("Authorization", &auth)
+            .send()
+            .await
+            .expect("Failed to mark read");
+
+        assert!(read.status().is_success());
+        ctx.backdate_reads(chapter_id, days_ago).await;
+    }
+
+    let completed = ctx
+        .client
+        .put(format!("http://localhost:8080/api/library/{}", novel_id))
+        .header("Authorization", &auth)
+        .json(&json!({ "status": "Completed", "personal_rating": "4.5" }))
+        .send()
+        .await
+        .expect("Failed to complete novel");
+
+    assert_eq!(completed.status(), 200);
+
+    let stats: serde_json::Value = ctx
+        .client
+        .get("http://localhost:8080/api/me/stats")
+        .header("Authorization", &auth)
+        .send()
+        .await
+        .expect("Failed to get stats")
+        .json()
+        .await
+        .expect("Failed to parse stats");
+
+    assert_eq!(stats["chapters_read"], 3);
+    assert_eq!(stats["per_day"].as_array().unwrap().len(), 3);
+    let monthly: i64 = stats["per_month"].as_array().unwrap().iter().map(|month| month["chapters"].as_i64().unwrap()).sum();
+    assert_eq!(monthly, 3);
+    assert_eq!(stats["streaks"]["current"], 2);
+    assert_eq!(stats["streaks"]["longest"], 2);
+    assert_eq!(stats["novels_completed"], 1);
+    assert_eq!(stats["novels_dropped"], 0);
+    assert_eq!(stats["average_rating"], 4.5);
+    assert_eq!(stats["top_languages"][0]["name"], "ko");
+
+    // Completions count in the year they happened
+    let last_year: serde_json::Value = ctx
+        .client
+        .get("http://localhost:8080/api/me/stats?year=2000")
+        .header("Authorization", &auth)
+        .send()
+        .await
+        .expect("Failed to get yearly stats")
+        .json()
+        .await
+        .expect("Failed to parse yearly stats");
+
+    assert_eq!(last_year["novels_completed"], 0);
+    assert_eq!(last_year["chapters_read"], 0);
+}
+
+#[tokio::test]
+#[serial]
+async fn test_notifications_and_preferences() {
+    let ctx = TestContext::new().await;
+    let auth = ctx.register_and_login("subscriber").await;
+
+    let count: serde_json::Value = ctx
+        .client
+        .get("http://localhost:8080/api/me/notifications/unread-count")
+        .header("Authorization", &auth)
+        .send()
+        .await
+        .expect("Failed to get unread count")
+        .json()
+        .await
+        .expect("Failed to parse unread count");
+
+    assert_eq!(count["unread"], 0);
+
+    let notifications: serde_json::Value = ctx
+        .client
+        .get("http://localhost:8080/api/me/notifications?unread=true")
+        .header("Authorization", &auth)
+        .send()
+        .await
+        .expect("Failed to list notifications")
+        .json()
+        .await
+        .expect("Failed to parse notifications");
+
+    assert_eq!(notifications, json!([]));
+
+    // Every kind is on until turned off
+    let defaults: serde_json::Value = ctx
+        .client
+        .get("http://localhost:8080/api/me/notifications/preferences")
+        .header("Authorization", &auth)
+        .send()
+        .await
+        .expect("Failed to get notification preferences")
+        .json()
+        .await
+        .expect("Failed to parse notification preferences");
+
+    assert_eq!(defaults, json!({"new_chapter": true, "new_source": true, "review_reply": true, "digest": "Never"}));
+
+    let saved: serde_json::Value = ctx
+        .client
+        .put("http://localhost:8080/api/me/notifications/preferences")
+        .header("Authorization", &auth)
+        .json(&json!({"new_chapter": true, "new_source": false, "review_reply": true}))
+        .send()
+        .await
+        .expect("Failed to put notification preferences")
+        .json()
+        .await
+        .expect("Failed to parse notification preferences");
+
+    assert_eq!(saved["new_source"], false);
+
+    let mute_response = ctx
+        .client
+        .put("http://localhost:8080/api/me/notifications/mutes/999999")
+        .header("Authorization", &auth)
+        .send()
+        .await
+        .expect("Failed to mute novel");
+
+    assert_eq!(mute_response.status(), 404);
+
+    let missing = ctx
+        .client
+        .post("http://localhost:8080/api/me/notifications/999999/read")
+        .header("Authorization", &auth)
+        .send()
+        .await
+        .expect("Failed to mark notification read");
+
+    assert_eq!(missing.status(), 404);
+}
+
+/// Unread notifications of the signed-in user.
+async fn unread_count(ctx: &TestContext, auth: &str) -> i64 {
+    let count: serde_json::Value = ctx
+        .client
+        .get("http://localhost:8080/api/me/notifications/unread-count")
+        .header("Authorization", auth)
+        .send()
+        .await
+        .expect("Failed to get unread count")
+        .json()
+        .await
+        .expect("Failed to parse unread count");
+    count["unread"].as_i64().expect("No unread count")
+}
+
+async fn put_notification_preferences(ctx: &TestContext, auth: &str, new_chapter: bool, new_source: bool) {
+    let response = ctx
+        .client
+        .put("http://localhost:8080/api/me/notifications/preferences")
+        .header("Authorization", auth)
+        .json(&json!({"new_chapter": new_chapter, "new_source": new_source, "review_reply": true}))
+        .send()
+        .await
+        .expect("Failed to put notification preferences");
+
+    assert_eq!(response.status(), 200);
+}
+
+#[tokio::test]
+#[serial]
+async fn test_new_chapters_and_sources_notify() {
+    let ctx = TestContext::new().await;
+    let auth = ctx.register_and_login("follower").await;
+    let novel_id = ctx.insert_novel("Followed Novel", Some("ko")).await;
+    let source_id = ctx.insert_source(novel_id).await;
+    let entry = ctx
+        .client
+        .put(format!("http://localhost:8080/api/library/{}", novel_id))
+        .header("Authorization", &auth)
+        .json(&json!({ "status": "Reading" }))
+        .send()
+        .await
+        .expect("Failed to put library entry");
+
+    assert_eq!(entry.status(), 201);
+
+    let source: serde_json::Value = ctx
+        .client
+        .get(format!("http://localhost:8080/api/sources/{}", source_id))
+        .send()
+        .await
+        .expect("Failed to get source")
+        .json()
+        .await
+        .expect("Failed to parse source");
+    let post_chapter = |number: &str| {
+        ctx.client
+            .post("http://localhost:8080/api/chapters")
+            .json(&json!({ "language": "en", "number": number, "title": "", "source": source }))
+            .send()
+    };
+
+    // A new chapter of a novel in the library notifies its reader
+    assert_eq!(post_chapter("2").await.expect("Failed to post chapter").status(), 200);
+    assert_eq!(unread_count(&ctx, &auth).await, 1);
+    let notifications: serde_json::Value = ctx
+        .client
+        .get("http://localhost:8080/api/me/notifications")
+        .header("Authorization", &auth)
+        .send()
+        .await
+        .expect("Failed to list notifications")
+        .json()
+        .await
+        .expect("Failed to parse notifications");
+
+    assert_eq!(notifications[0]["kind"], "NewChapter");
+    assert_eq!(notifications[0]["novel_id"], novel_id);
+
+    // Turning the kind off or muting the novel keeps chapters quiet
+    put_notification_preferences(&ctx, &auth, false, true).await;
+    assert_eq!(post_chapter("3").await.expect("Failed to post chapter").status(), 200);
+    assert_eq!(unread_count(&ctx, &auth).await, 1);
+
+    put_notification_preferences(&ctx, &auth, true, true).await;
+    let mute = ctx
+        .client
+        .put(format!("http://localhost:8080/api/me/notifications/mutes/{}", novel_id))
+        .header("Authorization", &auth)
+        .send()
+        .await
+        .expect("Failed to mute novel");
+
+    assert!(mute.status().is_success());
+    assert_eq!(post_chapter("4").await.expect("Failed to post chapter").status(), 200);
+    assert_eq!(unread_count(&ctx, &auth).await, 1);
+
+    // A followed group picking up a novel notifies its followers
+    let follow = ctx
+        .client
+        .post(format!("http://localhost:8080/api/groups/{}/follow", source["group"]["id"]))
+        .header("Authorization", &auth)
+        .send()
+        .await
+        .expect("Failed to follow group");
+
+    assert!(follow.status().is_success());
+
+    let other_novel_id = ctx.insert_novel("Picked Up Novel", Some("zh")).await;
+    let other_novel: serde_json::Value = ctx
+        .client
+        .get(format!("http://localhost:8080/api/novels/{}", other_novel_id))
+        .send()
+        .await
+        .expect("Failed to get novel")
+        .json()
+        .await
+        .expect("Failed to parse novel");
+    let post_source = |name: &str| {
+        ctx.client
+            .post("http://localhost:8080/api/sources")
+            .json(&json!({ "language": "en", "name": name, "novel": other_novel, "group": source["group"] }))
+            .send()
+    };
+
+    assert_eq!(post_source("New Source").await.expect("Failed to post source").status(), 200);
+    assert_eq!(unread_count(&ctx, &auth).await, 2);
+
+    put_notification_preferences(&ctx, &auth, true, false).await;
+    assert_eq!(post_source("Another Source").await.expect("Failed to post source").status(), 200);
+    assert_eq!(unread_count(&ctx, &auth).await, 2);
+}
+
+#[tokio::test]
+#[serial]
+async fn test_digest_frequency_and_unsubscribe() {
+    let ctx = TestContext::new().await;
+    let auth = ctx.register_and_login("digester").await;
+
+    // Digests only go to verified addresses
+    let verify = ctx
+        .client
+        .post("http://localhost:8080/api/auth/verify-email")
+        .json(&json!({ "token": ctx.last_mail_token("digester@example.com") }))
+        .send()
+        .await
+        .expect("Failed to verify email");
+
+    assert_eq!(verify.status(), 204);
+
+    let novel_id = ctx.insert_novel("Digested Novel", Some("ko")).await;
+    ctx.insert_chapter(novel_id, "1").await;
+    let entry = ctx
+        .client
+        .put(format!("http://localhost:8080/api/library/{}", novel_id))
+        .header("Authorization", &auth)
+        .json(&json!({ "status": "Reading" }))
+        .send()
+        .await
+        .expect("Failed to put library entry");
+
+    assert_eq!(entry.status(), 201);
+
+    let saved: serde_json::Value = ctx
+        .client
+        .put("http://localhost:8080/api/me/notifications/preferences")
+        .header("Authorization", &auth)
+        .json(&json!({"new_chapter": true, "new_source": true, "review_reply": true, "digest": "Weekly"}))
+        .send()
+        .await
+        .expect("Failed to put notification preferences")
+        .json()
+        .await
+        .expect("Failed to parse notification preferences");
+
+    assert_eq!(saved["digest"], "Weekly");
+
+    // The test server looks for due digests every second
+    let mut digest = None;
+    for _ in 0..10 {
+        tokio::time::sleep(tokio::time::Duration::from_secs(1)).await;
+        digest = ctx
+            .sent_mails("digester@example.com")
+            .into_iter()
+            .find(|mail| mail["subject"] == "Your weekly NovelUpdates digest");
+        if digest.is_some() {
+            break;
+        }
+    }
+    let digest = digest.expect("No digest sent");
+    assert!(digest["body"].as_str().unwrap().contains("Digested Novel"));
+    let unsubscribe_url = digest["list_unsubscribe"].as_str().expect("No List-Unsubscribe URL").to_string();
+    let token = ctx.last_mail_token("digester@example.com");
+    assert!(unsubscribe_url.ends_with(&format!("token={}", token)));
+
+    // Opening the link only asks for confirmation
+    let page = ctx
+        .client
+        .get(&unsubscribe_url)
+        .send()
+        .await
+        .expect("Failed to open unsubscribe page");
+
+    assert_eq!(page.status(), 200);
+    assert!(page.headers()["content-type"].to_str().unwrap().starts_with("text/html"));
+    assert!(page.text().await.unwrap().contains("method=\"post\""));
+
+    let preferences: serde_json::Value = ctx
+        .client
+        .get("http://localhost:8080/api/me/notifications/preferences")
+        .header("Authorization", &auth)
+        .send()
+        .await
+        .expect("Failed to get notification preferences")
+        .json()
+        .await
+        .expect("Failed to parse notification preferences");
+
+    assert_eq!(preferences["digest"], "Weekly");
+
+    // One-click unsubscribe posts to the same URL without signing in
+    let unsubscribed = ctx
+        .client
+        .post(&unsubscribe_url)
+        .header("Content-Type", "application/x-www-form-urlencoded")
+        .body("List-Unsubscribe=One-Click")
+        .send()
+        .await
+        .expect("Failed to unsubscribe");
+
+    assert_eq!(unsubscribed.status(), 200);
+
+    let preferences: serde_json::Value = ctx
+        .client
+        .get("http://localhost:8080/api/me/notifications/preferences")
+        .header("Authorization", &auth)
+        .send()
+        .await
+        .expect("Failed to get notification preferences")
+        .json()
+        .await
+        .expect("Failed to parse notification preferences");
+
+    assert_eq!(preferences["digest"], "Never");
+
+    // Only real tokens are accepted
+    for request in [
+        ctx.client.get("http://localhost:8080/api/digest/unsubscribe?token=not-a-token"),
+        ctx.client.post("http://localhost:8080/api/digest/unsubscribe?token=not-a-token"),
+    ] {
+        let unknown = request.send().await.expect("Failed to unsubscribe");
+
+        assert_eq!(unknown.status(), 404);
+    }
+}
+
+#[tokio::test]
+#[serial]
+async fn test_bio_markdown_is_rendered_and_sanitized() {
+    let ctx = TestContext::new().await;
+    let auth = ctx.register_and_login("markdowner").await;
+
+    let me: serde_json::Value = ctx
+        .client
+        .patch("http://localhost:8080/api/me")
+        .header("Authorization", &auth)
+        .json(&json!({ "bio": "**Bold** reader, ||the butler did it|| <script>alert(1)</script>" }))
+        .send()
+        .await
+        .expect("Failed to patch me")
+        .json()
+        .await
+        .expect("Failed to parse me");
+
+    assert_eq!(me["bio"], "**Bold** reader, ||the butler did it|| <script>alert(1)</script>");
+    let html = me["bio_html"].as_str().expect("No rendered bio");
+    assert!(html.contains("<strong>Bold</strong>"));
+    assert!(html.contains(r#"<span class="spoiler">the butler did it</span>"#));
+    assert!(!html.contains("<script"));
+
+    let profile: serde_json::Value = ctx
+        .client
+        .get("http://localhost:8080/api/profiles/markdowner")
+        .send()
+        .await
+        .expect("Failed to get profile")
+        .json()
+        .await
+        .expect("Failed to parse profile");
+
+    assert_eq!(profile["bio_html"], me["bio_html"]);
+}
+
+#[tokio::test]
+#[serial]
+async fn test_reputation_on_account_and_profile() {
+    let ctx = TestContext::new().await;
+    let auth = ctx.register_and_login("newcomer").await;
+
+    let me: serde_json::Value = ctx
+        .client
+        .get("http://localhost:8080/api/me")
+        .header("Authorization", &auth)
+        .send()
+        .await
+        .expect("Failed to get me")
+        .json()
+        .await
+        .expect("Failed to parse me");
+
+    assert_eq!(me["reputation"]["score"], 0);
+    assert_eq!(me["reputation"]["penalties"], 0);
+    assert_eq!(me["reputation"]["abilities"], json!([]));
+
+    let profile: serde_json::Value = ctx
+        .client
+        .get("http://localhost:8080/api/profiles/newcomer")
+        .send()
+        .await
+        .expect("Failed to get profile")
+        .json()
+        .await
+        .expect("Failed to parse profile");
+
+    assert_eq!(profile["reputation"], 0);
+
+    // Helpful votes on the user's reviews count for them
+    let novel_id = ctx.insert_novel("Reviewed Novel", Some("ko")).await;
+    let review_id = ctx.insert_review("newcomer", novel_id).await;
+    for voter in ["fan_one", "fan_two"] {
+        let voter_auth = ctx.register_and_login(voter).await;
+        let vote = ctx
+            .client
+            .put(format!("http://localhost:8080/api/reviews/{}/vote", review_id))
+            .header("Authorization", &voter_auth)
+            .json(&json!({ "helpful": true }))
+            .send()
+            .await
+            .expect("Failed to vote");
+
+        assert_eq!(vote.status(), 200);
+    }
+
+    let me: serde_json::Value = ctx
+        .client
+        .get("http://localhost:8080/api/me")
+        .header("Authorization", &auth)
+        .send()
+        .await
+        .expect("Failed to get me")
+        .json()
+        .await
+        .expect("Failed to parse me");
+
+    assert_eq!(me["reputation"]["score"], 2);
+    assert_eq!(me["reputation"]["helpful_votes"], 2);
+
+    // A hidden review loses its votes and costs a moderation penalty
+    let moderator = ctx.register_and_login("rep_moderator").await;
+    ctx.set_role("rep_moderator", "moderator").await;
+    let report: serde_json::Value = ctx
+        .client
+        .post("http://localhost:8080/api/reports")
+        .header("Authorization", &moderator)
+        .json(&json!({ "target_type": "Review", "target_id": review_id, "reason": "Spoiler" }))
+        .send()
+        .await
+        .expect("Failed to report")
+        .json()
+        .await
+        .expect("Failed to parse report");
+    let hide = ctx
+        .client
+        .post("http://localhost:8080/api/admin/reports/actions")
+        .header("Authorization", &moderator)
+        .json(&json!({ "report_ids": [report["id"]], "action": "HideReview" }))
+        .send()
+        .await
+        .expect("Failed to hide review");
+
+    assert_eq!(hide.status(), 200);
+
+    let profile: serde_json::Value = ctx
+        .client
+        .get("http://localhost:8080/api/profiles/newcomer")
+        .send()
+        .await
+        .expect("Failed to get profile")
+        .json()
+        .await
+        .expect("Failed to parse profile");
+
+    assert_eq!(profile["reputation"], -20);
+}
+
+#[tokio::test]
+#[serial]
+async fn test_reassigning_a_review_does_not_move_reputation() {
+    let ctx = TestContext::new().await;
+    let author = ctx.register_and_login("rep_author").await;
+    let other = ctx.register_and_login("rep_claimant").await;
+
+    let novel_id = ctx.insert_novel("Claimed Novel", Some("ja")).await;
+    let review_id = ctx.insert_review("rep_author", novel_id).await;
+    let voter = ctx.register_and_login("rep_voter").await;
+    let vote = ctx
+        .client
+        .put(format!("http://localhost:8080/api/reviews/{}/vote", review_id))
+        .header("Authorization", &voter)
+        .json(&json!({ "helpful": true }))
+        .send()
+        .await
+        .expect("Failed to vote");
+
+    assert_eq!(vote.status(), 200);
+
+    let claimant: serde_json::Value = ctx
+        .client
+        .get("http://localhost:8080/api/me")
+        .header("Authorization", &other)
+        .send()
+        .await
+        .expect("Failed to get me")
+        .json()
+        .await
+        .expect("Failed to parse me");
+    let claimant_id = claimant["id"].clone();
+
+    // Someone else can't take the review over
+    let taken = ctx
+        .client
+        .patch(format!("http://localhost:8080/api/reviews/{}", review_id))
+        .header("Authorization", &other)
+        .json(&json!({ "user": { "id": claimant_id } }))
+        .send()
+        .await
+        .expect("Failed to patch review");
+
+    assert_eq!(taken.status(), 403);
+
+    // and the author can't hand it over, the author field is ignored
+    let handed = ctx
+        .client
+        .patch(format!("http://localhost:8080/api/reviews/{}", review_id))
+        .header("Authorization", &author)
+        .json(&json!({ "title": "Still mine", "user": { "id": claimant_id } }))
+        .send()
+        .await
+        .expect("Failed to patch review");
+
+    assert_eq!(handed.status(), 200);
+
+    for (username, score) in [("rep_author", 1), ("rep_claimant", 0)] {
+        let profile: serde_json::Value = ctx
+            .client
+            .get(format!("http://localhost:8080/api/profiles/{}", username))
+            .send()
+            .await
+            .expect("Failed to get profile")
+            .json()
+            .await
+            .expect("Failed to parse profile");
+
+        assert_eq!(profile["reputation"], score, "reputation of {}", username);
+    }
+}